@@ -0,0 +1,7 @@
+CREATE TABLE rate_limit_buckets (
+    scope TEXT NOT NULL,
+    key TEXT NOT NULL,
+    tokens DOUBLE PRECISION NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (scope, key)
+);
@@ -0,0 +1,23 @@
+CREATE TABLE jobs (
+    id uuid PRIMARY KEY,
+    kind TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    -- queued, running, succeeded or dead
+    status TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL,
+    run_at TIMESTAMPTZ NOT NULL,
+    -- Running jobs are handed to another worker past this time
+    locked_until TIMESTAMPTZ,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    finished_at TIMESTAMPTZ
+);
+
+CREATE INDEX jobs_pending_idx ON jobs (run_at) WHERE status IN ('queued', 'running');
+
+CREATE TABLE job_schedules (
+    kind TEXT PRIMARY KEY,
+    cron TEXT NOT NULL,
+    next_run_at TIMESTAMPTZ NOT NULL
+);
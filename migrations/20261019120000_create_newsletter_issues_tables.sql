@@ -0,0 +1,29 @@
+CREATE TABLE newsletter_issues (
+    id uuid PRIMARY KEY,
+    title TEXT NOT NULL,
+    text_content TEXT NOT NULL,
+    html_content TEXT NOT NULL,
+    -- draft, scheduled, sending or sent
+    status TEXT NOT NULL,
+    send_at TIMESTAMPTZ,
+    created_by uuid REFERENCES users(user_id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    sent_at TIMESTAMPTZ
+);
+
+CREATE INDEX newsletter_issues_due_idx ON newsletter_issues (send_at) WHERE status = 'scheduled';
+
+CREATE TABLE issue_deliveries (
+    issue_id uuid NOT NULL REFERENCES newsletter_issues(id) ON DELETE CASCADE,
+    subscriber_id uuid NOT NULL REFERENCES subscriptions(id) ON DELETE CASCADE,
+    email TEXT NOT NULL,
+    -- pending, sending, sent or failed
+    status TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    updated_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (issue_id, subscriber_id)
+);
+
+CREATE INDEX issue_deliveries_pending_idx ON issue_deliveries (issue_id) WHERE status IN ('pending', 'sending');
@@ -0,0 +1,14 @@
+-- Responses saved by key, `owner` is the user the key belongs to or, for
+-- anonymous endpoints, the client IP
+CREATE TABLE idempotency_keys (
+    owner TEXT NOT NULL,
+    key TEXT NOT NULL,
+    request_path TEXT NOT NULL,
+    response_status SMALLINT,
+    response_headers JSONB,
+    response_body BYTEA,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (owner, key)
+);
+
+CREATE INDEX idempotency_keys_created_at ON idempotency_keys (created_at);
@@ -0,0 +1,31 @@
+-- Every deployment may run several publications, the existing subscriptions
+-- and issues belong to the default `newsletter` list
+CREATE TABLE lists (
+    id uuid PRIMARY KEY,
+    slug TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    sender_email TEXT,
+    require_confirmation BOOLEAN NOT NULL DEFAULT TRUE,
+    confirmation_ttl BIGINT,
+    confirmation_template_html TEXT,
+    issue_template_html TEXT,
+    issue_template_text TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+INSERT INTO lists (id, slug, name)
+VALUES ('00000000-0000-0000-0000-000000000001', 'newsletter', 'Newsletter');
+
+ALTER TABLE subscriptions
+    ADD COLUMN list_id uuid REFERENCES lists (id);
+UPDATE subscriptions SET list_id = '00000000-0000-0000-0000-000000000001';
+ALTER TABLE subscriptions
+    ALTER COLUMN list_id SET NOT NULL,
+    DROP CONSTRAINT subscriptions_email_key,
+    ADD CONSTRAINT subscriptions_list_id_email_key UNIQUE (list_id, email);
+
+ALTER TABLE newsletter_issues
+    ADD COLUMN list_id uuid REFERENCES lists (id);
+UPDATE newsletter_issues SET list_id = '00000000-0000-0000-0000-000000000001';
+ALTER TABLE newsletter_issues
+    ALTER COLUMN list_id SET NOT NULL;
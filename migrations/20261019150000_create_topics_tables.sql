@@ -0,0 +1,25 @@
+-- Subscribers receive every topic of their list until they opt out, the
+-- preferences only record their choices
+CREATE TABLE topics (
+    id uuid PRIMARY KEY,
+    list_id uuid NOT NULL REFERENCES lists (id) ON DELETE CASCADE,
+    slug TEXT NOT NULL,
+    name TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (list_id, slug)
+);
+
+CREATE TABLE topic_preferences (
+    subscriber_id uuid NOT NULL REFERENCES subscriptions (id) ON DELETE CASCADE,
+    topic_id uuid NOT NULL REFERENCES topics (id) ON DELETE CASCADE,
+    subscribed BOOLEAN NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (subscriber_id, topic_id)
+);
+
+-- Issues without topics are sent to every subscriber of their list
+CREATE TABLE newsletter_issue_topics (
+    issue_id uuid NOT NULL REFERENCES newsletter_issues (id) ON DELETE CASCADE,
+    topic_id uuid NOT NULL REFERENCES topics (id) ON DELETE CASCADE,
+    PRIMARY KEY (issue_id, topic_id)
+);
@@ -0,0 +1,8 @@
+-- Issues with a segment are only sent to the subscribers it matches, the
+-- segment is stored in its canonical text form
+ALTER TABLE newsletter_issues ADD COLUMN segment TEXT;
+
+-- Set the first time the tracking pixel of the delivery is loaded
+ALTER TABLE issue_deliveries ADD COLUMN opened_at TIMESTAMPTZ;
+
+CREATE INDEX issue_deliveries_subscriber_idx ON issue_deliveries (subscriber_id);
@@ -0,0 +1,17 @@
+-- Emails whose owner asked to be forgotten, as the SHA-256 digest of the
+-- lowercased address, they cannot be subscribed again
+CREATE TABLE suppressed_emails (
+    email_hash TEXT PRIMARY KEY,
+    suppressed_at TIMESTAMPTZ NOT NULL
+);
+
+-- Never holds personal data, people are referred to by their email hash
+CREATE TABLE audit_log (
+    id uuid PRIMARY KEY,
+    action TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    details JSONB NOT NULL,
+    occurred_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX subscriptions_email_idx ON subscriptions (lower(email));
@@ -0,0 +1,25 @@
+-- Ledger of the consents given and withdrawn by subscribers, rows are only
+-- deleted along with their subscription
+CREATE TABLE consent_events (
+    id uuid PRIMARY KEY,
+    subscriber_id uuid NOT NULL REFERENCES subscriptions (id) ON DELETE CASCADE,
+    action TEXT NOT NULL,
+    source TEXT NOT NULL,
+    ip TEXT,
+    user_agent TEXT,
+    policy_version TEXT,
+    topics TEXT[] NOT NULL DEFAULT '{}',
+    occurred_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX consent_events_subscriber_id_idx ON consent_events (subscriber_id, occurred_at);
+
+CREATE FUNCTION reject_consent_event_update() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'consent events are append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER consent_events_append_only
+    BEFORE UPDATE ON consent_events
+    FOR EACH ROW EXECUTE FUNCTION reject_consent_event_update();
@@ -0,0 +1,21 @@
+CREATE TABLE subscriber_imports (
+    id uuid PRIMARY KEY,
+    list_id uuid NOT NULL REFERENCES lists (id) ON DELETE CASCADE,
+    confirmed BOOLEAN NOT NULL,
+    status TEXT NOT NULL,
+    rows BIGINT NOT NULL DEFAULT 0,
+    imported BIGINT NOT NULL DEFAULT 0,
+    failed BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL,
+    finished_at TIMESTAMPTZ
+);
+
+-- The rows of an import that were not imported, erased along with the
+-- subscriptions of their email address
+CREATE TABLE subscriber_import_errors (
+    import_id uuid NOT NULL REFERENCES subscriber_imports (id) ON DELETE CASCADE,
+    line BIGINT NOT NULL,
+    email TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    PRIMARY KEY (import_id, line)
+);
@@ -0,0 +1,5 @@
+-- Editors list subscribers newest first and search them by the start of
+-- their email address or name
+CREATE INDEX subscriptions_subscribed_at_idx ON subscriptions (subscribed_at DESC, id DESC);
+CREATE INDEX subscriptions_email_prefix_idx ON subscriptions (lower(email) text_pattern_ops);
+CREATE INDEX subscriptions_name_prefix_idx ON subscriptions (lower(name) text_pattern_ops);
@@ -0,0 +1,11 @@
+-- Editors signed in to the dashboard, the cookie carries a token of which
+-- only the SHA-256 digest is stored
+CREATE TABLE admin_sessions (
+    id TEXT PRIMARY KEY,
+    user_id uuid NOT NULL REFERENCES users (user_id) ON DELETE CASCADE,
+    csrf_token TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX admin_sessions_expires_at_idx ON admin_sessions (expires_at);
@@ -0,0 +1,8 @@
+-- Nonces of the form tokens already submitted, kept until the tokens expire so
+-- that each token is only accepted once
+CREATE TABLE used_form_tokens (
+    nonce TEXT PRIMARY KEY,
+    used_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX used_form_tokens_used_at ON used_form_tokens (used_at);
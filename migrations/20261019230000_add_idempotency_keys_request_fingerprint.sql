@@ -0,0 +1,3 @@
+-- Digest of the body of the request that claimed the key, NULL for the keys
+-- claimed before it was recorded
+ALTER TABLE idempotency_keys ADD COLUMN request_fingerprint TEXT;
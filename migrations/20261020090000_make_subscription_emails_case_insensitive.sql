@@ -0,0 +1,5 @@
+-- Email addresses are looked up regardless of case, a list cannot hold the
+-- same address twice in different cases
+ALTER TABLE subscriptions DROP CONSTRAINT subscriptions_list_id_email_key;
+CREATE UNIQUE INDEX subscriptions_list_id_lower_email_key
+    ON subscriptions (list_id, lower(email));
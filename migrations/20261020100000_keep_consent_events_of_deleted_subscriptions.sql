@@ -0,0 +1,36 @@
+-- Consent events outlive their subscription, they are only purged when the
+-- subscriber erases their data. The list and the digest of the address, as
+-- computed by `EmailHash`, are kept to tell whose consent they were.
+ALTER TABLE consent_events
+    DROP CONSTRAINT consent_events_subscriber_id_fkey,
+    ADD COLUMN list_id uuid REFERENCES lists (id),
+    ADD COLUMN email_hash TEXT;
+
+CREATE FUNCTION consent_event_subscription() RETURNS trigger AS $$
+BEGIN
+    SELECT list_id, encode(sha256(convert_to(lower(email), 'UTF8')), 'hex')
+    INTO NEW.list_id, NEW.email_hash
+    FROM subscriptions WHERE id = NEW.subscriber_id;
+    IF NOT FOUND THEN
+        RAISE EXCEPTION 'unknown subscriber %', NEW.subscriber_id;
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+ALTER TABLE consent_events DISABLE TRIGGER consent_events_append_only;
+UPDATE consent_events
+SET list_id = subscriptions.list_id,
+    email_hash = encode(sha256(convert_to(lower(subscriptions.email), 'UTF8')), 'hex')
+FROM subscriptions WHERE subscriptions.id = consent_events.subscriber_id;
+ALTER TABLE consent_events ENABLE TRIGGER consent_events_append_only;
+
+ALTER TABLE consent_events
+    ALTER COLUMN list_id SET NOT NULL,
+    ALTER COLUMN email_hash SET NOT NULL;
+
+CREATE TRIGGER consent_events_subscription
+    BEFORE INSERT ON consent_events
+    FOR EACH ROW EXECUTE FUNCTION consent_event_subscription();
+
+CREATE INDEX consent_events_email_hash_idx ON consent_events (email_hash);
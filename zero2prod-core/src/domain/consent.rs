@@ -0,0 +1,177 @@
+use std::{fmt, str::FromStr, time::SystemTime};
+
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::error::CoreError;
+
+use super::TopicSlug;
+
+/// What a subscriber agreed to, or withdrew.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsentAction {
+    Subscribe,
+    Confirm,
+    Unsubscribe,
+    /// Chose the topics they receive.
+    Preferences,
+}
+
+impl ConsentAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConsentAction::Subscribe => "subscribe",
+            ConsentAction::Confirm => "confirm",
+            ConsentAction::Unsubscribe => "unsubscribe",
+            ConsentAction::Preferences => "preferences",
+        }
+    }
+}
+
+impl FromStr for ConsentAction {
+    type Err = CoreError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "subscribe" => Ok(ConsentAction::Subscribe),
+            "confirm" => Ok(ConsentAction::Confirm),
+            "unsubscribe" => Ok(ConsentAction::Unsubscribe),
+            "preferences" => Ok(ConsentAction::Preferences),
+            _ => Err(CoreError::InvalidDomain(format!(
+                "Unknown consent action {}",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ConsentAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Through what a consent was given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsentSource {
+    /// The subscriber, through the forms and links we serve.
+    Form,
+    /// An editor, through the API.
+    Api,
+    /// An import of subscribers.
+    Import,
+}
+
+impl ConsentSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConsentSource::Form => "form",
+            ConsentSource::Api => "api",
+            ConsentSource::Import => "import",
+        }
+    }
+}
+
+impl FromStr for ConsentSource {
+    type Err = CoreError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "form" => Ok(ConsentSource::Form),
+            "api" => Ok(ConsentSource::Api),
+            "import" => Ok(ConsentSource::Import),
+            _ => Err(CoreError::InvalidDomain(format!(
+                "Unknown consent source {}",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ConsentSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How a consent was given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsentContext {
+    pub source: ConsentSource,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    /// Version of the privacy policy shown when consenting.
+    pub policy_version: Option<String>,
+}
+
+impl ConsentContext {
+    pub fn new(source: ConsentSource) -> Self {
+        Self {
+            source,
+            ip: None,
+            user_agent: None,
+            policy_version: None,
+        }
+    }
+
+    /// Replaces the IP and user agent by their hex encoded SHA-256 digest,
+    /// which still proves them without storing them.
+    pub fn hashed(self) -> Self {
+        let hash = |value: String| hex::encode(Sha256::digest(value.as_bytes()));
+        Self {
+            ip: self.ip.map(hash),
+            user_agent: self.user_agent.map(hash),
+            ..self
+        }
+    }
+}
+
+/// An entry of the consent ledger, which is never changed once written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsentEvent {
+    pub subscriber_id: Uuid,
+    pub action: ConsentAction,
+    pub context: ConsentContext,
+    /// The topics received after a change of preferences.
+    pub topics: Vec<TopicSlug>,
+}
+
+impl ConsentEvent {
+    pub fn new(subscriber_id: Uuid, action: ConsentAction, context: &ConsentContext) -> Self {
+        Self {
+            subscriber_id,
+            action,
+            context: context.clone(),
+            topics: vec![],
+        }
+    }
+}
+
+/// A consent event as stored, with the time it was recorded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedConsent {
+    pub event: ConsentEvent,
+    pub occurred_at: SystemTime,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashed_contexts_do_not_keep_the_client() {
+        let context = ConsentContext {
+            source: ConsentSource::Form,
+            ip: Some("203.0.113.7".into()),
+            user_agent: Some("Mozilla/5.0".into()),
+            policy_version: Some("2026-10".into()),
+        };
+
+        let hashed = context.clone().hashed();
+
+        assert_eq!(hashed.source, ConsentSource::Form);
+        assert_eq!(hashed.policy_version, context.policy_version);
+        assert_eq!(hashed.ip.as_ref().unwrap().len(), 64);
+        assert_ne!(hashed.ip, context.ip);
+        assert_ne!(hashed.user_agent, context.user_agent);
+        assert_eq!(hashed, context.hashed());
+    }
+}
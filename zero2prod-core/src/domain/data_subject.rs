@@ -0,0 +1,80 @@
+use std::{fmt, time::SystemTime};
+
+use email_address::EmailAddress;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use super::{ListSlug, RecordedConsent, SubscriberName, SubscriptionStatus, TopicSlug};
+
+/// The hex encoded SHA-256 digest of a lowercased email address, kept instead
+/// of the address once its owner asked to be forgotten.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmailHash(String);
+
+impl EmailHash {
+    pub fn of(email: &EmailAddress) -> Self {
+        let digest = Sha256::digest(email.as_str().to_lowercase().as_bytes());
+        Self(hex::encode(digest))
+    }
+}
+
+impl AsRef<str> for EmailHash {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for EmailHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Everything stored about the person owning an email address, across lists.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubjectData {
+    pub email: EmailAddress,
+    pub subscriptions: Vec<SubscriptionData>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubscriptionData {
+    pub id: Uuid,
+    pub list: ListSlug,
+    pub name: SubscriberName,
+    pub status: SubscriptionStatus,
+    pub subscribed_at: SystemTime,
+    /// The topics the subscriber chose to receive or not, the others are
+    /// received.
+    pub topics: Vec<(TopicSlug, bool)>,
+    /// Confirmation tokens stored before tokens were signed.
+    pub tokens: Vec<String>,
+    pub deliveries: Vec<DeliveryData>,
+    /// The consent ledger of the subscription, oldest first.
+    pub consents: Vec<RecordedConsent>,
+}
+
+/// An issue sent, or being sent, to a subscriber.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeliveryData {
+    pub issue_id: Uuid,
+    pub title: String,
+    pub status: String,
+    pub attempts: u32,
+    pub updated_at: SystemTime,
+    pub opened_at: Option<SystemTime>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn email_hashes_ignore_case() {
+        let lower: EmailAddress = "john.doe@gmail.com".parse().unwrap();
+        let upper: EmailAddress = "John.Doe@Gmail.com".parse().unwrap();
+
+        assert_eq!(EmailHash::of(&lower), EmailHash::of(&upper));
+        assert_eq!(EmailHash::of(&lower).as_ref().len(), 64);
+    }
+}
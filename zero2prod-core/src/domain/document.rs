@@ -1,19 +1,100 @@
+use email_address::EmailAddress;
 use serde::Serialize;
 
+use super::MailingList;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Document {
     pub title: String,
     pub kind: DocumentKind,
+    /// Address the document is sent from, the default sender when unset.
+    pub sender: Option<EmailAddress>,
+    /// Templates rendering the document instead of the default ones.
+    pub templates: DocumentTemplates,
+}
+
+/// Handlebars sources of the HTML and plain text versions of a document.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DocumentTemplates {
+    pub html: Option<String>,
+    pub text: Option<String>,
 }
 
 impl Document {
     pub fn new(title: String, kind: DocumentKind) -> Self {
-        Self { title, kind }
+        Self {
+            title,
+            kind,
+            sender: None,
+            templates: DocumentTemplates::default(),
+        }
+    }
+
+    /// Sends the document on behalf of `list`, with its sender and templates.
+    pub fn for_list(self, list: &MailingList) -> Self {
+        let settings = &list.settings;
+        let templates = match self.kind {
+            DocumentKind::Confirmation { .. } => DocumentTemplates {
+                html: settings.templates.confirmation_html.clone(),
+                text: None,
+            },
+            DocumentKind::Issue { .. } => DocumentTemplates {
+                html: settings.templates.issue_html.clone(),
+                text: settings.templates.issue_text.clone(),
+            },
+            DocumentKind::DataAccess { .. } => DocumentTemplates::default(),
+        };
+        Self {
+            sender: settings.sender.clone(),
+            templates,
+            ..self
+        }
     }
 }
 
 #[derive(Serialize, Debug, Clone, PartialEq)]
 #[serde(tag = "type")]
 pub enum DocumentKind {
-    Confirmation { confirmation_link: String },
+    Confirmation {
+        confirmation_link: String,
+        unsubscribe_link: String,
+    },
+    Issue {
+        html_content: String,
+        text_content: String,
+        unsubscribe_link: String,
+        /// Where the recipient chooses the topics they receive.
+        preferences_link: String,
+        /// Image reporting that the issue was opened, none in previews.
+        tracking_pixel_link: Option<String>,
+    },
+    /// Sent to people asking what is stored about them.
+    DataAccess {
+        export_link: String,
+        erase_link: String,
+    },
+}
+
+impl DocumentKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DocumentKind::Confirmation { .. } => "confirmation",
+            DocumentKind::Issue { .. } => "issue",
+            DocumentKind::DataAccess { .. } => "data_access",
+        }
+    }
+
+    /// Link through which the recipient leaves the list the document was sent
+    /// for.
+    pub fn unsubscribe_link(&self) -> Option<&str> {
+        match self {
+            DocumentKind::Confirmation {
+                unsubscribe_link, ..
+            }
+            | DocumentKind::Issue {
+                unsubscribe_link, ..
+            } => Some(unsubscribe_link),
+            DocumentKind::DataAccess { .. } => None,
+        }
+    }
 }
@@ -0,0 +1,98 @@
+use std::time::{Duration, SystemTime};
+
+use serde_json::Value;
+use uuid::Uuid;
+
+/// A job to run in the background, by the handler registered for its kind.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewJob {
+    pub kind: String,
+    pub payload: Value,
+    /// The job is not run before `run_at`, it is run as soon as possible when
+    /// unset.
+    pub run_at: Option<SystemTime>,
+}
+
+impl NewJob {
+    pub fn new(kind: impl Into<String>, payload: Value) -> Self {
+        Self {
+            kind: kind.into(),
+            payload,
+            run_at: None,
+        }
+    }
+
+    pub fn at(mut self, run_at: SystemTime) -> Self {
+        self.run_at = Some(run_at);
+        self
+    }
+}
+
+/// A job handed to a worker.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Job {
+    pub id: Uuid,
+    pub kind: String,
+    pub payload: Value,
+    /// Starts at 1 for the first run of the job.
+    pub attempt: u32,
+    pub max_attempts: u32,
+}
+
+impl Job {
+    /// Whether a failure of this run leaves the job dead.
+    pub fn is_last_attempt(&self) -> bool {
+        self.attempt >= self.max_attempts
+    }
+}
+
+/// Exponential backoff between the attempts of a failing job.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Backoff {
+    pub base: Duration,
+    pub max: Duration,
+}
+
+impl Backoff {
+    /// Delay before retrying a job that failed its `attempt`th run.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.base.saturating_mul(factor).min(self.max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BACKOFF: Backoff = Backoff {
+        base: Duration::from_secs(10),
+        max: Duration::from_secs(60),
+    };
+
+    #[test]
+    fn backoff_doubles_the_delay_after_each_attempt() {
+        assert_eq!(BACKOFF.delay(1), Duration::from_secs(10));
+        assert_eq!(BACKOFF.delay(2), Duration::from_secs(20));
+        assert_eq!(BACKOFF.delay(3), Duration::from_secs(40));
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        assert_eq!(BACKOFF.delay(4), Duration::from_secs(60));
+        assert_eq!(BACKOFF.delay(u32::MAX), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn the_last_attempt_is_the_max_attempt() {
+        let job = Job {
+            id: Uuid::new_v4(),
+            kind: "test".into(),
+            payload: Value::Null,
+            attempt: 2,
+            max_attempts: 3,
+        };
+        assert!(!job.is_last_attempt());
+        assert!(Job { attempt: 3, ..job }.is_last_attempt());
+    }
+}
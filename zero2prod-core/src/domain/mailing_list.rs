@@ -0,0 +1,142 @@
+use std::fmt;
+
+use email_address::EmailAddress;
+use uuid::Uuid;
+
+use crate::error::{CoreError, CoreResult};
+
+/// Name of a list in URLs, made of lowercase letters, digits and dashes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListSlug(String);
+
+impl ListSlug {
+    pub fn parse(s: String) -> CoreResult<Self> {
+        match is_slug(&s) {
+            true => Ok(Self(s)),
+            false => Err(CoreError::InvalidDomain(format!("Invalid list slug {}", s))),
+        }
+    }
+}
+
+/// Whether `s` is made of lowercase words separated by dashes.
+pub(crate) fn is_slug(s: &str) -> bool {
+    !s.is_empty()
+        && s.len() <= 64
+        && !s.starts_with('-')
+        && !s.ends_with('-')
+        && s.chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+impl AsRef<str> for ListSlug {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ListSlug {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Handlebars templates replacing the default ones for the emails of a list.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ListTemplates {
+    pub confirmation_html: Option<String>,
+    pub issue_html: Option<String>,
+    pub issue_text: Option<String>,
+}
+
+/// What editors may change about a list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListSettings {
+    pub name: String,
+    /// Address the emails of the list are sent from, the deployment sender
+    /// when unset.
+    pub sender: Option<EmailAddress>,
+    /// Whether subscribers must confirm their email address, they are
+    /// subscribed right away otherwise.
+    pub require_confirmation: bool,
+    /// Seconds the confirmation links are valid for, the deployment default
+    /// when unset.
+    pub confirmation_ttl: Option<u64>,
+    pub templates: ListTemplates,
+}
+
+impl ListSettings {
+    pub fn parse(
+        name: String,
+        sender: Option<EmailAddress>,
+        require_confirmation: bool,
+        confirmation_ttl: Option<u64>,
+        templates: ListTemplates,
+    ) -> CoreResult<Self> {
+        let name = name.trim().to_string();
+        if name.is_empty() || name.chars().count() > 256 {
+            return Err(CoreError::InvalidDomain("Invalid list name length".into()));
+        }
+        if confirmation_ttl == Some(0) {
+            return Err(CoreError::InvalidDomain("Invalid confirmation ttl".into()));
+        }
+        Ok(Self {
+            name,
+            sender,
+            require_confirmation,
+            confirmation_ttl,
+            templates,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewList {
+    pub slug: ListSlug,
+    pub settings: ListSettings,
+}
+
+/// A publication, people subscribe to each list separately.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MailingList {
+    pub id: Uuid,
+    pub slug: ListSlug,
+    pub settings: ListSettings,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugs_are_lowercase_words_separated_by_dashes() {
+        for slug in ["weekly", "rust-news", "2024"] {
+            assert!(ListSlug::parse(slug.into()).is_ok(), "{}", slug);
+        }
+        for slug in ["", "Weekly", "rust news", "-weekly", "weekly-", "a/b"] {
+            assert!(ListSlug::parse(slug.into()).is_err(), "{}", slug);
+        }
+        assert!(ListSlug::parse("a".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn lists_need_a_name() {
+        let parse = |name: &str| {
+            ListSettings::parse(name.into(), None, true, None, ListTemplates::default())
+        };
+
+        assert_eq!(parse(" Weekly ").unwrap().name, "Weekly");
+        assert!(parse("  ").is_err());
+    }
+
+    #[test]
+    fn confirmation_links_cannot_expire_right_away() {
+        assert!(ListSettings::parse(
+            "Weekly".into(),
+            None,
+            true,
+            Some(0),
+            ListTemplates::default()
+        )
+        .is_err());
+    }
+}
@@ -1,7 +1,31 @@
+mod consent;
+mod data_subject;
 mod document;
+mod job;
+mod mailing_list;
 mod new_subscriber;
+mod newsletter_issue;
+mod redacted;
+mod segment;
+mod subscriber;
+mod subscriber_import;
 mod subscriber_name;
+mod subscription_token;
+mod topic;
+mod user;
 
+pub use consent::*;
+pub use data_subject::*;
 pub use document::*;
+pub use job::*;
+pub use mailing_list::*;
 pub use new_subscriber::*;
+pub use newsletter_issue::*;
+pub use redacted::*;
+pub use segment::*;
+pub use subscriber::*;
+pub use subscriber_import::*;
 pub use subscriber_name::*;
+pub use subscription_token::*;
+pub use topic::*;
+pub use user::*;
@@ -1,10 +1,12 @@
 use email_address::EmailAddress;
-use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 use super::SubscriberName;
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+/// Someone subscribing to a list.
+#[derive(Debug, PartialEq, Clone)]
 pub struct NewSubscriber {
+    pub list_id: Uuid,
     pub name: SubscriberName,
     pub email: EmailAddress,
 }
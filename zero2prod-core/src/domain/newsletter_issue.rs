@@ -0,0 +1,177 @@
+use std::{fmt, str::FromStr, time::SystemTime};
+
+use uuid::Uuid;
+
+use crate::error::{CoreError, CoreResult};
+
+use super::Segment;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueStatus {
+    Draft,
+    /// Waiting for its send time.
+    Scheduled,
+    /// Being delivered to the subscribers.
+    Sending,
+    Sent,
+}
+
+impl IssueStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IssueStatus::Draft => "draft",
+            IssueStatus::Scheduled => "scheduled",
+            IssueStatus::Sending => "sending",
+            IssueStatus::Sent => "sent",
+        }
+    }
+
+    /// Issues can be edited, rescheduled or deleted until they start being
+    /// sent.
+    pub fn is_editable(&self) -> bool {
+        matches!(self, IssueStatus::Draft | IssueStatus::Scheduled)
+    }
+}
+
+impl FromStr for IssueStatus {
+    type Err = CoreError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "draft" => Ok(IssueStatus::Draft),
+            "scheduled" => Ok(IssueStatus::Scheduled),
+            "sending" => Ok(IssueStatus::Sending),
+            "sent" => Ok(IssueStatus::Sent),
+            _ => Err(CoreError::InvalidDomain(format!(
+                "Unknown issue status {}",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for IssueStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What editors write, the same for every recipient.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IssueContent {
+    pub title: String,
+    pub text_content: String,
+    pub html_content: String,
+}
+
+impl IssueContent {
+    pub fn parse(title: String, text_content: String, html_content: String) -> CoreResult<Self> {
+        let title = title.trim().to_string();
+        if title.is_empty() || title.chars().count() > 256 {
+            return Err(CoreError::InvalidDomain("Invalid title length".into()));
+        }
+        if text_content.trim().is_empty() || html_content.trim().is_empty() {
+            return Err(CoreError::InvalidDomain("Empty content".into()));
+        }
+        Ok(Self {
+            title,
+            text_content,
+            html_content,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewsletterIssue {
+    pub id: Uuid,
+    /// The list the issue is sent to.
+    pub list_id: Uuid,
+    pub content: IssueContent,
+    pub status: IssueStatus,
+    /// When a scheduled issue is due, or when a sent one was due.
+    pub send_at: Option<SystemTime>,
+    /// The user who created the issue, unless they have been deleted since.
+    pub created_by: Option<Uuid>,
+    pub created_at: SystemTime,
+    pub sent_at: Option<SystemTime>,
+    pub audience: IssueAudience,
+}
+
+/// Which confirmed subscribers of its list an issue is sent to.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IssueAudience {
+    /// The topics the issue is about, the subscribers who opted out of all of
+    /// them are skipped. Every subscriber is sent the issue when empty.
+    pub topic_ids: Vec<Uuid>,
+    /// Only the subscribers in the segment are sent the issue when set.
+    pub segment: Option<Segment>,
+}
+
+/// A recipient of an issue being sent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delivery {
+    pub subscriber_id: Uuid,
+    pub email: String,
+    /// Starts at 1 for the first attempt to send the issue to the recipient.
+    pub attempt: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeliveryOutcome {
+    Sent,
+    /// The email could not be sent, it will be attempted again.
+    Retry(String),
+    /// The email could not be sent and will not be attempted again.
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeliveryStats {
+    pub pending: u64,
+    pub sent: u64,
+    pub failed: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statuses_round_trip() {
+        for status in [
+            IssueStatus::Draft,
+            IssueStatus::Scheduled,
+            IssueStatus::Sending,
+            IssueStatus::Sent,
+        ] {
+            assert_eq!(status.as_str().parse::<IssueStatus>(), Ok(status));
+        }
+        assert!("archived".parse::<IssueStatus>().is_err());
+    }
+
+    #[test]
+    fn only_issues_not_sent_yet_are_editable() {
+        assert!(IssueStatus::Draft.is_editable());
+        assert!(IssueStatus::Scheduled.is_editable());
+        assert!(!IssueStatus::Sending.is_editable());
+        assert!(!IssueStatus::Sent.is_editable());
+    }
+
+    #[test]
+    fn issues_need_a_title_and_content() {
+        let parse = |title: &str, text: &str, html: &str| {
+            IssueContent::parse(title.into(), text.into(), html.into())
+        };
+        assert!(parse("Issue #1", "Hello", "<p>Hello</p>").is_ok());
+        assert!(parse("  ", "Hello", "<p>Hello</p>").is_err());
+        assert!(parse(&"a".repeat(257), "Hello", "<p>Hello</p>").is_err());
+        assert!(parse("Issue #1", "", "<p>Hello</p>").is_err());
+        assert!(parse("Issue #1", "Hello", " ").is_err());
+    }
+
+    #[test]
+    fn titles_are_trimmed() {
+        let content =
+            IssueContent::parse(" Issue #1 ".into(), "Hello".into(), "<p>Hello</p>".into());
+        assert_eq!(content.unwrap().title, "Issue #1");
+    }
+}
@@ -0,0 +1,204 @@
+use std::{cell::RefCell, fmt, sync::RwLock};
+
+use hmac::{Hmac, Mac};
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+static REDACTOR: RwLock<Redactor> = RwLock::new(Redactor::Off);
+
+thread_local! {
+    static SCOPED_REDACTOR: RefCell<Option<Redactor>> = const { RefCell::new(None) };
+}
+
+/// How personal data wrapped in [`Redacted`] is rendered.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RedactionMode {
+    /// Values are rendered as is.
+    #[default]
+    Off,
+    /// Values are masked, only keeping their first character and email domain.
+    Mask,
+    /// Values are replaced by a truncated HMAC-SHA256 digest, which still
+    /// allows correlating log lines about the same person.
+    Hash,
+}
+
+/// The hash mode was selected without a key.
+#[derive(Debug, PartialEq, Eq)]
+pub struct MissingRedactionKey;
+
+impl fmt::Display for MissingRedactionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("A key is required to hash personal data")
+    }
+}
+
+impl std::error::Error for MissingRedactionKey {}
+
+/// Renders personal data according to a [`RedactionMode`].
+#[derive(Clone)]
+pub enum Redactor {
+    Off,
+    Mask,
+    /// Digests are keyed, so that a known email address cannot be looked up
+    /// in the logs without the key.
+    Hash(SecretString),
+}
+
+impl Redactor {
+    pub fn new(
+        mode: RedactionMode,
+        key: Option<SecretString>,
+    ) -> Result<Self, MissingRedactionKey> {
+        match mode {
+            RedactionMode::Off => Ok(Redactor::Off),
+            RedactionMode::Mask => Ok(Redactor::Mask),
+            RedactionMode::Hash => key.map(Redactor::Hash).ok_or(MissingRedactionKey),
+        }
+    }
+
+    pub fn redact(&self, value: &str) -> String {
+        match self {
+            Redactor::Off => value.to_owned(),
+            Redactor::Mask => mask(value),
+            Redactor::Hash(key) => hash(key, value),
+        }
+    }
+}
+
+/// Sets the redactor used by every thread of the process.
+pub fn set_redactor(redactor: Redactor) {
+    *REDACTOR.write().unwrap_or_else(|e| e.into_inner()) = redactor;
+}
+
+/// Runs `f` with `redactor` used on the current thread only, leaving the
+/// process wide redactor untouched.
+pub fn with_redactor<R>(redactor: Redactor, f: impl FnOnce() -> R) -> R {
+    let previous = SCOPED_REDACTOR.with(|scoped| scoped.replace(Some(redactor)));
+    let result = f();
+    SCOPED_REDACTOR.with(|scoped| scoped.replace(previous));
+    result
+}
+
+fn redact(value: &str) -> String {
+    SCOPED_REDACTOR
+        .with(|scoped| {
+            scoped
+                .borrow()
+                .as_ref()
+                .map(|redactor| redactor.redact(value))
+        })
+        .unwrap_or_else(|| {
+            REDACTOR
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .redact(value)
+        })
+}
+
+/// Wraps personal data so that it is redacted when formatted, according to the
+/// redactor set with [`set_redactor`].
+///
+/// Use it whenever personal data is recorded in a span or an event:
+/// `info!(subscriber_email = %Redacted(&email))`.
+pub struct Redacted<T>(pub T);
+
+fn mask(value: &str) -> String {
+    let first = value.chars().next().map(String::from).unwrap_or_default();
+    match value.rsplit_once('@') {
+        Some((_, domain)) => format!("{}***@{}", first, domain),
+        None => format!("{}***", first),
+    }
+}
+
+fn hash(key: &SecretString, value: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(key.expose_secret().as_bytes())
+        .expect("HMAC accepts keys of any size");
+    mac.update(value.as_bytes());
+    let digest = mac.finalize().into_bytes();
+    format!("hmac:{}", hex::encode(&digest[..8]))
+}
+
+impl<T: AsRef<str>> fmt::Display for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&redact(self.0.as_ref()))
+    }
+}
+
+impl<T: AsRef<str>> fmt::Debug for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Redacted({})", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn hash_redactor(key: &str) -> Redactor {
+        Redactor::new(RedactionMode::Hash, Some(key.to_owned().into())).unwrap()
+    }
+
+    #[test]
+    fn off_renders_the_raw_value() {
+        let value = Redactor::Off.redact("john.doe@gmail.com");
+        assert_eq!(value, "john.doe@gmail.com");
+    }
+
+    #[test]
+    fn mask_keeps_the_first_character_and_the_domain() {
+        let value = Redactor::Mask.redact("john.doe@gmail.com");
+        assert_eq!(value, "j***@gmail.com");
+    }
+
+    #[test]
+    fn mask_does_not_leak_the_length_of_a_name() {
+        let value = Redactor::Mask.redact("John Doe");
+        assert_eq!(value, "J***");
+    }
+
+    #[test]
+    fn hash_is_stable_and_does_not_contain_the_value() {
+        let first = hash_redactor("key").redact("john.doe@gmail.com");
+        let second = hash_redactor("key").redact("john.doe@gmail.com");
+        assert_eq!(first, second);
+        assert!(first.starts_with("hmac:"));
+        assert!(!first.contains("john"));
+    }
+
+    #[test]
+    fn hash_depends_on_the_key() {
+        let first = hash_redactor("key").redact("john.doe@gmail.com");
+        let second = hash_redactor("other key").redact("john.doe@gmail.com");
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn hash_requires_a_key() {
+        assert!(matches!(
+            Redactor::new(RedactionMode::Hash, None),
+            Err(MissingRedactionKey)
+        ));
+    }
+
+    #[test]
+    fn scoped_redactor_only_applies_within_the_closure() {
+        let value = with_redactor(Redactor::Mask, || Redacted("John Doe").to_string());
+        assert_eq!(value, "J***");
+        assert_eq!(
+            with_redactor(Redactor::Off, || Redacted("John Doe").to_string()),
+            "John Doe"
+        );
+    }
+
+    #[test]
+    fn deserialize_redaction_mode() {
+        let mode: RedactionMode = serde_json::from_str(r#""hash""#).unwrap();
+        assert_eq!(mode, RedactionMode::Hash);
+    }
+}
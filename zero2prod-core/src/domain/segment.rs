@@ -0,0 +1,479 @@
+use std::fmt;
+
+use crate::error::{CoreError, CoreResult};
+
+use super::{SubscriptionStatus, TopicSlug};
+
+const MAX_LENGTH: usize = 1024;
+const MAX_DEPTH: usize = 16;
+const MAX_DAYS: u32 = 3650;
+
+/// A day of the calendar, segments compare it with UTC timestamps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SegmentDate {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl SegmentDate {
+    /// Parses a `YYYY-MM-DD` date.
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut parts = s.split('-');
+        let (year, month, day) = (parts.next()?, parts.next()?, parts.next()?);
+        if parts.next().is_some() || year.len() != 4 || month.len() != 2 || day.len() != 2 {
+            return None;
+        }
+        let date = Self {
+            year: year.parse().ok()?,
+            month: month.parse().ok()?,
+            day: day.parse().ok()?,
+        };
+        let leap = date.year % 4 == 0 && (date.year % 100 != 0 || date.year % 400 == 0);
+        let days_in_month = match date.month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if leap => 29,
+            2 => 28,
+            _ => return None,
+        };
+        (1..=days_in_month).contains(&date.day).then_some(date)
+    }
+}
+
+impl fmt::Display for SegmentDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// What a subscriber is tested on.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Status(SubscriptionStatus),
+    /// Subscribed on the day or later.
+    SubscribedAfter(SegmentDate),
+    /// Subscribed before the day.
+    SubscribedBefore(SegmentDate),
+    /// Receives the topic of their list.
+    Topic(TopicSlug),
+    /// Opened an issue in the last `days` days.
+    OpenedWithin {
+        days: u32,
+    },
+}
+
+/// A filter over the subscribers of a list, written in a small language:
+///
+/// ```text
+/// confirmed, subscribed after 2024-01-01, topic = rust, not opened in 90 days
+/// ```
+///
+/// Conditions are `status = <status>` or a bare status, `subscribed after
+/// <date>`, `subscribed before <date>`, `topic = <slug>` and `opened in <n>
+/// days`. They are combined with `not`, `and` (or a comma), `or` and
+/// parentheses, from the tightest to the loosest. Keywords are case
+/// insensitive.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Condition(Condition),
+    Not(Box<Segment>),
+    And(Vec<Segment>),
+    Or(Vec<Segment>),
+}
+
+impl Segment {
+    pub fn parse(s: &str) -> CoreResult<Self> {
+        if s.len() > MAX_LENGTH {
+            return Err(invalid("too long"));
+        }
+        let tokens = tokenize(s)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        let segment = parser.or(0)?;
+        match parser.peek() {
+            None => Ok(segment),
+            Some(token) => Err(invalid(&format!("unexpected {}", token))),
+        }
+    }
+
+    /// The topics the segment refers to.
+    pub fn topics(&self) -> Vec<&TopicSlug> {
+        match self {
+            Segment::Condition(Condition::Topic(topic)) => vec![topic],
+            Segment::Condition(_) => vec![],
+            Segment::Not(segment) => segment.topics(),
+            Segment::And(segments) | Segment::Or(segments) => {
+                segments.iter().flat_map(Segment::topics).collect()
+            }
+        }
+    }
+}
+
+fn invalid(reason: &str) -> CoreError {
+    CoreError::InvalidDomain(format!("Invalid segment, {}", reason))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Equals,
+    Comma,
+    Open,
+    Close,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "'{}'", word),
+            Token::Equals => f.write_str("'='"),
+            Token::Comma => f.write_str("','"),
+            Token::Open => f.write_str("'('"),
+            Token::Close => f.write_str("')'"),
+        }
+    }
+}
+
+fn tokenize(s: &str) -> CoreResult<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '=' => tokens.push(Token::Equals),
+            ',' => tokens.push(Token::Comma),
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            c if c.is_whitespace() => {}
+            c if c.is_ascii_alphanumeric() => {
+                let mut word = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+            c => return Err(invalid(&format!("unexpected '{}'", c))),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> CoreResult<Token> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| invalid("unexpected end"))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    /// Consumes the next token when it is the keyword `keyword`.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword) => {
+                self.position += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> CoreResult<()> {
+        match self.keyword(keyword) {
+            true => Ok(()),
+            false => Err(invalid(&format!("expected '{}'", keyword))),
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> CoreResult<()> {
+        match self.next()? {
+            token if token == expected => Ok(()),
+            token => Err(invalid(&format!("expected {}, found {}", expected, token))),
+        }
+    }
+
+    fn word(&mut self) -> CoreResult<String> {
+        match self.next()? {
+            Token::Word(word) => Ok(word),
+            token => Err(invalid(&format!("unexpected {}", token))),
+        }
+    }
+
+    fn or(&mut self, depth: usize) -> CoreResult<Segment> {
+        let mut segments = vec![self.and(depth)?];
+        while self.keyword("or") {
+            segments.push(self.and(depth)?);
+        }
+        Ok(match segments.len() {
+            1 => segments.remove(0),
+            _ => Segment::Or(segments),
+        })
+    }
+
+    fn and(&mut self, depth: usize) -> CoreResult<Segment> {
+        let mut segments = vec![self.not(depth)?];
+        loop {
+            if self.peek() == Some(&Token::Comma) {
+                self.position += 1;
+            } else if !self.keyword("and") {
+                break;
+            }
+            segments.push(self.not(depth)?);
+        }
+        Ok(match segments.len() {
+            1 => segments.remove(0),
+            _ => Segment::And(segments),
+        })
+    }
+
+    fn not(&mut self, depth: usize) -> CoreResult<Segment> {
+        if depth > MAX_DEPTH {
+            return Err(invalid("too deeply nested"));
+        }
+        if self.keyword("not") {
+            return Ok(Segment::Not(Box::new(self.not(depth + 1)?)));
+        }
+        if self.peek() == Some(&Token::Open) {
+            self.position += 1;
+            let segment = self.or(depth + 1)?;
+            self.expect(Token::Close)?;
+            return Ok(segment);
+        }
+        self.condition().map(Segment::Condition)
+    }
+
+    fn condition(&mut self) -> CoreResult<Condition> {
+        let word = self.word()?;
+        match word.to_ascii_lowercase().as_str() {
+            "status" => {
+                self.expect(Token::Equals)?;
+                let status = self.word()?;
+                status
+                    .to_ascii_lowercase()
+                    .parse()
+                    .map(Condition::Status)
+                    .map_err(|_| invalid(&format!("unknown status '{}'", status)))
+            }
+            "pending" => Ok(Condition::Status(SubscriptionStatus::Pending)),
+            "confirmed" => Ok(Condition::Status(SubscriptionStatus::Confirmed)),
+            "unsubscribed" => Ok(Condition::Status(SubscriptionStatus::Unsubscribed)),
+            "subscribed" => {
+                let after = match self.word()?.to_ascii_lowercase().as_str() {
+                    "after" => true,
+                    "before" => false,
+                    _ => return Err(invalid("expected 'after' or 'before'")),
+                };
+                let date = self.word()?;
+                let date = SegmentDate::parse(&date)
+                    .ok_or_else(|| invalid(&format!("invalid date '{}'", date)))?;
+                Ok(match after {
+                    true => Condition::SubscribedAfter(date),
+                    false => Condition::SubscribedBefore(date),
+                })
+            }
+            "topic" => {
+                self.expect(Token::Equals)?;
+                Ok(Condition::Topic(TopicSlug::parse(self.word()?)?))
+            }
+            "opened" => {
+                self.expect_keyword("in")?;
+                let days = self.word()?;
+                let days = days
+                    .parse()
+                    .ok()
+                    .filter(|days| (1..=MAX_DAYS).contains(days))
+                    .ok_or_else(|| invalid(&format!("invalid number of days '{}'", days)))?;
+                if !self.keyword("days") {
+                    self.expect_keyword("day")?;
+                }
+                Ok(Condition::OpenedWithin { days })
+            }
+            _ => Err(invalid(&format!("unknown condition '{}'", word))),
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::Status(status) => write!(f, "status = {}", status),
+            Condition::SubscribedAfter(date) => write!(f, "subscribed after {}", date),
+            Condition::SubscribedBefore(date) => write!(f, "subscribed before {}", date),
+            Condition::Topic(topic) => write!(f, "topic = {}", topic),
+            Condition::OpenedWithin { days } => write!(f, "opened in {} days", days),
+        }
+    }
+}
+
+/// Writes the segment back in the language, parsing it again gives the same
+/// segment.
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Segment::Condition(condition) => write!(f, "{}", condition),
+            Segment::Not(segment) => {
+                f.write_str("not ")?;
+                write_operand(f, segment)
+            }
+            Segment::And(segments) => write_joined(f, segments, "and"),
+            Segment::Or(segments) => write_joined(f, segments, "or"),
+        }
+    }
+}
+
+fn write_joined(f: &mut fmt::Formatter<'_>, segments: &[Segment], operator: &str) -> fmt::Result {
+    for (i, segment) in segments.iter().enumerate() {
+        if i > 0 {
+            write!(f, " {} ", operator)?;
+        }
+        write_operand(f, segment)?;
+    }
+    Ok(())
+}
+
+/// Writes an operand of `not`, `and` or `or`, in parentheses when it is a
+/// combination itself.
+fn write_operand(f: &mut fmt::Formatter<'_>, segment: &Segment) -> fmt::Result {
+    match segment {
+        Segment::And(_) | Segment::Or(_) => write!(f, "({})", segment),
+        segment => write!(f, "{}", segment),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> SegmentDate {
+        SegmentDate { year, month, day }
+    }
+
+    fn topic(slug: &str) -> Segment {
+        Segment::Condition(Condition::Topic(TopicSlug::parse(slug.into()).unwrap()))
+    }
+
+    fn status(status: SubscriptionStatus) -> Segment {
+        Segment::Condition(Condition::Status(status))
+    }
+
+    #[test]
+    fn dates_must_exist() {
+        assert_eq!(SegmentDate::parse("2024-02-29"), Some(date(2024, 2, 29)));
+        for invalid in [
+            "2023-02-29",
+            "2024-13-01",
+            "2024-1-01",
+            "20240101",
+            "2024-01-01-01",
+        ] {
+            assert_eq!(SegmentDate::parse(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn commas_combine_conditions() {
+        assert_eq!(
+            Segment::parse(
+                "confirmed, subscribed after 2024-01-01, topic=rust, not opened in 90 days"
+            ),
+            Ok(Segment::And(vec![
+                status(SubscriptionStatus::Confirmed),
+                Segment::Condition(Condition::SubscribedAfter(date(2024, 1, 1))),
+                topic("rust"),
+                Segment::Not(Box::new(Segment::Condition(Condition::OpenedWithin {
+                    days: 90
+                }))),
+            ]))
+        );
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(
+            Segment::parse("topic = rust or topic = go and NOT status = pending"),
+            Ok(Segment::Or(vec![
+                topic("rust"),
+                Segment::And(vec![
+                    topic("go"),
+                    Segment::Not(Box::new(status(SubscriptionStatus::Pending))),
+                ]),
+            ]))
+        );
+        assert_eq!(
+            Segment::parse("(topic = rust or topic = go) and confirmed"),
+            Ok(Segment::And(vec![
+                Segment::Or(vec![topic("rust"), topic("go")]),
+                status(SubscriptionStatus::Confirmed),
+            ]))
+        );
+    }
+
+    #[test]
+    fn invalid_segments_are_rejected() {
+        for invalid in [
+            "",
+            "confirmed,",
+            "status = deleted",
+            "subscribed since 2024-01-01",
+            "subscribed after 2024-02-30",
+            "topic = Rust",
+            "opened in 0 days",
+            "opened in 90",
+            "(confirmed",
+            "confirmed)",
+            "confirmed; DROP TABLE subscriptions",
+            "clicked in 90 days",
+        ] {
+            assert!(Segment::parse(invalid).is_err(), "{}", invalid);
+        }
+        assert!(Segment::parse(&"(".repeat(20)).is_err());
+        assert!(Segment::parse(&format!("{}confirmed", "confirmed or ".repeat(100))).is_err());
+    }
+
+    #[test]
+    fn segments_are_written_back_in_the_language() {
+        for source in [
+            "confirmed, subscribed after 2024-01-01, topic=rust, not opened in 90 days",
+            "topic = rust or topic = go and not (pending or unsubscribed)",
+            "not (topic = rust and subscribed before 2023-06-01)",
+            "(topic = rust or topic = go) and opened in 1 day",
+        ] {
+            let segment = Segment::parse(source).unwrap();
+            assert_eq!(
+                Segment::parse(&segment.to_string()),
+                Ok(segment),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn segments_list_their_topics() {
+        let segment = Segment::parse("topic = rust or not (topic = go, confirmed)").unwrap();
+        let topics = segment
+            .topics()
+            .into_iter()
+            .map(|topic| topic.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(topics, vec!["rust", "go"]);
+    }
+}
@@ -0,0 +1,164 @@
+use std::{
+    fmt,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use email_address::EmailAddress;
+use uuid::Uuid;
+
+use crate::error::CoreError;
+
+use super::{SubscriberName, TopicSlug};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscriptionStatus {
+    /// Waiting for the subscriber to confirm their email address.
+    Pending,
+    Confirmed,
+    Unsubscribed,
+}
+
+impl SubscriptionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubscriptionStatus::Pending => "pending",
+            SubscriptionStatus::Confirmed => "confirmed",
+            SubscriptionStatus::Unsubscribed => "unsubscribed",
+        }
+    }
+}
+
+impl FromStr for SubscriptionStatus {
+    type Err = CoreError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(SubscriptionStatus::Pending),
+            "confirmed" => Ok(SubscriptionStatus::Confirmed),
+            "unsubscribed" => Ok(SubscriptionStatus::Unsubscribed),
+            _ => Err(CoreError::InvalidDomain(format!(
+                "Unknown subscription status {}",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for SubscriptionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subscriber {
+    /// Identifies the subscription, a person subscribed to several lists has
+    /// one per list.
+    pub id: Uuid,
+    pub list_id: Uuid,
+    pub email: EmailAddress,
+    pub name: SubscriberName,
+    pub status: SubscriptionStatus,
+}
+
+/// A subscriber as the editors see them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubscriberEntry {
+    pub subscriber: Subscriber,
+    pub subscribed_at: SystemTime,
+}
+
+/// Which subscribers to list, every criterion is optional.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SubscriberFilter {
+    pub list_id: Option<Uuid>,
+    pub status: Option<SubscriptionStatus>,
+    /// Subscribed at or after.
+    pub subscribed_after: Option<SystemTime>,
+    /// Subscribed strictly before.
+    pub subscribed_before: Option<SystemTime>,
+    /// Start of the email address or of the name, whatever the case.
+    pub search: Option<String>,
+}
+
+/// Where a listing of subscribers, newest first, resumes: right after the
+/// subscriber it was taken from.
+///
+/// Written as the microseconds since the epoch the subscriber subscribed at
+/// and their id, separated by a dot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubscriberCursor {
+    pub subscribed_at: SystemTime,
+    pub id: Uuid,
+}
+
+impl From<&SubscriberEntry> for SubscriberCursor {
+    fn from(entry: &SubscriberEntry) -> Self {
+        Self {
+            subscribed_at: entry.subscribed_at,
+            id: entry.subscriber.id,
+        }
+    }
+}
+
+impl FromStr for SubscriberCursor {
+    type Err = CoreError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || CoreError::InvalidDomain(format!("Invalid cursor {}", s));
+        let (micros, id) = s.split_once('.').ok_or_else(invalid)?;
+        let micros = micros.parse::<u64>().map_err(|_| invalid())?;
+        Ok(Self {
+            subscribed_at: UNIX_EPOCH + Duration::from_micros(micros),
+            id: id.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl fmt::Display for SubscriberCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let micros = self
+            .subscribed_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros();
+        write!(f, "{}.{}", micros, self.id.simple())
+    }
+}
+
+/// The changes an editor makes to a subscriber, the fields left out are kept.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SubscriberUpdate {
+    pub name: Option<SubscriberName>,
+    pub status: Option<SubscriptionStatus>,
+    /// The only topics the subscriber receives.
+    pub topics: Option<Vec<TopicSlug>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statuses_round_trip() {
+        for status in [
+            SubscriptionStatus::Pending,
+            SubscriptionStatus::Confirmed,
+            SubscriptionStatus::Unsubscribed,
+        ] {
+            assert_eq!(status.as_str().parse::<SubscriptionStatus>(), Ok(status));
+        }
+        assert!("deleted".parse::<SubscriptionStatus>().is_err());
+    }
+
+    #[test]
+    fn cursors_round_trip() {
+        let cursor = SubscriberCursor {
+            subscribed_at: UNIX_EPOCH + Duration::from_micros(1_700_000_000_123_456),
+            id: Uuid::from_u128(42),
+        };
+
+        assert_eq!(cursor.to_string().parse(), Ok(cursor));
+        assert!("1700000000".parse::<SubscriberCursor>().is_err());
+        assert!("yesterday.42".parse::<SubscriberCursor>().is_err());
+    }
+}
@@ -0,0 +1,374 @@
+use std::{collections::HashSet, fmt, ops::AddAssign, str::FromStr, time::SystemTime};
+
+use email_address::EmailAddress;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::CoreError;
+
+use super::{
+    ConsentAction, ConsentContext, ConsentEvent, ConsentSource, NewSubscriber, SubscriptionStatus,
+};
+
+/// How the subscribers of an import are subscribed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ImportOptions {
+    /// Subscribes them as confirmed, rather than sending them a confirmation
+    /// email.
+    pub confirmed: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportStatus {
+    Running,
+    Completed,
+    /// The import stopped before the end of the file, the rows read until
+    /// then were imported.
+    Failed,
+}
+
+impl ImportStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportStatus::Running => "running",
+            ImportStatus::Completed => "completed",
+            ImportStatus::Failed => "failed",
+        }
+    }
+}
+
+impl FromStr for ImportStatus {
+    type Err = CoreError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "running" => Ok(ImportStatus::Running),
+            "completed" => Ok(ImportStatus::Completed),
+            "failed" => Ok(ImportStatus::Failed),
+            _ => Err(CoreError::InvalidDomain(format!(
+                "Unknown import status {}",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ImportStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Counts of the rows processed by an import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ImportProgress {
+    pub rows: u64,
+    /// Rows whose subscriber was created or updated.
+    pub imported: u64,
+    pub failed: u64,
+}
+
+impl AddAssign for ImportProgress {
+    fn add_assign(&mut self, other: Self) {
+        self.rows += other.rows;
+        self.imported += other.imported;
+        self.failed += other.failed;
+    }
+}
+
+/// An import of subscribers to a list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubscriberImport {
+    pub id: Uuid,
+    pub list_id: Uuid,
+    pub options: ImportOptions,
+    pub status: ImportStatus,
+    pub progress: ImportProgress,
+    pub created_at: SystemTime,
+    pub finished_at: Option<SystemTime>,
+}
+
+/// A row of an imported file, as read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportRow {
+    /// The line of the file the row starts on.
+    pub line: u64,
+    pub name: String,
+    pub email: String,
+}
+
+/// Why a row of an import was not imported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportRowError {
+    pub line: u64,
+    pub email: String,
+    pub reason: String,
+}
+
+/// A valid row of an import.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedSubscriber {
+    pub line: u64,
+    pub subscriber: NewSubscriber,
+}
+
+/// A subscriber created or updated by an import.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpsertedSubscriber {
+    pub id: Uuid,
+    pub email: EmailAddress,
+    /// Whether the subscriber is new to the list.
+    pub created: bool,
+    /// Whether the subscriber was pending and is now confirmed.
+    pub promoted: bool,
+}
+
+/// The rows of a batch of an import, split into the valid ones and the errors
+/// of the others.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportBatch {
+    pub rows: u64,
+    pub subscribers: Vec<ImportedSubscriber>,
+    pub errors: Vec<ImportRowError>,
+    pub options: ImportOptions,
+}
+
+/// What importing a batch records, once its subscribers are upserted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportBatchOutcome {
+    pub progress: ImportProgress,
+    /// The errors of the batch by line, including the subscribers left out
+    /// of the upsert.
+    pub errors: Vec<ImportRowError>,
+    pub consents: Vec<ConsentEvent>,
+    /// The new subscribers to send their confirmation email to.
+    pub confirmations: Option<ImportConfirmations>,
+}
+
+impl ImportBatch {
+    /// The status the subscribers of the batch are upserted with.
+    pub fn status(&self) -> SubscriptionStatus {
+        if self.options.confirmed {
+            SubscriptionStatus::Confirmed
+        } else {
+            SubscriptionStatus::Pending
+        }
+    }
+
+    /// New subscribers are recorded as consenting through the import, as
+    /// well as confirming when the import subscribes them as confirmed.
+    /// Pending subscribers it confirms are recorded as confirming.
+    pub fn outcome(&self, upserted: &[UpsertedSubscriber]) -> ImportBatchOutcome {
+        // Upserts leave out the suppressed emails
+        let upserted_emails = upserted
+            .iter()
+            .map(|subscriber| subscriber.email.as_str().to_lowercase())
+            .collect::<HashSet<_>>();
+        let mut errors = self.errors.clone();
+        errors.extend(
+            self.subscribers
+                .iter()
+                .filter(|imported| {
+                    !upserted_emails.contains(&imported.subscriber.email.as_str().to_lowercase())
+                })
+                .map(|imported| ImportRowError {
+                    line: imported.line,
+                    email: imported.subscriber.email.to_string(),
+                    reason: "Email address is suppressed".into(),
+                }),
+        );
+        errors.sort_by_key(|error| error.line);
+
+        let context = ConsentContext::new(ConsentSource::Import);
+        let consents = upserted
+            .iter()
+            .flat_map(|subscriber| {
+                let actions: &[ConsentAction] = match subscriber.created {
+                    true if self.options.confirmed => {
+                        &[ConsentAction::Subscribe, ConsentAction::Confirm]
+                    }
+                    true => &[ConsentAction::Subscribe],
+                    false if subscriber.promoted => &[ConsentAction::Confirm],
+                    false => &[],
+                };
+                actions
+                    .iter()
+                    .map(|action| ConsentEvent::new(subscriber.id, *action, &context))
+            })
+            .collect();
+
+        let created = upserted
+            .iter()
+            .filter(|subscriber| subscriber.created)
+            .map(|subscriber| subscriber.id)
+            .collect::<Vec<_>>();
+        let confirmations =
+            (!self.options.confirmed && !created.is_empty()).then_some(ImportConfirmations {
+                subscriber_ids: created,
+            });
+
+        ImportBatchOutcome {
+            progress: ImportProgress {
+                rows: self.rows,
+                imported: upserted.len() as u64,
+                failed: errors.len() as u64,
+            },
+            errors,
+            consents,
+            confirmations,
+        }
+    }
+}
+
+/// Payload of the jobs sending their confirmation email to imported
+/// subscribers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportConfirmations {
+    pub subscriber_ids: Vec<Uuid>,
+}
+
+impl ImportConfirmations {
+    pub const KIND: &'static str = "send-import-confirmations";
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::SubscriberName;
+
+    use super::*;
+
+    fn imported(line: u64, email: &str) -> ImportedSubscriber {
+        ImportedSubscriber {
+            line,
+            subscriber: NewSubscriber {
+                list_id: Uuid::nil(),
+                name: SubscriberName::parse("John Doe".into()).unwrap(),
+                email: email.parse().unwrap(),
+            },
+        }
+    }
+
+    fn batch(confirmed: bool, subscribers: Vec<ImportedSubscriber>) -> ImportBatch {
+        ImportBatch {
+            rows: subscribers.len() as u64 + 1,
+            subscribers,
+            errors: vec![ImportRowError {
+                line: 3,
+                email: "not an email".into(),
+                reason: "Invalid email address".into(),
+            }],
+            options: ImportOptions { confirmed },
+        }
+    }
+
+    fn upserted(
+        imported: &ImportedSubscriber,
+        created: bool,
+        promoted: bool,
+    ) -> UpsertedSubscriber {
+        UpsertedSubscriber {
+            id: Uuid::from_u128(imported.line as u128),
+            email: imported.subscriber.email.clone(),
+            created,
+            promoted,
+        }
+    }
+
+    fn actions(outcome: &ImportBatchOutcome) -> Vec<(u128, ConsentAction)> {
+        outcome
+            .consents
+            .iter()
+            .map(|event| (event.subscriber_id.as_u128(), event.action))
+            .collect()
+    }
+
+    #[test]
+    fn suppressed_subscribers_are_reported() {
+        let batch = batch(
+            true,
+            vec![
+                imported(2, "john.doe@gmail.com"),
+                imported(4, "eve@gmail.com"),
+            ],
+        );
+
+        // Eve is suppressed
+        let outcome = batch.outcome(&[upserted(&batch.subscribers[0], true, false)]);
+
+        let lines = outcome
+            .errors
+            .iter()
+            .map(|error| error.line)
+            .collect::<Vec<_>>();
+        assert_eq!(lines, [3, 4]);
+        assert_eq!(outcome.errors[1].reason, "Email address is suppressed");
+        assert_eq!(
+            outcome.progress,
+            ImportProgress {
+                rows: 3,
+                imported: 1,
+                failed: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn confirmed_imports_record_the_confirmation_of_subscribers() {
+        let batch = batch(
+            true,
+            vec![
+                imported(2, "john.doe@gmail.com"),
+                imported(4, "jane.doe@gmail.com"),
+                imported(5, "eve@gmail.com"),
+            ],
+        );
+        let upserted = [
+            upserted(&batch.subscribers[0], true, false),
+            // Jane was pending
+            upserted(&batch.subscribers[1], false, true),
+            // Eve was already confirmed
+            upserted(&batch.subscribers[2], false, false),
+        ];
+
+        let outcome = batch.outcome(&upserted);
+
+        assert_eq!(
+            actions(&outcome),
+            [
+                (2, ConsentAction::Subscribe),
+                (2, ConsentAction::Confirm),
+                (4, ConsentAction::Confirm),
+            ]
+        );
+        assert!(outcome
+            .consents
+            .iter()
+            .all(|event| event.context.source == ConsentSource::Import));
+        assert_eq!(outcome.confirmations, None);
+    }
+
+    #[test]
+    fn new_pending_subscribers_are_sent_a_confirmation() {
+        let batch = batch(
+            false,
+            vec![
+                imported(2, "john.doe@gmail.com"),
+                imported(4, "jane.doe@gmail.com"),
+            ],
+        );
+        let upserted = [
+            upserted(&batch.subscribers[0], true, false),
+            // Jane was already subscribed
+            upserted(&batch.subscribers[1], false, false),
+        ];
+
+        let outcome = batch.outcome(&upserted);
+
+        assert_eq!(actions(&outcome), [(2, ConsentAction::Subscribe)]);
+        assert_eq!(
+            outcome.confirmations,
+            Some(ImportConfirmations {
+                subscriber_ids: vec![Uuid::from_u128(2)],
+            })
+        );
+    }
+}
@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+
+use hmac::{Hmac, Mac};
+use secrecy::{ExposeSecret, SecretString};
+use sha2::Sha256;
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// What a subscription token allows its bearer to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenPurpose {
+    Confirm,
+    Unsubscribe,
+    /// Choosing the topics received.
+    Preferences,
+    /// Reporting that an issue was opened.
+    Open,
+    /// Exporting or erasing everything stored about the owner of the email
+    /// address.
+    Access,
+}
+
+impl TokenPurpose {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TokenPurpose::Confirm => "confirm",
+            TokenPurpose::Unsubscribe => "unsubscribe",
+            TokenPurpose::Preferences => "preferences",
+            TokenPurpose::Open => "open",
+            TokenPurpose::Access => "access",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "confirm" => Some(TokenPurpose::Confirm),
+            "unsubscribe" => Some(TokenPurpose::Unsubscribe),
+            "preferences" => Some(TokenPurpose::Preferences),
+            "open" => Some(TokenPurpose::Open),
+            "access" => Some(TokenPurpose::Access),
+            _ => None,
+        }
+    }
+}
+
+/// A stateless token sent to a subscriber, the server only needs its keys to
+/// check it.
+///
+/// Tokens are formatted as `<kid>.<purpose>.<subscriber id>.<expires at>.<signature>`,
+/// where the expiry is a unix timestamp in seconds and the signature is the hex
+/// encoded HMAC-SHA256 of everything before it, computed with the key `kid`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscriptionToken {
+    pub purpose: TokenPurpose,
+    pub subscriber_id: Uuid,
+    pub expires_at: u64,
+}
+
+/// A random confirmation token, stored along with the subscriber it was issued
+/// for rather than signed. It does not expire, it is deleted with the
+/// subscriber.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredToken(String);
+
+impl StoredToken {
+    pub fn generate() -> Self {
+        Self(format!(
+            "{}{}",
+            Uuid::new_v4().simple(),
+            Uuid::new_v4().simple()
+        ))
+    }
+}
+
+impl AsRef<str> for StoredToken {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenError {
+    /// The token was not issued by us, or not for this purpose.
+    Invalid,
+    /// The token was issued by us but is no longer valid.
+    Expired(SubscriptionToken),
+}
+
+impl SubscriptionToken {
+    pub fn new(purpose: TokenPurpose, subscriber_id: Uuid, expires_at: u64) -> Self {
+        Self {
+            purpose,
+            subscriber_id,
+            expires_at,
+        }
+    }
+
+    fn payload(&self, kid: &str) -> String {
+        format!(
+            "{}.{}.{}.{}",
+            kid,
+            self.purpose.as_str(),
+            self.subscriber_id.simple(),
+            self.expires_at
+        )
+    }
+}
+
+/// Keys used to sign and verify subscription tokens.
+///
+/// Tokens are signed with the active key and verified with the key they name,
+/// so that keys can be rotated by adding a new active key while keeping the
+/// previous ones until the tokens they signed have expired.
+pub struct TokenKeys {
+    active_kid: String,
+    keys: HashMap<String, SecretString>,
+}
+
+impl TokenKeys {
+    pub fn new(active_kid: String, keys: HashMap<String, SecretString>) -> Result<Self, String> {
+        if let Some(kid) = keys.keys().find(|kid| kid.is_empty() || kid.contains('.')) {
+            return Err(format!("Invalid key id '{}'", kid));
+        }
+        if !keys.contains_key(&active_kid) {
+            return Err(format!("Unknown active key id '{}'", active_kid));
+        }
+        Ok(Self { active_kid, keys })
+    }
+
+    pub fn sign(&self, token: &SubscriptionToken) -> String {
+        let payload = token.payload(&self.active_kid);
+        let signature = self.mac(&self.keys[&self.active_kid], &payload);
+        format!(
+            "{}.{}",
+            payload,
+            hex::encode(signature.finalize().into_bytes())
+        )
+    }
+
+    /// Checks a token issued for `purpose`, tokens expired at `now` are
+    /// reported as such only when their signature is valid.
+    pub fn verify(
+        &self,
+        token: &str,
+        purpose: TokenPurpose,
+        now: u64,
+    ) -> Result<SubscriptionToken, TokenError> {
+        let (payload, signature) = token.rsplit_once('.').ok_or(TokenError::Invalid)?;
+        let (kid, _) = payload.split_once('.').ok_or(TokenError::Invalid)?;
+        let key = self.keys.get(kid).ok_or(TokenError::Invalid)?;
+        let signature = hex::decode(signature).map_err(|_| TokenError::Invalid)?;
+        self.mac(key, payload)
+            .verify_slice(&signature)
+            .map_err(|_| TokenError::Invalid)?;
+
+        let token = parse_payload(payload).ok_or(TokenError::Invalid)?;
+        if token.purpose != purpose {
+            return Err(TokenError::Invalid);
+        }
+        if token.expires_at <= now {
+            return Err(TokenError::Expired(token));
+        }
+        Ok(token)
+    }
+
+    fn mac(&self, key: &SecretString, payload: &str) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(key.expose_secret().as_bytes())
+            .expect("HMAC accepts keys of any size");
+        mac.update(payload.as_bytes());
+        mac
+    }
+}
+
+fn parse_payload(payload: &str) -> Option<SubscriptionToken> {
+    let mut parts = payload.split('.').skip(1);
+    let purpose = TokenPurpose::parse(parts.next()?)?;
+    let subscriber_id = Uuid::try_parse(parts.next()?).ok()?;
+    let expires_at = parts.next()?.parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(SubscriptionToken::new(purpose, subscriber_id, expires_at))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn keys(active_kid: &str, kids: &[(&str, &str)]) -> TokenKeys {
+        TokenKeys::new(
+            active_kid.into(),
+            kids.iter()
+                .map(|(kid, secret)| (kid.to_string(), SecretString::new(secret.to_string())))
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    fn confirmation(expires_at: u64) -> SubscriptionToken {
+        SubscriptionToken::new(TokenPurpose::Confirm, Uuid::new_v4(), expires_at)
+    }
+
+    #[test]
+    fn signed_tokens_are_valid() {
+        let keys = keys("k1", &[("k1", "secret")]);
+        let token = confirmation(NOW + 60);
+
+        assert_eq!(
+            keys.verify(&keys.sign(&token), TokenPurpose::Confirm, NOW),
+            Ok(token)
+        );
+    }
+
+    #[test]
+    fn tokens_name_their_key() {
+        let keys = keys("k1", &[("k1", "secret")]);
+        assert!(keys.sign(&confirmation(NOW)).starts_with("k1.confirm."));
+    }
+
+    #[test]
+    fn expired_tokens_are_reported_with_their_content() {
+        let keys = keys("k1", &[("k1", "secret")]);
+        let token = confirmation(NOW);
+
+        assert_eq!(
+            keys.verify(&keys.sign(&token), TokenPurpose::Confirm, NOW),
+            Err(TokenError::Expired(token))
+        );
+    }
+
+    #[test]
+    fn tokens_are_only_valid_for_their_purpose() {
+        let keys = keys("k1", &[("k1", "secret")]);
+        let token = keys.sign(&confirmation(NOW + 60));
+
+        assert_eq!(
+            keys.verify(&token, TokenPurpose::Unsubscribe, NOW),
+            Err(TokenError::Invalid)
+        );
+    }
+
+    #[test]
+    fn tampered_tokens_are_invalid() {
+        let keys = keys("k1", &[("k1", "secret")]);
+        let token = keys.sign(&confirmation(NOW + 60));
+        let tampered = token.replacen(&(NOW + 60).to_string(), &(NOW + 6000).to_string(), 1);
+
+        assert_eq!(
+            keys.verify(&tampered, TokenPurpose::Confirm, NOW),
+            Err(TokenError::Invalid)
+        );
+        assert_eq!(
+            keys.verify("garbage", TokenPurpose::Confirm, NOW),
+            Err(TokenError::Invalid)
+        );
+    }
+
+    #[test]
+    fn tokens_signed_with_a_previous_key_remain_valid_after_a_rotation() {
+        let before = keys("k1", &[("k1", "old secret")]);
+        let after = keys("k2", &[("k1", "old secret"), ("k2", "new secret")]);
+        let token = confirmation(NOW + 60);
+
+        assert_eq!(
+            after.verify(&before.sign(&token), TokenPurpose::Confirm, NOW),
+            Ok(token.clone())
+        );
+        assert!(after.sign(&token).starts_with("k2."));
+    }
+
+    #[test]
+    fn tokens_signed_with_a_retired_key_are_invalid() {
+        let before = keys("k1", &[("k1", "old secret")]);
+        let after = keys("k2", &[("k2", "new secret")]);
+
+        assert_eq!(
+            after.verify(
+                &before.sign(&confirmation(NOW + 60)),
+                TokenPurpose::Confirm,
+                NOW
+            ),
+            Err(TokenError::Invalid)
+        );
+    }
+
+    #[test]
+    fn the_active_key_must_exist() {
+        assert!(TokenKeys::new("k1".into(), HashMap::new()).is_err());
+    }
+}
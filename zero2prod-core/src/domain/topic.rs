@@ -0,0 +1,90 @@
+use std::fmt;
+
+use uuid::Uuid;
+
+use crate::error::{CoreError, CoreResult};
+
+use super::mailing_list::is_slug;
+
+/// Name of a topic, made of lowercase letters, digits and dashes like list
+/// slugs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicSlug(String);
+
+impl TopicSlug {
+    pub fn parse(s: String) -> CoreResult<Self> {
+        match is_slug(&s) {
+            true => Ok(Self(s)),
+            false => Err(CoreError::InvalidDomain(format!(
+                "Invalid topic slug {}",
+                s
+            ))),
+        }
+    }
+}
+
+impl AsRef<str> for TopicSlug {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for TopicSlug {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewTopic {
+    pub slug: TopicSlug,
+    pub name: String,
+}
+
+impl NewTopic {
+    pub fn parse(slug: String, name: String) -> CoreResult<Self> {
+        let name = name.trim().to_string();
+        if name.is_empty() || name.chars().count() > 256 {
+            return Err(CoreError::InvalidDomain("Invalid topic name length".into()));
+        }
+        Ok(Self {
+            slug: TopicSlug::parse(slug)?,
+            name,
+        })
+    }
+}
+
+/// A subject of the issues of a list, e.g. releases or blog posts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Topic {
+    pub id: Uuid,
+    pub list_id: Uuid,
+    pub slug: TopicSlug,
+    pub name: String,
+}
+
+/// Whether a subscriber receives the issues about a topic. Subscribers
+/// receive every topic until they opt out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopicPreference {
+    pub topic: Topic,
+    pub subscribed: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn topics_need_a_slug_and_a_name() {
+        assert_eq!(
+            NewTopic::parse("releases".into(), " Releases ".into()),
+            Ok(NewTopic {
+                slug: TopicSlug::parse("releases".into()).unwrap(),
+                name: "Releases".into()
+            })
+        );
+        assert!(NewTopic::parse("Releases".into(), "Releases".into()).is_err());
+        assert!(NewTopic::parse("releases".into(), " ".into()).is_err());
+    }
+}
@@ -1,10 +1,28 @@
 use std::fmt::Display;
 
-#[non_exhaustive]
 #[derive(Debug, Clone, PartialEq)]
 pub enum CoreError {
     EmailAlreadyExists,
+    /// The owner of the email address asked to be forgotten.
+    EmailSuppressed,
+    UsernameAlreadyExists,
     InvalidDomain(String),
+    SubscriberNotFound,
+    /// The token was not issued by us, or not for this purpose.
+    InvalidToken,
+    ExpiredToken,
+    ListNotFound,
+    ListAlreadyExists,
+    TopicAlreadyExists,
+    IssueNotFound,
+    /// The issue has started being sent.
+    IssueNotEditable,
+    ImportNotFound,
+    /// Too many attempts, the operation may be retried after `retry_after`
+    /// seconds.
+    RateLimited {
+        retry_after: u64,
+    },
     Unexpected(String),
 }
 
@@ -12,7 +30,21 @@ impl Display for CoreError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             CoreError::EmailAlreadyExists => write!(f, "Email already exists"),
+            CoreError::EmailSuppressed => write!(f, "Email is suppressed"),
+            CoreError::UsernameAlreadyExists => write!(f, "Username already exists"),
             CoreError::InvalidDomain(msg) => write!(f, "Invalid domain: {}", msg),
+            CoreError::SubscriberNotFound => write!(f, "Subscriber not found"),
+            CoreError::InvalidToken => write!(f, "Invalid token"),
+            CoreError::ExpiredToken => write!(f, "Expired token"),
+            CoreError::ListNotFound => write!(f, "List not found"),
+            CoreError::ListAlreadyExists => write!(f, "List already exists"),
+            CoreError::TopicAlreadyExists => write!(f, "Topic already exists"),
+            CoreError::IssueNotFound => write!(f, "Issue not found"),
+            CoreError::IssueNotEditable => write!(f, "Issue is no longer editable"),
+            CoreError::ImportNotFound => write!(f, "Import not found"),
+            CoreError::RateLimited { retry_after } => {
+                write!(f, "Rate limited, retry after {}s", retry_after)
+            }
             CoreError::Unexpected(msg) => write!(f, "Unexpected error: {}", msg),
         }
     }
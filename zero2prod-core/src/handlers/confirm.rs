@@ -0,0 +1,474 @@
+use tracing::{info, instrument};
+use uuid::Uuid;
+
+use crate::domain::{
+    ConsentAction, ConsentContext, ConsentEvent, Document, MailingList, StoredToken,
+    SubscriptionStatus, SubscriptionToken, TokenError, TokenKeys, TokenPurpose,
+};
+use crate::error::{CoreError, CoreResult};
+use crate::repository::{ConsentRepository, ListRepository, SubscriptionRepository};
+use crate::service::email_service::EmailService;
+use crate::service::rate_limiter::RateLimiter;
+
+/// Confirms the subscription a confirmation token was issued for, confirming
+/// it twice is not an error.
+#[instrument(name = "Confirmation", skip_all, fields(subscriber_id))]
+pub async fn confirm<S, C>(
+    subscriber_repo: &S,
+    consent_repo: &C,
+    keys: &TokenKeys,
+    token: &str,
+    now: u64,
+    context: &ConsentContext,
+) -> CoreResult<()>
+where
+    S: SubscriptionRepository,
+    C: ConsentRepository,
+{
+    let token = verify_confirmation(subscriber_repo, keys, token, now)
+        .await?
+        .map_err(|err| match err {
+            TokenError::Invalid => CoreError::InvalidToken,
+            TokenError::Expired(_) => CoreError::ExpiredToken,
+        })?;
+    tracing::Span::current().record("subscriber_id", token.subscriber_id.to_string());
+
+    let subscriber = subscriber_repo
+        .find_by_id(token.subscriber_id)
+        .await?
+        .ok_or(CoreError::SubscriberNotFound)?;
+
+    match subscriber.status {
+        SubscriptionStatus::Pending => {
+            info!("Confirming subscription");
+            subscriber_repo
+                .set_status(subscriber.id, SubscriptionStatus::Confirmed)
+                .await?;
+            consent_repo
+                .record(&ConsentEvent::new(
+                    subscriber.id,
+                    ConsentAction::Confirm,
+                    context,
+                ))
+                .await
+        }
+        SubscriptionStatus::Confirmed => Ok(()),
+        // The subscriber left after receiving the link
+        SubscriptionStatus::Unsubscribed => Err(CoreError::InvalidToken),
+    }
+}
+
+/// Sends a new confirmation email to the subscriber a confirmation token was
+/// issued for, the token may have expired.
+///
+/// Nothing is sent when the subscription no longer needs to be confirmed.
+#[allow(clippy::too_many_arguments)]
+#[instrument(name = "Confirmation resend", skip_all, fields(subscriber_id))]
+pub async fn resend_confirmation<S, M, E, L, D>(
+    subscriber_repo: &S,
+    list_repo: &M,
+    email_client: &E,
+    email_limiter: &L,
+    keys: &TokenKeys,
+    token: &str,
+    now: u64,
+    confirmation_email: D,
+) -> CoreResult<()>
+where
+    S: SubscriptionRepository,
+    M: ListRepository,
+    E: EmailService,
+    L: RateLimiter,
+    D: FnOnce(&MailingList, Uuid, &StoredToken) -> Document,
+{
+    let token = match verify_confirmation(subscriber_repo, keys, token, now).await? {
+        Ok(token) | Err(TokenError::Expired(token)) => token,
+        Err(TokenError::Invalid) => return Err(CoreError::InvalidToken),
+    };
+    tracing::Span::current().record("subscriber_id", token.subscriber_id.to_string());
+
+    let subscriber = subscriber_repo
+        .find_by_id(token.subscriber_id)
+        .await?
+        .ok_or(CoreError::SubscriberNotFound)?;
+    if subscriber.status != SubscriptionStatus::Pending {
+        info!("Subscription is not pending, not resending");
+        return Ok(());
+    }
+
+    email_limiter
+        .acquire(&subscriber.email.as_str().to_lowercase())
+        .await?;
+
+    let list = list_repo
+        .find_by_id(subscriber.list_id)
+        .await?
+        .ok_or(CoreError::ListNotFound)?;
+
+    info!("Resending confirmation email");
+    let email =
+        issue_confirmation(subscriber_repo, &list, subscriber.id, confirmation_email).await?;
+    email_client
+        .send_email(subscriber.email.as_ref(), email)
+        .await
+}
+
+/// Verifies a confirmation token. Tokens not signed with `keys` are looked up
+/// among the stored ones, which do not expire.
+async fn verify_confirmation<S>(
+    subscriber_repo: &S,
+    keys: &TokenKeys,
+    token: &str,
+    now: u64,
+) -> CoreResult<Result<SubscriptionToken, TokenError>>
+where
+    S: SubscriptionRepository,
+{
+    match keys.verify(token, TokenPurpose::Confirm, now) {
+        Err(TokenError::Invalid) => {}
+        verified => return Ok(verified),
+    }
+    let subscriber_id = subscriber_repo.find_by_token(token).await?;
+    Ok(subscriber_id
+        .map(|id| SubscriptionToken::new(TokenPurpose::Confirm, id, u64::MAX))
+        .ok_or(TokenError::Invalid))
+}
+
+/// Stores a new token for the subscriber and builds their confirmation email,
+/// whose link carries either this token or a signed one.
+pub(crate) async fn issue_confirmation<S, D>(
+    subscriber_repo: &S,
+    list: &MailingList,
+    subscriber_id: Uuid,
+    confirmation_email: D,
+) -> CoreResult<Document>
+where
+    S: SubscriptionRepository,
+    D: FnOnce(&MailingList, Uuid, &StoredToken) -> Document,
+{
+    let token = StoredToken::generate();
+    subscriber_repo.store_token(subscriber_id, &token).await?;
+    Ok(confirmation_email(list, subscriber_id, &token))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashMap;
+
+    use fake::faker::{internet::en::SafeEmail, name::en::Name};
+    use fake::Fake;
+    use mockall::predicate::{always, eq};
+    use secrecy::SecretString;
+
+    use crate::{
+        domain::ConsentSource,
+        domain::{
+            DocumentKind, ListSettings, ListSlug, ListTemplates, Subscriber, SubscriptionToken,
+        },
+        repository::{MockConsentRepository, MockListRepository, MockSubscriptionRepository},
+        service::{email_service::MockEmailService, rate_limiter::MockRateLimiter},
+    };
+
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn keys() -> TokenKeys {
+        TokenKeys::new(
+            "k1".into(),
+            HashMap::from([("k1".to_owned(), SecretString::new("secret".into()))]),
+        )
+        .unwrap()
+    }
+
+    fn subscriber(status: SubscriptionStatus) -> Subscriber {
+        Subscriber {
+            id: Uuid::new_v4(),
+            list_id: Uuid::new_v4(),
+            email: SafeEmail().fake::<String>().parse().unwrap(),
+            name: Name().fake::<String>().parse().unwrap(),
+            status,
+        }
+    }
+
+    fn confirmation_token(subscriber: &Subscriber, expires_at: u64) -> String {
+        keys().sign(&SubscriptionToken::new(
+            TokenPurpose::Confirm,
+            subscriber.id,
+            expires_at,
+        ))
+    }
+
+    fn context() -> ConsentContext {
+        ConsentContext::new(ConsentSource::Form)
+    }
+
+    fn repo_with(subscriber: &Subscriber) -> MockSubscriptionRepository {
+        let mut mock_repo = MockSubscriptionRepository::new();
+        let found = subscriber.clone();
+        mock_repo
+            .expect_find_by_id()
+            .with(eq(subscriber.id))
+            .returning(move |_| Ok(Some(found.clone())));
+        mock_repo
+    }
+
+    fn list() -> MailingList {
+        MailingList {
+            id: Uuid::from_u128(1),
+            slug: ListSlug::parse("weekly".into()).unwrap(),
+            settings: ListSettings::parse(
+                "Weekly".into(),
+                Some("weekly@example.com".parse().unwrap()),
+                true,
+                None,
+                ListTemplates::default(),
+            )
+            .unwrap(),
+        }
+    }
+
+    fn lists() -> MockListRepository {
+        let mut mock_lists = MockListRepository::new();
+        mock_lists
+            .expect_find_by_id()
+            .returning(|_| Ok(Some(list())));
+        mock_lists
+    }
+
+    fn confirmation_email(list: &MailingList, subscriber_id: Uuid, _: &StoredToken) -> Document {
+        Document::new(
+            "Welcome !".into(),
+            DocumentKind::Confirmation {
+                confirmation_link: format!("https://my.link.com/{}", subscriber_id),
+                unsubscribe_link: "https://my.link.com/unsubscribe".to_owned(),
+            },
+        )
+        .for_list(list)
+    }
+
+    #[test]
+    fn confirm_nominal_case() {
+        let subscriber = subscriber(SubscriptionStatus::Pending);
+        let token = confirmation_token(&subscriber, NOW + 60);
+
+        let mut mock_repo = repo_with(&subscriber);
+        mock_repo
+            .expect_set_status()
+            .times(1)
+            .with(eq(subscriber.id), eq(SubscriptionStatus::Confirmed))
+            .returning(|_, _| Ok(()));
+        let mut consents = MockConsentRepository::new();
+        consents
+            .expect_record()
+            .times(1)
+            .with(eq(ConsentEvent::new(
+                subscriber.id,
+                ConsentAction::Confirm,
+                &context(),
+            )))
+            .returning(|_| Ok(()));
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                confirm(&mock_repo, &consents, &keys(), &token, NOW, &context()).await,
+                Ok(())
+            );
+        })
+    }
+
+    #[test]
+    fn confirm_is_idempotent() {
+        let subscriber = subscriber(SubscriptionStatus::Confirmed);
+        let token = confirmation_token(&subscriber, NOW + 60);
+
+        let mut mock_repo = repo_with(&subscriber);
+        mock_repo.expect_set_status().times(0);
+        let mut consents = MockConsentRepository::new();
+        consents.expect_record().times(0);
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                confirm(&mock_repo, &consents, &keys(), &token, NOW, &context()).await,
+                Ok(())
+            );
+        })
+    }
+
+    #[test]
+    fn confirm_with_an_expired_token() {
+        let subscriber = subscriber(SubscriptionStatus::Pending);
+        let token = confirmation_token(&subscriber, NOW);
+
+        let mut mock_repo = MockSubscriptionRepository::new();
+        mock_repo.expect_find_by_id().times(0);
+        let consents = MockConsentRepository::new();
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                confirm(&mock_repo, &consents, &keys(), &token, NOW, &context()).await,
+                Err(CoreError::ExpiredToken)
+            );
+        })
+    }
+
+    #[test]
+    fn confirm_does_not_resubscribe() {
+        let subscriber = subscriber(SubscriptionStatus::Unsubscribed);
+        let token = confirmation_token(&subscriber, NOW + 60);
+
+        let mut mock_repo = repo_with(&subscriber);
+        mock_repo.expect_set_status().times(0);
+        let consents = MockConsentRepository::new();
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                confirm(&mock_repo, &consents, &keys(), &token, NOW, &context()).await,
+                Err(CoreError::InvalidToken)
+            );
+        })
+    }
+
+    #[test]
+    fn confirm_with_a_stored_token() {
+        let subscriber = subscriber(SubscriptionStatus::Pending);
+
+        let mut mock_repo = repo_with(&subscriber);
+        let subscriber_id = subscriber.id;
+        mock_repo
+            .expect_find_by_token()
+            .with(eq("stored"))
+            .returning(move |_| Ok(Some(subscriber_id)));
+        mock_repo
+            .expect_set_status()
+            .times(1)
+            .with(eq(subscriber.id), eq(SubscriptionStatus::Confirmed))
+            .returning(|_, _| Ok(()));
+        let mut consents = MockConsentRepository::new();
+        consents.expect_record().times(1).returning(|_| Ok(()));
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                confirm(&mock_repo, &consents, &keys(), "stored", NOW, &context()).await,
+                Ok(())
+            );
+        })
+    }
+
+    #[test]
+    fn confirm_with_an_unknown_token() {
+        let mut mock_repo = MockSubscriptionRepository::new();
+        mock_repo.expect_find_by_token().returning(|_| Ok(None));
+        mock_repo.expect_find_by_id().times(0);
+        let consents = MockConsentRepository::new();
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                confirm(&mock_repo, &consents, &keys(), "unknown", NOW, &context()).await,
+                Err(CoreError::InvalidToken)
+            );
+        })
+    }
+
+    #[test]
+    fn resend_confirmation_accepts_expired_tokens() {
+        let subscriber = subscriber(SubscriptionStatus::Pending);
+        let token = confirmation_token(&subscriber, NOW - 3600);
+        let recipient = subscriber.email.as_str().to_owned();
+
+        let mut mock_repo = repo_with(&subscriber);
+        mock_repo
+            .expect_store_token()
+            .times(1)
+            .with(eq(subscriber.id), always())
+            .returning(|_, _| Ok(()));
+        let mut mock_limiter = MockRateLimiter::new();
+        mock_limiter.expect_acquire().times(1).returning(|_| Ok(()));
+        let mut mock_email_service = MockEmailService::new();
+        mock_email_service
+            .expect_send_email()
+            .times(1)
+            .with(
+                eq(recipient),
+                eq(confirmation_email(
+                    &list(),
+                    subscriber.id,
+                    &StoredToken::generate(),
+                )),
+            )
+            .returning(|_, _| Ok(()));
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                resend_confirmation(
+                    &mock_repo,
+                    &lists(),
+                    &mock_email_service,
+                    &mock_limiter,
+                    &keys(),
+                    &token,
+                    NOW,
+                    confirmation_email
+                )
+                .await,
+                Ok(())
+            );
+        })
+    }
+
+    #[test]
+    fn resend_confirmation_skips_confirmed_subscribers() {
+        let subscriber = subscriber(SubscriptionStatus::Confirmed);
+        let token = confirmation_token(&subscriber, NOW - 3600);
+
+        let mock_repo = repo_with(&subscriber);
+        let mut mock_limiter = MockRateLimiter::new();
+        mock_limiter.expect_acquire().times(0);
+        let mut mock_email_service = MockEmailService::new();
+        mock_email_service.expect_send_email().times(0);
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                resend_confirmation(
+                    &mock_repo,
+                    &lists(),
+                    &mock_email_service,
+                    &mock_limiter,
+                    &keys(),
+                    &token,
+                    NOW,
+                    confirmation_email
+                )
+                .await,
+                Ok(())
+            );
+        })
+    }
+
+    #[test]
+    fn resend_confirmation_rejects_forged_tokens() {
+        let mut mock_repo = MockSubscriptionRepository::new();
+        mock_repo.expect_find_by_token().returning(|_| Ok(None));
+        mock_repo.expect_find_by_id().times(0);
+        let mock_limiter = MockRateLimiter::new();
+        let mock_email_service = MockEmailService::new();
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                resend_confirmation(
+                    &mock_repo,
+                    &lists(),
+                    &mock_email_service,
+                    &mock_limiter,
+                    &keys(),
+                    "k1.confirm.forged",
+                    NOW,
+                    confirmation_email
+                )
+                .await,
+                Err(CoreError::InvalidToken)
+            );
+        })
+    }
+}
@@ -0,0 +1,278 @@
+use email_address::EmailAddress;
+use tracing::{info, instrument};
+use uuid::Uuid;
+
+use crate::domain::{
+    Document, EmailHash, Redacted, SubjectData, Subscriber, TokenError, TokenKeys, TokenPurpose,
+};
+use crate::error::{CoreError, CoreResult};
+use crate::repository::{DataSubjectRepository, SubscriptionRepository};
+use crate::service::email_service::EmailService;
+use crate::service::rate_limiter::RateLimiter;
+
+/// Emails the owner of an address the links to export or erase their data.
+///
+/// Nothing is sent to addresses subscribed to no list, without telling the
+/// caller, so that the endpoint does not reveal who is subscribed.
+#[instrument(name = "Data access request", skip_all, fields(email = %Redacted(&email)))]
+pub async fn request_data_access<S, E, L, D>(
+    subscriber_repo: &S,
+    email_client: &E,
+    email_limiter: &L,
+    email: EmailAddress,
+    access_email: D,
+) -> CoreResult<()>
+where
+    S: SubscriptionRepository,
+    E: EmailService,
+    L: RateLimiter,
+    D: FnOnce(Uuid) -> Document,
+{
+    email_limiter
+        .acquire(&email.as_str().to_lowercase())
+        .await?;
+
+    let subscriptions = subscriber_repo.find_by_email(&email).await?;
+    let Some(subscriber) = subscriptions.first() else {
+        info!("Email is not subscribed, not sending");
+        return Ok(());
+    };
+
+    info!("Sending data access email");
+    email_client
+        .send_email(subscriber.email.as_ref(), access_email(subscriber.id))
+        .await
+}
+
+/// Everything stored about the bearer of an access token.
+#[instrument(name = "Data export", skip_all, fields(subscriber_id))]
+pub async fn export_data<S, P>(
+    subscriber_repo: &S,
+    subject_repo: &P,
+    keys: &TokenKeys,
+    token: &str,
+    now: u64,
+) -> CoreResult<SubjectData>
+where
+    S: SubscriptionRepository,
+    P: DataSubjectRepository,
+{
+    let subscriber = data_subject(subscriber_repo, keys, token, now).await?;
+    info!("Exporting data");
+    subject_repo.export(&subscriber.email).await
+}
+
+/// Forgets the bearer of an access token, their email address is suppressed
+/// so that it cannot be subscribed again.
+#[instrument(name = "Data erasure", skip_all, fields(subscriber_id))]
+pub async fn erase_data<S, P>(
+    subscriber_repo: &S,
+    subject_repo: &P,
+    keys: &TokenKeys,
+    token: &str,
+    now: u64,
+) -> CoreResult<()>
+where
+    S: SubscriptionRepository,
+    P: DataSubjectRepository,
+{
+    let subscriber = data_subject(subscriber_repo, keys, token, now).await?;
+    let erased = subject_repo
+        .erase(&subscriber.email, &EmailHash::of(&subscriber.email))
+        .await?;
+    info!(erased, "Erased data");
+    Ok(())
+}
+
+/// The subscriber an access token was issued for.
+async fn data_subject<S>(
+    subscriber_repo: &S,
+    keys: &TokenKeys,
+    token: &str,
+    now: u64,
+) -> CoreResult<Subscriber>
+where
+    S: SubscriptionRepository,
+{
+    let token = keys
+        .verify(token, TokenPurpose::Access, now)
+        .map_err(|err| match err {
+            TokenError::Invalid => CoreError::InvalidToken,
+            TokenError::Expired(_) => CoreError::ExpiredToken,
+        })?;
+    tracing::Span::current().record("subscriber_id", token.subscriber_id.to_string());
+
+    // Erased subscribers are no longer found
+    subscriber_repo
+        .find_by_id(token.subscriber_id)
+        .await?
+        .ok_or(CoreError::SubscriberNotFound)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashMap;
+
+    use mockall::predicate::eq;
+    use secrecy::SecretString;
+
+    use crate::{
+        domain::{DocumentKind, SubscriptionStatus, SubscriptionToken},
+        repository::{MockDataSubjectRepository, MockSubscriptionRepository},
+        service::{email_service::MockEmailService, rate_limiter::MockRateLimiter},
+    };
+
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn keys() -> TokenKeys {
+        TokenKeys::new(
+            "k1".into(),
+            HashMap::from([("k1".to_owned(), SecretString::new("secret".into()))]),
+        )
+        .unwrap()
+    }
+
+    fn subscriber() -> Subscriber {
+        Subscriber {
+            id: Uuid::from_u128(7),
+            list_id: Uuid::from_u128(1),
+            email: "John.Doe@gmail.com".parse().unwrap(),
+            name: "John Doe".parse().unwrap(),
+            status: SubscriptionStatus::Confirmed,
+        }
+    }
+
+    fn token(purpose: TokenPurpose) -> String {
+        keys().sign(&SubscriptionToken::new(purpose, subscriber().id, NOW + 60))
+    }
+
+    fn repo_with_subscriber() -> MockSubscriptionRepository {
+        let mut mock_repo = MockSubscriptionRepository::new();
+        mock_repo
+            .expect_find_by_id()
+            .with(eq(subscriber().id))
+            .returning(|_| Ok(Some(subscriber())));
+        mock_repo
+    }
+
+    fn access_email(subscriber_id: Uuid) -> Document {
+        Document::new(
+            "Your data".into(),
+            DocumentKind::DataAccess {
+                export_link: format!("https://my.link.com/export/{}", subscriber_id),
+                erase_link: format!("https://my.link.com/erase/{}", subscriber_id),
+            },
+        )
+    }
+
+    fn limiter() -> MockRateLimiter {
+        let mut mock_limiter = MockRateLimiter::new();
+        mock_limiter.expect_acquire().returning(|_| Ok(()));
+        mock_limiter
+    }
+
+    #[test]
+    fn request_data_access_emails_subscribers() {
+        let mut mock_repo = MockSubscriptionRepository::new();
+        mock_repo
+            .expect_find_by_email()
+            .returning(|_| Ok(vec![subscriber()]));
+        let mut mock_email = MockEmailService::new();
+        mock_email
+            .expect_send_email()
+            .times(1)
+            .with(eq("John.Doe@gmail.com"), eq(access_email(subscriber().id)))
+            .returning(|_, _| Ok(()));
+
+        tokio_test::block_on(async {
+            let result = request_data_access(
+                &mock_repo,
+                &mock_email,
+                &limiter(),
+                "john.doe@gmail.com".parse().unwrap(),
+                access_email,
+            )
+            .await;
+            assert_eq!(result, Ok(()));
+        })
+    }
+
+    #[test]
+    fn request_data_access_does_not_reveal_unknown_emails() {
+        let mut mock_repo = MockSubscriptionRepository::new();
+        mock_repo.expect_find_by_email().returning(|_| Ok(vec![]));
+        let mut mock_email = MockEmailService::new();
+        mock_email.expect_send_email().times(0);
+
+        tokio_test::block_on(async {
+            let result = request_data_access(
+                &mock_repo,
+                &mock_email,
+                &limiter(),
+                "jane.doe@gmail.com".parse().unwrap(),
+                access_email,
+            )
+            .await;
+            assert_eq!(result, Ok(()));
+        })
+    }
+
+    #[test]
+    fn erase_data_suppresses_the_email() {
+        let email = subscriber().email;
+        let mut mock_subjects = MockDataSubjectRepository::new();
+        mock_subjects
+            .expect_erase()
+            .times(1)
+            .with(eq(email.clone()), eq(EmailHash::of(&email)))
+            .returning(|_, _| Ok(1));
+
+        tokio_test::block_on(async {
+            let result = erase_data(
+                &repo_with_subscriber(),
+                &mock_subjects,
+                &keys(),
+                &token(TokenPurpose::Access),
+                NOW,
+            )
+            .await;
+            assert_eq!(result, Ok(()));
+        })
+    }
+
+    #[test]
+    fn data_subjects_need_an_access_token() {
+        let mut mock_subjects = MockDataSubjectRepository::new();
+        mock_subjects.expect_export().times(0);
+        mock_subjects.expect_erase().times(0);
+
+        tokio_test::block_on(async {
+            let token = token(TokenPurpose::Unsubscribe);
+            assert_eq!(
+                export_data(
+                    &repo_with_subscriber(),
+                    &mock_subjects,
+                    &keys(),
+                    &token,
+                    NOW
+                )
+                .await,
+                Err(CoreError::InvalidToken)
+            );
+            assert_eq!(
+                erase_data(
+                    &repo_with_subscriber(),
+                    &mock_subjects,
+                    &keys(),
+                    &token,
+                    NOW
+                )
+                .await,
+                Err(CoreError::InvalidToken)
+            );
+        })
+    }
+}
@@ -0,0 +1,61 @@
+use std::time::Duration;
+
+use tracing::{info, instrument};
+
+use crate::error::CoreResult;
+use crate::repository::SubscriptionRepository;
+
+/// Forgets the subscribers who never confirmed their subscription within
+/// `ttl`, returns how many were forgotten.
+#[instrument(name = "Pending subscriptions expiry", skip(subscriber_repo))]
+pub async fn expire_pending_subscriptions<S>(subscriber_repo: &S, ttl: Duration) -> CoreResult<u64>
+where
+    S: SubscriptionRepository,
+{
+    let expired = subscriber_repo.delete_stale_pending(ttl).await?;
+    if expired > 0 {
+        info!(expired, "Expired pending subscriptions");
+    }
+    Ok(expired)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use mockall::predicate::eq;
+
+    use crate::{error::CoreError, repository::MockSubscriptionRepository};
+
+    use super::*;
+
+    #[test]
+    fn expire_pending_subscriptions_nominal_case() {
+        let ttl = Duration::from_secs(3600);
+
+        let mut mock_repo = MockSubscriptionRepository::new();
+        mock_repo
+            .expect_delete_stale_pending()
+            .times(1)
+            .with(eq(ttl))
+            .returning(|_| Ok(2));
+
+        tokio_test::block_on(async {
+            assert_eq!(expire_pending_subscriptions(&mock_repo, ttl).await, Ok(2));
+        })
+    }
+
+    #[test]
+    fn expire_pending_subscriptions_when_there_is_a_database_error() {
+        let mut mock_repo = MockSubscriptionRepository::new();
+        mock_repo
+            .expect_delete_stale_pending()
+            .returning(|_| Err(CoreError::Unexpected("connection lost".into())));
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                expire_pending_subscriptions(&mock_repo, Duration::from_secs(60)).await,
+                Err(CoreError::Unexpected("connection lost".into()))
+            );
+        })
+    }
+}
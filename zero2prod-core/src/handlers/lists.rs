@@ -0,0 +1,94 @@
+use tracing::{info, instrument};
+use uuid::Uuid;
+
+use crate::domain::{ListSettings, ListSlug, NewList};
+use crate::error::{CoreError, CoreResult};
+use crate::repository::ListRepository;
+
+#[instrument(name = "List creation", skip_all, fields(list = %new_list.slug))]
+pub async fn create_list<R>(list_repo: &R, new_list: NewList) -> CoreResult<Uuid>
+where
+    R: ListRepository,
+{
+    let list_id = list_repo.create(&new_list).await?;
+    info!(%list_id, "Created list");
+    Ok(list_id)
+}
+
+#[instrument(name = "List update", skip(list_repo, settings), fields(list = %slug))]
+pub async fn update_list<R>(
+    list_repo: &R,
+    slug: &ListSlug,
+    settings: ListSettings,
+) -> CoreResult<()>
+where
+    R: ListRepository,
+{
+    let list = list_repo
+        .find_by_slug(slug)
+        .await?
+        .ok_or(CoreError::ListNotFound)?;
+    list_repo.update(list.id, &settings).await
+}
+
+#[cfg(test)]
+mod tests {
+    use mockall::predicate::eq;
+
+    use crate::{
+        domain::{ListTemplates, MailingList},
+        repository::MockListRepository,
+    };
+
+    use super::*;
+
+    fn slug() -> ListSlug {
+        ListSlug::parse("weekly".into()).unwrap()
+    }
+
+    fn settings(name: &str) -> ListSettings {
+        ListSettings::parse(name.into(), None, true, None, ListTemplates::default()).unwrap()
+    }
+
+    #[test]
+    fn update_list_changes_the_settings() {
+        let list = MailingList {
+            id: Uuid::new_v4(),
+            slug: slug(),
+            settings: settings("Weekly"),
+        };
+        let list_id = list.id;
+
+        let mut mock_repo = MockListRepository::new();
+        mock_repo
+            .expect_find_by_slug()
+            .with(eq(slug()))
+            .returning(move |_| Ok(Some(list.clone())));
+        mock_repo
+            .expect_update()
+            .times(1)
+            .with(eq(list_id), eq(settings("Monthly")))
+            .returning(|_, _| Ok(()));
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                update_list(&mock_repo, &slug(), settings("Monthly")).await,
+                Ok(())
+            );
+        })
+    }
+
+    #[test]
+    fn unknown_lists_are_not_found() {
+        let mut mock_repo = MockListRepository::new();
+        mock_repo.expect_find_by_slug().returning(|_| Ok(None));
+        mock_repo.expect_update().times(0);
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                update_list(&mock_repo, &slug(), settings("Monthly")).await,
+                Err(CoreError::ListNotFound)
+            );
+        })
+    }
+}
@@ -1,3 +1,27 @@
+mod confirm;
+mod data_subject;
+mod expire_pending;
+mod lists;
+mod newsletter_issues;
+mod preferences;
+mod segments;
+mod send_issues;
 mod subscribe;
+mod subscriber_import;
+mod subscribers;
+mod topics;
+mod unsubscribe;
 
+pub use confirm::*;
+pub use data_subject::*;
+pub use expire_pending::*;
+pub use lists::*;
+pub use newsletter_issues::*;
+pub use preferences::*;
+pub use segments::*;
+pub use send_issues::*;
 pub use subscribe::*;
+pub use subscriber_import::*;
+pub use subscribers::*;
+pub use topics::*;
+pub use unsubscribe::*;
@@ -0,0 +1,380 @@
+use std::time::SystemTime;
+
+use tracing::{info, instrument};
+use uuid::Uuid;
+
+use crate::domain::{
+    IssueAudience, IssueContent, NewsletterIssue, Segment, TokenError, TokenKeys, TokenPurpose,
+    TopicSlug,
+};
+use crate::error::{CoreError, CoreResult};
+use crate::repository::{NewsletterIssueRepository, TopicRepository};
+
+use super::topics::{resolve_topics, validate_segment};
+
+/// Creates a draft issue of a list. Issues about `topics` are only sent to
+/// the subscribers of one of them, the others to every subscriber, and
+/// issues with a `segment` only to the subscribers in the segment.
+#[instrument(name = "Issue creation", skip(issue_repo, topic_repo, content))]
+pub async fn create_issue<R, T>(
+    issue_repo: &R,
+    topic_repo: &T,
+    list_id: Uuid,
+    content: IssueContent,
+    topics: &[TopicSlug],
+    segment: Option<Segment>,
+    created_by: Uuid,
+) -> CoreResult<Uuid>
+where
+    R: NewsletterIssueRepository,
+    T: TopicRepository,
+{
+    let audience = audience(topic_repo, list_id, topics, segment).await?;
+    let issue_id = issue_repo
+        .create(list_id, &content, &audience, created_by)
+        .await?;
+    info!(%issue_id, "Created draft issue");
+    Ok(issue_id)
+}
+
+#[instrument(name = "Issue update", skip(issue_repo, topic_repo, content))]
+pub async fn update_issue<R, T>(
+    issue_repo: &R,
+    topic_repo: &T,
+    issue_id: Uuid,
+    content: IssueContent,
+    topics: &[TopicSlug],
+    segment: Option<Segment>,
+) -> CoreResult<()>
+where
+    R: NewsletterIssueRepository,
+    T: TopicRepository,
+{
+    let issue = editable_issue(issue_repo, issue_id).await?;
+    let audience = audience(topic_repo, issue.list_id, topics, segment).await?;
+    issue_repo.update(issue_id, &content, &audience).await
+}
+
+/// Sends the issue right away, that is on the next run of the scheduler.
+/// The issue is only sent to `segment` when there is one, instead of the
+/// segment it was written for.
+#[instrument(name = "Issue publication", skip(issue_repo, topic_repo))]
+pub async fn publish_issue<R, T>(
+    issue_repo: &R,
+    topic_repo: &T,
+    issue_id: Uuid,
+    segment: Option<Segment>,
+    now: SystemTime,
+) -> CoreResult<()>
+where
+    R: NewsletterIssueRepository,
+    T: TopicRepository,
+{
+    let issue = editable_issue(issue_repo, issue_id).await?;
+    if let Some(segment) = segment {
+        validate_segment(topic_repo, issue.list_id, &segment).await?;
+        let audience = IssueAudience {
+            segment: Some(segment),
+            ..issue.audience
+        };
+        issue_repo
+            .update(issue_id, &issue.content, &audience)
+            .await?;
+    }
+    info!("Publishing issue");
+    issue_repo.schedule(issue_id, Some(now)).await
+}
+
+/// Records that the bearer of an open token opened the issue.
+#[instrument(name = "Issue open", skip(issue_repo, keys, token))]
+pub async fn record_open<R>(
+    issue_repo: &R,
+    keys: &TokenKeys,
+    token: &str,
+    issue_id: Uuid,
+    now: u64,
+) -> CoreResult<()>
+where
+    R: NewsletterIssueRepository,
+{
+    let token = keys
+        .verify(token, TokenPurpose::Open, now)
+        .map_err(|err| match err {
+            TokenError::Invalid => CoreError::InvalidToken,
+            TokenError::Expired(_) => CoreError::ExpiredToken,
+        })?;
+    issue_repo.record_open(issue_id, token.subscriber_id).await
+}
+
+async fn audience<T>(
+    topic_repo: &T,
+    list_id: Uuid,
+    topics: &[TopicSlug],
+    segment: Option<Segment>,
+) -> CoreResult<IssueAudience>
+where
+    T: TopicRepository,
+{
+    if let Some(segment) = &segment {
+        validate_segment(topic_repo, list_id, segment).await?;
+    }
+    Ok(IssueAudience {
+        topic_ids: resolve_topics(topic_repo, list_id, topics).await?,
+        segment,
+    })
+}
+
+#[instrument(name = "Issue deletion", skip(issue_repo))]
+pub async fn delete_issue<R>(issue_repo: &R, issue_id: Uuid) -> CoreResult<()>
+where
+    R: NewsletterIssueRepository,
+{
+    editable_issue(issue_repo, issue_id).await?;
+    issue_repo.delete(issue_id).await
+}
+
+/// Schedules an issue to be sent at `send_at`, an issue due in the past is
+/// sent right away.
+#[instrument(name = "Issue scheduling", skip(issue_repo))]
+pub async fn schedule_issue<R>(
+    issue_repo: &R,
+    issue_id: Uuid,
+    send_at: SystemTime,
+) -> CoreResult<()>
+where
+    R: NewsletterIssueRepository,
+{
+    editable_issue(issue_repo, issue_id).await?;
+    info!("Scheduling issue");
+    issue_repo.schedule(issue_id, Some(send_at)).await
+}
+
+/// Makes a scheduled issue a draft again.
+#[instrument(name = "Issue unscheduling", skip(issue_repo))]
+pub async fn unschedule_issue<R>(issue_repo: &R, issue_id: Uuid) -> CoreResult<()>
+where
+    R: NewsletterIssueRepository,
+{
+    editable_issue(issue_repo, issue_id).await?;
+    issue_repo.schedule(issue_id, None).await
+}
+
+async fn editable_issue<R>(issue_repo: &R, issue_id: Uuid) -> CoreResult<NewsletterIssue>
+where
+    R: NewsletterIssueRepository,
+{
+    let issue = issue_repo
+        .find_by_id(issue_id)
+        .await?
+        .ok_or(CoreError::IssueNotFound)?;
+    match issue.status.is_editable() {
+        true => Ok(issue),
+        false => Err(CoreError::IssueNotEditable),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use mockall::predicate::eq;
+
+    use crate::{
+        domain::{IssueStatus, Topic},
+        repository::{MockNewsletterIssueRepository, MockTopicRepository},
+    };
+
+    use super::*;
+
+    fn content() -> IssueContent {
+        IssueContent::parse("Issue #1".into(), "Hello".into(), "<p>Hello</p>".into()).unwrap()
+    }
+
+    fn issue(id: Uuid, status: IssueStatus) -> NewsletterIssue {
+        NewsletterIssue {
+            id,
+            list_id: Uuid::nil(),
+            content: content(),
+            status,
+            send_at: None,
+            created_by: None,
+            created_at: SystemTime::UNIX_EPOCH,
+            sent_at: None,
+            audience: IssueAudience::default(),
+        }
+    }
+
+    fn repo_with(id: Uuid, status: IssueStatus) -> MockNewsletterIssueRepository {
+        let mut mock_repo = MockNewsletterIssueRepository::new();
+        mock_repo
+            .expect_find_by_id()
+            .with(eq(id))
+            .returning(move |id| Ok(Some(issue(id, status))));
+        mock_repo
+    }
+
+    #[test]
+    fn create_issue_creates_a_draft() {
+        let id = Uuid::new_v4();
+        let list_id = Uuid::new_v4();
+        let editor = Uuid::new_v4();
+        let mut mock_repo = MockNewsletterIssueRepository::new();
+        mock_repo
+            .expect_create()
+            .times(1)
+            .with(
+                eq(list_id),
+                eq(content()),
+                eq(IssueAudience::default()),
+                eq(editor),
+            )
+            .returning(move |_, _, _, _| Ok(id));
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                create_issue(
+                    &mock_repo,
+                    &MockTopicRepository::new(),
+                    list_id,
+                    content(),
+                    &[],
+                    None,
+                    editor
+                )
+                .await,
+                Ok(id)
+            );
+        })
+    }
+
+    #[test]
+    fn create_issue_targets_topics_of_the_list() {
+        let list_id = Uuid::new_v4();
+        let releases = Topic {
+            id: Uuid::new_v4(),
+            list_id,
+            slug: TopicSlug::parse("releases".into()).unwrap(),
+            name: "Releases".into(),
+        };
+        let releases_id = releases.id;
+        let mut mock_topics = MockTopicRepository::new();
+        mock_topics
+            .expect_list()
+            .with(eq(list_id))
+            .returning(move |_| Ok(vec![releases.clone()]));
+        let mut mock_repo = MockNewsletterIssueRepository::new();
+        mock_repo
+            .expect_create()
+            .times(1)
+            .withf(move |_, _, audience, _| audience.topic_ids == [releases_id])
+            .returning(|_, _, _, _| Ok(Uuid::new_v4()));
+
+        tokio_test::block_on(async {
+            assert!(create_issue(
+                &mock_repo,
+                &mock_topics,
+                list_id,
+                content(),
+                &[TopicSlug::parse("releases".into()).unwrap()],
+                None,
+                Uuid::new_v4()
+            )
+            .await
+            .is_ok());
+        })
+    }
+
+    #[test]
+    fn drafts_can_be_updated() {
+        let id = Uuid::new_v4();
+        let mut mock_repo = repo_with(id, IssueStatus::Draft);
+        mock_repo
+            .expect_update()
+            .times(1)
+            .with(eq(id), eq(content()), eq(IssueAudience::default()))
+            .returning(|_, _, _| Ok(()));
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                update_issue(
+                    &mock_repo,
+                    &MockTopicRepository::new(),
+                    id,
+                    content(),
+                    &[],
+                    None
+                )
+                .await,
+                Ok(())
+            );
+        })
+    }
+
+    #[test]
+    fn issues_being_sent_cannot_be_changed() {
+        let id = Uuid::new_v4();
+        let mut mock_repo = repo_with(id, IssueStatus::Sending);
+        mock_repo.expect_update().never();
+        mock_repo.expect_delete().never();
+        mock_repo.expect_schedule().never();
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                update_issue(
+                    &mock_repo,
+                    &MockTopicRepository::new(),
+                    id,
+                    content(),
+                    &[],
+                    None
+                )
+                .await,
+                Err(CoreError::IssueNotEditable)
+            );
+            assert_eq!(
+                delete_issue(&mock_repo, id).await,
+                Err(CoreError::IssueNotEditable)
+            );
+            assert_eq!(
+                schedule_issue(&mock_repo, id, SystemTime::now()).await,
+                Err(CoreError::IssueNotEditable)
+            );
+        })
+    }
+
+    #[test]
+    fn scheduled_issues_can_be_rescheduled_or_unscheduled() {
+        let id = Uuid::new_v4();
+        let send_at = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut mock_repo = repo_with(id, IssueStatus::Scheduled);
+        mock_repo
+            .expect_schedule()
+            .times(1)
+            .with(eq(id), eq(Some(send_at)))
+            .returning(|_, _| Ok(()));
+        mock_repo
+            .expect_schedule()
+            .times(1)
+            .with(eq(id), eq(None))
+            .returning(|_, _| Ok(()));
+
+        tokio_test::block_on(async {
+            assert_eq!(schedule_issue(&mock_repo, id, send_at).await, Ok(()));
+            assert_eq!(unschedule_issue(&mock_repo, id).await, Ok(()));
+        })
+    }
+
+    #[test]
+    fn unknown_issues_are_not_found() {
+        let mut mock_repo = MockNewsletterIssueRepository::new();
+        mock_repo.expect_find_by_id().returning(|_| Ok(None));
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                delete_issue(&mock_repo, Uuid::new_v4()).await,
+                Err(CoreError::IssueNotFound)
+            );
+        })
+    }
+}
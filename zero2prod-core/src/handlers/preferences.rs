@@ -0,0 +1,234 @@
+use tracing::{info, instrument};
+
+use crate::domain::{
+    ConsentAction, ConsentContext, ConsentEvent, Subscriber, TokenError, TokenKeys, TokenPurpose,
+    TopicPreference, TopicSlug,
+};
+use crate::error::{CoreError, CoreResult};
+use crate::repository::{ConsentRepository, SubscriptionRepository, TopicRepository};
+
+/// What the preference center shows a subscriber.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preferences {
+    pub subscriber: Subscriber,
+    pub topics: Vec<TopicPreference>,
+}
+
+/// The topic preferences of the bearer of a preferences token.
+#[instrument(name = "Preferences", skip_all, fields(subscriber_id))]
+pub async fn preferences<S, T>(
+    subscriber_repo: &S,
+    topic_repo: &T,
+    keys: &TokenKeys,
+    token: &str,
+    now: u64,
+) -> CoreResult<Preferences>
+where
+    S: SubscriptionRepository,
+    T: TopicRepository,
+{
+    let subscriber = token_subscriber(subscriber_repo, keys, token, now).await?;
+    let topics = topic_repo.preferences(subscriber.id).await?;
+    Ok(Preferences { subscriber, topics })
+}
+
+/// Subscribes the bearer of a preferences token to the topics of `topics`
+/// only.
+#[instrument(name = "Preferences update", skip_all, fields(subscriber_id))]
+#[allow(clippy::too_many_arguments)]
+pub async fn update_preferences<S, T, C>(
+    subscriber_repo: &S,
+    topic_repo: &T,
+    consent_repo: &C,
+    keys: &TokenKeys,
+    token: &str,
+    now: u64,
+    topics: &[TopicSlug],
+    context: &ConsentContext,
+) -> CoreResult<Preferences>
+where
+    S: SubscriptionRepository,
+    T: TopicRepository,
+    C: ConsentRepository,
+{
+    let subscriber = token_subscriber(subscriber_repo, keys, token, now).await?;
+    let topic_ids = super::topics::resolve_topics(topic_repo, subscriber.list_id, topics).await?;
+
+    info!(topics = topic_ids.len(), "Updating topic preferences");
+    topic_repo
+        .set_preferences(subscriber.id, &topic_ids)
+        .await?;
+    consent_repo
+        .record(&ConsentEvent {
+            topics: topics.to_vec(),
+            ..ConsentEvent::new(subscriber.id, ConsentAction::Preferences, context)
+        })
+        .await?;
+    let topics = topic_repo.preferences(subscriber.id).await?;
+    Ok(Preferences { subscriber, topics })
+}
+
+async fn token_subscriber<S>(
+    subscriber_repo: &S,
+    keys: &TokenKeys,
+    token: &str,
+    now: u64,
+) -> CoreResult<Subscriber>
+where
+    S: SubscriptionRepository,
+{
+    let token = keys
+        .verify(token, TokenPurpose::Preferences, now)
+        .map_err(|err| match err {
+            TokenError::Invalid => CoreError::InvalidToken,
+            TokenError::Expired(_) => CoreError::ExpiredToken,
+        })?;
+    tracing::Span::current().record("subscriber_id", token.subscriber_id.to_string());
+
+    subscriber_repo
+        .find_by_id(token.subscriber_id)
+        .await?
+        .ok_or(CoreError::SubscriberNotFound)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashMap;
+
+    use email_address::EmailAddress;
+    use mockall::predicate::{eq, function};
+    use secrecy::SecretString;
+    use uuid::Uuid;
+
+    use crate::{
+        domain::{ConsentSource, SubscriberName, SubscriptionStatus, SubscriptionToken, Topic},
+        repository::{MockConsentRepository, MockSubscriptionRepository, MockTopicRepository},
+    };
+
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn keys() -> TokenKeys {
+        TokenKeys::new(
+            "k1".into(),
+            HashMap::from([("k1".to_owned(), SecretString::new("secret".into()))]),
+        )
+        .unwrap()
+    }
+
+    fn subscriber() -> Subscriber {
+        Subscriber {
+            id: Uuid::from_u128(1),
+            list_id: Uuid::from_u128(2),
+            email: EmailAddress::new_unchecked("john.doe@gmail.com"),
+            name: SubscriberName::parse("John Doe".into()).unwrap(),
+            status: SubscriptionStatus::Confirmed,
+        }
+    }
+
+    fn token(purpose: TokenPurpose) -> String {
+        keys().sign(&SubscriptionToken::new(purpose, subscriber().id, NOW + 60))
+    }
+
+    fn topic(slug: &str) -> Topic {
+        Topic {
+            id: Uuid::new_v4(),
+            list_id: subscriber().list_id,
+            slug: TopicSlug::parse(slug.into()).unwrap(),
+            name: slug.into(),
+        }
+    }
+
+    fn subscribers() -> MockSubscriptionRepository {
+        let mut mock_repo = MockSubscriptionRepository::new();
+        mock_repo
+            .expect_find_by_id()
+            .with(eq(subscriber().id))
+            .returning(|_| Ok(Some(subscriber())));
+        mock_repo
+    }
+
+    #[test]
+    fn update_preferences_keeps_the_chosen_topics() {
+        let releases = topic("releases");
+        let blog = topic("blog");
+        let releases_id = releases.id;
+        let topics = vec![releases.clone(), blog.clone()];
+
+        let mut mock_topics = MockTopicRepository::new();
+        mock_topics
+            .expect_list()
+            .with(eq(subscriber().list_id))
+            .returning(move |_| Ok(topics.clone()));
+        mock_topics
+            .expect_set_preferences()
+            .times(1)
+            .with(
+                eq(subscriber().id),
+                function(move |ids: &[Uuid]| ids == [releases_id]),
+            )
+            .returning(|_, _| Ok(()));
+        mock_topics.expect_preferences().returning(move |_| {
+            Ok(vec![
+                TopicPreference {
+                    topic: blog.clone(),
+                    subscribed: false,
+                },
+                TopicPreference {
+                    topic: releases.clone(),
+                    subscribed: true,
+                },
+            ])
+        });
+
+        let context = ConsentContext::new(ConsentSource::Form);
+        let mut mock_consents = MockConsentRepository::new();
+        mock_consents
+            .expect_record()
+            .times(1)
+            .with(eq(ConsentEvent {
+                topics: vec![TopicSlug::parse("releases".into()).unwrap()],
+                ..ConsentEvent::new(subscriber().id, ConsentAction::Preferences, &context)
+            }))
+            .returning(|_| Ok(()));
+
+        tokio_test::block_on(async {
+            let preferences = update_preferences(
+                &subscribers(),
+                &mock_topics,
+                &mock_consents,
+                &keys(),
+                &token(TokenPurpose::Preferences),
+                NOW,
+                &[TopicSlug::parse("releases".into()).unwrap()],
+                &context,
+            )
+            .await
+            .unwrap();
+            assert_eq!(preferences.subscriber, subscriber());
+            assert_eq!(preferences.topics.len(), 2);
+        })
+    }
+
+    #[test]
+    fn preferences_require_a_preferences_token() {
+        let mut mock_topics = MockTopicRepository::new();
+        mock_topics.expect_preferences().times(0);
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                preferences(
+                    &MockSubscriptionRepository::new(),
+                    &mock_topics,
+                    &keys(),
+                    &token(TokenPurpose::Unsubscribe),
+                    NOW,
+                )
+                .await,
+                Err(CoreError::InvalidToken)
+            );
+        })
+    }
+}
@@ -0,0 +1,75 @@
+use tracing::{info, instrument};
+use uuid::Uuid;
+
+use crate::domain::Segment;
+use crate::error::CoreResult;
+use crate::repository::{SubscriptionRepository, TopicRepository};
+
+use super::topics::validate_segment;
+
+/// Counts the subscribers of a list in a segment, so that editors can check
+/// a segment before sending an issue to it.
+#[instrument(name = "Segment count", skip(subscription_repo, topic_repo))]
+pub async fn count_segment<S, T>(
+    subscription_repo: &S,
+    topic_repo: &T,
+    list_id: Uuid,
+    segment: &Segment,
+) -> CoreResult<u64>
+where
+    S: SubscriptionRepository,
+    T: TopicRepository,
+{
+    validate_segment(topic_repo, list_id, segment).await?;
+    let count = subscription_repo.count_segment(list_id, segment).await?;
+    info!(count, "Counted segment");
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use mockall::predicate::eq;
+
+    use crate::{
+        domain::{Topic, TopicSlug},
+        repository::{MockSubscriptionRepository, MockTopicRepository},
+    };
+
+    use super::*;
+
+    #[test]
+    fn count_segment_rejects_unknown_topics() {
+        let list_id = Uuid::new_v4();
+        let mut mock_topics = MockTopicRepository::new();
+        mock_topics
+            .expect_list()
+            .with(eq(list_id))
+            .returning(move |_| {
+                Ok(vec![Topic {
+                    id: Uuid::new_v4(),
+                    list_id,
+                    slug: TopicSlug::parse("releases".into()).unwrap(),
+                    name: "Releases".into(),
+                }])
+            });
+        let mut mock_subscriptions = MockSubscriptionRepository::new();
+        mock_subscriptions
+            .expect_count_segment()
+            .times(1)
+            .returning(|_, _| Ok(42));
+
+        tokio_test::block_on(async {
+            let known = Segment::parse("confirmed, topic = releases").unwrap();
+            let unknown = Segment::parse("confirmed, topic = blog").unwrap();
+            assert_eq!(
+                count_segment(&mock_subscriptions, &mock_topics, list_id, &known).await,
+                Ok(42)
+            );
+            assert!(
+                count_segment(&mock_subscriptions, &mock_topics, list_id, &unknown)
+                    .await
+                    .is_err()
+            );
+        })
+    }
+}
@@ -0,0 +1,390 @@
+use std::time::{Duration, SystemTime};
+
+use tracing::{error, info, instrument, warn};
+use uuid::Uuid;
+
+use crate::domain::{DeliveryOutcome, Document, IssueStatus, MailingList, NewsletterIssue};
+use crate::error::{CoreError, CoreResult};
+use crate::repository::{ListRepository, NewsletterIssueRepository};
+use crate::service::email_service::EmailService;
+
+/// How the deliveries of the issues are attempted.
+#[derive(Debug, Clone, Copy)]
+pub struct DeliveryPolicy {
+    /// Attempts to send an issue to a recipient before giving up.
+    pub max_attempts: u32,
+    /// Deliveries claimed longer ago than this were interrupted, they are not
+    /// attempted again since their email may have been sent.
+    pub interrupted_after: Duration,
+}
+
+/// Starts sending the issues due at `now`, then delivers every issue being
+/// sent. Sending resumes where it stopped when the previous run was
+/// interrupted, and no recipient is sent an issue twice.
+///
+/// A failure of the email service stops the delivery of the issue, its
+/// recipients left are attempted again on the next run.
+#[instrument(name = "Issues sending", skip_all)]
+pub async fn send_due_issues<R, M, E, D>(
+    issue_repo: &R,
+    list_repo: &M,
+    email_client: &E,
+    now: SystemTime,
+    policy: DeliveryPolicy,
+    issue_email: D,
+) -> CoreResult<()>
+where
+    R: NewsletterIssueRepository,
+    M: ListRepository,
+    E: EmailService,
+    D: Fn(&MailingList, &NewsletterIssue, Uuid) -> Document,
+{
+    issue_repo.start_due(now).await?;
+
+    let mut result = Ok(());
+    for issue in issue_repo.list(Some(IssueStatus::Sending)).await? {
+        let delivery = async {
+            let list = list_repo
+                .find_by_id(issue.list_id)
+                .await?
+                .ok_or(CoreError::ListNotFound)?;
+            deliver_issue(
+                issue_repo,
+                email_client,
+                &list,
+                &issue,
+                policy,
+                &issue_email,
+            )
+            .await
+        };
+        if let Err(err) = delivery.await {
+            warn!(issue_id = %issue.id, "Stopped delivering issue: {}", err);
+            result = Err(err);
+        }
+    }
+    result
+}
+
+#[instrument(name = "Issue delivery", skip_all, fields(issue_id = %issue.id))]
+async fn deliver_issue<R, E, D>(
+    issue_repo: &R,
+    email_client: &E,
+    list: &MailingList,
+    issue: &NewsletterIssue,
+    policy: DeliveryPolicy,
+    issue_email: &D,
+) -> CoreResult<()>
+where
+    R: NewsletterIssueRepository,
+    E: EmailService,
+    D: Fn(&MailingList, &NewsletterIssue, Uuid) -> Document,
+{
+    let interrupted = issue_repo
+        .fail_interrupted(issue.id, policy.interrupted_after)
+        .await?;
+    if interrupted > 0 {
+        error!(
+            interrupted,
+            "Deliveries were interrupted, not sending them again"
+        );
+    }
+
+    while let Some(delivery) = issue_repo.next_delivery(issue.id).await? {
+        let document = issue_email(list, issue, delivery.subscriber_id);
+        match email_client.send_email(&delivery.email, document).await {
+            Ok(()) => {
+                issue_repo
+                    .record_delivery(issue.id, delivery.subscriber_id, DeliveryOutcome::Sent)
+                    .await?
+            }
+            Err(err) if delivery.attempt >= policy.max_attempts => {
+                error!(subscriber_id = %delivery.subscriber_id, "Giving up delivery: {}", err);
+                issue_repo
+                    .record_delivery(
+                        issue.id,
+                        delivery.subscriber_id,
+                        DeliveryOutcome::Failed(err.to_string()),
+                    )
+                    .await?
+            }
+            Err(err) => {
+                issue_repo
+                    .record_delivery(
+                        issue.id,
+                        delivery.subscriber_id,
+                        DeliveryOutcome::Retry(err.to_string()),
+                    )
+                    .await?;
+                return Err(err);
+            }
+        }
+    }
+
+    if issue_repo.complete(issue.id).await? {
+        info!("Issue sent");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::{Arc, Mutex};
+
+    use mockall::predicate::{always, eq};
+
+    use crate::{
+        domain::{
+            Delivery, DocumentKind, IssueAudience, IssueContent, ListSettings, ListSlug,
+            ListTemplates,
+        },
+        repository::{MockListRepository, MockNewsletterIssueRepository},
+        service::email_service::MockEmailService,
+    };
+
+    use super::*;
+
+    const POLICY: DeliveryPolicy = DeliveryPolicy {
+        max_attempts: 2,
+        interrupted_after: Duration::from_secs(300),
+    };
+
+    fn sending_issue() -> NewsletterIssue {
+        NewsletterIssue {
+            id: Uuid::new_v4(),
+            list_id: Uuid::new_v4(),
+            content: IssueContent::parse("Issue #1".into(), "Hello".into(), "<p>Hello</p>".into())
+                .unwrap(),
+            status: IssueStatus::Sending,
+            send_at: None,
+            created_by: None,
+            created_at: SystemTime::UNIX_EPOCH,
+            sent_at: None,
+            audience: IssueAudience::default(),
+        }
+    }
+
+    fn list(list_id: Uuid) -> MailingList {
+        MailingList {
+            id: list_id,
+            slug: ListSlug::parse("weekly".into()).unwrap(),
+            settings: ListSettings::parse(
+                "Weekly".into(),
+                Some("weekly@example.com".parse().unwrap()),
+                true,
+                None,
+                ListTemplates::default(),
+            )
+            .unwrap(),
+        }
+    }
+
+    fn lists() -> MockListRepository {
+        let mut mock_lists = MockListRepository::new();
+        mock_lists
+            .expect_find_by_id()
+            .returning(|list_id| Ok(Some(list(list_id))));
+        mock_lists
+    }
+
+    fn issue_email(list: &MailingList, issue: &NewsletterIssue, subscriber_id: Uuid) -> Document {
+        Document::new(
+            issue.content.title.clone(),
+            DocumentKind::Issue {
+                html_content: issue.content.html_content.clone(),
+                text_content: issue.content.text_content.clone(),
+                unsubscribe_link: format!("https://unsubscribe/{}", subscriber_id),
+                preferences_link: format!("https://preferences/{}", subscriber_id),
+                tracking_pixel_link: None,
+            },
+        )
+        .for_list(list)
+    }
+
+    /// A repository sending `issue` to the `deliveries`, recording their
+    /// outcomes in `outcomes`.
+    fn repo_delivering(
+        issue: NewsletterIssue,
+        deliveries: Vec<Delivery>,
+        outcomes: Arc<Mutex<Vec<(Uuid, DeliveryOutcome)>>>,
+    ) -> MockNewsletterIssueRepository {
+        let mut mock_repo = MockNewsletterIssueRepository::new();
+        mock_repo.expect_start_due().times(1).returning(|_| Ok(()));
+        mock_repo
+            .expect_list()
+            .with(eq(Some(IssueStatus::Sending)))
+            .returning(move |_| Ok(vec![issue.clone()]));
+        mock_repo.expect_fail_interrupted().returning(|_, _| Ok(0));
+        let deliveries = Mutex::new(deliveries.into_iter());
+        mock_repo
+            .expect_next_delivery()
+            .returning(move |_| Ok(deliveries.lock().unwrap().next()));
+        mock_repo
+            .expect_record_delivery()
+            .returning(move |_, subscriber_id, outcome| {
+                outcomes.lock().unwrap().push((subscriber_id, outcome));
+                Ok(())
+            });
+        mock_repo
+    }
+
+    fn delivery(attempt: u32) -> Delivery {
+        Delivery {
+            subscriber_id: Uuid::new_v4(),
+            email: "john.doe@example.com".into(),
+            attempt,
+        }
+    }
+
+    #[test]
+    fn every_recipient_is_sent_the_issue_once() {
+        let issue = sending_issue();
+        let deliveries = vec![delivery(1), delivery(1)];
+        let outcomes = Arc::new(Mutex::new(Vec::new()));
+        let mut mock_repo = repo_delivering(issue.clone(), deliveries.clone(), outcomes.clone());
+        mock_repo
+            .expect_complete()
+            .times(1)
+            .with(eq(issue.id))
+            .returning(|_| Ok(true));
+
+        let mut mock_email = MockEmailService::new();
+        mock_email
+            .expect_send_email()
+            .times(2)
+            .returning(|_, _| Ok(()));
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                send_due_issues(
+                    &mock_repo,
+                    &lists(),
+                    &mock_email,
+                    SystemTime::now(),
+                    POLICY,
+                    issue_email
+                )
+                .await,
+                Ok(())
+            );
+        });
+        assert_eq!(
+            *outcomes.lock().unwrap(),
+            deliveries
+                .iter()
+                .map(|delivery| (delivery.subscriber_id, DeliveryOutcome::Sent))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn recipients_get_their_own_unsubscribe_link_from_the_list_sender() {
+        let issue = sending_issue();
+        let recipient = delivery(1);
+        let mut mock_repo = repo_delivering(issue.clone(), vec![recipient.clone()], Arc::default());
+        mock_repo.expect_complete().returning(|_| Ok(true));
+
+        let mut mock_email = MockEmailService::new();
+        mock_email
+            .expect_send_email()
+            .times(1)
+            .with(
+                eq("john.doe@example.com"),
+                eq(issue_email(
+                    &list(issue.list_id),
+                    &issue,
+                    recipient.subscriber_id,
+                )),
+            )
+            .returning(|_, _| Ok(()));
+
+        tokio_test::block_on(async {
+            send_due_issues(
+                &mock_repo,
+                &lists(),
+                &mock_email,
+                SystemTime::now(),
+                POLICY,
+                issue_email,
+            )
+            .await
+            .unwrap();
+        });
+    }
+
+    #[test]
+    fn email_failures_stop_the_delivery_until_the_next_run() {
+        let issue = sending_issue();
+        let first = delivery(1);
+        let outcomes = Arc::new(Mutex::new(Vec::new()));
+        let mut mock_repo =
+            repo_delivering(issue, vec![first.clone(), delivery(1)], outcomes.clone());
+        mock_repo.expect_complete().never();
+
+        let mut mock_email = MockEmailService::new();
+        mock_email
+            .expect_send_email()
+            .times(1)
+            .returning(|_, _| Err(CoreError::Unexpected("provider down".into())));
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                send_due_issues(
+                    &mock_repo,
+                    &lists(),
+                    &mock_email,
+                    SystemTime::now(),
+                    POLICY,
+                    issue_email
+                )
+                .await,
+                Err(CoreError::Unexpected("provider down".into()))
+            );
+        });
+        assert_eq!(
+            *outcomes.lock().unwrap(),
+            vec![(
+                first.subscriber_id,
+                DeliveryOutcome::Retry("Unexpected error: provider down".into())
+            )]
+        );
+    }
+
+    #[test]
+    fn deliveries_are_given_up_after_their_last_attempt() {
+        let issue = sending_issue();
+        let last = delivery(POLICY.max_attempts);
+        let outcomes = Arc::new(Mutex::new(Vec::new()));
+        let mut mock_repo = repo_delivering(issue, vec![last.clone()], outcomes.clone());
+        mock_repo.expect_complete().times(1).returning(|_| Ok(true));
+
+        let mut mock_email = MockEmailService::new();
+        mock_email
+            .expect_send_email()
+            .with(always(), always())
+            .returning(|_, _| Err(CoreError::Unexpected("mailbox full".into())));
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                send_due_issues(
+                    &mock_repo,
+                    &lists(),
+                    &mock_email,
+                    SystemTime::now(),
+                    POLICY,
+                    issue_email
+                )
+                .await,
+                Ok(())
+            );
+        });
+        assert_eq!(
+            *outcomes.lock().unwrap(),
+            vec![(
+                last.subscriber_id,
+                DeliveryOutcome::Failed("Unexpected error: mailbox full".into())
+            )]
+        );
+    }
+}
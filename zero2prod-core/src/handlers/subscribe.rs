@@ -1,31 +1,81 @@
-use tracing::{info, instrument, Span};
+use tracing::{info, instrument};
+use uuid::Uuid;
 
-use crate::domain::{Document, NewSubscriber};
+use crate::domain::{
+    ConsentAction, ConsentContext, ConsentEvent, Document, MailingList, NewSubscriber, Redacted,
+    StoredToken, SubscriptionStatus,
+};
 use crate::error::CoreResult;
-use crate::repository::SubscriptionRepository;
+use crate::repository::{ConsentRepository, SubscriptionRepository};
 use crate::service::email_service::EmailService;
+use crate::service::rate_limiter::RateLimiter;
 
-#[instrument(name = "Subscription", skip_all)]
-pub async fn subscribe<S, E>(
+use super::confirm::issue_confirmation;
+
+/// Subscribes to `list`, the subscriber is sent a confirmation email when the
+/// list requires it and is subscribed right away otherwise.
+#[instrument(
+    name = "Subscription",
+    skip_all,
+    fields(
+        list = %list.slug,
+        subscriber_email = %Redacted(&new_subscriber.email),
+        subscriber_name = %Redacted(&new_subscriber.name),
+    )
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn subscribe<S, C, E, L, D>(
     subscriber_repo: &S,
+    consent_repo: &C,
     email_client: &E,
+    email_limiter: &L,
+    list: &MailingList,
     new_subscriber: NewSubscriber,
-    confirmation_email: Document,
+    context: &ConsentContext,
+    confirmation_email: D,
 ) -> CoreResult<()>
 where
     S: SubscriptionRepository,
+    C: ConsentRepository,
     E: EmailService,
+    L: RateLimiter,
+    D: FnOnce(&MailingList, Uuid, &StoredToken) -> Document,
 {
-    Span::current()
-        .record("subscriber_email", new_subscriber.email.as_ref())
-        .record("subscriber_name", new_subscriber.name.as_ref());
+    // Every attempt counts against the recipient, so that the endpoint cannot
+    // be used to flood an inbox with confirmation emails.
+    email_limiter
+        .acquire(&new_subscriber.email.as_str().to_lowercase())
+        .await?;
 
     info!("Adding a new subscriber");
-    subscriber_repo.create(&new_subscriber).await?;
+    let subscriber_id = subscriber_repo.create(&new_subscriber).await?;
+    consent_repo
+        .record(&ConsentEvent::new(
+            subscriber_id,
+            ConsentAction::Subscribe,
+            context,
+        ))
+        .await?;
+
+    if !list.settings.require_confirmation {
+        info!("Confirming subscription, the list does not require it");
+        subscriber_repo
+            .set_status(subscriber_id, SubscriptionStatus::Confirmed)
+            .await?;
+        return consent_repo
+            .record(&ConsentEvent::new(
+                subscriber_id,
+                ConsentAction::Confirm,
+                context,
+            ))
+            .await;
+    }
 
     info!("Sending confirmation email");
+    let email =
+        issue_confirmation(subscriber_repo, list, subscriber_id, confirmation_email).await?;
     email_client
-        .send_email(new_subscriber.email.as_ref(), confirmation_email)
+        .send_email(new_subscriber.email.as_ref(), email)
         .await?;
     Ok(())
 }
@@ -33,31 +83,84 @@ where
 #[cfg(test)]
 mod tests {
 
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
     use fake::faker::{internet::en::SafeEmail, lorem::en::Sentence, name::en::Name};
     use fake::Fake;
-    use mockall::predicate::eq;
+    use mockall::predicate::{always, eq};
 
     use crate::{
-        domain::DocumentKind, error::CoreError, repository::MockSubscriptionRepository,
-        service::email_service::MockEmailService,
+        domain::{
+            with_redactor, ConsentSource, DocumentKind, ListSettings, ListSlug, ListTemplates,
+            RedactionMode, Redactor,
+        },
+        error::CoreError,
+        repository::{MockConsentRepository, MockSubscriptionRepository},
+        service::{email_service::MockEmailService, rate_limiter::MockRateLimiter},
     };
 
     use super::*;
 
+    #[derive(Clone, Default)]
+    struct Sink(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Sink {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn list(require_confirmation: bool) -> MailingList {
+        MailingList {
+            id: Uuid::new_v4(),
+            slug: ListSlug::parse("weekly".into()).unwrap(),
+            settings: ListSettings::parse(
+                "Weekly".into(),
+                None,
+                require_confirmation,
+                None,
+                ListTemplates::default(),
+            )
+            .unwrap(),
+        }
+    }
+
     fn random_subscriber() -> NewSubscriber {
         NewSubscriber {
+            list_id: Uuid::new_v4(),
             email: SafeEmail().fake::<String>().parse().unwrap(),
             name: Name().fake::<String>().parse().unwrap(),
         }
     }
 
+    fn consents() -> MockConsentRepository {
+        let mut mock_consents = MockConsentRepository::new();
+        mock_consents.expect_record().returning(|_| Ok(()));
+        mock_consents
+    }
+
+    fn context() -> ConsentContext {
+        ConsentContext::new(ConsentSource::Form)
+    }
+
+    fn unlimited() -> MockRateLimiter {
+        let mut mock_limiter = MockRateLimiter::new();
+        mock_limiter.expect_acquire().returning(|_| Ok(()));
+        mock_limiter
+    }
+
     fn random_confirmation_email() -> Document {
-        Document {
-            title: Sentence(1..2).fake::<String>(),
-            kind: DocumentKind::Confirmation {
+        Document::new(
+            Sentence(1..2).fake::<String>(),
+            DocumentKind::Confirmation {
                 confirmation_link: "https://my.link.com".to_owned(),
+                unsubscribe_link: "https://my.link.com/unsubscribe".to_owned(),
             },
-        }
+        )
     }
 
     #[test]
@@ -75,7 +178,17 @@ mod tests {
 
         tokio_test::block_on(async {
             assert_eq!(
-                subscribe(&mock_repo, &mock_email_service, new_subscriber, email).await,
+                subscribe(
+                    &mock_repo,
+                    &consents(),
+                    &mock_email_service,
+                    &unlimited(),
+                    &list(true),
+                    new_subscriber,
+                    &context(),
+                    move |_, _, _| email
+                )
+                .await,
                 Err(CoreError::EmailAlreadyExists)
             );
         })
@@ -87,7 +200,11 @@ mod tests {
         let email = random_confirmation_email();
 
         let mut mock_repo = MockSubscriptionRepository::new();
-        mock_repo.expect_create().times(1).returning(|_| Ok(()));
+        mock_repo
+            .expect_create()
+            .times(1)
+            .returning(|_| Ok(Uuid::new_v4()));
+        mock_repo.expect_store_token().returning(|_, _| Ok(()));
 
         let mut mock_email_service = MockEmailService::new();
         mock_email_service
@@ -97,7 +214,17 @@ mod tests {
 
         tokio_test::block_on(async {
             assert_eq!(
-                subscribe(&mock_repo, &mock_email_service, new_subscriber, email).await,
+                subscribe(
+                    &mock_repo,
+                    &consents(),
+                    &mock_email_service,
+                    &unlimited(),
+                    &list(true),
+                    new_subscriber,
+                    &context(),
+                    move |_, _, _| email
+                )
+                .await,
                 Err(CoreError::Unexpected("failed to send mail".into()))
             );
         })
@@ -108,13 +235,19 @@ mod tests {
         let new_subscriber = random_subscriber();
         let email = random_confirmation_email();
         let expected_recipient: String = new_subscriber.email.as_str().to_owned();
+        let subscriber_id = Uuid::new_v4();
 
         let mut mock_repo = MockSubscriptionRepository::new();
         mock_repo
             .expect_create()
             .times(1)
             .with(eq(new_subscriber.clone()))
-            .returning(|_| Ok(()));
+            .returning(move |_| Ok(subscriber_id));
+        mock_repo
+            .expect_store_token()
+            .times(1)
+            .with(eq(subscriber_id), always())
+            .returning(|_, _| Ok(()));
 
         let mut mock_email_service = MockEmailService::new();
         mock_email_service
@@ -125,9 +258,157 @@ mod tests {
 
         tokio_test::block_on(async {
             assert_eq!(
-                subscribe(&mock_repo, &mock_email_service, new_subscriber, email).await,
+                subscribe(
+                    &mock_repo,
+                    &consents(),
+                    &mock_email_service,
+                    &unlimited(),
+                    &list(true),
+                    new_subscriber,
+                    &context(),
+                    move |_, id, _| {
+                        assert_eq!(id, subscriber_id);
+                        email
+                    }
+                )
+                .await,
                 Err(CoreError::Unexpected("failed to send mail".into()))
             );
         })
     }
+
+    #[test]
+    fn subscribe_to_a_list_without_confirmation() {
+        let new_subscriber = random_subscriber();
+        let subscriber_id = Uuid::new_v4();
+
+        let mut mock_repo = MockSubscriptionRepository::new();
+        mock_repo
+            .expect_create()
+            .times(1)
+            .returning(move |_| Ok(subscriber_id));
+        mock_repo
+            .expect_set_status()
+            .times(1)
+            .with(eq(subscriber_id), eq(SubscriptionStatus::Confirmed))
+            .returning(|_, _| Ok(()));
+
+        let mut mock_email_service = MockEmailService::new();
+        mock_email_service.expect_send_email().times(0);
+        let mut mock_consents = MockConsentRepository::new();
+        let mut sequence = mockall::Sequence::new();
+        for action in [ConsentAction::Subscribe, ConsentAction::Confirm] {
+            mock_consents
+                .expect_record()
+                .times(1)
+                .in_sequence(&mut sequence)
+                .with(eq(ConsentEvent::new(subscriber_id, action, &context())))
+                .returning(|_| Ok(()));
+        }
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                subscribe(
+                    &mock_repo,
+                    &mock_consents,
+                    &mock_email_service,
+                    &unlimited(),
+                    &list(false),
+                    new_subscriber,
+                    &context(),
+                    |_, _, _| random_confirmation_email()
+                )
+                .await,
+                Ok(())
+            );
+        })
+    }
+
+    #[test]
+    fn subscribe_when_the_recipient_is_rate_limited() {
+        let new_subscriber = random_subscriber();
+        let email = random_confirmation_email();
+        let expected_key = new_subscriber.email.as_str().to_lowercase();
+
+        let mut mock_repo = MockSubscriptionRepository::new();
+        mock_repo.expect_create().times(0);
+
+        let mut mock_email_service = MockEmailService::new();
+        mock_email_service.expect_send_email().times(0);
+
+        let mut mock_limiter = MockRateLimiter::new();
+        mock_limiter
+            .expect_acquire()
+            .times(1)
+            .with(eq(expected_key))
+            .returning(|_| Err(CoreError::RateLimited { retry_after: 42 }));
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                subscribe(
+                    &mock_repo,
+                    &consents(),
+                    &mock_email_service,
+                    &mock_limiter,
+                    &list(true),
+                    new_subscriber,
+                    &context(),
+                    move |_, _, _| email
+                )
+                .await,
+                Err(CoreError::RateLimited { retry_after: 42 })
+            );
+        })
+    }
+
+    #[test]
+    fn subscribe_does_not_log_personal_data_when_redacted() {
+        let new_subscriber = random_subscriber();
+        let email = random_confirmation_email();
+        let raw_email = new_subscriber.email.as_str().to_owned();
+        let raw_name = new_subscriber.name.as_ref().to_owned();
+
+        let mut mock_repo = MockSubscriptionRepository::new();
+        mock_repo.expect_create().returning(|_| Ok(Uuid::new_v4()));
+        mock_repo.expect_store_token().returning(|_, _| Ok(()));
+        let mut mock_email_service = MockEmailService::new();
+        mock_email_service
+            .expect_send_email()
+            .returning(|_, _| Ok(()));
+
+        let sink = Sink::default();
+        let writer = sink.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .with_max_level(tracing::Level::TRACE)
+            .finish();
+
+        let redactor = Redactor::new(RedactionMode::Hash, Some("key".to_owned().into())).unwrap();
+
+        with_redactor(redactor, || {
+            tracing::subscriber::with_default(subscriber, || {
+                tokio_test::block_on(async {
+                    subscribe(
+                        &mock_repo,
+                        &consents(),
+                        &mock_email_service,
+                        &unlimited(),
+                        &list(true),
+                        new_subscriber,
+                        &context(),
+                        move |_, _, _| email,
+                    )
+                    .await
+                    .unwrap();
+                })
+            })
+        });
+
+        let output = String::from_utf8(sink.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("Adding a new subscriber"));
+        assert!(output.contains("subscriber_email=hmac:"));
+        assert!(!output.contains(&raw_email));
+        assert!(!output.contains(&raw_name));
+    }
 }
@@ -0,0 +1,315 @@
+use std::collections::HashSet;
+
+use email_address::EmailAddress;
+use tracing::{info, instrument, warn};
+use uuid::Uuid;
+
+use crate::domain::{
+    Document, ImportBatch, ImportConfirmations, ImportOptions, ImportProgress, ImportRow,
+    ImportRowError, ImportStatus, ImportedSubscriber, MailingList, NewSubscriber, StoredToken,
+    SubscriberImport, SubscriberName, SubscriptionStatus,
+};
+use crate::error::{CoreError, CoreResult};
+use crate::repository::{ListRepository, SubscriberImportRepository, SubscriptionRepository};
+use crate::service::email_service::EmailService;
+
+use super::confirm::issue_confirmation;
+
+/// Starts importing subscribers to `list`, the rows are then imported batch by
+/// batch with [`import_batch`].
+#[instrument(name = "Subscriber import", skip_all, fields(list = %list.slug))]
+pub async fn start_import<I>(
+    import_repo: &I,
+    list: &MailingList,
+    options: ImportOptions,
+) -> CoreResult<SubscriberImport>
+where
+    I: SubscriberImportRepository,
+{
+    let import_id = import_repo.create(list.id, &options).await?;
+    info!(%import_id, "Started import");
+    import_repo
+        .find_by_id(import_id)
+        .await?
+        .ok_or_else(|| CoreError::Unexpected("Import not found once created".into()))
+}
+
+/// Validates the rows and imports the valid ones, the others are recorded as
+/// the errors of the import. Returns the progress made.
+///
+/// See [`ImportBatch::outcome`] for the consents and confirmation emails the
+/// batch leads to, they are recorded along with the subscribers.
+#[instrument(name = "Subscriber import batch", skip_all, fields(import_id = %import.id))]
+pub async fn import_batch<I>(
+    import_repo: &I,
+    import: &SubscriberImport,
+    rows: Vec<ImportRow>,
+) -> CoreResult<ImportProgress>
+where
+    I: SubscriberImportRepository,
+{
+    let rows_count = rows.len() as u64;
+    let mut errors = vec![];
+    let mut seen = HashSet::new();
+    let mut subscribers = vec![];
+    for row in rows {
+        match validate(import.list_id, &row) {
+            Ok(subscriber) if !seen.insert(subscriber.email.as_str().to_lowercase()) => {
+                errors.push(row_error(row, "Duplicate email address".into()))
+            }
+            Ok(subscriber) => subscribers.push(ImportedSubscriber {
+                line: row.line,
+                subscriber,
+            }),
+            Err(reason) => errors.push(row_error(row, reason)),
+        }
+    }
+
+    let batch = ImportBatch {
+        rows: rows_count,
+        subscribers,
+        errors,
+        options: import.options,
+    };
+    let progress = import_repo
+        .import_batch(import.id, import.list_id, &batch)
+        .await?;
+    info!(
+        imported = progress.imported,
+        failed = progress.failed,
+        "Imported batch"
+    );
+    Ok(progress)
+}
+
+/// Ends an import, it failed when it stopped before the end of its file.
+#[instrument(name = "Subscriber import end", skip(import_repo))]
+pub async fn finish_import<I>(
+    import_repo: &I,
+    import_id: Uuid,
+    status: ImportStatus,
+) -> CoreResult<()>
+where
+    I: SubscriberImportRepository,
+{
+    if status == ImportStatus::Failed {
+        warn!("Import failed");
+    }
+    import_repo.finish(import_id, status).await
+}
+
+/// Sends their confirmation email to the imported subscribers still pending.
+#[instrument(name = "Import confirmations", skip_all, fields(subscribers = confirmations.subscriber_ids.len()))]
+pub async fn send_import_confirmations<S, M, E, D>(
+    subscriber_repo: &S,
+    list_repo: &M,
+    email_client: &E,
+    confirmations: &ImportConfirmations,
+    confirmation_email: D,
+) -> CoreResult<()>
+where
+    S: SubscriptionRepository,
+    M: ListRepository,
+    E: EmailService,
+    D: Fn(&MailingList, Uuid, &StoredToken) -> Document,
+{
+    // Imports are to a single list
+    let mut list: Option<MailingList> = None;
+    for subscriber_id in &confirmations.subscriber_ids {
+        let Some(subscriber) = subscriber_repo.find_by_id(*subscriber_id).await? else {
+            continue;
+        };
+        if subscriber.status != SubscriptionStatus::Pending {
+            continue;
+        }
+        let list = match list {
+            Some(ref list) if list.id == subscriber.list_id => list,
+            _ => list.insert(
+                list_repo
+                    .find_by_id(subscriber.list_id)
+                    .await?
+                    .ok_or(CoreError::ListNotFound)?,
+            ),
+        };
+        let email =
+            issue_confirmation(subscriber_repo, list, subscriber.id, &confirmation_email).await?;
+        email_client
+            .send_email(subscriber.email.as_ref(), email)
+            .await?;
+    }
+    Ok(())
+}
+
+fn validate(list_id: Uuid, row: &ImportRow) -> Result<NewSubscriber, String> {
+    let email = row
+        .email
+        .trim()
+        .parse::<EmailAddress>()
+        .map_err(|err| format!("Invalid email address: {}", err))?;
+    let name = SubscriberName::parse(row.name.clone()).map_err(|err| match err {
+        CoreError::InvalidDomain(reason) => format!("Invalid name: {}", reason),
+        err => err.to_string(),
+    })?;
+    Ok(NewSubscriber {
+        list_id,
+        name,
+        email,
+    })
+}
+
+fn row_error(row: ImportRow, reason: String) -> ImportRowError {
+    ImportRowError {
+        line: row.line,
+        email: row.email,
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use mockall::predicate::eq;
+
+    use crate::{
+        domain::{DocumentKind, ListSettings, ListSlug, ListTemplates, Subscriber},
+        repository::{
+            MockListRepository, MockSubscriberImportRepository, MockSubscriptionRepository,
+        },
+        service::email_service::MockEmailService,
+    };
+
+    use super::*;
+
+    fn import(confirmed: bool) -> SubscriberImport {
+        SubscriberImport {
+            id: Uuid::new_v4(),
+            list_id: Uuid::new_v4(),
+            options: ImportOptions { confirmed },
+            status: ImportStatus::Running,
+            progress: ImportProgress::default(),
+            created_at: SystemTime::now(),
+            finished_at: None,
+        }
+    }
+
+    fn row(line: u64, name: &str, email: &str) -> ImportRow {
+        ImportRow {
+            line,
+            name: name.into(),
+            email: email.into(),
+        }
+    }
+
+    #[test]
+    fn invalid_rows_are_reported_and_valid_ones_imported() {
+        let import = import(true);
+        let rows = vec![
+            row(2, "John Doe", "john.doe@gmail.com"),
+            row(3, "Jane Doe", "not an email"),
+            row(4, "  ", "nameless@gmail.com"),
+            row(5, "John Again", "John.Doe@gmail.com"),
+            row(6, "Eve", "eve@gmail.com"),
+        ];
+
+        let ids = (import.id, import.list_id);
+        let mut mock_imports = MockSubscriberImportRepository::new();
+        mock_imports
+            .expect_import_batch()
+            .times(1)
+            .withf(move |import_id, list_id, batch| {
+                let lines = batch.subscribers.iter().map(|s| s.line).collect::<Vec<_>>();
+                let error_lines = batch.errors.iter().map(|e| e.line).collect::<Vec<_>>();
+                (*import_id, *list_id) == ids
+                    && batch.rows == 5
+                    && lines == [2, 6]
+                    && error_lines == [3, 4, 5]
+                    && batch.errors[2].reason == "Duplicate email address"
+                    && batch.status() == SubscriptionStatus::Confirmed
+            })
+            .returning(|_, _, batch| {
+                Ok(ImportProgress {
+                    rows: batch.rows,
+                    imported: batch.subscribers.len() as u64,
+                    failed: batch.errors.len() as u64,
+                })
+            });
+
+        let progress = tokio_test::block_on(import_batch(&mock_imports, &import, rows)).unwrap();
+
+        assert_eq!(progress.imported, 2);
+        assert_eq!(progress.failed, 3);
+    }
+
+    #[test]
+    fn confirmations_are_only_sent_to_pending_subscribers() {
+        let list = MailingList {
+            id: Uuid::new_v4(),
+            slug: ListSlug::parse("weekly".into()).unwrap(),
+            settings: ListSettings::parse(
+                "Weekly".into(),
+                None,
+                true,
+                None,
+                ListTemplates::default(),
+            )
+            .unwrap(),
+        };
+        let list_id = list.id;
+        let subscriber = move |id: u128, status| Subscriber {
+            id: Uuid::from_u128(id),
+            list_id,
+            email: format!("subscriber{}@gmail.com", id).parse().unwrap(),
+            name: "John Doe".parse().unwrap(),
+            status,
+        };
+
+        let mut mock_subscribers = MockSubscriptionRepository::new();
+        mock_subscribers.expect_find_by_id().returning(move |id| {
+            Ok(match id.as_u128() {
+                1 => Some(subscriber(1, SubscriptionStatus::Pending)),
+                2 => Some(subscriber(2, SubscriptionStatus::Confirmed)),
+                3 => Some(subscriber(3, SubscriptionStatus::Pending)),
+                _ => None,
+            })
+        });
+        mock_subscribers
+            .expect_store_token()
+            .times(2)
+            .returning(|_, _| Ok(()));
+        let mut mock_lists = MockListRepository::new();
+        mock_lists
+            .expect_find_by_id()
+            .times(1)
+            .with(eq(list_id))
+            .returning(move |_| Ok(Some(list.clone())));
+        let mut mock_email_service = MockEmailService::new();
+        mock_email_service
+            .expect_send_email()
+            .times(2)
+            .withf(|recipient, _| {
+                recipient == "subscriber1@gmail.com" || recipient == "subscriber3@gmail.com"
+            })
+            .returning(|_, _| Ok(()));
+
+        let confirmations = ImportConfirmations {
+            subscriber_ids: (1..=4).map(Uuid::from_u128).collect(),
+        };
+        tokio_test::block_on(send_import_confirmations(
+            &mock_subscribers,
+            &mock_lists,
+            &mock_email_service,
+            &confirmations,
+            |_, _, _| {
+                Document::new(
+                    "Welcome".into(),
+                    DocumentKind::Confirmation {
+                        confirmation_link: "https://my.link.com".to_owned(),
+                        unsubscribe_link: "https://my.link.com/unsubscribe".to_owned(),
+                    },
+                )
+            },
+        ))
+        .unwrap();
+    }
+}
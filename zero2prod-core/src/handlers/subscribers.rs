@@ -0,0 +1,313 @@
+use tracing::{info, instrument};
+use uuid::Uuid;
+
+use crate::domain::{
+    ConsentAction, ConsentContext, ConsentEvent, ConsentSource, SubscriberCursor, SubscriberEntry,
+    SubscriberFilter, SubscriberUpdate, SubscriptionStatus, TopicPreference,
+};
+use crate::error::{CoreError, CoreResult};
+use crate::repository::{ConsentRepository, SubscriptionRepository, TopicRepository};
+
+/// A page of a listing of subscribers, with where the next page starts when
+/// there is one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubscriberPage {
+    pub subscribers: Vec<SubscriberEntry>,
+    pub next: Option<SubscriberCursor>,
+}
+
+/// A subscriber with their preferences for every topic of their list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubscriberDetails {
+    pub entry: SubscriberEntry,
+    pub topics: Vec<TopicPreference>,
+}
+
+/// Lists up to `limit` subscribers matching the filter, newest first,
+/// starting right after `after`.
+pub async fn list_subscribers<S>(
+    subscriber_repo: &S,
+    filter: &SubscriberFilter,
+    after: Option<SubscriberCursor>,
+    limit: u64,
+) -> CoreResult<SubscriberPage>
+where
+    S: SubscriptionRepository,
+{
+    // One more subscriber tells whether there is a next page
+    let mut subscribers = subscriber_repo.list(filter, after, limit + 1).await?;
+    let next = match subscribers.len() as u64 > limit {
+        true => {
+            subscribers.truncate(limit as usize);
+            subscribers.last().map(SubscriberCursor::from)
+        }
+        false => None,
+    };
+    Ok(SubscriberPage { subscribers, next })
+}
+
+pub async fn subscriber_details<S, T>(
+    subscriber_repo: &S,
+    topic_repo: &T,
+    subscriber_id: Uuid,
+) -> CoreResult<SubscriberDetails>
+where
+    S: SubscriptionRepository,
+    T: TopicRepository,
+{
+    let entry = subscriber_repo
+        .find_entry(subscriber_id)
+        .await?
+        .ok_or(CoreError::SubscriberNotFound)?;
+    let topics = topic_repo.preferences(subscriber_id).await?;
+    Ok(SubscriberDetails { entry, topics })
+}
+
+/// Applies the changes of an editor to a subscriber. Changes of status and
+/// topics are recorded in the consent ledger as made through the API.
+#[instrument(name = "Subscriber update", skip_all, fields(%subscriber_id))]
+pub async fn update_subscriber<S, T, C>(
+    subscriber_repo: &S,
+    topic_repo: &T,
+    consent_repo: &C,
+    subscriber_id: Uuid,
+    update: &SubscriberUpdate,
+) -> CoreResult<SubscriberDetails>
+where
+    S: SubscriptionRepository,
+    T: TopicRepository,
+    C: ConsentRepository,
+{
+    let subscriber = subscriber_repo
+        .find_by_id(subscriber_id)
+        .await?
+        .ok_or(CoreError::SubscriberNotFound)?;
+    // Unknown topics are rejected before anything changes
+    let topic_ids = match &update.topics {
+        Some(topics) => {
+            Some(super::topics::resolve_topics(topic_repo, subscriber.list_id, topics).await?)
+        }
+        None => None,
+    };
+    let context = ConsentContext::new(ConsentSource::Api);
+
+    if let Some(name) = &update.name {
+        subscriber_repo.set_name(subscriber_id, name).await?;
+    }
+    if let Some(status) = update.status.filter(|status| *status != subscriber.status) {
+        info!(from = %subscriber.status, to = %status, "Changing status");
+        subscriber_repo.set_status(subscriber_id, status).await?;
+        let action = match status {
+            SubscriptionStatus::Pending => None,
+            SubscriptionStatus::Confirmed => Some(ConsentAction::Confirm),
+            SubscriptionStatus::Unsubscribed => Some(ConsentAction::Unsubscribe),
+        };
+        if let Some(action) = action {
+            consent_repo
+                .record(&ConsentEvent::new(subscriber_id, action, &context))
+                .await?;
+        }
+    }
+    if let (Some(topics), Some(topic_ids)) = (&update.topics, topic_ids) {
+        info!(topics = topic_ids.len(), "Changing topics");
+        topic_repo
+            .set_preferences(subscriber_id, &topic_ids)
+            .await?;
+        consent_repo
+            .record(&ConsentEvent {
+                topics: topics.clone(),
+                ..ConsentEvent::new(subscriber_id, ConsentAction::Preferences, &context)
+            })
+            .await?;
+    }
+
+    subscriber_details(subscriber_repo, topic_repo, subscriber_id).await
+}
+
+#[instrument(name = "Subscriber deletion", skip_all, fields(%subscriber_id))]
+pub async fn delete_subscriber<S>(subscriber_repo: &S, subscriber_id: Uuid) -> CoreResult<()>
+where
+    S: SubscriptionRepository,
+{
+    info!("Deleting subscriber");
+    subscriber_repo.delete(subscriber_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use email_address::EmailAddress;
+    use mockall::predicate::{eq, function};
+
+    use crate::{
+        domain::{Subscriber, SubscriberName, Topic, TopicSlug},
+        repository::{MockConsentRepository, MockSubscriptionRepository, MockTopicRepository},
+    };
+
+    use super::*;
+
+    fn entry(n: u128) -> SubscriberEntry {
+        SubscriberEntry {
+            subscriber: Subscriber {
+                id: Uuid::from_u128(n),
+                list_id: Uuid::from_u128(100),
+                email: EmailAddress::new_unchecked(format!("subscriber{}@gmail.com", n)),
+                name: SubscriberName::parse("John Doe".into()).unwrap(),
+                status: SubscriptionStatus::Pending,
+            },
+            subscribed_at: UNIX_EPOCH + Duration::from_secs(1_700_000_000 - n as u64),
+        }
+    }
+
+    fn topic(slug: &str) -> Topic {
+        Topic {
+            id: Uuid::new_v4(),
+            list_id: Uuid::from_u128(100),
+            slug: TopicSlug::parse(slug.into()).unwrap(),
+            name: slug.into(),
+        }
+    }
+
+    #[test]
+    fn pages_point_at_the_next_one() {
+        let mut mock_repo = MockSubscriptionRepository::new();
+        mock_repo
+            .expect_list()
+            .with(eq(SubscriberFilter::default()), eq(None), eq(3))
+            .returning(|_, _, _| Ok((1..=3).map(entry).collect()));
+
+        let page = tokio_test::block_on(list_subscribers(
+            &mock_repo,
+            &SubscriberFilter::default(),
+            None,
+            2,
+        ))
+        .unwrap();
+
+        assert_eq!(page.subscribers, [entry(1), entry(2)]);
+        assert_eq!(page.next, Some(SubscriberCursor::from(&entry(2))));
+    }
+
+    #[test]
+    fn the_last_page_has_no_next_one() {
+        let mut mock_repo = MockSubscriptionRepository::new();
+        mock_repo
+            .expect_list()
+            .returning(|_, _, _| Ok(vec![entry(3)]));
+
+        let page = tokio_test::block_on(list_subscribers(
+            &mock_repo,
+            &SubscriberFilter::default(),
+            Some(SubscriberCursor::from(&entry(2))),
+            2,
+        ))
+        .unwrap();
+
+        assert_eq!(page.subscribers, [entry(3)]);
+        assert_eq!(page.next, None);
+    }
+
+    #[test]
+    fn status_and_topic_changes_are_recorded_as_consents() {
+        let mut subscriber_repo = MockSubscriptionRepository::new();
+        subscriber_repo
+            .expect_find_by_id()
+            .returning(|_| Ok(Some(entry(1).subscriber)));
+        subscriber_repo
+            .expect_set_status()
+            .with(eq(Uuid::from_u128(1)), eq(SubscriptionStatus::Confirmed))
+            .times(1)
+            .returning(|_, _| Ok(()));
+        subscriber_repo
+            .expect_find_entry()
+            .returning(|_| Ok(Some(entry(1))));
+        let mut topic_repo = MockTopicRepository::new();
+        topic_repo
+            .expect_list()
+            .returning(|_| Ok(vec![topic("releases"), topic("posts")]));
+        topic_repo
+            .expect_set_preferences()
+            .with(
+                eq(Uuid::from_u128(1)),
+                function(|ids: &[Uuid]| ids.len() == 1),
+            )
+            .times(1)
+            .returning(|_, _| Ok(()));
+        topic_repo.expect_preferences().returning(|_| Ok(vec![]));
+        let mut consent_repo = MockConsentRepository::new();
+        consent_repo
+            .expect_record()
+            .with(function(|event: &ConsentEvent| {
+                event.action == ConsentAction::Confirm
+                    && event.context == ConsentContext::new(ConsentSource::Api)
+            }))
+            .times(1)
+            .returning(|_| Ok(()));
+        consent_repo
+            .expect_record()
+            .with(function(|event: &ConsentEvent| {
+                event.action == ConsentAction::Preferences && event.topics.len() == 1
+            }))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let update = SubscriberUpdate {
+            status: Some(SubscriptionStatus::Confirmed),
+            topics: Some(vec![TopicSlug::parse("posts".into()).unwrap()]),
+            ..Default::default()
+        };
+        tokio_test::block_on(update_subscriber(
+            &subscriber_repo,
+            &topic_repo,
+            &consent_repo,
+            Uuid::from_u128(1),
+            &update,
+        ))
+        .unwrap();
+    }
+
+    #[test]
+    fn unknown_topics_change_nothing() {
+        let mut subscriber_repo = MockSubscriptionRepository::new();
+        subscriber_repo
+            .expect_find_by_id()
+            .returning(|_| Ok(Some(entry(1).subscriber)));
+        subscriber_repo.expect_set_name().never();
+        let mut topic_repo = MockTopicRepository::new();
+        topic_repo
+            .expect_list()
+            .returning(|_| Ok(vec![topic("releases")]));
+        let consent_repo = MockConsentRepository::new();
+
+        let update = SubscriberUpdate {
+            name: Some(SubscriberName::parse("Jane Doe".into()).unwrap()),
+            topics: Some(vec![TopicSlug::parse("posts".into()).unwrap()]),
+            ..Default::default()
+        };
+        let result = tokio_test::block_on(update_subscriber(
+            &subscriber_repo,
+            &topic_repo,
+            &consent_repo,
+            Uuid::from_u128(1),
+            &update,
+        ));
+
+        assert!(matches!(result, Err(CoreError::InvalidDomain(_))));
+    }
+
+    #[test]
+    fn unknown_subscribers_are_not_found() {
+        let mut subscriber_repo = MockSubscriptionRepository::new();
+        subscriber_repo.expect_find_entry().returning(|_| Ok(None));
+        let topic_repo = MockTopicRepository::new();
+
+        let result = tokio_test::block_on(subscriber_details(
+            &subscriber_repo,
+            &topic_repo,
+            Uuid::from_u128(1),
+        ));
+
+        assert_eq!(result, Err(CoreError::SubscriberNotFound));
+    }
+}
@@ -0,0 +1,153 @@
+use tracing::{info, instrument};
+use uuid::Uuid;
+
+use crate::domain::{ListSlug, NewTopic, Segment, TopicSlug};
+use crate::error::{CoreError, CoreResult};
+use crate::repository::{ListRepository, TopicRepository};
+
+#[instrument(name = "Topic creation", skip_all, fields(list = %slug, topic = %new_topic.slug))]
+pub async fn create_topic<L, T>(
+    list_repo: &L,
+    topic_repo: &T,
+    slug: &ListSlug,
+    new_topic: NewTopic,
+) -> CoreResult<Uuid>
+where
+    L: ListRepository,
+    T: TopicRepository,
+{
+    let list = list_repo
+        .find_by_slug(slug)
+        .await?
+        .ok_or(CoreError::ListNotFound)?;
+    let topic_id = topic_repo.create(list.id, &new_topic).await?;
+    info!(%topic_id, "Created topic");
+    Ok(topic_id)
+}
+
+/// The ids of the topics of a list named by `slugs`, unknown topics are
+/// rejected.
+pub(crate) async fn resolve_topics<T>(
+    topic_repo: &T,
+    list_id: Uuid,
+    slugs: &[TopicSlug],
+) -> CoreResult<Vec<Uuid>>
+where
+    T: TopicRepository,
+{
+    if slugs.is_empty() {
+        return Ok(vec![]);
+    }
+    let topics = topic_repo.list(list_id).await?;
+    slugs
+        .iter()
+        .map(|slug| {
+            topics
+                .iter()
+                .find(|topic| &topic.slug == slug)
+                .map(|topic| topic.id)
+                .ok_or_else(|| CoreError::InvalidDomain(format!("Unknown topic {}", slug)))
+        })
+        .collect()
+}
+
+/// Checks that the topics of a segment exist in the list.
+pub(crate) async fn validate_segment<T>(
+    topic_repo: &T,
+    list_id: Uuid,
+    segment: &Segment,
+) -> CoreResult<()>
+where
+    T: TopicRepository,
+{
+    let topics = segment.topics().into_iter().cloned().collect::<Vec<_>>();
+    resolve_topics(topic_repo, list_id, &topics).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use mockall::predicate::eq;
+
+    use crate::{
+        domain::{ListSettings, ListTemplates, MailingList, Topic},
+        repository::{MockListRepository, MockTopicRepository},
+    };
+
+    use super::*;
+
+    fn list_slug() -> ListSlug {
+        ListSlug::parse("weekly".into()).unwrap()
+    }
+
+    fn topic(list_id: Uuid, slug: &str) -> Topic {
+        Topic {
+            id: Uuid::new_v4(),
+            list_id,
+            slug: TopicSlug::parse(slug.into()).unwrap(),
+            name: slug.into(),
+        }
+    }
+
+    #[test]
+    fn create_topic_adds_it_to_the_list() {
+        let list = MailingList {
+            id: Uuid::new_v4(),
+            slug: list_slug(),
+            settings: ListSettings::parse(
+                "Weekly".into(),
+                None,
+                true,
+                None,
+                ListTemplates::default(),
+            )
+            .unwrap(),
+        };
+        let list_id = list.id;
+        let new_topic = NewTopic::parse("releases".into(), "Releases".into()).unwrap();
+        let topic_id = Uuid::new_v4();
+
+        let mut mock_lists = MockListRepository::new();
+        mock_lists
+            .expect_find_by_slug()
+            .returning(move |_| Ok(Some(list.clone())));
+        let mut mock_topics = MockTopicRepository::new();
+        mock_topics
+            .expect_create()
+            .times(1)
+            .with(eq(list_id), eq(new_topic.clone()))
+            .returning(move |_, _| Ok(topic_id));
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                create_topic(&mock_lists, &mock_topics, &list_slug(), new_topic).await,
+                Ok(topic_id)
+            );
+        })
+    }
+
+    #[test]
+    fn resolve_topics_rejects_unknown_topics() {
+        let list_id = Uuid::new_v4();
+        let releases = topic(list_id, "releases");
+        let releases_id = releases.id;
+        let mut mock_topics = MockTopicRepository::new();
+        mock_topics
+            .expect_list()
+            .with(eq(list_id))
+            .returning(move |_| Ok(vec![releases.clone()]));
+        let slug = |s: &str| TopicSlug::parse(s.into()).unwrap();
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                resolve_topics(&mock_topics, list_id, &[slug("releases")]).await,
+                Ok(vec![releases_id])
+            );
+            assert!(
+                resolve_topics(&mock_topics, list_id, &[slug("releases"), slug("blog")])
+                    .await
+                    .is_err()
+            );
+        })
+    }
+}
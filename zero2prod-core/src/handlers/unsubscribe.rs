@@ -0,0 +1,132 @@
+use tracing::{info, instrument};
+
+use crate::domain::{
+    ConsentAction, ConsentContext, ConsentEvent, SubscriptionStatus, TokenError, TokenKeys,
+    TokenPurpose,
+};
+use crate::error::{CoreError, CoreResult};
+use crate::repository::{ConsentRepository, SubscriptionRepository};
+
+#[instrument(name = "Unsubscription", skip_all, fields(subscriber_id))]
+pub async fn unsubscribe<S, C>(
+    subscriber_repo: &S,
+    consent_repo: &C,
+    keys: &TokenKeys,
+    token: &str,
+    now: u64,
+    context: &ConsentContext,
+) -> CoreResult<()>
+where
+    S: SubscriptionRepository,
+    C: ConsentRepository,
+{
+    let token = keys
+        .verify(token, TokenPurpose::Unsubscribe, now)
+        .map_err(|err| match err {
+            TokenError::Invalid => CoreError::InvalidToken,
+            TokenError::Expired(_) => CoreError::ExpiredToken,
+        })?;
+    tracing::Span::current().record("subscriber_id", token.subscriber_id.to_string());
+
+    info!("Unsubscribing");
+    subscriber_repo
+        .set_status(token.subscriber_id, SubscriptionStatus::Unsubscribed)
+        .await?;
+    consent_repo
+        .record(&ConsentEvent::new(
+            token.subscriber_id,
+            ConsentAction::Unsubscribe,
+            context,
+        ))
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashMap;
+
+    use mockall::predicate::eq;
+    use secrecy::SecretString;
+    use uuid::Uuid;
+
+    use crate::{
+        domain::{ConsentSource, SubscriptionToken},
+        repository::{MockConsentRepository, MockSubscriptionRepository},
+    };
+
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn keys() -> TokenKeys {
+        TokenKeys::new(
+            "k1".into(),
+            HashMap::from([("k1".to_owned(), SecretString::new("secret".into()))]),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn unsubscribe_nominal_case() {
+        let subscriber_id = Uuid::new_v4();
+        let token = keys().sign(&SubscriptionToken::new(
+            TokenPurpose::Unsubscribe,
+            subscriber_id,
+            NOW + 60,
+        ));
+
+        let mut mock_repo = MockSubscriptionRepository::new();
+        mock_repo
+            .expect_set_status()
+            .times(1)
+            .with(eq(subscriber_id), eq(SubscriptionStatus::Unsubscribed))
+            .returning(|_, _| Ok(()));
+        let context = ConsentContext::new(ConsentSource::Form);
+        let mut mock_consents = MockConsentRepository::new();
+        mock_consents
+            .expect_record()
+            .times(1)
+            .with(eq(ConsentEvent::new(
+                subscriber_id,
+                ConsentAction::Unsubscribe,
+                &context,
+            )))
+            .returning(|_| Ok(()));
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                unsubscribe(&mock_repo, &mock_consents, &keys(), &token, NOW, &context).await,
+                Ok(())
+            );
+        })
+    }
+
+    #[test]
+    fn unsubscribe_rejects_confirmation_tokens() {
+        let token = keys().sign(&SubscriptionToken::new(
+            TokenPurpose::Confirm,
+            Uuid::new_v4(),
+            NOW + 60,
+        ));
+
+        let mut mock_repo = MockSubscriptionRepository::new();
+        mock_repo.expect_set_status().times(0);
+        let context = ConsentContext::new(ConsentSource::Form);
+
+        tokio_test::block_on(async {
+            assert_eq!(
+                unsubscribe(
+                    &mock_repo,
+                    &MockConsentRepository::new(),
+                    &keys(),
+                    &token,
+                    NOW,
+                    &context
+                )
+                .await,
+                Err(CoreError::InvalidToken)
+            );
+        })
+    }
+}
@@ -0,0 +1,17 @@
+use async_trait::async_trait;
+
+use crate::{domain::ConsentEvent, error::CoreResult};
+
+#[cfg(test)]
+use mockall::{automock, predicate::*};
+
+/// The consent ledger, events are only ever appended to it.
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait ConsentRepository {
+    /// Appends an event to the ledger, as of now.
+    async fn record(&self, event: &ConsentEvent) -> CoreResult<()>;
+
+    /// Appends several events at once, as of now.
+    async fn record_all(&self, events: &[ConsentEvent]) -> CoreResult<()>;
+}
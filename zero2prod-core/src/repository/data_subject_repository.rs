@@ -0,0 +1,23 @@
+use async_trait::async_trait;
+use email_address::EmailAddress;
+
+use crate::{
+    domain::{EmailHash, SubjectData},
+    error::CoreResult,
+};
+
+#[cfg(test)]
+use mockall::{automock, predicate::*};
+
+/// The data of the people behind the subscriptions, as a whole.
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait DataSubjectRepository {
+    /// Everything stored about the owner of an email address.
+    async fn export(&self, email: &EmailAddress) -> CoreResult<SubjectData>;
+
+    /// Deletes everything stored about the owner of an email address, adds
+    /// `email_hash` to the suppressed emails and records a tombstone in the
+    /// audit log. Returns how many subscriptions were deleted.
+    async fn erase(&self, email: &EmailAddress, email_hash: &EmailHash) -> CoreResult<u64>;
+}
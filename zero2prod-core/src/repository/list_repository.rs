@@ -0,0 +1,30 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    domain::{ListSettings, ListSlug, MailingList, NewList},
+    error::CoreResult,
+};
+
+#[cfg(test)]
+use mockall::{automock, predicate::*};
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait ListRepository {
+    /// Creates a list, returns its id. Fails with
+    /// [`CoreError::ListAlreadyExists`](crate::error::CoreError::ListAlreadyExists)
+    /// when the slug is taken.
+    async fn create(&self, new_list: &NewList) -> CoreResult<Uuid>;
+
+    async fn find_by_id(&self, list_id: Uuid) -> CoreResult<Option<MailingList>>;
+
+    async fn find_by_slug(&self, slug: &ListSlug) -> CoreResult<Option<MailingList>>;
+
+    /// Lists the lists by slug.
+    async fn list(&self) -> CoreResult<Vec<MailingList>>;
+
+    /// Fails with [`CoreError::ListNotFound`](crate::error::CoreError::ListNotFound)
+    /// when the list does not exist.
+    async fn update(&self, list_id: Uuid, settings: &ListSettings) -> CoreResult<()>;
+}
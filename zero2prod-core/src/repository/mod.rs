@@ -1,3 +1,17 @@
+mod consent_repository;
+mod data_subject_repository;
+mod list_repository;
+mod newsletter_issue_repository;
+mod subscriber_import_repository;
 mod subscriptions_repository;
+mod topic_repository;
+mod user_repository;
 
+pub use consent_repository::*;
+pub use data_subject_repository::*;
+pub use list_repository::*;
+pub use newsletter_issue_repository::*;
+pub use subscriber_import_repository::*;
 pub use subscriptions_repository::*;
+pub use topic_repository::*;
+pub use user_repository::*;
@@ -0,0 +1,81 @@
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        Delivery, DeliveryOutcome, DeliveryStats, IssueAudience, IssueContent, IssueStatus,
+        NewsletterIssue,
+    },
+    error::CoreResult,
+};
+
+#[cfg(test)]
+use mockall::{automock, predicate::*};
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait NewsletterIssueRepository {
+    /// Creates a draft issue of a list, returns its id.
+    async fn create(
+        &self,
+        list_id: Uuid,
+        content: &IssueContent,
+        audience: &IssueAudience,
+        created_by: Uuid,
+    ) -> CoreResult<Uuid>;
+
+    async fn find_by_id(&self, issue_id: Uuid) -> CoreResult<Option<NewsletterIssue>>;
+
+    /// Lists the issues, the most recent first.
+    async fn list(&self, status: Option<IssueStatus>) -> CoreResult<Vec<NewsletterIssue>>;
+
+    /// The issue must be editable, the changes of an issue that started being
+    /// sent are refused with
+    /// [`CoreError::IssueNotEditable`](crate::error::CoreError::IssueNotEditable).
+    async fn update(
+        &self,
+        issue_id: Uuid,
+        content: &IssueContent,
+        audience: &IssueAudience,
+    ) -> CoreResult<()>;
+
+    /// Same as [`NewsletterIssueRepository::update`].
+    async fn delete(&self, issue_id: Uuid) -> CoreResult<()>;
+
+    /// Schedules an editable issue to be sent at `send_at`, or makes it a
+    /// draft again when `send_at` is unset. Same as
+    /// [`NewsletterIssueRepository::update`].
+    async fn schedule(&self, issue_id: Uuid, send_at: Option<SystemTime>) -> CoreResult<()>;
+
+    /// Starts sending the scheduled issues due at `now`, a pending delivery is
+    /// recorded for every confirmed subscriber of their list in the audience
+    /// of the issue at that time.
+    async fn start_due(&self, now: SystemTime) -> CoreResult<()>;
+
+    /// Claims the next pending delivery of an issue, a claimed delivery is not
+    /// handed out again until its outcome is recorded.
+    async fn next_delivery(&self, issue_id: Uuid) -> CoreResult<Option<Delivery>>;
+
+    async fn record_delivery(
+        &self,
+        issue_id: Uuid,
+        subscriber_id: Uuid,
+        outcome: DeliveryOutcome,
+    ) -> CoreResult<()>;
+
+    /// Fails the deliveries claimed more than `older_than` ago, their email
+    /// may have been sent before the outcome could be recorded. Returns how
+    /// many were failed.
+    async fn fail_interrupted(&self, issue_id: Uuid, older_than: Duration) -> CoreResult<u64>;
+
+    /// Marks the issue sent when no delivery is left, returns whether it was.
+    async fn complete(&self, issue_id: Uuid) -> CoreResult<bool>;
+
+    async fn delivery_stats(&self, issue_id: Uuid) -> CoreResult<DeliveryStats>;
+
+    /// Records that the subscriber opened the issue, only the first time
+    /// counts.
+    async fn record_open(&self, issue_id: Uuid, subscriber_id: Uuid) -> CoreResult<()>;
+}
@@ -0,0 +1,42 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        ImportBatch, ImportOptions, ImportProgress, ImportRowError, ImportStatus, SubscriberImport,
+    },
+    error::CoreResult,
+};
+
+#[cfg(test)]
+use mockall::{automock, predicate::*};
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait SubscriberImportRepository {
+    /// Starts an import to the list, returns its id.
+    async fn create(&self, list_id: Uuid, options: &ImportOptions) -> CoreResult<Uuid>;
+
+    async fn find_by_id(&self, import_id: Uuid) -> CoreResult<Option<SubscriberImport>>;
+
+    /// Imports a batch to the list in a single transaction. Creates the
+    /// subscribers with the status of the batch, or renames the existing
+    /// ones: existing pending subscribers are given the status of the batch,
+    /// the others keep theirs. Suppressed emails are left out. Then records
+    /// the [`ImportBatch::outcome`] of the upserted subscribers: their
+    /// consents, the job sending their confirmation, the errors of the batch
+    /// and its progress.
+    ///
+    /// Emails must be unique within the batch. Returns the progress made.
+    async fn import_batch(
+        &self,
+        import_id: Uuid,
+        list_id: Uuid,
+        batch: &ImportBatch,
+    ) -> CoreResult<ImportProgress>;
+
+    async fn finish(&self, import_id: Uuid, status: ImportStatus) -> CoreResult<()>;
+
+    /// The errors of the rows of the import, by line.
+    async fn errors(&self, import_id: Uuid) -> CoreResult<Vec<ImportRowError>>;
+}
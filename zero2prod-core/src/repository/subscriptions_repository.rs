@@ -1,6 +1,17 @@
+use std::time::Duration;
+
 use async_trait::async_trait;
 
-use crate::{domain::NewSubscriber, error::CoreResult};
+use email_address::EmailAddress;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        NewSubscriber, Segment, StoredToken, Subscriber, SubscriberCursor, SubscriberEntry,
+        SubscriberFilter, SubscriberName, SubscriptionStatus,
+    },
+    error::CoreResult,
+};
 
 #[cfg(test)]
 use mockall::{automock, predicate::*};
@@ -8,5 +19,58 @@ use mockall::{automock, predicate::*};
 #[cfg_attr(test, automock)]
 #[async_trait]
 pub trait SubscriptionRepository {
-    async fn create(&self, new_subscriber: &NewSubscriber) -> CoreResult<()>;
+    /// Creates a pending subscription to the list of the subscriber, returns
+    /// the id of the subscriber. Emails are unique per list.
+    ///
+    /// Subscribers who left get a new pending subscription, those who have
+    /// not confirmed yet keep theirs as is and get their id returned so that
+    /// the confirmation is sent again. Other existing subscribers of the list
+    /// make it fail with
+    /// [`CoreError::EmailAlreadyExists`](crate::error::CoreError::EmailAlreadyExists).
+    /// Suppressed emails make it fail with
+    /// [`CoreError::EmailSuppressed`](crate::error::CoreError::EmailSuppressed).
+    async fn create(&self, new_subscriber: &NewSubscriber) -> CoreResult<Uuid>;
+
+    async fn find_by_id(&self, subscriber_id: Uuid) -> CoreResult<Option<Subscriber>>;
+
+    async fn store_token(&self, subscriber_id: Uuid, token: &StoredToken) -> CoreResult<()>;
+
+    /// The subscriber a stored token was issued for.
+    async fn find_by_token(&self, token: &str) -> CoreResult<Option<Uuid>>;
+
+    async fn find_entry(&self, subscriber_id: Uuid) -> CoreResult<Option<SubscriberEntry>>;
+
+    /// Lists at most `limit` subscribers matching the filter, newest first,
+    /// starting right after `after`.
+    async fn list(
+        &self,
+        filter: &SubscriberFilter,
+        after: Option<SubscriberCursor>,
+        limit: u64,
+    ) -> CoreResult<Vec<SubscriberEntry>>;
+
+    /// The subscriptions of an email address to every list, whatever the
+    /// case of the address.
+    async fn find_by_email(&self, email: &EmailAddress) -> CoreResult<Vec<Subscriber>>;
+
+    /// Fails with [`CoreError::SubscriberNotFound`](crate::error::CoreError::SubscriberNotFound)
+    /// when the subscriber does not exist.
+    async fn set_status(&self, subscriber_id: Uuid, status: SubscriptionStatus) -> CoreResult<()>;
+
+    /// Fails with [`CoreError::SubscriberNotFound`](crate::error::CoreError::SubscriberNotFound)
+    /// when the subscriber does not exist.
+    async fn set_name(&self, subscriber_id: Uuid, name: &SubscriberName) -> CoreResult<()>;
+
+    /// Deletes the subscriber with their tokens, deliveries and consents.
+    /// Fails with [`CoreError::SubscriberNotFound`](crate::error::CoreError::SubscriberNotFound)
+    /// when the subscriber does not exist.
+    async fn delete(&self, subscriber_id: Uuid) -> CoreResult<()>;
+
+    /// Deletes the subscriptions left pending for longer than `older_than`,
+    /// returns how many were deleted.
+    async fn delete_stale_pending(&self, older_than: Duration) -> CoreResult<u64>;
+
+    /// Counts the subscribers of the list in the segment, whatever their
+    /// status.
+    async fn count_segment(&self, list_id: Uuid, segment: &Segment) -> CoreResult<u64>;
 }
@@ -0,0 +1,30 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    domain::{NewTopic, Topic, TopicPreference},
+    error::CoreResult,
+};
+
+#[cfg(test)]
+use mockall::{automock, predicate::*};
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait TopicRepository {
+    /// Creates a topic of a list, returns its id. Fails with
+    /// [`CoreError::TopicAlreadyExists`](crate::error::CoreError::TopicAlreadyExists)
+    /// when the list already has a topic with the same slug.
+    async fn create(&self, list_id: Uuid, new_topic: &NewTopic) -> CoreResult<Uuid>;
+
+    /// Lists the topics of a list by slug.
+    async fn list(&self, list_id: Uuid) -> CoreResult<Vec<Topic>>;
+
+    /// The preferences of a subscriber for every topic of their list, by
+    /// topic slug.
+    async fn preferences(&self, subscriber_id: Uuid) -> CoreResult<Vec<TopicPreference>>;
+
+    /// Subscribes a subscriber to the topics of `topic_ids` and opts them out
+    /// of the other topics of their list.
+    async fn set_preferences(&self, subscriber_id: Uuid, topic_ids: &[Uuid]) -> CoreResult<()>;
+}
@@ -0,0 +1,17 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    domain::{NewUser, User},
+    error::CoreResult,
+};
+
+#[cfg(test)]
+use mockall::{automock, predicate::*};
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait UserRepository {
+    async fn create(&self, new_user: &NewUser) -> CoreResult<Uuid>;
+    async fn find_by_username(&self, username: &str) -> CoreResult<Option<User>>;
+}
@@ -0,0 +1,28 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+#[cfg(test)]
+use mockall::{automock, predicate::*};
+
+use crate::{
+    domain::{Job, NewJob},
+    error::CoreResult,
+};
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait JobQueue {
+    /// Queues a job, returns its id.
+    async fn enqueue(&self, job: NewJob) -> CoreResult<Uuid>;
+}
+
+/// Runs the jobs of one kind. A job may run more than once, when it fails or
+/// when its worker dies, so handlers must be idempotent.
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait JobHandler: Send + Sync {
+    fn kind(&self) -> &'static str;
+
+    /// Failing makes the job retried later, until it runs out of attempts.
+    async fn run(&self, job: &Job) -> CoreResult<()>;
+}
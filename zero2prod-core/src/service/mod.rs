@@ -1 +1,3 @@
 pub mod email_service;
+pub mod jobs;
+pub mod rate_limiter;
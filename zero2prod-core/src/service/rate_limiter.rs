@@ -0,0 +1,15 @@
+use async_trait::async_trait;
+
+#[cfg(test)]
+use mockall::{automock, predicate::*};
+
+use crate::error::CoreResult;
+
+#[cfg_attr(test, automock)]
+#[async_trait]
+pub trait RateLimiter {
+    /// Consumes one token from the bucket identified by `key`, fails with
+    /// [`CoreError::RateLimited`](crate::error::CoreError::RateLimited) when the
+    /// bucket is empty.
+    async fn acquire(&self, key: &str) -> CoreResult<()>;
+}
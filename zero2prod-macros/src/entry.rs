@@ -2,20 +2,46 @@ use proc_macro2::{TokenStream, TokenTree};
 use quote::{quote, ToTokens};
 use syn::{
     braced,
-    parse::{Parse, ParseStream},
-    ReturnType, Signature, Visibility,
+    parse::{Parse, ParseStream, Parser},
+    Path, ReturnType, Signature, Visibility,
 };
 
-pub(crate) fn integration_test(item: TokenStream) -> TokenStream {
+pub(crate) fn integration_test(args: TokenStream, item: TokenStream) -> TokenStream {
     let input: ItemFn = match syn::parse2(item.clone()) {
         Ok(input) => input,
         Err(e) => return token_stream_with_error(item, e),
     };
 
-    parse_knobs(input)
+    let config = match Config::parse(args) {
+        Ok(config) => config,
+        Err(e) => return token_stream_with_error(item, e),
+    };
+
+    parse_knobs(input, config)
 }
 
-fn parse_knobs(mut input: ItemFn) -> TokenStream {
+#[derive(Default)]
+struct Config {
+    configure: Option<Path>,
+}
+
+impl Config {
+    fn parse(args: TokenStream) -> syn::Result<Self> {
+        let mut config = Config::default();
+        let parser = syn::meta::parser(|meta| {
+            if meta.path.is_ident("configure") {
+                config.configure = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported integration_test argument"))
+            }
+        });
+        Parser::parse2(parser, args)?;
+        Ok(config)
+    }
+}
+
+fn parse_knobs(mut input: ItemFn, config: Config) -> TokenStream {
     input.sig.asyncness = None;
     input.sig.inputs.clear();
     input.sig.output = ReturnType::Default;
@@ -25,10 +51,17 @@ fn parse_knobs(mut input: ItemFn) -> TokenStream {
     };
 
     let body = input.body();
-    let body = quote! {
-        ::zero2prod_web::testing::run_test(|test_stack: ::zero2prod_web::testing::TestStack| {
-            ::std::boxed::Box::pin(async move #body)
-        });
+    let body = match config.configure {
+        Some(configure) => quote! {
+            ::zero2prod_web::testing::run_test_with(#configure, |test_stack: ::zero2prod_web::testing::TestStack| {
+                ::std::boxed::Box::pin(async move #body)
+            });
+        },
+        None => quote! {
+            ::zero2prod_web::testing::run_test(|test_stack: ::zero2prod_web::testing::TestStack| {
+                ::std::boxed::Box::pin(async move #body)
+            });
+        },
     };
 
     input.into_tokens(header, body)
@@ -128,6 +161,7 @@ fn token_stream_with_error(mut tokens: TokenStream, error: syn::Error) -> TokenS
 #[cfg(test)]
 mod tests {
 
+    use proc_macro2::TokenStream;
     use quote::quote;
 
     #[test]
@@ -138,7 +172,7 @@ mod tests {
             }
         };
 
-        let output = super::integration_test(source.into());
+        let output = super::integration_test(TokenStream::new(), source);
 
         let expected = quote! {
             #[::core::prelude::v1::test]
@@ -160,7 +194,7 @@ mod tests {
             struct MyStruct;
         };
 
-        let output = super::integration_test(source.into());
+        let output = super::integration_test(TokenStream::new(), source);
 
         let expected = quote! {
             struct MyStruct;
@@ -169,4 +203,43 @@ mod tests {
 
         assert_eq!(output.to_string(), expected.to_string());
     }
+
+    #[test]
+    fn integration_test_with_configure_expand_as_expected() {
+        let args = quote! { configure = my_config };
+        let source = quote! {
+            async fn my_test() {
+                assert_eq!(2 + 2, 4);
+            }
+        };
+
+        let output = super::integration_test(args, source);
+
+        let expected = quote! {
+            #[::core::prelude::v1::test]
+            fn my_test() {
+            ::zero2prod_web::testing::run_test_with(my_config, |test_stack: ::zero2prod_web::testing::TestStack| {
+                ::std::boxed::Box::pin(async move {
+                    assert_eq!(2 + 2, 4);
+                })
+            });
+            }
+        };
+
+        assert_eq!(output.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn integration_test_returns_token_on_unknown_argument() {
+        let args = quote! { unknown = my_config };
+        let source = quote! {
+            async fn my_test() {}
+        };
+
+        let output = super::integration_test(args, source);
+
+        assert!(output
+            .to_string()
+            .contains("unsupported integration_test argument"));
+    }
 }
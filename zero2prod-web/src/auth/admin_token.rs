@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use axum::{extract::FromRequestParts, response::IntoResponse, response::Response};
+use http::{
+    header::{AUTHORIZATION, WWW_AUTHENTICATE},
+    request::Parts,
+};
+use hyper::StatusCode;
+use secrecy::ExposeSecret;
+
+use crate::configuration::Configuration;
+
+use super::constant_time_eq;
+
+/// Guards the operational endpoints, the request must carry the bearer token
+/// configured in `admin.token`.
+pub struct AdminToken;
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AdminToken
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let expected = parts
+            .extensions
+            .get::<Arc<Configuration>>()
+            .and_then(|config| config.admin.token.clone())
+            .ok_or_else(|| {
+                (StatusCode::FORBIDDEN, "admin endpoints are disabled").into_response()
+            })?;
+
+        let provided = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        match provided {
+            Some(token)
+                if constant_time_eq(token.as_bytes(), expected.expose_secret().as_bytes()) =>
+            {
+                Ok(AdminToken)
+            }
+            _ => {
+                tracing::warn!("Rejected unauthenticated request to an admin endpoint");
+                Err((
+                    StatusCode::UNAUTHORIZED,
+                    [(WWW_AUTHENTICATE, "Bearer")],
+                    "invalid admin token",
+                )
+                    .into_response())
+            }
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use axum::{
+    extract::FromRequestParts,
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use http::{
+    header::{AUTHORIZATION, WWW_AUTHENTICATE},
+    request::Parts,
+};
+use hyper::StatusCode;
+use once_cell::sync::Lazy;
+use secrecy::SecretString;
+use uuid::Uuid;
+use zero2prod_core::{error::CoreResult, repository::UserRepository};
+
+use crate::{
+    error::core_error,
+    repository::UserRepositoryImpl,
+    service::{hash_password, verify_password},
+};
+
+/// Checked when the username is unknown, so that unknown usernames take as
+/// long to reject as wrong passwords.
+static DUMMY_HASH: Lazy<SecretString> = Lazy::new(|| {
+    hash_password(&SecretString::new("not a password".into())).expect("Failed to hash password")
+});
+
+/// Guards the editorial endpoints, the request must carry the basic
+/// credentials of a user.
+pub struct Editor {
+    pub user_id: Uuid,
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Editor
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let users = parts
+            .extensions
+            .get::<Arc<UserRepositoryImpl>>()
+            .cloned()
+            .expect("The user repository is not available");
+
+        let Some((username, password)) = basic_credentials(parts) else {
+            return Err(unauthorized());
+        };
+        match authenticate(&users, &username, password)
+            .await
+            .map_err(core_error)?
+        {
+            Some(user_id) => Ok(Editor { user_id }),
+            None => {
+                tracing::warn!("Rejected invalid credentials");
+                Err(unauthorized())
+            }
+        }
+    }
+}
+
+/// The id of the user with the credentials, `None` when they are invalid.
+pub(crate) async fn authenticate(
+    users: &UserRepositoryImpl,
+    username: &str,
+    password: SecretString,
+) -> CoreResult<Option<Uuid>> {
+    let user = users.find_by_username(username).await?;
+
+    let hash = user
+        .as_ref()
+        .map(|user| user.password_hash.clone())
+        .unwrap_or_else(|| DUMMY_HASH.clone());
+    // Hashing is expensive enough to stall the other requests of the thread
+    let verified = tokio::task::spawn_blocking(move || verify_password(&password, &hash))
+        .await
+        .unwrap_or(false);
+
+    Ok(user.filter(|_| verified).map(|user| user.id))
+}
+
+fn basic_credentials(parts: &Parts) -> Option<(String, SecretString)> {
+    let encoded = parts
+        .headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Basic ")?;
+    let decoded = String::from_utf8(STANDARD.decode(encoded).ok()?).ok()?;
+    let (username, password) = decoded.split_once(':')?;
+    Some((username.to_owned(), SecretString::new(password.to_owned())))
+}
+
+fn unauthorized() -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        [(WWW_AUTHENTICATE, r#"Basic realm="zero2prod""#)],
+        "invalid credentials",
+    )
+        .into_response()
+}
@@ -0,0 +1,13 @@
+mod admin_token;
+mod editor;
+mod session;
+
+pub use admin_token::AdminToken;
+pub(crate) use editor::authenticate;
+pub use editor::Editor;
+pub use session::{cookie, random_token, set_cookie, EditorSession, SessionStore, SESSION_COOKIE};
+
+/// Compares two secrets in constant time.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
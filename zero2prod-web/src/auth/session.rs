@@ -0,0 +1,212 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use axum::{
+    extract::FromRequestParts,
+    response::{IntoResponse, Redirect, Response},
+};
+use http::{
+    header::{COOKIE, SET_COOKIE},
+    request::Parts,
+    HeaderMap,
+};
+use hyper::StatusCode;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+use zero2prod_core::error::CoreResult;
+
+use crate::{configuration::Configuration, error::core_error};
+
+use super::constant_time_eq;
+
+/// Cookie carrying the session token of an editor signed in to the dashboard.
+pub const SESSION_COOKIE: &str = "z2p_session";
+
+/// A session of an editor signed in to the dashboard.
+pub struct Session {
+    pub user_id: Uuid,
+    /// Sent back with every form of the dashboard, so that other sites cannot
+    /// submit them on behalf of the editor.
+    pub csrf_token: String,
+}
+
+/// Keeps the sessions of the dashboard in Postgres, by the digest of their
+/// token so that a leak of the table does not let anyone in.
+pub struct SessionStore {
+    pool: PgPool,
+    ttl: Duration,
+}
+
+impl SessionStore {
+    pub fn new(pool: PgPool, ttl: Duration) -> Self {
+        Self { pool, ttl }
+    }
+
+    /// Signs the user in, returns the token of the session.
+    pub async fn create(&self, user_id: Uuid) -> CoreResult<String> {
+        let token = random_token();
+        // Signing in is rare enough to clean up the expired sessions
+        sqlx::query!("DELETE FROM admin_sessions WHERE expires_at < now()")
+            .execute(&self.pool)
+            .await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO admin_sessions (id, user_id, csrf_token, created_at, expires_at)
+            VALUES ($1, $2, $3, now(), now() + make_interval(secs => $4))
+            "#,
+            digest(&token),
+            user_id,
+            random_token(),
+            self.ttl.as_secs_f64()
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(token)
+    }
+
+    pub async fn find(&self, token: &str) -> CoreResult<Option<Session>> {
+        let session = sqlx::query_as!(
+            Session,
+            r#"
+            SELECT user_id, csrf_token FROM admin_sessions
+            WHERE id = $1 AND expires_at > now()
+            "#,
+            digest(token)
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(session)
+    }
+
+    pub async fn delete(&self, token: &str) -> CoreResult<()> {
+        sqlx::query!("DELETE FROM admin_sessions WHERE id = $1", digest(token))
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+}
+
+/// Guards the pages of the dashboard, editors without a session are sent to
+/// the login page.
+pub struct EditorSession {
+    pub token: String,
+    pub session: Session,
+}
+
+impl EditorSession {
+    /// Rejects the forms not carrying the CSRF token of the session.
+    pub fn verify_csrf(&self, csrf_token: &str) -> Result<(), InvalidCsrfToken> {
+        match constant_time_eq(self.session.csrf_token.as_bytes(), csrf_token.as_bytes()) {
+            true => Ok(()),
+            false => {
+                tracing::warn!("Rejected a form with an invalid CSRF token");
+                Err(InvalidCsrfToken)
+            }
+        }
+    }
+}
+
+pub struct InvalidCsrfToken;
+
+impl IntoResponse for InvalidCsrfToken {
+    fn into_response(self) -> Response {
+        (StatusCode::FORBIDDEN, "invalid csrf token").into_response()
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for EditorSession
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let sessions = parts
+            .extensions
+            .get::<Arc<SessionStore>>()
+            .cloned()
+            .expect("The session store is not available");
+
+        let Some(token) = cookie(&parts.headers, SESSION_COOKIE) else {
+            return Err(Redirect::to("/admin/login").into_response());
+        };
+        match sessions.find(&token).await.map_err(core_error)? {
+            Some(session) => Ok(EditorSession { token, session }),
+            None => Err(Redirect::to("/admin/login").into_response()),
+        }
+    }
+}
+
+/// The value of a cookie sent with the request.
+pub fn cookie(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_owned())
+}
+
+/// A `Set-Cookie` header for a dashboard cookie, which scripts cannot read
+/// and other sites cannot send. The cookie is removed when `max_age` is 0.
+pub fn set_cookie(
+    config: &Configuration,
+    name: &str,
+    value: &str,
+    path: &str,
+    max_age: Duration,
+) -> (http::HeaderName, String) {
+    let secure = match config.admin.secure_cookies {
+        true => "; Secure",
+        false => "",
+    };
+    (
+        SET_COOKIE,
+        format!(
+            "{}={}; Path={}; Max-Age={}; HttpOnly; SameSite=Strict{}",
+            name,
+            value,
+            path,
+            max_age.as_secs(),
+            secure
+        ),
+    )
+}
+
+/// 32 random bytes, hex encoded.
+pub fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+fn digest(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use http::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn cookies_are_found_by_name() {
+        let mut headers = HeaderMap::new();
+        headers.append(COOKIE, HeaderValue::from_static("a=1; z2p_session=abc"));
+        headers.append(COOKIE, HeaderValue::from_static("b=2"));
+
+        assert_eq!(cookie(&headers, SESSION_COOKIE), Some("abc".into()));
+        assert_eq!(cookie(&headers, "b"), Some("2".into()));
+        assert_eq!(cookie(&headers, "z2p"), None);
+    }
+}
@@ -1,23 +1,20 @@
 use tracing::info;
-use zero2prod_web::{configuration::get_configuration, server};
+use zero2prod_web::{
+    cli::{self, ServeArgs},
+    configuration::get_configuration,
+};
 
+/// Kept for existing deployments, equivalent to `zero2prod serve`.
 #[tokio::main]
 async fn main() {
-    zero2prod_web::telemetry::setup_subscriber(
-        "zero2prod",
-        "zero2prod_web::layer::trace_id=trace,info",
-        std::io::stdout,
-    );
-
     let configuration = get_configuration().expect("Failed to read configuration.");
-    info!("Active profile : {}", configuration.profile);
 
-    let (server, _, pool) = server::start(&configuration).await;
+    zero2prod_web::telemetry::setup_subscriber(&configuration.telemetry, std::io::stdout);
+    info!("Active profile : {}", configuration.profile);
 
-    sqlx::migrate!("../migrations")
-        .run(&pool)
+    cli::serve(configuration, ServeArgs::default())
         .await
-        .expect("Failed to migrate database.");
+        .expect("Failed to run application.");
 
-    server.await.unwrap();
+    zero2prod_web::telemetry::shutdown();
 }
@@ -0,0 +1,31 @@
+use std::process::ExitCode;
+
+use clap::Parser;
+use tracing::{error, info};
+use zero2prod_web::{
+    cli::{Cli, Command},
+    configuration::get_configuration,
+};
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let configuration = get_configuration().expect("Failed to read configuration.");
+
+    // Keep stdout for the output of the one-off commands.
+    match cli.command {
+        Command::Serve(_) => {
+            zero2prod_web::telemetry::setup_subscriber(&configuration.telemetry, std::io::stdout)
+        }
+        _ => zero2prod_web::telemetry::setup_subscriber(&configuration.telemetry, std::io::stderr),
+    }
+    info!("Active profile : {}", configuration.profile);
+
+    let code = cli.run(configuration).await.unwrap_or_else(|e| {
+        error!("{}", e);
+        ExitCode::FAILURE
+    });
+
+    zero2prod_web::telemetry::shutdown();
+    code
+}
@@ -0,0 +1,115 @@
+use hmac::{Hmac, Mac};
+use secrecy::{ExposeSecret, SecretString};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Signs the time at which a subscription form was served, so that forms
+/// cannot be submitted without having been fetched first.
+///
+/// Tokens are formatted as `<issued at>.<nonce>.<hex signature>`, the issue
+/// time being a unix timestamp in seconds. The random nonce lets the token be
+/// accepted only once.
+pub struct FormTokenSigner {
+    key: SecretString,
+}
+
+/// The content of a token carrying a valid signature.
+#[derive(Debug, PartialEq, Eq)]
+pub struct FormToken {
+    pub issued_at: u64,
+    pub nonce: String,
+}
+
+impl FormTokenSigner {
+    pub fn new(key: SecretString) -> Self {
+        Self { key }
+    }
+
+    pub fn issue(&self, issued_at: u64) -> String {
+        let nonce = hex::encode(rand::random::<[u8; 16]>());
+        let signature = hex::encode(self.sign(issued_at, &nonce));
+        format!("{}.{}.{}", issued_at, nonce, signature)
+    }
+
+    pub fn verify(&self, token: &str) -> Option<FormToken> {
+        let mut parts = token.split('.');
+        let (issued_at, nonce, signature) = (parts.next()?, parts.next()?, parts.next()?);
+        if parts.next().is_some() {
+            return None;
+        }
+        let issued_at = issued_at.parse().ok()?;
+        let signature = hex::decode(signature).ok()?;
+
+        self.mac(issued_at, nonce)
+            .verify_slice(&signature)
+            .ok()
+            .map(|_| FormToken {
+                issued_at,
+                nonce: nonce.to_owned(),
+            })
+    }
+
+    fn sign(&self, issued_at: u64, nonce: &str) -> Vec<u8> {
+        self.mac(issued_at, nonce).finalize().into_bytes().to_vec()
+    }
+
+    fn mac(&self, issued_at: u64, nonce: &str) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(self.key.expose_secret().as_bytes())
+            .expect("HMAC accepts keys of any size");
+        mac.update(b"form-token:");
+        mac.update(issued_at.to_string().as_bytes());
+        mac.update(b".");
+        mac.update(nonce.as_bytes());
+        mac
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signer(key: &str) -> FormTokenSigner {
+        FormTokenSigner::new(SecretString::new(key.into()))
+    }
+
+    #[test]
+    fn issued_tokens_are_valid() {
+        let signer = signer("secret");
+        let token = signer.verify(&signer.issue(1_700_000_000)).unwrap();
+        assert_eq!(token.issued_at, 1_700_000_000);
+    }
+
+    #[test]
+    fn issued_tokens_have_distinct_nonces() {
+        let signer = signer("secret");
+        let first = signer.verify(&signer.issue(1_700_000_000)).unwrap();
+        let second = signer.verify(&signer.issue(1_700_000_000)).unwrap();
+        assert_ne!(first.nonce, second.nonce);
+    }
+
+    #[test]
+    fn tokens_signed_with_another_key_are_invalid() {
+        let token = signer("other").issue(1_700_000_000);
+        assert_eq!(signer("secret").verify(&token), None);
+    }
+
+    #[test]
+    fn tampered_tokens_are_invalid() {
+        let signer = signer("secret");
+        let token = signer.issue(1_700_000_000);
+        let (_, rest) = token.split_once('.').unwrap();
+        let (nonce, signature) = rest.split_once('.').unwrap();
+
+        assert_eq!(
+            signer.verify(&format!("1600000000.{}.{}", nonce, signature)),
+            None
+        );
+        assert_eq!(
+            signer.verify(&format!("1700000000.{}0.{}", nonce, signature)),
+            None
+        );
+        assert_eq!(signer.verify("1700000000"), None);
+        assert_eq!(signer.verify("garbage.garbage.garbage"), None);
+    }
+}
@@ -0,0 +1,237 @@
+mod form_token;
+
+use std::net::IpAddr;
+
+use serde::Deserialize;
+use sqlx::PgPool;
+use zero2prod_core::error::CoreResult;
+
+use crate::{
+    clock::unix_now,
+    configuration::BotProtectionConfig,
+    service::{CaptchaVerifier, CaptchaVerifierImpl},
+};
+
+pub use form_token::{FormToken, FormTokenSigner};
+
+/// Fields added to the subscription form to tell humans from bots.
+#[derive(Deserialize, Default)]
+pub struct BotFields {
+    /// Honeypot, hidden from humans so that only bots fill it in.
+    #[serde(default)]
+    pub website: String,
+    #[serde(default)]
+    pub form_token: Option<String>,
+    #[serde(default, alias = "h-captcha-response", alias = "cf-turnstile-response")]
+    pub captcha_response: Option<String>,
+}
+
+/// Why a form was considered as submitted by a bot, only ever logged and
+/// counted: bots are not told which check they failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BotRejection {
+    Honeypot,
+    MissingFormToken,
+    InvalidFormToken,
+    TooFast,
+    ExpiredFormToken,
+    /// The token was already submitted with another form.
+    ReusedFormToken,
+    FormTokenUnavailable,
+    MissingCaptcha,
+    FailedCaptcha,
+    CaptchaUnavailable,
+}
+
+impl BotRejection {
+    pub fn reason(&self) -> &'static str {
+        match self {
+            BotRejection::Honeypot => "honeypot",
+            BotRejection::MissingFormToken => "missing_form_token",
+            BotRejection::InvalidFormToken => "invalid_form_token",
+            BotRejection::TooFast => "too_fast",
+            BotRejection::ExpiredFormToken => "expired_form_token",
+            BotRejection::ReusedFormToken => "reused_form_token",
+            BotRejection::FormTokenUnavailable => "form_token_unavailable",
+            BotRejection::MissingCaptcha => "missing_captcha",
+            BotRejection::FailedCaptcha => "failed_captcha",
+            BotRejection::CaptchaUnavailable => "captcha_unavailable",
+        }
+    }
+}
+
+pub struct BotProtection {
+    pool: PgPool,
+    form_tokens: Option<FormTokenSigner>,
+    min_fill_time: u64,
+    max_form_age: u64,
+    captcha: Option<CaptchaVerifierImpl>,
+}
+
+impl BotProtection {
+    pub fn from_config(config: &BotProtectionConfig, pool: PgPool) -> Self {
+        Self {
+            pool,
+            form_tokens: config.form_secret.clone().map(FormTokenSigner::new),
+            min_fill_time: config.min_fill_time,
+            max_form_age: config.max_form_age,
+            captcha: config
+                .captcha
+                .as_ref()
+                .map(CaptchaVerifierImpl::from_config),
+        }
+    }
+
+    /// Issues a form token, `None` when form tokens are disabled.
+    pub fn issue_form_token(&self) -> Option<String> {
+        self.form_tokens
+            .as_ref()
+            .map(|signer| signer.issue(unix_now()))
+    }
+
+    /// Runs the checks from the cheapest to the most expensive one.
+    pub async fn check(
+        &self,
+        fields: &BotFields,
+        client_ip: Option<IpAddr>,
+    ) -> Result<(), BotRejection> {
+        if !fields.website.is_empty() {
+            return Err(BotRejection::Honeypot);
+        }
+
+        if let Some(signer) = &self.form_tokens {
+            let token = fields
+                .form_token
+                .as_deref()
+                .ok_or(BotRejection::MissingFormToken)?;
+            let token = signer.verify(token).ok_or(BotRejection::InvalidFormToken)?;
+            let age = unix_now().saturating_sub(token.issued_at);
+            if age < self.min_fill_time {
+                return Err(BotRejection::TooFast);
+            }
+            if age > self.max_form_age {
+                return Err(BotRejection::ExpiredFormToken);
+            }
+            match self.use_form_token(&token).await {
+                Ok(true) => {}
+                Ok(false) => return Err(BotRejection::ReusedFormToken),
+                Err(err) => {
+                    tracing::error!("Failed to record the use of a form token: {}", err);
+                    return Err(BotRejection::FormTokenUnavailable);
+                }
+            }
+        }
+
+        if let Some(captcha) = &self.captcha {
+            let response = fields
+                .captcha_response
+                .as_deref()
+                .filter(|response| !response.is_empty())
+                .ok_or(BotRejection::MissingCaptcha)?;
+            match captcha.verify(response, client_ip).await {
+                Ok(true) => {}
+                Ok(false) => return Err(BotRejection::FailedCaptcha),
+                Err(err) => {
+                    tracing::error!("Failed to verify the captcha: {}", err);
+                    return Err(BotRejection::CaptchaUnavailable);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Records the nonce of `token`, returns false when it was already used.
+    async fn use_form_token(&self, token: &FormToken) -> Result<bool, sqlx::Error> {
+        let inserted = sqlx::query!(
+            "INSERT INTO used_form_tokens (nonce) VALUES ($1) ON CONFLICT DO NOTHING",
+            token.nonce
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+        Ok(inserted == 1)
+    }
+
+    /// Forgets the nonces of the tokens that have expired since they were
+    /// used.
+    pub async fn prune_form_tokens(&self) -> CoreResult<u64> {
+        let pruned = sqlx::query!(
+            "DELETE FROM used_form_tokens WHERE used_at < now() - make_interval(secs => $1)",
+            self.max_form_age as f64
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+        Ok(pruned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use secrecy::SecretString;
+
+    use super::*;
+
+    /// Checks rejecting the form before the token is recorded never connect
+    /// to the database.
+    fn protection(min_fill_time: u64) -> BotProtection {
+        BotProtection::from_config(
+            &BotProtectionConfig {
+                form_secret: Some(SecretString::new("secret".into())),
+                min_fill_time,
+                max_form_age: 3600,
+                captcha: None,
+            },
+            PgPool::connect_lazy("postgres://localhost/unused").unwrap(),
+        )
+    }
+
+    fn fields_with_token(token: String) -> BotFields {
+        BotFields {
+            form_token: Some(token),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn filled_honeypots_are_rejected() {
+        let fields = BotFields {
+            website: "http://spam.example".into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            protection(0).check(&fields, None).await,
+            Err(BotRejection::Honeypot)
+        );
+    }
+
+    #[tokio::test]
+    async fn forms_without_token_are_rejected() {
+        assert_eq!(
+            protection(0).check(&BotFields::default(), None).await,
+            Err(BotRejection::MissingFormToken)
+        );
+    }
+
+    #[tokio::test]
+    async fn forms_submitted_too_fast_are_rejected() {
+        let protection = protection(60);
+        let fields = fields_with_token(protection.issue_form_token().unwrap());
+        assert_eq!(
+            protection.check(&fields, None).await,
+            Err(BotRejection::TooFast)
+        );
+    }
+
+    #[tokio::test]
+    async fn expired_forms_are_rejected() {
+        let protection = protection(0);
+        let signer = FormTokenSigner::new(SecretString::new("secret".into()));
+        let fields = fields_with_token(signer.issue(unix_now() - 7200));
+        assert_eq!(
+            protection.check(&fields, None).await,
+            Err(BotRejection::ExpiredFormToken)
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use std::{io::BufRead, process::ExitCode};
+
+use clap::Args;
+use rand::{distributions::Alphanumeric, Rng};
+use secrecy::{ExposeSecret, SecretString};
+use zero2prod_core::{domain::NewUser, repository::UserRepository};
+
+use crate::{configuration::Configuration, repository::UserRepositoryImpl, service::hash_password};
+
+use super::CliResult;
+
+const GENERATED_PASSWORD_LENGTH: usize = 24;
+
+#[derive(Args, Debug, Clone)]
+pub struct CreateAdminArgs {
+    /// Name used to log in.
+    #[arg(long)]
+    pub username: String,
+    /// Read the password from the first line of stdin instead of generating one.
+    #[arg(long)]
+    pub password_stdin: bool,
+}
+
+/// Creates an admin, reading the password from the first line of `input` when
+/// asked to.
+pub async fn create_admin(
+    configuration: Configuration,
+    args: CreateAdminArgs,
+    mut input: impl BufRead,
+) -> CliResult {
+    let username = args.username.trim();
+    if username.is_empty() || username.len() > 64 || username.contains(char::is_whitespace) {
+        eprintln!("Usernames must be 1 to 64 characters long, without whitespaces");
+        return Ok(ExitCode::FAILURE);
+    }
+
+    let (password, generated) = match args.password_stdin {
+        true => {
+            let mut line = String::new();
+            input.read_line(&mut line)?;
+            (SecretString::new(line.trim_end().to_owned()), false)
+        }
+        false => (generate_password(), true),
+    };
+    if password.expose_secret().is_empty() {
+        eprintln!("The password cannot be empty");
+        return Ok(ExitCode::FAILURE);
+    }
+
+    let pool = super::connect(&configuration).await?;
+    let new_user = NewUser {
+        username: username.to_owned(),
+        password_hash: hash_password(&password).map_err(|e| e.to_string())?,
+    };
+    let result = UserRepositoryImpl::new(pool.clone())
+        .create(&new_user)
+        .await;
+    pool.close().await;
+
+    let user_id = result.map_err(|e| e.to_string())?;
+    println!("Created admin {} ({})", username, user_id);
+    if generated {
+        println!("Password: {}", password.expose_secret());
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn generate_password() -> SecretString {
+    SecretString::new(
+        rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(GENERATED_PASSWORD_LENGTH)
+            .map(char::from)
+            .collect(),
+    )
+}
@@ -0,0 +1,28 @@
+use std::process::ExitCode;
+
+use clap::Subcommand;
+
+use crate::configuration::Configuration;
+
+use super::CliResult;
+
+#[derive(Subcommand, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigCommand {
+    /// Print the effective configuration, with secrets redacted.
+    Check,
+}
+
+pub async fn run(configuration: Configuration, command: ConfigCommand) -> CliResult {
+    match command {
+        ConfigCommand::Check => {
+            println!("{}", render(&configuration)?);
+            Ok(ExitCode::SUCCESS)
+        }
+    }
+}
+
+/// Renders the configuration merged from the files of the active profile and
+/// the environment.
+pub fn render(configuration: &Configuration) -> Result<String, serde_json::Error> {
+    serde_json::to_string_pretty(configuration)
+}
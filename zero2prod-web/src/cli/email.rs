@@ -0,0 +1,76 @@
+use std::{process::ExitCode, sync::Arc};
+
+use clap::{Args, ValueEnum};
+use email_address::EmailAddress;
+use zero2prod_core::{
+    domain::{Document, DocumentKind},
+    service::email_service::EmailService,
+};
+
+use crate::{
+    configuration::Configuration, metrics::Metrics, service::EmailServiceImpl,
+    template::TemplateEngine,
+};
+
+use super::CliResult;
+
+#[derive(Args, Debug, Clone)]
+pub struct SendTestEmailArgs {
+    /// Recipient of the test email.
+    pub recipient: EmailAddress,
+    /// Document to render.
+    #[arg(long, value_enum, default_value_t = TestEmailKind::Confirmation)]
+    pub kind: TestEmailKind,
+    /// Print the rendered email instead of sending it.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestEmailKind {
+    Confirmation,
+}
+
+impl TestEmailKind {
+    fn document(self, configuration: &Configuration) -> Document {
+        match self {
+            TestEmailKind::Confirmation => Document::new(
+                "Welcome ! (test)".into(),
+                DocumentKind::Confirmation {
+                    confirmation_link: format!(
+                        "http://{}/subscriptions/confirm?token=test",
+                        configuration.app.host
+                    ),
+                    unsubscribe_link: format!(
+                        "http://{}/subscriptions/unsubscribe?token=test",
+                        configuration.app.host
+                    ),
+                },
+            ),
+        }
+    }
+}
+
+pub async fn send_test_email(configuration: Configuration, args: SendTestEmailArgs) -> CliResult {
+    let template_engine = Arc::new(TemplateEngine::init());
+    let document = args.kind.document(&configuration);
+
+    if args.dry_run {
+        println!("Subject: {}\n", document.title);
+        println!("{}", template_engine.render(&document));
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let email_client = EmailServiceImpl::from_config(
+        &configuration.email_client,
+        template_engine,
+        Arc::new(Metrics::new()),
+    );
+    email_client
+        .send_email(args.recipient.as_ref(), document)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    println!("Sent a {:?} email to {}", args.kind, args.recipient);
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,56 @@
+use std::process::ExitCode;
+
+use clap::Subcommand;
+
+use crate::{configuration::Configuration, migration};
+
+use super::CliResult;
+
+#[derive(Subcommand, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrateCommand {
+    /// Apply the pending migrations.
+    Up,
+    /// List the migrations and whether they have been applied.
+    Status,
+    /// Exit with a non zero status when migrations are pending.
+    Check,
+}
+
+pub async fn run(configuration: Configuration, command: MigrateCommand) -> CliResult {
+    let pool = super::connect(&configuration).await?;
+
+    let code = match command {
+        MigrateCommand::Up => {
+            migration::run(&pool).await?;
+            ExitCode::SUCCESS
+        }
+        MigrateCommand::Status => {
+            for migration in migration::status(&pool).await? {
+                println!(
+                    "{:<16} {:<8} {}",
+                    migration.version,
+                    if migration.applied {
+                        "applied"
+                    } else {
+                        "pending"
+                    },
+                    migration.description
+                );
+            }
+            ExitCode::SUCCESS
+        }
+        MigrateCommand::Check => {
+            let pending = migration::pending_migrations(&pool).await?;
+            if pending.is_empty() {
+                println!("Database is up to date");
+                ExitCode::SUCCESS
+            } else {
+                println!("{} pending migration(s): {:?}", pending.len(), pending);
+                ExitCode::FAILURE
+            }
+        }
+    };
+
+    pool.close().await;
+    Ok(code)
+}
@@ -0,0 +1,144 @@
+mod admin;
+mod config;
+mod email;
+mod migrate;
+mod serve;
+
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use sqlx::{postgres::PgPoolOptions, PgPool};
+
+use crate::configuration::{Configuration, WithDb};
+
+pub use admin::{create_admin, CreateAdminArgs};
+pub use config::{render as render_config, ConfigCommand};
+pub use email::{send_test_email, SendTestEmailArgs, TestEmailKind};
+pub use migrate::MigrateCommand;
+pub use serve::{prepare_database, run as serve, MigrationPolicy, ServeArgs};
+
+pub type CliResult = Result<ExitCode, Box<dyn std::error::Error>>;
+
+/// zero2prod newsletter service.
+#[derive(Parser, Debug)]
+#[command(name = "zero2prod", version)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Serve the application.
+    Serve(ServeArgs),
+    /// Manage the database schema.
+    Migrate {
+        #[command(subcommand)]
+        command: MigrateCommand,
+    },
+    /// Create a user allowed to use the admin endpoints.
+    CreateAdmin(CreateAdminArgs),
+    /// Render an email and send it with the configured email provider.
+    SendTestEmail(SendTestEmailArgs),
+    /// Inspect the configuration.
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+impl Cli {
+    pub async fn run(self, configuration: Configuration) -> CliResult {
+        match self.command {
+            Command::Serve(args) => serve::run(configuration, args).await,
+            Command::Migrate { command } => migrate::run(configuration, command).await,
+            Command::CreateAdmin(args) => {
+                admin::create_admin(configuration, args, std::io::stdin().lock()).await
+            }
+            Command::SendTestEmail(args) => email::send_test_email(configuration, args).await,
+            Command::Config { command } => config::run(configuration, command).await,
+        }
+    }
+}
+
+/// Pool used by the one-off commands, connected eagerly so that a wrong
+/// configuration is reported straight away.
+async fn connect(configuration: &Configuration) -> Result<PgPool, sqlx::Error> {
+    PgPoolOptions::new()
+        .max_connections(1)
+        .acquire_timeout(std::time::Duration::from_millis(configuration.db.timeout))
+        .connect_with(configuration.db.connection_options(WithDb::Yes))
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn cli_is_well_formed() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn serve_runs_migrations_by_default() {
+        let cli = Cli::parse_from(["zero2prod", "serve"]);
+        assert!(matches!(
+            cli.command,
+            Command::Serve(ServeArgs {
+                migrations: MigrationPolicy::Run
+            })
+        ));
+    }
+
+    #[test]
+    fn serve_can_require_migrations() {
+        let cli = Cli::parse_from(["zero2prod", "serve", "--migrations", "require"]);
+        assert!(matches!(
+            cli.command,
+            Command::Serve(ServeArgs {
+                migrations: MigrationPolicy::Require
+            })
+        ));
+    }
+
+    #[test]
+    fn send_test_email_is_parsed() {
+        let cli = Cli::parse_from([
+            "zero2prod",
+            "send-test-email",
+            "john.doe@gmail.com",
+            "--kind",
+            "confirmation",
+        ]);
+        assert!(matches!(
+            cli.command,
+            Command::SendTestEmail(SendTestEmailArgs {
+                kind: TestEmailKind::Confirmation,
+                dry_run: false,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn send_test_email_rejects_invalid_recipients() {
+        let cli = Cli::try_parse_from(["zero2prod", "send-test-email", "not-an-email"]);
+        assert!(cli.is_err());
+    }
+
+    #[test]
+    fn migrate_subcommands_are_parsed() {
+        for (arg, expected) in [
+            ("up", MigrateCommand::Up),
+            ("status", MigrateCommand::Status),
+            ("check", MigrateCommand::Check),
+        ] {
+            let cli = Cli::parse_from(["zero2prod", "migrate", arg]);
+            assert!(matches!(cli.command, Command::Migrate { command } if command == expected));
+        }
+    }
+}
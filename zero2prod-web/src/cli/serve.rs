@@ -0,0 +1,72 @@
+use std::process::ExitCode;
+
+use clap::{Args, ValueEnum};
+use tracing::{error, info};
+
+use crate::{configuration::Configuration, migration, server};
+
+use super::CliResult;
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct ServeArgs {
+    /// What to do with pending migrations before accepting traffic.
+    #[arg(long, value_enum, default_value_t = MigrationPolicy::Run)]
+    pub migrations: MigrationPolicy,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MigrationPolicy {
+    /// Apply pending migrations.
+    #[default]
+    Run,
+    /// Refuse to start when migrations are pending.
+    Require,
+    /// Start without looking at migrations.
+    Skip,
+}
+
+pub async fn run(configuration: Configuration, args: ServeArgs) -> CliResult {
+    if !prepare_database(&configuration, args.migrations).await? {
+        return Ok(ExitCode::FAILURE);
+    }
+
+    let application = server::start(&configuration).await;
+    application.run_until_stopped().await?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Brings the database in line with the migration policy, returns `false` when
+/// the application must not start.
+pub async fn prepare_database(
+    configuration: &Configuration,
+    policy: MigrationPolicy,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    if policy == MigrationPolicy::Skip {
+        return Ok(true);
+    }
+
+    let pool = super::connect(configuration).await?;
+    let ready = match policy {
+        MigrationPolicy::Run => {
+            migration::run(&pool).await?;
+            true
+        }
+        MigrationPolicy::Require => {
+            let pending = migration::pending_migrations(&pool).await?;
+            if !pending.is_empty() {
+                error!(
+                    "Refusing to start, {} migration(s) are pending: {:?}",
+                    pending.len(),
+                    pending
+                );
+            } else {
+                info!("Database migrations are up to date");
+            }
+            pending.is_empty()
+        }
+        MigrationPolicy::Skip => true,
+    };
+
+    pool.close().await;
+    Ok(ready)
+}
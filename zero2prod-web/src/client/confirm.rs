@@ -0,0 +1,35 @@
+use super::Z2PClient;
+
+impl Z2PClient {
+    pub async fn confirm(&self, token: &str) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .get(format!("{}/subscriptions/confirm", self.base_url))
+            .query(&[("token", token)])
+            .send()
+            .await
+    }
+
+    pub async fn resend_confirmation(&self, token: &str) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .post(format!("{}/subscriptions/confirm/resend", self.base_url))
+            .form(&[("token", token)])
+            .send()
+            .await
+    }
+
+    pub async fn unsubscribe_page(&self, token: &str) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .get(format!("{}/subscriptions/unsubscribe", self.base_url))
+            .query(&[("token", token)])
+            .send()
+            .await
+    }
+
+    pub async fn unsubscribe(&self, token: &str) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .post(format!("{}/subscriptions/unsubscribe", self.base_url))
+            .query(&[("token", token)])
+            .send()
+            .await
+    }
+}
@@ -0,0 +1,34 @@
+use reqwest::header::COOKIE;
+
+use super::Z2PClient;
+
+impl Z2PClient {
+    /// Loads a page of the dashboard with the `cookies`, as in a `Cookie`
+    /// header.
+    pub async fn dashboard_page(
+        &self,
+        path: &str,
+        cookies: &str,
+    ) -> reqwest::Result<reqwest::Response> {
+        self.browser
+            .get(format!("{}{}", self.base_url, path))
+            .header(COOKIE, cookies)
+            .send()
+            .await
+    }
+
+    /// Submits a form of the dashboard with the `cookies`.
+    pub async fn dashboard_form(
+        &self,
+        path: &str,
+        cookies: &str,
+        form: &[(&str, &str)],
+    ) -> reqwest::Result<reqwest::Response> {
+        self.browser
+            .post(format!("{}{}", self.base_url, path))
+            .header(COOKIE, cookies)
+            .form(form)
+            .send()
+            .await
+    }
+}
@@ -0,0 +1,37 @@
+use super::Z2PClient;
+
+impl Z2PClient {
+    /// Asks for the links to export or erase the data of `email`.
+    pub async fn request_data_access(&self, email: &str) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .post(format!("{}/me", self.base_url))
+            .form(&[("email", email)])
+            .send()
+            .await
+    }
+
+    pub async fn export_data(&self, token: &str) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .get(format!("{}/me/export", self.base_url))
+            .query(&[("token", token)])
+            .send()
+            .await
+    }
+
+    /// The page the erase link of the data access email leads to.
+    pub async fn erase_page(&self, token: &str) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .get(format!("{}/me/erase", self.base_url))
+            .query(&[("token", token)])
+            .send()
+            .await
+    }
+
+    pub async fn erase_data(&self, token: &str) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .post(format!("{}/me/erase", self.base_url))
+            .form(&[("token", token)])
+            .send()
+            .await
+    }
+}
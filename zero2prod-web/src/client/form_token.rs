@@ -0,0 +1,10 @@
+use super::Z2PClient;
+
+impl Z2PClient {
+    pub async fn form_token(&self) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .get(format!("{}/subscriptions/form-token", self.base_url))
+            .send()
+            .await
+    }
+}
@@ -3,7 +3,21 @@ use super::Z2PClient;
 impl Z2PClient {
     pub async fn health_check(&self) -> reqwest::Result<reqwest::Response> {
         self.client
-            .get(&format!("{}/health_check", self.base_url))
+            .get(format!("{}/health_check", self.base_url))
+            .send()
+            .await
+    }
+
+    pub async fn health_live(&self) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .get(format!("{}/health/live", self.base_url))
+            .send()
+            .await
+    }
+
+    pub async fn health_ready(&self) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .get(format!("{}/health/ready", self.base_url))
             .send()
             .await
     }
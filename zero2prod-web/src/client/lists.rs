@@ -0,0 +1,105 @@
+use serde::Serialize;
+
+use super::{Credentials, Z2PClient};
+
+impl Z2PClient {
+    pub async fn create_list<T>(
+        &self,
+        credentials: &Credentials,
+        list: &T,
+    ) -> reqwest::Result<reqwest::Response>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.client
+            .post(format!("{}/lists", self.base_url))
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .json(list)
+            .send()
+            .await
+    }
+
+    pub async fn list_lists(
+        &self,
+        credentials: &Credentials,
+    ) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .get(format!("{}/lists", self.base_url))
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .send()
+            .await
+    }
+
+    pub async fn get_list(
+        &self,
+        credentials: &Credentials,
+        slug: &str,
+    ) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .get(format!("{}/lists/{}", self.base_url, slug))
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .send()
+            .await
+    }
+
+    pub async fn update_list<T>(
+        &self,
+        credentials: &Credentials,
+        slug: &str,
+        list: &T,
+    ) -> reqwest::Result<reqwest::Response>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.client
+            .put(format!("{}/lists/{}", self.base_url, slug))
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .json(list)
+            .send()
+            .await
+    }
+
+    pub async fn create_topic<T>(
+        &self,
+        credentials: &Credentials,
+        list: &str,
+        topic: &T,
+    ) -> reqwest::Result<reqwest::Response>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.client
+            .post(format!("{}/lists/{}/topics", self.base_url, list))
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .json(topic)
+            .send()
+            .await
+    }
+
+    pub async fn list_topics(
+        &self,
+        credentials: &Credentials,
+        list: &str,
+    ) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .get(format!("{}/lists/{}/topics", self.base_url, list))
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .send()
+            .await
+    }
+
+    /// Counts the subscribers of the list in the segment.
+    pub async fn count_segment(
+        &self,
+        credentials: &Credentials,
+        list: &str,
+        segment: &str,
+    ) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .post(format!("{}/lists/{}/segments/count", self.base_url, list))
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .json(&serde_json::json!({ "segment": segment }))
+            .send()
+            .await
+    }
+}
@@ -0,0 +1,27 @@
+use super::Z2PClient;
+
+impl Z2PClient {
+    pub async fn get_log_filter(&self, token: &str) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .get(format!("{}/admin/log-filter", self.base_url))
+            .bearer_auth(token)
+            .send()
+            .await
+    }
+
+    pub async fn set_log_filter<T>(
+        &self,
+        token: &str,
+        directives: T,
+    ) -> reqwest::Result<reqwest::Response>
+    where
+        T: Into<reqwest::Body>,
+    {
+        self.client
+            .put(format!("{}/admin/log-filter", self.base_url))
+            .bearer_auth(token)
+            .body(directives)
+            .send()
+            .await
+    }
+}
@@ -0,0 +1,10 @@
+use super::Z2PClient;
+
+impl Z2PClient {
+    pub async fn metrics(&self) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .get(format!("{}/metrics", self.base_url))
+            .send()
+            .await
+    }
+}
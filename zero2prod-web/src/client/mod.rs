@@ -1,9 +1,32 @@
+mod confirm;
+mod dashboard;
+mod data_subject;
+mod form_token;
 mod health_check;
+mod lists;
+mod log_filter;
+mod metrics;
+mod newsletter_issues;
+mod preferences;
 mod subscribe;
+mod subscriber_export;
+mod subscriber_import;
+mod subscribers;
+
+pub use subscribers::SubscriberQuery;
 
 pub struct Z2PClient {
     base_url: String,
     client: reqwest::Client,
+    /// Does not follow redirects, so that the cookies and redirects of the
+    /// dashboard can be checked.
+    browser: reqwest::Client,
+}
+
+/// Basic credentials of a user.
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
 }
 
 impl Z2PClient {
@@ -11,6 +34,10 @@ impl Z2PClient {
         Self {
             base_url,
             client: reqwest::Client::new(),
+            browser: reqwest::Client::builder()
+                .redirect(reqwest::redirect::Policy::none())
+                .build()
+                .expect("Failed to build the browser client"),
         }
     }
 }
@@ -0,0 +1,192 @@
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::{Credentials, Z2PClient};
+
+impl Z2PClient {
+    pub async fn create_issue<T>(
+        &self,
+        credentials: &Credentials,
+        issue: &T,
+    ) -> reqwest::Result<reqwest::Response>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.client
+            .post(format!("{}/newsletters/issues", self.base_url))
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .json(issue)
+            .send()
+            .await
+    }
+
+    pub async fn list_issues(
+        &self,
+        credentials: &Credentials,
+        status: Option<&str>,
+    ) -> reqwest::Result<reqwest::Response> {
+        let mut request = self
+            .client
+            .get(format!("{}/newsletters/issues", self.base_url))
+            .basic_auth(&credentials.username, Some(&credentials.password));
+        if let Some(status) = status {
+            request = request.query(&[("status", status)]);
+        }
+        request.send().await
+    }
+
+    pub async fn get_issue(
+        &self,
+        credentials: &Credentials,
+        issue_id: Uuid,
+    ) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .get(format!("{}/newsletters/issues/{}", self.base_url, issue_id))
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .send()
+            .await
+    }
+
+    pub async fn update_issue<T>(
+        &self,
+        credentials: &Credentials,
+        issue_id: Uuid,
+        issue: &T,
+    ) -> reqwest::Result<reqwest::Response>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.client
+            .put(format!("{}/newsletters/issues/{}", self.base_url, issue_id))
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .json(issue)
+            .send()
+            .await
+    }
+
+    pub async fn delete_issue(
+        &self,
+        credentials: &Credentials,
+        issue_id: Uuid,
+    ) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .delete(format!("{}/newsletters/issues/{}", self.base_url, issue_id))
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .send()
+            .await
+    }
+
+    pub async fn preview_issue(
+        &self,
+        credentials: &Credentials,
+        issue_id: Uuid,
+    ) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .get(format!(
+                "{}/newsletters/issues/{}/preview",
+                self.base_url, issue_id
+            ))
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .send()
+            .await
+    }
+
+    /// Schedules the issue at `send_at`, an RFC 3339 date time.
+    pub async fn schedule_issue(
+        &self,
+        credentials: &Credentials,
+        issue_id: Uuid,
+        send_at: &str,
+    ) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .post(format!(
+                "{}/newsletters/issues/{}/schedule",
+                self.base_url, issue_id
+            ))
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .json(&serde_json::json!({ "send_at": send_at }))
+            .send()
+            .await
+    }
+
+    pub async fn unschedule_issue(
+        &self,
+        credentials: &Credentials,
+        issue_id: Uuid,
+    ) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .delete(format!(
+                "{}/newsletters/issues/{}/schedule",
+                self.base_url, issue_id
+            ))
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .send()
+            .await
+    }
+
+    pub async fn publish_issue(
+        &self,
+        credentials: &Credentials,
+        issue_id: Uuid,
+    ) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .post(format!(
+                "{}/newsletters/issues/{}/publish",
+                self.base_url, issue_id
+            ))
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .send()
+            .await
+    }
+
+    pub async fn publish_issue_idempotently(
+        &self,
+        credentials: &Credentials,
+        issue_id: Uuid,
+        idempotency_key: &str,
+    ) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .post(format!(
+                "{}/newsletters/issues/{}/publish",
+                self.base_url, issue_id
+            ))
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .header("Idempotency-Key", idempotency_key)
+            .send()
+            .await
+    }
+
+    /// Publishes the issue to the subscribers in `segment` only.
+    pub async fn publish_issue_to_segment(
+        &self,
+        credentials: &Credentials,
+        issue_id: Uuid,
+        segment: &str,
+    ) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .post(format!(
+                "{}/newsletters/issues/{}/publish",
+                self.base_url, issue_id
+            ))
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .json(&serde_json::json!({ "segment": segment }))
+            .send()
+            .await
+    }
+
+    /// Loads the tracking pixel of an issue.
+    pub async fn issue_opened(
+        &self,
+        issue_id: Uuid,
+        token: &str,
+    ) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .get(format!(
+                "{}/newsletters/issues/{}/opened",
+                self.base_url, issue_id
+            ))
+            .query(&[("token", token)])
+            .send()
+            .await
+    }
+}
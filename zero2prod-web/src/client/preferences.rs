@@ -0,0 +1,26 @@
+use super::Z2PClient;
+
+impl Z2PClient {
+    pub async fn preferences(&self, token: &str) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .get(format!("{}/preferences", self.base_url))
+            .query(&[("token", token)])
+            .send()
+            .await
+    }
+
+    /// Submits the preference center with the `topics` checked.
+    pub async fn update_preferences(
+        &self,
+        token: &str,
+        topics: &[&str],
+    ) -> reqwest::Result<reqwest::Response> {
+        let mut form = vec![("token", token)];
+        form.extend(topics.iter().map(|topic| ("topics", *topic)));
+        self.client
+            .post(format!("{}/preferences", self.base_url))
+            .form(&form)
+            .send()
+            .await
+    }
+}
@@ -1,15 +1,35 @@
 use super::Z2PClient;
 
 impl Z2PClient {
-    pub async fn subscribe<T>(&self, body: T) -> reqwest::Result<reqwest::Response>
+    pub async fn subscribe<T>(&self, list: &str, body: T) -> reqwest::Result<reqwest::Response>
     where
         T: Into<reqwest::Body>,
     {
         self.client
-            .post(&format!("{}/subscriptions", self.base_url))
+            .post(format!("{}/lists/{}/subscriptions", self.base_url, list))
             .header("Content-Type", "application/x-www-form-urlencoded")
             .body(body)
             .send()
             .await
     }
+
+    /// Subscribes with an `Idempotency-Key`, retries with the same key get
+    /// the response to the first request back.
+    pub async fn subscribe_idempotently<T>(
+        &self,
+        list: &str,
+        body: T,
+        idempotency_key: &str,
+    ) -> reqwest::Result<reqwest::Response>
+    where
+        T: Into<reqwest::Body>,
+    {
+        self.client
+            .post(format!("{}/lists/{}/subscriptions", self.base_url, list))
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .header("Idempotency-Key", idempotency_key)
+            .body(body)
+            .send()
+            .await
+    }
 }
@@ -0,0 +1,18 @@
+use super::{Credentials, Z2PClient};
+
+impl Z2PClient {
+    /// Exports the subscribers, `params` picks the format and optionally the
+    /// status.
+    pub async fn export_subscribers(
+        &self,
+        credentials: &Credentials,
+        params: &[(&str, &str)],
+    ) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .get(format!("{}/subscribers/export", self.base_url))
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .query(params)
+            .send()
+            .await
+    }
+}
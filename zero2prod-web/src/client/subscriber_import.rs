@@ -0,0 +1,57 @@
+use uuid::Uuid;
+
+use super::{Credentials, Z2PClient};
+
+impl Z2PClient {
+    /// Imports the subscribers of a CSV file, `params` names the list and
+    /// optionally maps the columns and subscribes the rows as confirmed.
+    pub async fn import_subscribers<T>(
+        &self,
+        credentials: &Credentials,
+        params: &[(&str, &str)],
+        csv: T,
+    ) -> reqwest::Result<reqwest::Response>
+    where
+        T: Into<reqwest::Body>,
+    {
+        self.client
+            .post(format!("{}/subscribers/import", self.base_url))
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .header("Content-Type", "text/csv")
+            .query(params)
+            .body(csv)
+            .send()
+            .await
+    }
+
+    pub async fn get_import(
+        &self,
+        credentials: &Credentials,
+        import_id: Uuid,
+    ) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .get(format!(
+                "{}/subscribers/import/{}",
+                self.base_url, import_id
+            ))
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .send()
+            .await
+    }
+
+    /// The CSV report of the rows an import did not import.
+    pub async fn import_errors(
+        &self,
+        credentials: &Credentials,
+        import_id: Uuid,
+    ) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .get(format!(
+                "{}/subscribers/import/{}/errors",
+                self.base_url, import_id
+            ))
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .send()
+            .await
+    }
+}
@@ -0,0 +1,76 @@
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::{Credentials, Z2PClient};
+
+/// Parameters of a listing of subscribers, every one is optional.
+#[derive(Serialize, Default)]
+pub struct SubscriberQuery<'a> {
+    pub list: Option<&'a str>,
+    pub status: Option<&'a str>,
+    /// RFC 3339 date the subscribers subscribed at or after.
+    pub subscribed_after: Option<&'a str>,
+    /// RFC 3339 date the subscribers subscribed before.
+    pub subscribed_before: Option<&'a str>,
+    pub search: Option<&'a str>,
+    /// The `next` cursor of the previous page.
+    pub after: Option<&'a str>,
+    pub limit: Option<u64>,
+}
+
+impl Z2PClient {
+    pub async fn list_subscribers(
+        &self,
+        credentials: &Credentials,
+        query: &SubscriberQuery<'_>,
+    ) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .get(format!("{}/subscribers", self.base_url))
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .query(query)
+            .send()
+            .await
+    }
+
+    pub async fn get_subscriber(
+        &self,
+        credentials: &Credentials,
+        subscriber_id: Uuid,
+    ) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .get(format!("{}/subscribers/{}", self.base_url, subscriber_id))
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .send()
+            .await
+    }
+
+    /// Changes the name, status or topics of a subscriber.
+    pub async fn update_subscriber<T>(
+        &self,
+        credentials: &Credentials,
+        subscriber_id: Uuid,
+        update: &T,
+    ) -> reqwest::Result<reqwest::Response>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.client
+            .patch(format!("{}/subscribers/{}", self.base_url, subscriber_id))
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .json(update)
+            .send()
+            .await
+    }
+
+    pub async fn delete_subscriber(
+        &self,
+        credentials: &Credentials,
+        subscriber_id: Uuid,
+    ) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .delete(format!("{}/subscribers/{}", self.base_url, subscriber_id))
+            .basic_auth(&credentials.username, Some(&credentials.password))
+            .send()
+            .await
+    }
+}
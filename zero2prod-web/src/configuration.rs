@@ -1,29 +1,64 @@
+use std::collections::HashMap;
+
 use config::Environment;
 use email_address::EmailAddress;
+use ipnet::IpNet;
 use secrecy::{ExposeSecret, SecretString};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize, Serializer};
 use sqlx::postgres::{PgConnectOptions, PgSslMode};
+use zero2prod_core::domain::{MissingRedactionKey, RedactionMode, Redactor};
 
 const DB_DEFAULT_TIMEOUT: u64 = 5000;
 const EMAIL_CLIENT_DEFAULT_TIMEOUT: u64 = 10000;
+const APP_DEFAULT_SHUTDOWN_TIMEOUT: u64 = 30000;
+const TOKENS_DEFAULT_CONFIRMATION_TTL: u64 = 48 * 3600;
+const TOKENS_DEFAULT_UNSUBSCRIBE_TTL: u64 = 365 * 24 * 3600;
+const TELEMETRY_DEFAULT_SERVICE_NAME: &str = "zero2prod";
+const TELEMETRY_DEFAULT_FILTER: &str = "zero2prod_web::layer::trace_id=trace,info";
 
-#[derive(Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Configuration {
     pub profile: String,
     pub app: AppConfig,
     pub db: DbConfig,
     pub email_client: EmailClientConfig,
+    pub telemetry: TelemetryConfig,
+    pub tokens: TokensConfig,
+    #[serde(default)]
+    pub admin: AdminConfig,
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    #[serde(default)]
+    pub bot_protection: BotProtectionConfig,
+    #[serde(default)]
+    pub subscriptions: SubscriptionsConfig,
+    #[serde(default)]
+    pub jobs: JobsConfig,
+    #[serde(default)]
+    pub newsletter: NewsletterConfig,
+    #[serde(default)]
+    pub idempotency: IdempotencyConfig,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct AppConfig {
     pub host: String,
     pub port: u16,
+    /// Public URL of the application, which the links sent to subscribers
+    /// point to.
+    pub url: String,
+    /// When set, operational endpoints such as `/metrics` are served on this
+    /// port instead of the public one.
+    pub admin_port: Option<u16>,
+    /// Time given to in-flight requests and background tasks to complete on
+    /// shutdown, in milliseconds.
+    pub shutdown_timeout: u64,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct DbConfig {
     pub username: String,
+    #[serde(serialize_with = "redact")]
     pub password: SecretString,
     pub host: String,
     pub port: u16,
@@ -32,12 +67,457 @@ pub struct DbConfig {
     pub ssl: bool,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct EmailClientConfig {
     pub base_url: String,
     pub sender_email: EmailAddress,
+    #[serde(serialize_with = "redact")]
     pub auth_token: SecretString,
     pub timeout: u64,
+    /// Path probed by the readiness check, the provider is not probed when unset.
+    pub health_check_path: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TelemetryConfig {
+    pub service_name: String,
+    #[serde(default)]
+    pub format: LogFormat,
+    /// Default `EnvFilter` directives, `RUST_LOG` takes precedence when set.
+    pub filter: String,
+    /// Spans are only exported to an OpenTelemetry collector when set.
+    pub otlp: Option<OtlpConfig>,
+    /// How personal data is rendered in logs and spans.
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct RedactionConfig {
+    #[serde(default)]
+    pub mode: RedactionMode,
+    /// Key of the digests rendered in the hash mode, which requires it.
+    #[serde(default, serialize_with = "redact_option")]
+    pub key: Option<SecretString>,
+}
+
+impl RedactionConfig {
+    pub fn redactor(&self) -> Result<Redactor, MissingRedactionKey> {
+        Redactor::new(self.mode, self.key.clone())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Json,
+    Pretty,
+    Compact,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct OtlpConfig {
+    pub endpoint: String,
+    #[serde(default)]
+    pub protocol: OtlpProtocol,
+    #[serde(default = "default_sampling_ratio")]
+    pub sampling_ratio: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OtlpProtocol {
+    #[default]
+    Grpc,
+    Http,
+}
+
+fn default_sampling_ratio() -> f64 {
+    1.0
+}
+
+/// Keys signing the confirmation and unsubscribe tokens sent to subscribers.
+///
+/// Keys are rotated by adding a key and making it the active one, the previous
+/// key is kept until the tokens it signed have expired.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TokensConfig {
+    /// Id of the key signing new tokens.
+    pub kid: String,
+    /// Keys by id, usually set through `Z2P_TOKENS_KEYS_<KID>`.
+    #[serde(default, serialize_with = "redact_values")]
+    pub keys: HashMap<String, SecretString>,
+    /// Validity of confirmation tokens, in seconds.
+    pub confirmation_ttl: u64,
+    /// Validity of unsubscribe tokens, in seconds.
+    pub unsubscribe_ttl: u64,
+    /// Tokens carried by the confirmation links, both kinds are accepted
+    /// whichever is sent.
+    #[serde(default)]
+    pub confirmation: ConfirmationTokens,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfirmationTokens {
+    /// Signed tokens, which expire after the confirmation ttl.
+    #[default]
+    Signed,
+    /// Random tokens stored with the subscriber, which do not expire.
+    Stored,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AdminConfig {
+    /// Bearer token granting access to the operational endpoints, which are
+    /// disabled when unset.
+    #[serde(serialize_with = "redact_option")]
+    pub token: Option<SecretString>,
+    /// Seconds an editor stays signed in to the dashboard.
+    #[serde(default = "default_session_ttl")]
+    pub session_ttl: u64,
+    /// Only sends the dashboard cookies over HTTPS, to be disabled when the
+    /// dashboard is served over plain HTTP.
+    #[serde(default = "default_secure_cookies")]
+    pub secure_cookies: bool,
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        Self {
+            token: None,
+            session_ttl: default_session_ttl(),
+            secure_cookies: default_secure_cookies(),
+        }
+    }
+}
+
+fn default_session_ttl() -> u64 {
+    8 * 3600
+}
+
+fn default_secure_cookies() -> bool {
+    true
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RateLimitConfig {
+    #[serde(default)]
+    pub storage: RateLimitStorage,
+    /// Proxies allowed to report the client address through `X-Forwarded-For`.
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpNet>,
+    /// Requests to the subscription, data access and login forms per client
+    /// IP.
+    #[serde(default = "default_per_ip_quota")]
+    pub per_ip: RateLimitQuota,
+    /// Confirmation emails per recipient.
+    #[serde(default = "default_per_email_quota")]
+    pub per_email: RateLimitQuota,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            storage: RateLimitStorage::default(),
+            trusted_proxies: Vec::new(),
+            per_ip: default_per_ip_quota(),
+            per_email: default_per_email_quota(),
+        }
+    }
+}
+
+/// Where the token buckets are kept, buckets held in memory are not shared
+/// between instances.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitStorage {
+    #[default]
+    Memory,
+    Postgres,
+}
+
+/// A token bucket holding up to `burst` tokens, refilled at a rate of `burst`
+/// tokens every `period` seconds.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct RateLimitQuota {
+    pub burst: u32,
+    pub period: u64,
+}
+
+fn default_per_ip_quota() -> RateLimitQuota {
+    RateLimitQuota {
+        burst: 10,
+        period: 60,
+    }
+}
+
+fn default_per_email_quota() -> RateLimitQuota {
+    RateLimitQuota {
+        burst: 3,
+        period: 3600,
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BotProtectionConfig {
+    /// Key signing the form tokens, subscription forms must carry a token
+    /// issued by `/subscriptions/form-token` when set.
+    #[serde(default, serialize_with = "redact_option")]
+    pub form_secret: Option<SecretString>,
+    /// Forms submitted less than `min_fill_time` seconds after their token
+    /// was issued are rejected.
+    #[serde(default = "default_min_fill_time")]
+    pub min_fill_time: u64,
+    /// Form tokens expire after `max_form_age` seconds, each is only accepted
+    /// once.
+    #[serde(default = "default_max_form_age")]
+    pub max_form_age: u64,
+    /// Subscription forms must carry a solved captcha when set.
+    pub captcha: Option<CaptchaConfig>,
+}
+
+impl Default for BotProtectionConfig {
+    fn default() -> Self {
+        Self {
+            form_secret: None,
+            min_fill_time: default_min_fill_time(),
+            max_form_age: default_max_form_age(),
+            captcha: None,
+        }
+    }
+}
+
+fn default_min_fill_time() -> u64 {
+    3
+}
+
+fn default_max_form_age() -> u64 {
+    86400
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CaptchaConfig {
+    pub provider: CaptchaProvider,
+    #[serde(serialize_with = "redact")]
+    pub secret: SecretString,
+    /// Verification endpoint, defaults to the one of the provider.
+    pub verify_url: Option<String>,
+    #[serde(default = "default_captcha_timeout")]
+    pub timeout: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptchaProvider {
+    HCaptcha,
+    Turnstile,
+}
+
+fn default_captcha_timeout() -> u64 {
+    5000
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SubscriptionsConfig {
+    /// Subscriptions still pending after `pending_ttl` seconds are deleted.
+    #[serde(default = "default_pending_ttl")]
+    pub pending_ttl: u64,
+    /// Seconds between two runs of the pending subscriptions expiry, unless
+    /// `jobs.schedules` sets another schedule.
+    #[serde(default = "default_expiry_interval")]
+    pub expiry_interval: u64,
+    /// Version of the privacy policy subscribers agree to, recorded with
+    /// their consents.
+    #[serde(default)]
+    pub policy_version: Option<String>,
+    /// Records the digest of the IP and user agent of subscribers rather than
+    /// the values themselves.
+    #[serde(default)]
+    pub hash_client_info: bool,
+    /// Rows of an import upserted at once.
+    #[serde(default = "default_import_batch_size")]
+    pub import_batch_size: usize,
+    /// Rows of an export read from the database at once.
+    #[serde(default = "default_export_batch_size")]
+    pub export_batch_size: usize,
+}
+
+impl Default for SubscriptionsConfig {
+    fn default() -> Self {
+        Self {
+            pending_ttl: default_pending_ttl(),
+            expiry_interval: default_expiry_interval(),
+            policy_version: None,
+            hash_client_info: false,
+            import_batch_size: default_import_batch_size(),
+            export_batch_size: default_export_batch_size(),
+        }
+    }
+}
+
+fn default_pending_ttl() -> u64 {
+    7 * 24 * 3600
+}
+
+fn default_expiry_interval() -> u64 {
+    3600
+}
+
+fn default_import_batch_size() -> usize {
+    1000
+}
+
+fn default_export_batch_size() -> usize {
+    1000
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NewsletterConfig {
+    /// Attempts to send an issue to a subscriber before giving up.
+    #[serde(default = "default_max_delivery_attempts")]
+    pub max_delivery_attempts: u32,
+    /// Seconds after which a delivery still being sent is considered
+    /// interrupted, it is then failed rather than sent again.
+    #[serde(default = "default_interrupted_after")]
+    pub interrupted_after: u64,
+}
+
+impl Default for NewsletterConfig {
+    fn default() -> Self {
+        Self {
+            max_delivery_attempts: default_max_delivery_attempts(),
+            interrupted_after: default_interrupted_after(),
+        }
+    }
+}
+
+fn default_max_delivery_attempts() -> u32 {
+    3
+}
+
+fn default_interrupted_after() -> u64 {
+    600
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct IdempotencyConfig {
+    /// Seconds an idempotency key is valid for, a request reusing the key
+    /// after that is processed again.
+    #[serde(default = "default_idempotency_ttl")]
+    pub ttl: u64,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            ttl: default_idempotency_ttl(),
+        }
+    }
+}
+
+fn default_idempotency_ttl() -> u64 {
+    24 * 3600
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct JobsConfig {
+    /// Number of jobs run concurrently by each instance.
+    #[serde(default = "default_job_workers")]
+    pub workers: usize,
+    /// Time idle workers wait before looking for due jobs again, in
+    /// milliseconds.
+    #[serde(default = "default_job_poll_interval")]
+    pub poll_interval: u64,
+    /// Seconds a worker may go without renewing the lease of its job before
+    /// the job is considered lost and handed to another worker. Running jobs
+    /// get their lease renewed every third of it.
+    #[serde(default = "default_job_lease")]
+    pub lease: u64,
+    /// Runs of a failing job before it is left dead.
+    #[serde(default = "default_job_max_attempts")]
+    pub max_attempts: u32,
+    /// Delay before the first retry of a failing job, doubled after every
+    /// attempt up to `backoff_max`, in seconds.
+    #[serde(default = "default_job_backoff_base")]
+    pub backoff_base: u64,
+    #[serde(default = "default_job_backoff_max")]
+    pub backoff_max: u64,
+    /// Seconds the succeeded jobs are kept for.
+    #[serde(default = "default_job_retention")]
+    pub retention: u64,
+    /// Cron expressions, or intervals such as `@every 60s`, overriding the
+    /// schedule of the recurring jobs, by job kind. An empty expression
+    /// disables the schedule.
+    #[serde(default)]
+    pub schedules: HashMap<String, String>,
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        Self {
+            workers: default_job_workers(),
+            poll_interval: default_job_poll_interval(),
+            lease: default_job_lease(),
+            max_attempts: default_job_max_attempts(),
+            backoff_base: default_job_backoff_base(),
+            backoff_max: default_job_backoff_max(),
+            retention: default_job_retention(),
+            schedules: HashMap::new(),
+        }
+    }
+}
+
+fn default_job_workers() -> usize {
+    2
+}
+
+fn default_job_poll_interval() -> u64 {
+    1000
+}
+
+fn default_job_lease() -> u64 {
+    300
+}
+
+fn default_job_max_attempts() -> u32 {
+    5
+}
+
+fn default_job_backoff_base() -> u64 {
+    10
+}
+
+fn default_job_backoff_max() -> u64 {
+    3600
+}
+
+fn default_job_retention() -> u64 {
+    7 * 24 * 3600
+}
+
+const REDACTED: &str = "[REDACTED]";
+
+fn redact<S: Serializer>(_: &SecretString, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(REDACTED)
+}
+
+fn redact_option<S: Serializer>(
+    secret: &Option<SecretString>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match secret {
+        Some(_) => serializer.serialize_some(REDACTED),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn redact_values<S: Serializer>(
+    secrets: &HashMap<String, SecretString>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(secrets.keys().map(|key| (key, REDACTED)))
 }
 
 #[derive(PartialEq, Eq)]
@@ -103,8 +583,13 @@ fn get_configuration_with_profile(
             .separator("_"),
     );
     builder = builder
+        .set_default("app.shutdown_timeout", APP_DEFAULT_SHUTDOWN_TIMEOUT)?
         .set_default("db.timeout", DB_DEFAULT_TIMEOUT)?
         .set_default("email_client.timeout", EMAIL_CLIENT_DEFAULT_TIMEOUT)?
+        .set_default("tokens.confirmation_ttl", TOKENS_DEFAULT_CONFIRMATION_TTL)?
+        .set_default("tokens.unsubscribe_ttl", TOKENS_DEFAULT_UNSUBSCRIBE_TTL)?
+        .set_default("telemetry.service_name", TELEMETRY_DEFAULT_SERVICE_NAME)?
+        .set_default("telemetry.filter", TELEMETRY_DEFAULT_FILTER)?
         .set_override("profile", app_profile)?;
 
     let configuration = builder.build()?;
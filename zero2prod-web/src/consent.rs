@@ -0,0 +1,67 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use axum::extract::{ConnectInfo, FromRequestParts};
+use http::{header::USER_AGENT, request::Parts, HeaderMap};
+use zero2prod_core::domain::{ConsentContext, ConsentSource};
+
+use crate::{configuration::Configuration, layer::client_ip};
+
+/// How the subscriber behind a request to our forms and links consents, as
+/// recorded in the consent ledger.
+pub struct Consent(pub ConsentContext);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Consent
+where
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let config = parts.extensions.get::<Arc<Configuration>>();
+        let ip = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .zip(config)
+            .map(|(ConnectInfo(peer), config)| {
+                client_ip(
+                    peer.ip(),
+                    &parts.headers,
+                    &config.rate_limit.trusted_proxies,
+                )
+            });
+
+        Ok(Consent(consent_context(
+            config.map(Arc::as_ref),
+            ip,
+            &parts.headers,
+        )))
+    }
+}
+
+/// The context of a consent given through a form from `ip`, with the IP and
+/// user agent hashed when configured so.
+pub(crate) fn consent_context(
+    config: Option<&Configuration>,
+    ip: Option<IpAddr>,
+    headers: &HeaderMap,
+) -> ConsentContext {
+    let user_agent = headers
+        .get(USER_AGENT)
+        .and_then(|value| value.to_str().ok());
+    let context = ConsentContext {
+        ip: ip.map(|ip| ip.to_string()),
+        user_agent: user_agent.map(str::to_owned),
+        policy_version: config.and_then(|config| config.subscriptions.policy_version.clone()),
+        ..ConsentContext::new(ConsentSource::Form)
+    };
+
+    match config {
+        Some(config) if config.subscriptions.hash_client_info => context.hashed(),
+        _ => context,
+    }
+}
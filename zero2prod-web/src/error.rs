@@ -1,31 +1,80 @@
-use axum::extract::rejection::FormRejection;
-use hyper::StatusCode;
+use axum::{
+    extract::rejection::{FormRejection, JsonRejection},
+    response::{IntoResponse, Response},
+};
+use hyper::{header::RETRY_AFTER, StatusCode};
 use zero2prod_core::error::CoreError;
 
-pub fn core_error(err: CoreError) -> (StatusCode, String) {
+pub fn core_error(err: CoreError) -> Response {
     match err {
         CoreError::EmailAlreadyExists => {
-            (StatusCode::BAD_REQUEST, "email already exists".to_string())
+            (StatusCode::BAD_REQUEST, "email already exists".to_string()).into_response()
         }
+        CoreError::EmailSuppressed => (
+            StatusCode::BAD_REQUEST,
+            "email address is suppressed".to_string(),
+        )
+            .into_response(),
+        CoreError::UsernameAlreadyExists => (
+            StatusCode::BAD_REQUEST,
+            "username already exists".to_string(),
+        )
+            .into_response(),
         CoreError::InvalidDomain(message) => (
             StatusCode::BAD_REQUEST,
             format!("invalid data: {}", message),
-        ),
+        )
+            .into_response(),
+        CoreError::SubscriberNotFound => {
+            (StatusCode::NOT_FOUND, "subscriber not found".to_string()).into_response()
+        }
+        CoreError::InvalidToken => {
+            (StatusCode::UNAUTHORIZED, "invalid token".to_string()).into_response()
+        }
+        CoreError::ListNotFound => {
+            (StatusCode::NOT_FOUND, "list not found".to_string()).into_response()
+        }
+        CoreError::ListAlreadyExists => {
+            (StatusCode::BAD_REQUEST, "list already exists".to_string()).into_response()
+        }
+        CoreError::TopicAlreadyExists => {
+            (StatusCode::BAD_REQUEST, "topic already exists".to_string()).into_response()
+        }
+        CoreError::IssueNotFound => {
+            (StatusCode::NOT_FOUND, "issue not found".to_string()).into_response()
+        }
+        CoreError::IssueNotEditable => (
+            StatusCode::CONFLICT,
+            "issue is no longer editable".to_string(),
+        )
+            .into_response(),
+        CoreError::ImportNotFound => {
+            (StatusCode::NOT_FOUND, "import not found".to_string()).into_response()
+        }
+        CoreError::ExpiredToken => (StatusCode::GONE, "expired token".to_string()).into_response(),
+        CoreError::RateLimited { retry_after } => (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(RETRY_AFTER, retry_after.to_string())],
+            "too many requests".to_string(),
+        )
+            .into_response(),
         CoreError::Unexpected(message) => {
             tracing::error!("Internal server error: {}", message);
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Internal server error".to_string(),
             )
+                .into_response()
         }
-        _ => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Internal server error".to_string(),
-        ),
     }
 }
 
-pub fn form_rejection(err: FormRejection) -> (StatusCode, String) {
+pub fn form_rejection(err: FormRejection) -> Response {
+    tracing::info!("Bad request: {}", err.body_text());
+    (StatusCode::BAD_REQUEST, err.body_text()).into_response()
+}
+
+pub fn json_rejection(err: JsonRejection) -> Response {
     tracing::info!("Bad request: {}", err.body_text());
-    (StatusCode::BAD_REQUEST, err.body_text())
+    (StatusCode::BAD_REQUEST, err.body_text()).into_response()
 }
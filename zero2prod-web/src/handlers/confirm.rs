@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{rejection::FormRejection, Query},
+    response::{Html, IntoResponse, Response},
+    Extension, Form,
+};
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use zero2prod_core::error::CoreError;
+
+use crate::{
+    clock::unix_now,
+    consent::Consent,
+    error::{core_error, form_rejection},
+    metrics::Metrics,
+    repository::{ConsentRepositoryImpl, ListRepositoryImpl, SubscriptionRepositoryImpl},
+    service::{EmailServiceImpl, RateLimiterImpl, SubscriptionLinks},
+    template::{Page, TemplateEngine},
+};
+
+#[derive(Deserialize, Serialize)]
+pub struct TokenParams {
+    pub token: String,
+}
+
+pub async fn confirm(
+    Extension(subscription_repository): Extension<Arc<SubscriptionRepositoryImpl>>,
+    Extension(consent_repository): Extension<Arc<ConsentRepositoryImpl>>,
+    Extension(links): Extension<Arc<SubscriptionLinks>>,
+    Extension(template_engine): Extension<Arc<TemplateEngine>>,
+    Consent(consent): Consent,
+    Query(params): Query<TokenParams>,
+) -> Result<&'static str, Response> {
+    let result = zero2prod_core::handlers::confirm(
+        subscription_repository.as_ref(),
+        consent_repository.as_ref(),
+        links.keys(),
+        &params.token,
+        unix_now(),
+        &consent,
+    )
+    .await;
+
+    match result {
+        Ok(()) => Ok("subscription confirmed"),
+        // Offer to send a new link
+        Err(CoreError::ExpiredToken) => Err((
+            StatusCode::GONE,
+            Html(template_engine.render_page(Page::ConfirmationExpired, &params)),
+        )
+            .into_response()),
+        Err(err) => Err(core_error(err)),
+    }
+}
+
+/// Sends a new confirmation link, the subscriber is identified by the token of
+/// a previous link.
+pub async fn resend_confirmation(
+    Extension(subscription_repository): Extension<Arc<SubscriptionRepositoryImpl>>,
+    Extension(list_repository): Extension<Arc<ListRepositoryImpl>>,
+    Extension(email_client): Extension<Arc<EmailServiceImpl>>,
+    Extension(email_limiter): Extension<Arc<RateLimiterImpl>>,
+    Extension(links): Extension<Arc<SubscriptionLinks>>,
+    Extension(metrics): Extension<Arc<Metrics>>,
+    form: Result<Form<TokenParams>, FormRejection>,
+) -> Result<&'static str, Response> {
+    let Form(params) = form.map_err(form_rejection)?;
+
+    let result = zero2prod_core::handlers::resend_confirmation(
+        subscription_repository.as_ref(),
+        list_repository.as_ref(),
+        email_client.as_ref(),
+        email_limiter.as_ref(),
+        links.keys(),
+        &params.token,
+        unix_now(),
+        |list, subscriber_id, stored| links.confirmation_email(list, subscriber_id, stored),
+    )
+    .await;
+
+    if let Err(CoreError::RateLimited { .. }) = result {
+        metrics.rate_limited(email_limiter.scope());
+    }
+    result.map_err(core_error)?;
+
+    Ok("a new confirmation link is on its way")
+}
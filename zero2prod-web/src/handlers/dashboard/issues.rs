@@ -0,0 +1,287 @@
+use std::{sync::Arc, time::SystemTime};
+
+use axum::{
+    extract::{rejection::FormRejection, Path},
+    response::{Html, IntoResponse, Redirect, Response},
+    Extension, Form,
+};
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use zero2prod_core::{
+    domain::{IssueContent, NewsletterIssue},
+    error::CoreResult,
+    repository::{ListRepository, NewsletterIssueRepository},
+};
+
+use crate::{
+    auth::EditorSession,
+    error::{core_error, form_rejection},
+    handlers::{
+        lists::find_list,
+        newsletter_issues::{find_issue, render_preview, DeliveriesResponse},
+    },
+    repository::{ListRepositoryImpl, NewsletterIssueRepositoryImpl, TopicRepositoryImpl},
+    template::{Page, TemplateEngine},
+};
+
+use super::{form_error, format_time, render, CsrfForm};
+
+/// Issues are composed for every subscriber of the list, topics and segments
+/// are left to the API.
+#[derive(Deserialize)]
+pub struct IssueForm {
+    csrf_token: String,
+    /// Slug of the list the issue is sent to.
+    list: String,
+    title: String,
+    html_content: String,
+    text_content: String,
+}
+
+#[derive(Serialize)]
+struct IssuesPage {
+    issues: Vec<IssueRow>,
+}
+
+#[derive(Serialize)]
+struct IssueRow {
+    id: Uuid,
+    title: String,
+    status: &'static str,
+    created_at: String,
+    sent_at: Option<String>,
+    deliveries: DeliveriesResponse,
+}
+
+#[derive(Serialize)]
+struct NewIssuePage {
+    lists: Vec<ListOption>,
+    title: String,
+    html_content: String,
+    text_content: String,
+}
+
+#[derive(Serialize)]
+struct ListOption {
+    slug: String,
+    name: String,
+    selected: bool,
+}
+
+#[derive(Serialize)]
+struct IssuePage {
+    issue: IssueRow,
+    /// Drafts and scheduled issues can be sent right away.
+    publishable: bool,
+    /// The issue as rendered for its recipients, escaped for the `srcdoc` of
+    /// a sandboxed frame.
+    preview: String,
+}
+
+/// Lists the issues, newest first, with how far their delivery went.
+pub async fn issues_page(
+    session: EditorSession,
+    Extension(issue_repository): Extension<Arc<NewsletterIssueRepositoryImpl>>,
+    Extension(template_engine): Extension<Arc<TemplateEngine>>,
+) -> Result<Html<String>, Response> {
+    let issues = issue_repository.list(None).await.map_err(core_error)?;
+
+    let mut rows = Vec::with_capacity(issues.len());
+    for issue in issues {
+        rows.push(
+            issue_row(&issue_repository, issue)
+                .await
+                .map_err(core_error)?,
+        );
+    }
+    Ok(render(
+        &template_engine,
+        Page::AdminIssues,
+        Some(&session),
+        None,
+        IssuesPage { issues: rows },
+    ))
+}
+
+pub async fn new_issue_page(
+    session: EditorSession,
+    Extension(list_repository): Extension<Arc<ListRepositoryImpl>>,
+    Extension(template_engine): Extension<Arc<TemplateEngine>>,
+) -> Result<Html<String>, Response> {
+    render_new_issue(
+        &list_repository,
+        &template_engine,
+        &session,
+        None,
+        None::<IssueForm>,
+    )
+    .await
+    .map_err(core_error)
+}
+
+/// Saves the issue as a draft, the editor sends it from its page once happy
+/// with the preview.
+pub async fn compose_issue(
+    session: EditorSession,
+    Extension(issue_repository): Extension<Arc<NewsletterIssueRepositoryImpl>>,
+    Extension(list_repository): Extension<Arc<ListRepositoryImpl>>,
+    Extension(topic_repository): Extension<Arc<TopicRepositoryImpl>>,
+    Extension(template_engine): Extension<Arc<TemplateEngine>>,
+    form: Result<Form<IssueForm>, FormRejection>,
+) -> Result<Response, Response> {
+    let Form(form) = form.map_err(form_rejection)?;
+    session
+        .verify_csrf(&form.csrf_token)
+        .map_err(IntoResponse::into_response)?;
+
+    let result = async {
+        let content = IssueContent::parse(
+            form.title.clone(),
+            form.text_content.clone(),
+            form.html_content.clone(),
+        )?;
+        let list = find_list(&list_repository, form.list.clone()).await?;
+        zero2prod_core::handlers::create_issue(
+            issue_repository.as_ref(),
+            topic_repository.as_ref(),
+            list.id,
+            content,
+            &[],
+            None,
+            session.session.user_id,
+        )
+        .await
+    }
+    .await;
+
+    match result {
+        Ok(issue_id) => Ok(Redirect::to(&format!("/admin/issues/{}", issue_id)).into_response()),
+        Err(err) => {
+            let error = form_error(err).map_err(core_error)?;
+            let page = render_new_issue(
+                &list_repository,
+                &template_engine,
+                &session,
+                Some(error),
+                Some(form),
+            )
+            .await
+            .map_err(core_error)?;
+            Ok((StatusCode::BAD_REQUEST, page).into_response())
+        }
+    }
+}
+
+pub async fn issue_page(
+    session: EditorSession,
+    Extension(issue_repository): Extension<Arc<NewsletterIssueRepositoryImpl>>,
+    Extension(list_repository): Extension<Arc<ListRepositoryImpl>>,
+    Extension(template_engine): Extension<Arc<TemplateEngine>>,
+    Path(issue_id): Path<Uuid>,
+) -> Result<Html<String>, Response> {
+    let issue = find_issue(&issue_repository, issue_id).await?;
+    let publishable = issue.status.is_editable();
+
+    let preview = render_preview(&list_repository, &template_engine, issue.clone())
+        .await
+        .map_err(core_error)?;
+    let content = IssuePage {
+        issue: issue_row(&issue_repository, issue)
+            .await
+            .map_err(core_error)?,
+        publishable,
+        preview,
+    };
+    Ok(render(
+        &template_engine,
+        Page::AdminIssue,
+        Some(&session),
+        None,
+        content,
+    ))
+}
+
+/// Sends the issue to every subscriber of its audience, on the next run of
+/// the scheduler.
+pub async fn send_issue(
+    session: EditorSession,
+    Extension(issue_repository): Extension<Arc<NewsletterIssueRepositoryImpl>>,
+    Extension(topic_repository): Extension<Arc<TopicRepositoryImpl>>,
+    Path(issue_id): Path<Uuid>,
+    form: Result<Form<CsrfForm>, FormRejection>,
+) -> Result<Redirect, Response> {
+    let Form(form) = form.map_err(form_rejection)?;
+    session
+        .verify_csrf(&form.csrf_token)
+        .map_err(IntoResponse::into_response)?;
+
+    zero2prod_core::handlers::publish_issue(
+        issue_repository.as_ref(),
+        topic_repository.as_ref(),
+        issue_id,
+        None,
+        SystemTime::now(),
+    )
+    .await
+    .map_err(core_error)?;
+
+    Ok(Redirect::to(&format!("/admin/issues/{}", issue_id)))
+}
+
+async fn issue_row(
+    issue_repository: &NewsletterIssueRepositoryImpl,
+    issue: NewsletterIssue,
+) -> CoreResult<IssueRow> {
+    let stats = issue_repository.delivery_stats(issue.id).await?;
+    Ok(IssueRow {
+        id: issue.id,
+        title: issue.content.title,
+        status: issue.status.as_str(),
+        created_at: format_time(issue.created_at),
+        sent_at: issue.sent_at.map(format_time),
+        deliveries: stats.into(),
+    })
+}
+
+/// The compose form, filled with the fields of `form` when it was rejected.
+async fn render_new_issue(
+    list_repository: &ListRepositoryImpl,
+    template_engine: &TemplateEngine,
+    session: &EditorSession,
+    error: Option<String>,
+    form: Option<IssueForm>,
+) -> CoreResult<Html<String>> {
+    let lists = list_repository.list().await?;
+    let selected = form.as_ref().map(|form| form.list.as_str());
+
+    let content = NewIssuePage {
+        lists: lists
+            .into_iter()
+            .map(|list| ListOption {
+                selected: Some(list.slug.as_ref()) == selected,
+                slug: list.slug.to_string(),
+                name: list.settings.name,
+            })
+            .collect(),
+        title: form
+            .as_ref()
+            .map(|form| form.title.clone())
+            .unwrap_or_default(),
+        html_content: form
+            .as_ref()
+            .map(|form| form.html_content.clone())
+            .unwrap_or_default(),
+        text_content: form
+            .as_ref()
+            .map(|form| form.text_content.clone())
+            .unwrap_or_default(),
+    };
+    Ok(render(
+        template_engine,
+        Page::AdminNewIssue,
+        Some(session),
+        error,
+        content,
+    ))
+}
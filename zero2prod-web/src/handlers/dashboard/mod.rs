@@ -0,0 +1,206 @@
+//! The dashboard, where editors manage the subscribers and issues from their
+//! browser. Its pages are rendered on the server and its forms work without
+//! scripts.
+
+mod issues;
+mod subscribers;
+
+use std::{sync::Arc, time::Duration, time::SystemTime};
+
+use axum::{
+    extract::rejection::FormRejection,
+    response::{AppendHeaders, Html, IntoResponse, Redirect, Response},
+    Extension, Form,
+};
+use chrono::{DateTime, Utc};
+use http::HeaderMap;
+use hyper::StatusCode;
+use secrecy::SecretString;
+use serde::{Deserialize, Serialize};
+use zero2prod_core::error::CoreError;
+
+use crate::{
+    auth::{
+        authenticate, constant_time_eq, cookie, random_token, set_cookie, EditorSession,
+        SessionStore, SESSION_COOKIE,
+    },
+    configuration::Configuration,
+    error::{core_error, form_rejection},
+    repository::UserRepositoryImpl,
+    template::{Page, TemplateEngine},
+};
+
+pub use issues::{compose_issue, issue_page, issues_page, new_issue_page, send_issue};
+pub use subscribers::{remove_subscriber, save_subscriber, subscriber_page, subscribers_page};
+
+/// Cookie the login form is checked against, so that other sites cannot
+/// sign editors in to an account of their own.
+const LOGIN_COOKIE: &str = "z2p_login";
+const LOGIN_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// The data of every page of the dashboard.
+#[derive(Serialize)]
+struct DashboardPage<T> {
+    /// Only set for signed in editors, the layout shows them the navigation.
+    csrf_token: Option<String>,
+    error: Option<String>,
+    #[serde(flatten)]
+    content: T,
+}
+
+#[derive(Serialize)]
+struct LoginPage {
+    login_token: String,
+    username: String,
+}
+
+#[derive(Deserialize)]
+pub struct LoginForm {
+    login_token: String,
+    username: String,
+    password: SecretString,
+}
+
+/// The forms of the dashboard which only carry their CSRF token.
+#[derive(Deserialize)]
+pub struct CsrfForm {
+    csrf_token: String,
+}
+
+fn render<T: Serialize>(
+    template_engine: &TemplateEngine,
+    page: Page,
+    session: Option<&EditorSession>,
+    error: Option<String>,
+    content: T,
+) -> Html<String> {
+    let page_data = DashboardPage {
+        csrf_token: session.map(|session| session.session.csrf_token.clone()),
+        error,
+        content,
+    };
+    Html(template_engine.render_page(page, &page_data))
+}
+
+/// The message to show along the form when the editor can fix the error.
+fn form_error(err: CoreError) -> Result<String, CoreError> {
+    match err {
+        CoreError::InvalidDomain(message) => Ok(message),
+        err => Err(err),
+    }
+}
+
+fn format_time(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time)
+        .format("%Y-%m-%d %H:%M UTC")
+        .to_string()
+}
+
+pub async fn dashboard(_: EditorSession) -> Redirect {
+    Redirect::to("/admin/subscribers")
+}
+
+pub async fn login_page(
+    Extension(config): Extension<Arc<Configuration>>,
+    Extension(template_engine): Extension<Arc<TemplateEngine>>,
+) -> Response {
+    login_form(&config, &template_engine, String::new(), None)
+}
+
+/// Signs the editor in with their username and password, the session lasts
+/// `admin.session_ttl` seconds.
+pub async fn login(
+    Extension(users): Extension<Arc<UserRepositoryImpl>>,
+    Extension(sessions): Extension<Arc<SessionStore>>,
+    Extension(config): Extension<Arc<Configuration>>,
+    Extension(template_engine): Extension<Arc<TemplateEngine>>,
+    headers: HeaderMap,
+    form: Result<Form<LoginForm>, FormRejection>,
+) -> Result<Response, Response> {
+    let Form(form) = form.map_err(form_rejection)?;
+    let login_token = cookie(&headers, LOGIN_COOKIE).unwrap_or_default();
+    if login_token.is_empty()
+        || !constant_time_eq(login_token.as_bytes(), form.login_token.as_bytes())
+    {
+        tracing::warn!("Rejected a login form with an invalid token");
+        return Err((StatusCode::FORBIDDEN, "invalid login token").into_response());
+    }
+
+    let Some(user_id) = authenticate(&users, &form.username, form.password)
+        .await
+        .map_err(core_error)?
+    else {
+        tracing::warn!("Rejected invalid credentials");
+        let page = login_form(
+            &config,
+            &template_engine,
+            form.username,
+            Some("Invalid username or password".into()),
+        );
+        return Ok((StatusCode::UNAUTHORIZED, page).into_response());
+    };
+    let token = sessions.create(user_id).await.map_err(core_error)?;
+
+    Ok((
+        AppendHeaders([
+            set_cookie(&config, SESSION_COOKIE, &token, "/admin", sessions.ttl()),
+            set_cookie(&config, LOGIN_COOKIE, "", "/admin/login", Duration::ZERO),
+        ]),
+        Redirect::to("/admin/subscribers"),
+    )
+        .into_response())
+}
+
+pub async fn logout(
+    editor: EditorSession,
+    Extension(sessions): Extension<Arc<SessionStore>>,
+    Extension(config): Extension<Arc<Configuration>>,
+    form: Result<Form<CsrfForm>, FormRejection>,
+) -> Result<Response, Response> {
+    let Form(form) = form.map_err(form_rejection)?;
+    editor
+        .verify_csrf(&form.csrf_token)
+        .map_err(IntoResponse::into_response)?;
+
+    sessions.delete(&editor.token).await.map_err(core_error)?;
+
+    Ok((
+        [set_cookie(
+            &config,
+            SESSION_COOKIE,
+            "",
+            "/admin",
+            Duration::ZERO,
+        )],
+        Redirect::to("/admin/login"),
+    )
+        .into_response())
+}
+
+/// The login page, with a fresh login token.
+fn login_form(
+    config: &Configuration,
+    template_engine: &TemplateEngine,
+    username: String,
+    error: Option<String>,
+) -> Response {
+    let login_token = random_token();
+    let cookie = set_cookie(
+        config,
+        LOGIN_COOKIE,
+        &login_token,
+        "/admin/login",
+        LOGIN_TTL,
+    );
+    let page = render(
+        template_engine,
+        Page::AdminLogin,
+        None,
+        error,
+        LoginPage {
+            login_token,
+            username,
+        },
+    );
+    ([cookie], page).into_response()
+}
@@ -0,0 +1,327 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{rejection::FormRejection, Path, Query},
+    response::{Html, IntoResponse, Redirect, Response},
+    Extension, Form,
+};
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use zero2prod_core::{
+    domain::{
+        SubscriberCursor, SubscriberFilter, SubscriberName, SubscriberUpdate, SubscriptionStatus,
+        TopicSlug,
+    },
+    error::{CoreError, CoreResult},
+    handlers::SubscriberDetails,
+    repository::ListRepository,
+};
+
+use crate::{
+    auth::EditorSession,
+    error::{core_error, form_rejection},
+    repository::{
+        ConsentRepositoryImpl, ListRepositoryImpl, SubscriptionRepositoryImpl, TopicRepositoryImpl,
+    },
+    template::{Page, TemplateEngine},
+};
+
+use super::{form_error, format_time, render, CsrfForm};
+
+const PAGE_SIZE: u64 = 50;
+
+const STATUSES: [SubscriptionStatus; 3] = [
+    SubscriptionStatus::Pending,
+    SubscriptionStatus::Confirmed,
+    SubscriptionStatus::Unsubscribed,
+];
+
+/// The search form leaves its fields empty rather than out.
+#[derive(Deserialize)]
+pub struct SearchParams {
+    #[serde(default)]
+    search: String,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    after: String,
+}
+
+#[derive(Serialize)]
+struct SubscribersPage {
+    search: String,
+    status: String,
+    statuses: Vec<StatusOption>,
+    subscribers: Vec<SubscriberRow>,
+    next: Option<String>,
+}
+
+#[derive(Serialize)]
+struct SubscriberPage {
+    subscriber: SubscriberRow,
+    statuses: Vec<StatusOption>,
+    topics: Vec<TopicChoice>,
+}
+
+#[derive(Serialize)]
+struct StatusOption {
+    value: &'static str,
+    selected: bool,
+}
+
+#[derive(Serialize)]
+struct SubscriberRow {
+    id: Uuid,
+    email: String,
+    name: String,
+    status: &'static str,
+    subscribed_at: String,
+    /// Name of the list, only shown on the page of the subscriber.
+    list: Option<String>,
+}
+
+#[derive(Serialize)]
+struct TopicChoice {
+    slug: String,
+    name: String,
+    subscribed: bool,
+}
+
+fn status_options(selected: Option<SubscriptionStatus>) -> Vec<StatusOption> {
+    STATUSES
+        .iter()
+        .map(|status| StatusOption {
+            value: status.as_str(),
+            selected: Some(*status) == selected,
+        })
+        .collect()
+}
+
+/// Lists the subscribers matching the search, newest first.
+pub async fn subscribers_page(
+    session: EditorSession,
+    Extension(subscription_repository): Extension<Arc<SubscriptionRepositoryImpl>>,
+    Extension(template_engine): Extension<Arc<TemplateEngine>>,
+    Query(params): Query<SearchParams>,
+) -> Result<Html<String>, Response> {
+    let status = Some(params.status.as_str())
+        .filter(|status| !status.is_empty())
+        .map(str::parse::<SubscriptionStatus>)
+        .transpose()
+        .map_err(core_error)?;
+    let filter = SubscriberFilter {
+        status,
+        search: Some(params.search.trim().to_owned()).filter(|search| !search.is_empty()),
+        ..Default::default()
+    };
+    let after = Some(params.after.as_str())
+        .filter(|after| !after.is_empty())
+        .map(str::parse::<SubscriberCursor>)
+        .transpose()
+        .map_err(core_error)?;
+
+    let page = zero2prod_core::handlers::list_subscribers(
+        subscription_repository.as_ref(),
+        &filter,
+        after,
+        PAGE_SIZE,
+    )
+    .await
+    .map_err(core_error)?;
+
+    let content = SubscribersPage {
+        search: params.search,
+        status: params.status,
+        statuses: status_options(status),
+        subscribers: page
+            .subscribers
+            .into_iter()
+            .map(|entry| SubscriberRow {
+                id: entry.subscriber.id,
+                email: entry.subscriber.email.to_string(),
+                name: entry.subscriber.name.as_ref().to_owned(),
+                status: entry.subscriber.status.as_str(),
+                subscribed_at: format_time(entry.subscribed_at),
+                list: None,
+            })
+            .collect(),
+        next: page.next.map(|cursor| cursor.to_string()),
+    };
+    Ok(render(
+        &template_engine,
+        Page::AdminSubscribers,
+        Some(&session),
+        None,
+        content,
+    ))
+}
+
+pub async fn subscriber_page(
+    session: EditorSession,
+    Extension(subscription_repository): Extension<Arc<SubscriptionRepositoryImpl>>,
+    Extension(topic_repository): Extension<Arc<TopicRepositoryImpl>>,
+    Extension(list_repository): Extension<Arc<ListRepositoryImpl>>,
+    Extension(template_engine): Extension<Arc<TemplateEngine>>,
+    Path(subscriber_id): Path<Uuid>,
+) -> Result<Html<String>, Response> {
+    let details = zero2prod_core::handlers::subscriber_details(
+        subscription_repository.as_ref(),
+        topic_repository.as_ref(),
+        subscriber_id,
+    )
+    .await
+    .map_err(core_error)?;
+
+    render_subscriber(&list_repository, &template_engine, &session, details, None)
+        .await
+        .map_err(core_error)
+}
+
+/// Saves the form of the subscriber page, which repeats the `topics` field
+/// for every topic checked. Their topics are left alone when unchanged, so
+/// that saving a new name does not record a consent.
+#[allow(clippy::too_many_arguments)]
+pub async fn save_subscriber(
+    session: EditorSession,
+    Extension(subscription_repository): Extension<Arc<SubscriptionRepositoryImpl>>,
+    Extension(topic_repository): Extension<Arc<TopicRepositoryImpl>>,
+    Extension(consent_repository): Extension<Arc<ConsentRepositoryImpl>>,
+    Extension(list_repository): Extension<Arc<ListRepositoryImpl>>,
+    Extension(template_engine): Extension<Arc<TemplateEngine>>,
+    Path(subscriber_id): Path<Uuid>,
+    form: Result<Form<Vec<(String, String)>>, FormRejection>,
+) -> Result<Response, Response> {
+    let Form(fields) = form.map_err(form_rejection)?;
+    let mut csrf_token = String::new();
+    let mut name = None;
+    let mut status = None;
+    let mut topics = vec![];
+    for (field, value) in fields {
+        match field.as_str() {
+            "csrf_token" => csrf_token = value,
+            "name" => name = Some(value),
+            "status" => status = Some(value),
+            "topics" => topics.push(value),
+            _ => {}
+        }
+    }
+    session
+        .verify_csrf(&csrf_token)
+        .map_err(IntoResponse::into_response)?;
+
+    let details = zero2prod_core::handlers::subscriber_details(
+        subscription_repository.as_ref(),
+        topic_repository.as_ref(),
+        subscriber_id,
+    )
+    .await
+    .map_err(core_error)?;
+    let mut subscribed = details
+        .topics
+        .iter()
+        .filter(|preference| preference.subscribed)
+        .map(|preference| preference.topic.slug.to_string())
+        .collect::<Vec<_>>();
+    subscribed.sort();
+    topics.sort();
+    let topics = Some(topics).filter(|topics| *topics != subscribed);
+
+    let result = async {
+        let update = SubscriberUpdate {
+            name: name.map(SubscriberName::parse).transpose()?,
+            status: status
+                .map(|status| status.parse::<SubscriptionStatus>())
+                .transpose()?,
+            topics: topics
+                .map(|topics| topics.into_iter().map(TopicSlug::parse).collect())
+                .transpose()?,
+        };
+        zero2prod_core::handlers::update_subscriber(
+            subscription_repository.as_ref(),
+            topic_repository.as_ref(),
+            consent_repository.as_ref(),
+            subscriber_id,
+            &update,
+        )
+        .await
+    }
+    .await;
+
+    match result {
+        Ok(_) => Ok(Redirect::to(&format!("/admin/subscribers/{}", subscriber_id)).into_response()),
+        Err(err) => {
+            let error = form_error(err).map_err(core_error)?;
+            let page = render_subscriber(
+                &list_repository,
+                &template_engine,
+                &session,
+                details,
+                Some(error),
+            )
+            .await
+            .map_err(core_error)?;
+            Ok((StatusCode::BAD_REQUEST, page).into_response())
+        }
+    }
+}
+
+pub async fn remove_subscriber(
+    session: EditorSession,
+    Extension(subscription_repository): Extension<Arc<SubscriptionRepositoryImpl>>,
+    Path(subscriber_id): Path<Uuid>,
+    form: Result<Form<CsrfForm>, FormRejection>,
+) -> Result<Redirect, Response> {
+    let Form(form) = form.map_err(form_rejection)?;
+    session
+        .verify_csrf(&form.csrf_token)
+        .map_err(IntoResponse::into_response)?;
+
+    zero2prod_core::handlers::delete_subscriber(subscription_repository.as_ref(), subscriber_id)
+        .await
+        .map_err(core_error)?;
+
+    Ok(Redirect::to("/admin/subscribers"))
+}
+
+async fn render_subscriber(
+    list_repository: &ListRepositoryImpl,
+    template_engine: &TemplateEngine,
+    session: &EditorSession,
+    details: SubscriberDetails,
+    error: Option<String>,
+) -> CoreResult<Html<String>> {
+    let subscriber = details.entry.subscriber;
+    let list = list_repository
+        .find_by_id(subscriber.list_id)
+        .await?
+        .ok_or(CoreError::ListNotFound)?;
+
+    let content = SubscriberPage {
+        subscriber: SubscriberRow {
+            id: subscriber.id,
+            email: subscriber.email.to_string(),
+            name: subscriber.name.as_ref().to_owned(),
+            status: subscriber.status.as_str(),
+            subscribed_at: format_time(details.entry.subscribed_at),
+            list: Some(list.settings.name),
+        },
+        statuses: status_options(Some(subscriber.status)),
+        topics: details
+            .topics
+            .into_iter()
+            .map(|preference| TopicChoice {
+                slug: preference.topic.slug.to_string(),
+                name: preference.topic.name,
+                subscribed: preference.subscribed,
+            })
+            .collect(),
+    };
+    Ok(render(
+        template_engine,
+        Page::AdminSubscriber,
+        Some(session),
+        error,
+        content,
+    ))
+}
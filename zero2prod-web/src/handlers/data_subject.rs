@@ -0,0 +1,241 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{rejection::FormRejection, Query},
+    response::{Html, Response},
+    Extension, Form, Json,
+};
+use chrono::{DateTime, Utc};
+use email_address::EmailAddress;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use zero2prod_core::{
+    domain::{DeliveryData, RecordedConsent, SubjectData, SubscriptionData},
+    error::CoreError,
+};
+
+use crate::{
+    clock::unix_now,
+    error::{core_error, form_rejection},
+    handlers::TokenParams,
+    metrics::Metrics,
+    repository::{DataSubjectRepositoryImpl, SubscriptionRepositoryImpl},
+    service::{EmailServiceImpl, RateLimiterImpl, SubscriptionLinks},
+    template::{Page, TemplateEngine},
+};
+
+#[derive(Deserialize)]
+pub struct DataAccessForm {
+    email: EmailAddress,
+}
+
+#[derive(Serialize)]
+pub struct SubjectResponse {
+    email: String,
+    subscriptions: Vec<SubscriptionResponse>,
+}
+
+#[derive(Serialize)]
+pub struct SubscriptionResponse {
+    id: Uuid,
+    list: String,
+    name: String,
+    status: &'static str,
+    subscribed_at: DateTime<Utc>,
+    topics: Vec<TopicChoiceResponse>,
+    tokens: Vec<String>,
+    deliveries: Vec<DeliveryResponse>,
+    consents: Vec<ConsentResponse>,
+}
+
+#[derive(Serialize)]
+pub struct TopicChoiceResponse {
+    topic: String,
+    subscribed: bool,
+}
+
+#[derive(Serialize)]
+pub struct DeliveryResponse {
+    issue_id: Uuid,
+    title: String,
+    status: String,
+    attempts: u32,
+    updated_at: DateTime<Utc>,
+    opened_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+pub struct ConsentResponse {
+    action: &'static str,
+    source: &'static str,
+    ip: Option<String>,
+    user_agent: Option<String>,
+    policy_version: Option<String>,
+    topics: Vec<String>,
+    occurred_at: DateTime<Utc>,
+}
+
+impl From<SubjectData> for SubjectResponse {
+    fn from(data: SubjectData) -> Self {
+        Self {
+            email: data.email.to_string(),
+            subscriptions: data
+                .subscriptions
+                .into_iter()
+                .map(SubscriptionResponse::from)
+                .collect(),
+        }
+    }
+}
+
+impl From<SubscriptionData> for SubscriptionResponse {
+    fn from(data: SubscriptionData) -> Self {
+        Self {
+            id: data.id,
+            list: data.list.to_string(),
+            name: data.name.as_ref().to_owned(),
+            status: data.status.as_str(),
+            subscribed_at: data.subscribed_at.into(),
+            topics: data
+                .topics
+                .into_iter()
+                .map(|(topic, subscribed)| TopicChoiceResponse {
+                    topic: topic.to_string(),
+                    subscribed,
+                })
+                .collect(),
+            tokens: data.tokens,
+            deliveries: data
+                .deliveries
+                .into_iter()
+                .map(DeliveryResponse::from)
+                .collect(),
+            consents: data
+                .consents
+                .into_iter()
+                .map(ConsentResponse::from)
+                .collect(),
+        }
+    }
+}
+
+impl From<DeliveryData> for DeliveryResponse {
+    fn from(data: DeliveryData) -> Self {
+        Self {
+            issue_id: data.issue_id,
+            title: data.title,
+            status: data.status,
+            attempts: data.attempts,
+            updated_at: data.updated_at.into(),
+            opened_at: data.opened_at.map(DateTime::from),
+        }
+    }
+}
+
+impl From<RecordedConsent> for ConsentResponse {
+    fn from(data: RecordedConsent) -> Self {
+        let RecordedConsent { event, occurred_at } = data;
+        Self {
+            action: event.action.as_str(),
+            source: event.context.source.as_str(),
+            ip: event.context.ip,
+            user_agent: event.context.user_agent,
+            policy_version: event.context.policy_version,
+            topics: event.topics.iter().map(ToString::to_string).collect(),
+            occurred_at: occurred_at.into(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ErasePage {
+    token: String,
+    erased: bool,
+}
+
+/// Emails links to export or erase the data of the owner of an address. The
+/// answer is the same whether the address is subscribed or not.
+pub async fn request_data_access(
+    Extension(subscription_repository): Extension<Arc<SubscriptionRepositoryImpl>>,
+    Extension(email_client): Extension<Arc<EmailServiceImpl>>,
+    Extension(email_limiter): Extension<Arc<RateLimiterImpl>>,
+    Extension(links): Extension<Arc<SubscriptionLinks>>,
+    Extension(metrics): Extension<Arc<Metrics>>,
+    form: Result<Form<DataAccessForm>, FormRejection>,
+) -> Result<&'static str, Response> {
+    let Form(form) = form.map_err(form_rejection)?;
+
+    let result = zero2prod_core::handlers::request_data_access(
+        subscription_repository.as_ref(),
+        email_client.as_ref(),
+        email_limiter.as_ref(),
+        form.email,
+        |subscriber_id| links.data_access_email(subscriber_id),
+    )
+    .await;
+
+    if let Err(CoreError::RateLimited { .. }) = result {
+        metrics.rate_limited(email_limiter.scope());
+    }
+    result.map_err(core_error)?;
+
+    Ok("if this address is subscribed, a link is on its way")
+}
+
+pub async fn export_data(
+    Extension(subscription_repository): Extension<Arc<SubscriptionRepositoryImpl>>,
+    Extension(subject_repository): Extension<Arc<DataSubjectRepositoryImpl>>,
+    Extension(links): Extension<Arc<SubscriptionLinks>>,
+    Query(params): Query<TokenParams>,
+) -> Result<Json<SubjectResponse>, Response> {
+    let data = zero2prod_core::handlers::export_data(
+        subscription_repository.as_ref(),
+        subject_repository.as_ref(),
+        links.keys(),
+        &params.token,
+        unix_now(),
+    )
+    .await
+    .map_err(core_error)?;
+
+    Ok(Json(SubjectResponse::from(data)))
+}
+
+/// Asks for a confirmation before erasing, so that mail clients following
+/// links erase nothing.
+pub async fn erase_page(
+    Extension(template_engine): Extension<Arc<TemplateEngine>>,
+    Query(params): Query<TokenParams>,
+) -> Html<String> {
+    let page = ErasePage {
+        token: params.token,
+        erased: false,
+    };
+    Html(template_engine.render_page(Page::Erase, &page))
+}
+
+pub async fn erase_data(
+    Extension(subscription_repository): Extension<Arc<SubscriptionRepositoryImpl>>,
+    Extension(subject_repository): Extension<Arc<DataSubjectRepositoryImpl>>,
+    Extension(links): Extension<Arc<SubscriptionLinks>>,
+    Extension(template_engine): Extension<Arc<TemplateEngine>>,
+    form: Result<Form<TokenParams>, FormRejection>,
+) -> Result<Html<String>, Response> {
+    let Form(params) = form.map_err(form_rejection)?;
+
+    zero2prod_core::handlers::erase_data(
+        subscription_repository.as_ref(),
+        subject_repository.as_ref(),
+        links.keys(),
+        &params.token,
+        unix_now(),
+    )
+    .await
+    .map_err(core_error)?;
+
+    let page = ErasePage {
+        token: String::new(),
+        erased: true,
+    };
+    Ok(Html(template_engine.render_page(Page::Erase, &page)))
+}
@@ -0,0 +1,23 @@
+use std::sync::Arc;
+
+use axum::{Extension, Json};
+use hyper::StatusCode;
+use serde::Serialize;
+
+use crate::bot_protection::BotProtection;
+
+#[derive(Serialize)]
+pub struct FormToken {
+    token: String,
+}
+
+/// Token to embed in the subscription form, forms do not need one when form
+/// tokens are disabled.
+pub async fn form_token(
+    Extension(bot_protection): Extension<Arc<BotProtection>>,
+) -> Result<Json<FormToken>, StatusCode> {
+    bot_protection
+        .issue_form_token()
+        .map(|token| Json(FormToken { token }))
+        .ok_or(StatusCode::NOT_FOUND)
+}
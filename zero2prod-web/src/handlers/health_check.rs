@@ -1,6 +1,151 @@
-use axum::response::IntoResponse;
+use std::{future::Future, sync::Arc, time::Instant};
+
+use axum::{extract::State, response::IntoResponse, Extension, Json};
 use hyper::StatusCode;
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::{migration::pending_migrations, service::EmailServiceImpl};
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Up,
+    Down,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ComponentHealth {
+    pub status: HealthStatus,
+    pub latency_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
 
-pub async fn health_check() -> impl IntoResponse {
+#[derive(Serialize, Debug)]
+pub struct Components {
+    pub database: ComponentHealth,
+    pub migrations: ComponentHealth,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<ComponentHealth>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Readiness {
+    pub status: HealthStatus,
+    pub components: Components,
+}
+
+/// Liveness probe: the process is up and able to serve requests.
+pub async fn health_live() -> impl IntoResponse {
     StatusCode::OK
 }
+
+/// Readiness probe: every dependency needed to serve traffic is reachable.
+pub async fn health_ready(
+    State(pool): State<PgPool>,
+    Extension(email_client): Extension<Arc<EmailServiceImpl>>,
+) -> (StatusCode, Json<Readiness>) {
+    let (database, migrations, email) = tokio::join!(
+        check(async {
+            sqlx::query("SELECT 1")
+                .execute(&pool)
+                .await
+                .map(|_| ())
+                .map_err(|e| Failure::new("database unreachable", e))
+        }),
+        check(async {
+            match pending_migrations(&pool).await {
+                Ok(pending) if pending.is_empty() => Ok(()),
+                Ok(pending) => Err(Failure::new(
+                    "pending migrations",
+                    format!("{} pending migration(s)", pending.len()),
+                )),
+                Err(e) => Err(Failure::new("migrations unavailable", e)),
+            }
+        }),
+        async {
+            match email_client.probe_enabled() {
+                true => Some(
+                    check(async {
+                        email_client
+                            .probe()
+                            .await
+                            .map_err(|e| Failure::new("email provider unreachable", e))
+                    })
+                    .await,
+                ),
+                false => None,
+            }
+        }
+    );
+
+    let healthy = database.status == HealthStatus::Up
+        && migrations.status == HealthStatus::Up
+        && email
+            .as_ref()
+            .is_none_or(|email| email.status == HealthStatus::Up);
+
+    let (code, status) = match healthy {
+        true => (StatusCode::OK, HealthStatus::Up),
+        false => (StatusCode::SERVICE_UNAVAILABLE, HealthStatus::Down),
+    };
+
+    (
+        code,
+        Json(Readiness {
+            status,
+            components: Components {
+                database,
+                migrations,
+                email,
+            },
+        }),
+    )
+}
+
+/// Why a dependency is down. Only the reason is reported, the probes are
+/// public and the detail may tell more about the infrastructure than callers
+/// should know.
+struct Failure {
+    reason: &'static str,
+    detail: String,
+}
+
+impl Failure {
+    fn new(reason: &'static str, detail: impl ToString) -> Self {
+        Self {
+            reason,
+            detail: detail.to_string(),
+        }
+    }
+}
+
+async fn check<F>(probe: F) -> ComponentHealth
+where
+    F: Future<Output = Result<(), Failure>>,
+{
+    let start = Instant::now();
+    let result = probe.await;
+    let latency_ms = start.elapsed().as_millis();
+
+    match result {
+        Ok(()) => ComponentHealth {
+            status: HealthStatus::Up,
+            latency_ms,
+            detail: None,
+        },
+        Err(failure) => {
+            tracing::warn!(
+                "Readiness check failed, {}: {}",
+                failure.reason,
+                failure.detail
+            );
+            ComponentHealth {
+                status: HealthStatus::Down,
+                latency_ms,
+                detail: Some(failure.reason.to_owned()),
+            }
+        }
+    }
+}
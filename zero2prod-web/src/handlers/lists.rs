@@ -0,0 +1,187 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{rejection::JsonRejection, Path},
+    response::Response,
+    Extension, Json,
+};
+use email_address::EmailAddress;
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use zero2prod_core::{
+    domain::{ListSettings, ListSlug, ListTemplates, MailingList, NewList},
+    error::{CoreError, CoreResult},
+    repository::ListRepository,
+};
+
+use crate::{
+    auth::Editor,
+    error::{core_error, json_rejection},
+    repository::ListRepositoryImpl,
+    template::TemplateEngine,
+};
+
+#[derive(Deserialize)]
+pub struct ListBody {
+    name: String,
+    #[serde(default)]
+    sender: Option<EmailAddress>,
+    #[serde(default = "default_require_confirmation")]
+    require_confirmation: bool,
+    #[serde(default)]
+    confirmation_ttl: Option<u64>,
+    #[serde(default)]
+    templates: TemplatesBody,
+}
+
+fn default_require_confirmation() -> bool {
+    true
+}
+
+#[derive(Deserialize, Serialize, Default)]
+pub struct TemplatesBody {
+    confirmation_html: Option<String>,
+    issue_html: Option<String>,
+    issue_text: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct NewListBody {
+    slug: String,
+    #[serde(flatten)]
+    list: ListBody,
+}
+
+impl TryFrom<ListBody> for ListSettings {
+    type Error = CoreError;
+
+    fn try_from(body: ListBody) -> CoreResult<Self> {
+        let templates = body.templates;
+        for (name, source) in [
+            ("confirmation_html", &templates.confirmation_html),
+            ("issue_html", &templates.issue_html),
+            ("issue_text", &templates.issue_text),
+        ] {
+            if let Some(source) = source {
+                TemplateEngine::check(source).map_err(|err| {
+                    CoreError::InvalidDomain(format!("Invalid {} template: {}", name, err))
+                })?;
+            }
+        }
+        ListSettings::parse(
+            body.name,
+            body.sender,
+            body.require_confirmation,
+            body.confirmation_ttl,
+            ListTemplates {
+                confirmation_html: templates.confirmation_html,
+                issue_html: templates.issue_html,
+                issue_text: templates.issue_text,
+            },
+        )
+    }
+}
+
+#[derive(Serialize)]
+pub struct ListResponse {
+    id: Uuid,
+    slug: String,
+    name: String,
+    sender: Option<String>,
+    require_confirmation: bool,
+    confirmation_ttl: Option<u64>,
+    templates: TemplatesBody,
+}
+
+impl From<MailingList> for ListResponse {
+    fn from(list: MailingList) -> Self {
+        let settings = list.settings;
+        Self {
+            id: list.id,
+            slug: list.slug.to_string(),
+            name: settings.name,
+            sender: settings.sender.map(|sender| sender.to_string()),
+            require_confirmation: settings.require_confirmation,
+            confirmation_ttl: settings.confirmation_ttl,
+            templates: TemplatesBody {
+                confirmation_html: settings.templates.confirmation_html,
+                issue_html: settings.templates.issue_html,
+                issue_text: settings.templates.issue_text,
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct CreatedList {
+    id: Uuid,
+}
+
+pub async fn create_list(
+    _: Editor,
+    Extension(list_repository): Extension<Arc<ListRepositoryImpl>>,
+    body: Result<Json<NewListBody>, JsonRejection>,
+) -> Result<(StatusCode, Json<CreatedList>), Response> {
+    let Json(body) = body.map_err(json_rejection)?;
+    let new_list = NewList {
+        slug: ListSlug::parse(body.slug).map_err(core_error)?,
+        settings: body.list.try_into().map_err(core_error)?,
+    };
+
+    let id = zero2prod_core::handlers::create_list(list_repository.as_ref(), new_list)
+        .await
+        .map_err(core_error)?;
+
+    Ok((StatusCode::CREATED, Json(CreatedList { id })))
+}
+
+pub async fn list_lists(
+    _: Editor,
+    Extension(list_repository): Extension<Arc<ListRepositoryImpl>>,
+) -> Result<Json<Vec<ListResponse>>, Response> {
+    let lists = list_repository.list().await.map_err(core_error)?;
+
+    Ok(Json(lists.into_iter().map(ListResponse::from).collect()))
+}
+
+pub async fn get_list(
+    _: Editor,
+    Extension(list_repository): Extension<Arc<ListRepositoryImpl>>,
+    Path(slug): Path<String>,
+) -> Result<Json<ListResponse>, Response> {
+    let list = find_list(&list_repository, slug)
+        .await
+        .map_err(core_error)?;
+
+    Ok(Json(list.into()))
+}
+
+pub async fn update_list(
+    _: Editor,
+    Extension(list_repository): Extension<Arc<ListRepositoryImpl>>,
+    Path(slug): Path<String>,
+    body: Result<Json<ListBody>, JsonRejection>,
+) -> Result<StatusCode, Response> {
+    let Json(body) = body.map_err(json_rejection)?;
+    let slug = ListSlug::parse(slug).map_err(|_| core_error(CoreError::ListNotFound))?;
+    let settings = body.try_into().map_err(core_error)?;
+
+    zero2prod_core::handlers::update_list(list_repository.as_ref(), &slug, settings)
+        .await
+        .map_err(core_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Finds the list named in a URL, invalid slugs cannot name any list.
+pub(crate) async fn find_list(
+    list_repository: &ListRepositoryImpl,
+    slug: String,
+) -> CoreResult<MailingList> {
+    let slug = ListSlug::parse(slug).map_err(|_| CoreError::ListNotFound)?;
+    list_repository
+        .find_by_slug(&slug)
+        .await?
+        .ok_or(CoreError::ListNotFound)
+}
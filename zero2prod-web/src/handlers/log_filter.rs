@@ -0,0 +1,26 @@
+use hyper::StatusCode;
+
+use crate::{
+    auth::AdminToken,
+    telemetry::{self, FilterError},
+};
+
+pub async fn get_log_filter(_: AdminToken) -> Result<String, (StatusCode, String)> {
+    telemetry::current_filter().map_err(filter_error)
+}
+
+pub async fn set_log_filter(
+    _: AdminToken,
+    directives: String,
+) -> Result<StatusCode, (StatusCode, String)> {
+    telemetry::set_filter(directives.trim()).map_err(filter_error)?;
+    tracing::warn!("Log filter changed to {}", directives.trim());
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn filter_error(err: FilterError) -> (StatusCode, String) {
+    match err {
+        FilterError::Invalid(_) => (StatusCode::BAD_REQUEST, err.to_string()),
+        FilterError::Unavailable => (StatusCode::SERVICE_UNAVAILABLE, err.to_string()),
+    }
+}
@@ -0,0 +1,18 @@
+use std::sync::Arc;
+
+use axum::{extract::State, response::IntoResponse, Extension};
+use http::header::CONTENT_TYPE;
+use sqlx::PgPool;
+
+use crate::metrics::Metrics;
+
+pub async fn metrics(
+    State(pool): State<PgPool>,
+    Extension(metrics): Extension<Arc<Metrics>>,
+) -> impl IntoResponse {
+    metrics.observe_pool(&pool);
+    (
+        [(CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics.render(),
+    )
+}
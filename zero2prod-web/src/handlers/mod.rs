@@ -1,5 +1,41 @@
+mod confirm;
+mod dashboard;
+mod data_subject;
+mod form_token;
 mod health_check;
+mod lists;
+mod log_filter;
+mod metrics;
+mod newsletter_issues;
+mod preferences;
+mod segments;
 mod subscribe;
+mod subscriber_export;
+mod subscriber_import;
+mod subscribers;
+mod topics;
+mod unsubscribe;
 
-pub use health_check::health_check;
+pub use confirm::{confirm, resend_confirmation, TokenParams};
+pub use dashboard::{
+    compose_issue, dashboard, issue_page, issues_page, login, login_page, logout, new_issue_page,
+    remove_subscriber, save_subscriber, send_issue, subscriber_page, subscribers_page,
+};
+pub use data_subject::{erase_data, erase_page, export_data, request_data_access};
+pub use form_token::form_token;
+pub use health_check::{health_live, health_ready};
+pub use lists::{create_list, get_list, list_lists, update_list};
+pub use log_filter::{get_log_filter, set_log_filter};
+pub use metrics::metrics;
+pub use newsletter_issues::{
+    create_issue, delete_issue, get_issue, issue_opened, list_issues, preview_issue, publish_issue,
+    schedule_issue, unschedule_issue, update_issue,
+};
+pub use preferences::{preferences, update_preferences};
+pub use segments::count_segment;
 pub use subscribe::subscribe;
+pub use subscriber_export::export_subscribers;
+pub use subscriber_import::{get_import, import_errors, import_subscribers};
+pub use subscribers::{delete_subscriber, get_subscriber, list_subscribers, update_subscriber};
+pub use topics::{create_topic, list_topics};
+pub use unsubscribe::{unsubscribe, unsubscribe_page};
@@ -0,0 +1,410 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{rejection::JsonRejection, Path, Query},
+    response::{Html, IntoResponse, Response},
+    Extension, Json,
+};
+use chrono::{DateTime, Utc};
+use http::Uri;
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use zero2prod_core::{
+    domain::{
+        DeliveryStats, Document, DocumentKind, IssueContent, IssueStatus, NewsletterIssue, Segment,
+        TopicSlug,
+    },
+    error::{CoreError, CoreResult},
+    repository::{ListRepository, NewsletterIssueRepository},
+};
+
+use crate::{
+    auth::Editor,
+    clock::unix_now,
+    error::{core_error, json_rejection},
+    handlers::lists::find_list,
+    handlers::TokenParams,
+    idempotency::{Fingerprinted, IdempotencyKey, IdempotencyStore},
+    repository::{ListRepositoryImpl, NewsletterIssueRepositoryImpl, TopicRepositoryImpl},
+    service::SubscriptionLinks,
+    template::TemplateEngine,
+};
+
+/// A transparent 1x1 GIF.
+const TRACKING_PIXEL: &[u8] = &[
+    0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x01, 0x00, 0x01, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xff, 0xff, 0xff, 0x21, 0xf9, 0x04, 0x01, 0x00, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x00,
+    0x01, 0x00, 0x01, 0x00, 0x00, 0x02, 0x02, 0x44, 0x01, 0x00, 0x3b,
+];
+
+#[derive(Deserialize)]
+pub struct IssueBody {
+    /// Slug of the list the issue is sent to.
+    list: String,
+    title: String,
+    text_content: String,
+    html_content: String,
+    /// Slugs of the topics of the list the issue is about, it is sent to
+    /// every subscriber when there are none.
+    #[serde(default)]
+    topics: Vec<String>,
+    /// Only the subscribers in the segment are sent the issue when set.
+    segment: Option<String>,
+}
+
+/// An issue as written by editors.
+struct ParsedIssue {
+    list: String,
+    content: IssueContent,
+    topics: Vec<TopicSlug>,
+    segment: Option<Segment>,
+}
+
+impl IssueBody {
+    fn parse(self) -> CoreResult<ParsedIssue> {
+        Ok(ParsedIssue {
+            list: self.list,
+            content: IssueContent::parse(self.title, self.text_content, self.html_content)?,
+            topics: self
+                .topics
+                .into_iter()
+                .map(TopicSlug::parse)
+                .collect::<CoreResult<_>>()?,
+            segment: self.segment.as_deref().map(Segment::parse).transpose()?,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+pub struct PublishBody {
+    /// Sends the issue to this segment instead of its own.
+    segment: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ScheduleBody {
+    send_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct ListParams {
+    status: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct IssueResponse {
+    id: Uuid,
+    list_id: Uuid,
+    title: String,
+    text_content: String,
+    html_content: String,
+    topics: Vec<Uuid>,
+    segment: Option<String>,
+    status: &'static str,
+    send_at: Option<DateTime<Utc>>,
+    created_by: Option<Uuid>,
+    created_at: DateTime<Utc>,
+    sent_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deliveries: Option<DeliveriesResponse>,
+}
+
+#[derive(Serialize)]
+pub struct DeliveriesResponse {
+    pending: u64,
+    sent: u64,
+    failed: u64,
+}
+
+impl From<DeliveryStats> for DeliveriesResponse {
+    fn from(stats: DeliveryStats) -> Self {
+        Self {
+            pending: stats.pending,
+            sent: stats.sent,
+            failed: stats.failed,
+        }
+    }
+}
+
+impl From<NewsletterIssue> for IssueResponse {
+    fn from(issue: NewsletterIssue) -> Self {
+        Self {
+            id: issue.id,
+            list_id: issue.list_id,
+            title: issue.content.title,
+            text_content: issue.content.text_content,
+            html_content: issue.content.html_content,
+            topics: issue.audience.topic_ids,
+            segment: issue.audience.segment.map(|segment| segment.to_string()),
+            status: issue.status.as_str(),
+            send_at: issue.send_at.map(DateTime::from),
+            created_by: issue.created_by,
+            created_at: issue.created_at.into(),
+            sent_at: issue.sent_at.map(DateTime::from),
+            deliveries: None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct CreatedIssue {
+    id: Uuid,
+}
+
+pub async fn create_issue(
+    editor: Editor,
+    Extension(issue_repository): Extension<Arc<NewsletterIssueRepositoryImpl>>,
+    Extension(list_repository): Extension<Arc<ListRepositoryImpl>>,
+    Extension(topic_repository): Extension<Arc<TopicRepositoryImpl>>,
+    body: Result<Json<IssueBody>, JsonRejection>,
+) -> Result<(StatusCode, Json<CreatedIssue>), Response> {
+    let Json(body) = body.map_err(json_rejection)?;
+    let issue = body.parse().map_err(core_error)?;
+    let list = find_list(&list_repository, issue.list)
+        .await
+        .map_err(core_error)?;
+
+    let id = zero2prod_core::handlers::create_issue(
+        issue_repository.as_ref(),
+        topic_repository.as_ref(),
+        list.id,
+        issue.content,
+        &issue.topics,
+        issue.segment,
+        editor.user_id,
+    )
+    .await
+    .map_err(core_error)?;
+
+    Ok((StatusCode::CREATED, Json(CreatedIssue { id })))
+}
+
+pub async fn list_issues(
+    _: Editor,
+    Extension(issue_repository): Extension<Arc<NewsletterIssueRepositoryImpl>>,
+    Query(params): Query<ListParams>,
+) -> Result<Json<Vec<IssueResponse>>, Response> {
+    let status = params
+        .status
+        .map(|status| status.parse::<IssueStatus>())
+        .transpose()
+        .map_err(core_error)?;
+
+    let issues = issue_repository.list(status).await.map_err(core_error)?;
+
+    Ok(Json(issues.into_iter().map(IssueResponse::from).collect()))
+}
+
+pub async fn get_issue(
+    _: Editor,
+    Extension(issue_repository): Extension<Arc<NewsletterIssueRepositoryImpl>>,
+    Path(issue_id): Path<Uuid>,
+) -> Result<Json<IssueResponse>, Response> {
+    let issue = find_issue(&issue_repository, issue_id).await?;
+    let stats = issue_repository
+        .delivery_stats(issue_id)
+        .await
+        .map_err(core_error)?;
+
+    let mut response = IssueResponse::from(issue);
+    response.deliveries = Some(stats.into());
+    Ok(Json(response))
+}
+
+pub async fn update_issue(
+    _: Editor,
+    Extension(issue_repository): Extension<Arc<NewsletterIssueRepositoryImpl>>,
+    Extension(topic_repository): Extension<Arc<TopicRepositoryImpl>>,
+    Path(issue_id): Path<Uuid>,
+    body: Result<Json<IssueBody>, JsonRejection>,
+) -> Result<StatusCode, Response> {
+    let Json(body) = body.map_err(json_rejection)?;
+    let issue = body.parse().map_err(core_error)?;
+
+    zero2prod_core::handlers::update_issue(
+        issue_repository.as_ref(),
+        topic_repository.as_ref(),
+        issue_id,
+        issue.content,
+        &issue.topics,
+        issue.segment,
+    )
+    .await
+    .map_err(core_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn delete_issue(
+    _: Editor,
+    Extension(issue_repository): Extension<Arc<NewsletterIssueRepositoryImpl>>,
+    Path(issue_id): Path<Uuid>,
+) -> Result<StatusCode, Response> {
+    zero2prod_core::handlers::delete_issue(issue_repository.as_ref(), issue_id)
+        .await
+        .map_err(core_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Renders the issue as its recipients will see it.
+pub async fn preview_issue(
+    _: Editor,
+    Extension(issue_repository): Extension<Arc<NewsletterIssueRepositoryImpl>>,
+    Extension(list_repository): Extension<Arc<ListRepositoryImpl>>,
+    Extension(template_engine): Extension<Arc<TemplateEngine>>,
+    Path(issue_id): Path<Uuid>,
+) -> Result<Html<String>, Response> {
+    let issue = find_issue(&issue_repository, issue_id).await?;
+
+    let preview = render_preview(&list_repository, &template_engine, issue)
+        .await
+        .map_err(core_error)?;
+    Ok(Html(preview))
+}
+
+pub async fn schedule_issue(
+    _: Editor,
+    Extension(issue_repository): Extension<Arc<NewsletterIssueRepositoryImpl>>,
+    Path(issue_id): Path<Uuid>,
+    body: Result<Json<ScheduleBody>, JsonRejection>,
+) -> Result<StatusCode, Response> {
+    let Json(body) = body.map_err(json_rejection)?;
+
+    zero2prod_core::handlers::schedule_issue(
+        issue_repository.as_ref(),
+        issue_id,
+        body.send_at.into(),
+    )
+    .await
+    .map_err(core_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn unschedule_issue(
+    _: Editor,
+    Extension(issue_repository): Extension<Arc<NewsletterIssueRepositoryImpl>>,
+    Path(issue_id): Path<Uuid>,
+) -> Result<StatusCode, Response> {
+    zero2prod_core::handlers::unschedule_issue(issue_repository.as_ref(), issue_id)
+        .await
+        .map_err(core_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Sends the issue right away, that is on the next run of the scheduler. The
+/// body is optional, it may name the segment to send the issue to.
+///
+/// Publishing is idempotent when the request carries an `Idempotency-Key`.
+#[allow(clippy::too_many_arguments)]
+pub async fn publish_issue(
+    editor: Editor,
+    idempotency_key: IdempotencyKey,
+    Extension(idempotency_store): Extension<Arc<IdempotencyStore>>,
+    Extension(issue_repository): Extension<Arc<NewsletterIssueRepositoryImpl>>,
+    Extension(topic_repository): Extension<Arc<TopicRepositoryImpl>>,
+    Path(issue_id): Path<Uuid>,
+    uri: Uri,
+    Fingerprinted(fingerprint, body): Fingerprinted<Result<Json<PublishBody>, JsonRejection>>,
+) -> Response {
+    let publish = async {
+        let segment = match body {
+            Ok(Json(body)) => body.segment,
+            Err(JsonRejection::MissingJsonContentType(_)) => None,
+            Err(rejection) => return Err(json_rejection(rejection)),
+        };
+        let segment = segment
+            .as_deref()
+            .map(Segment::parse)
+            .transpose()
+            .map_err(core_error)?;
+
+        zero2prod_core::handlers::publish_issue(
+            issue_repository.as_ref(),
+            topic_repository.as_ref(),
+            issue_id,
+            segment,
+            std::time::SystemTime::now(),
+        )
+        .await
+        .map_err(core_error)?;
+
+        Ok::<_, Response>(StatusCode::ACCEPTED)
+    };
+
+    idempotency_store
+        .run(
+            Some(&editor.user_id.to_string()),
+            idempotency_key,
+            uri.path(),
+            &fingerprint,
+            publish,
+        )
+        .await
+}
+
+/// Loaded by the tracking pixel of the issue emails.
+pub async fn issue_opened(
+    Extension(issue_repository): Extension<Arc<NewsletterIssueRepositoryImpl>>,
+    Extension(links): Extension<Arc<SubscriptionLinks>>,
+    Path(issue_id): Path<Uuid>,
+    Query(params): Query<TokenParams>,
+) -> Result<Response, Response> {
+    zero2prod_core::handlers::record_open(
+        issue_repository.as_ref(),
+        links.keys(),
+        &params.token,
+        issue_id,
+        unix_now(),
+    )
+    .await
+    .map_err(core_error)?;
+
+    Ok((
+        [
+            (http::header::CONTENT_TYPE, "image/gif"),
+            (http::header::CACHE_CONTROL, "no-store"),
+        ],
+        TRACKING_PIXEL,
+    )
+        .into_response())
+}
+
+/// The HTML of the issue, with links that lead nowhere.
+pub(crate) async fn render_preview(
+    list_repository: &ListRepositoryImpl,
+    template_engine: &TemplateEngine,
+    issue: NewsletterIssue,
+) -> CoreResult<String> {
+    let list = list_repository
+        .find_by_id(issue.list_id)
+        .await?
+        .ok_or(CoreError::ListNotFound)?;
+
+    let document = Document::new(
+        issue.content.title,
+        DocumentKind::Issue {
+            html_content: issue.content.html_content,
+            text_content: issue.content.text_content,
+            unsubscribe_link: "#".into(),
+            preferences_link: "#".into(),
+            tracking_pixel_link: None,
+        },
+    )
+    .for_list(&list);
+    Ok(template_engine.render(&document))
+}
+
+pub(crate) async fn find_issue(
+    issue_repository: &NewsletterIssueRepositoryImpl,
+    issue_id: Uuid,
+) -> Result<NewsletterIssue, Response> {
+    issue_repository
+        .find_by_id(issue_id)
+        .await
+        .map_err(core_error)?
+        .ok_or_else(|| core_error(CoreError::IssueNotFound))
+}
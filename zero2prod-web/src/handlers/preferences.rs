@@ -0,0 +1,145 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{rejection::FormRejection, Query},
+    response::{Html, Response},
+    Extension, Form,
+};
+use serde::Serialize;
+use zero2prod_core::{
+    domain::TopicSlug,
+    error::{CoreError, CoreResult},
+    handlers::Preferences,
+    repository::ListRepository,
+};
+
+use crate::{
+    clock::unix_now,
+    consent::Consent,
+    error::{core_error, form_rejection},
+    handlers::TokenParams,
+    repository::{
+        ConsentRepositoryImpl, ListRepositoryImpl, SubscriptionRepositoryImpl, TopicRepositoryImpl,
+    },
+    service::SubscriptionLinks,
+    template::{Page, TemplateEngine},
+};
+
+#[derive(Serialize)]
+struct PreferencesPage {
+    token: String,
+    list: String,
+    email: String,
+    topics: Vec<TopicPreferencePage>,
+    saved: bool,
+}
+
+#[derive(Serialize)]
+struct TopicPreferencePage {
+    slug: String,
+    name: String,
+    subscribed: bool,
+}
+
+/// The preference center, subscribers get there from the link of every
+/// issue.
+pub async fn preferences(
+    Extension(subscription_repository): Extension<Arc<SubscriptionRepositoryImpl>>,
+    Extension(topic_repository): Extension<Arc<TopicRepositoryImpl>>,
+    Extension(list_repository): Extension<Arc<ListRepositoryImpl>>,
+    Extension(links): Extension<Arc<SubscriptionLinks>>,
+    Extension(template_engine): Extension<Arc<TemplateEngine>>,
+    Query(params): Query<TokenParams>,
+) -> Result<Html<String>, Response> {
+    let preferences = zero2prod_core::handlers::preferences(
+        subscription_repository.as_ref(),
+        topic_repository.as_ref(),
+        links.keys(),
+        &params.token,
+        unix_now(),
+    )
+    .await
+    .map_err(core_error)?;
+
+    render(
+        &list_repository,
+        &template_engine,
+        params.token,
+        preferences,
+        false,
+    )
+    .await
+    .map_err(core_error)
+}
+
+/// Saves the topics checked in the preference center, the form repeats the
+/// `topics` field for every one of them.
+#[allow(clippy::too_many_arguments)]
+pub async fn update_preferences(
+    Extension(subscription_repository): Extension<Arc<SubscriptionRepositoryImpl>>,
+    Extension(topic_repository): Extension<Arc<TopicRepositoryImpl>>,
+    Extension(list_repository): Extension<Arc<ListRepositoryImpl>>,
+    Extension(links): Extension<Arc<SubscriptionLinks>>,
+    Extension(consent_repository): Extension<Arc<ConsentRepositoryImpl>>,
+    Extension(template_engine): Extension<Arc<TemplateEngine>>,
+    Consent(consent): Consent,
+    form: Result<Form<Vec<(String, String)>>, FormRejection>,
+) -> Result<Html<String>, Response> {
+    let Form(fields) = form.map_err(form_rejection)?;
+    let mut token = None;
+    let mut topics = vec![];
+    for (name, value) in fields {
+        match name.as_str() {
+            "token" => token = Some(value),
+            "topics" => topics.push(TopicSlug::parse(value).map_err(core_error)?),
+            _ => {}
+        }
+    }
+    let token = token.ok_or_else(|| core_error(CoreError::InvalidToken))?;
+
+    let preferences = zero2prod_core::handlers::update_preferences(
+        subscription_repository.as_ref(),
+        topic_repository.as_ref(),
+        consent_repository.as_ref(),
+        links.keys(),
+        &token,
+        unix_now(),
+        &topics,
+        &consent,
+    )
+    .await
+    .map_err(core_error)?;
+
+    render(&list_repository, &template_engine, token, preferences, true)
+        .await
+        .map_err(core_error)
+}
+
+async fn render(
+    list_repository: &ListRepositoryImpl,
+    template_engine: &TemplateEngine,
+    token: String,
+    preferences: Preferences,
+    saved: bool,
+) -> CoreResult<Html<String>> {
+    let list = list_repository
+        .find_by_id(preferences.subscriber.list_id)
+        .await?
+        .ok_or(CoreError::ListNotFound)?;
+    let page = PreferencesPage {
+        token,
+        list: list.settings.name,
+        email: preferences.subscriber.email.to_string(),
+        topics: preferences
+            .topics
+            .into_iter()
+            .map(|preference| TopicPreferencePage {
+                slug: preference.topic.slug.to_string(),
+                name: preference.topic.name,
+                subscribed: preference.subscribed,
+            })
+            .collect(),
+        saved,
+    };
+    Ok(Html(template_engine.render_page(Page::Preferences, &page)))
+}
@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{rejection::JsonRejection, Path},
+    response::Response,
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use zero2prod_core::domain::Segment;
+
+use crate::{
+    auth::Editor,
+    error::{core_error, json_rejection},
+    handlers::lists::find_list,
+    repository::{ListRepositoryImpl, SubscriptionRepositoryImpl, TopicRepositoryImpl},
+};
+
+#[derive(Deserialize)]
+pub struct SegmentBody {
+    segment: String,
+}
+
+#[derive(Serialize)]
+pub struct SegmentCount {
+    count: u64,
+}
+
+/// Counts the subscribers of the list in a segment without sending anything.
+pub async fn count_segment(
+    _: Editor,
+    Extension(subscription_repository): Extension<Arc<SubscriptionRepositoryImpl>>,
+    Extension(list_repository): Extension<Arc<ListRepositoryImpl>>,
+    Extension(topic_repository): Extension<Arc<TopicRepositoryImpl>>,
+    Path(slug): Path<String>,
+    body: Result<Json<SegmentBody>, JsonRejection>,
+) -> Result<Json<SegmentCount>, Response> {
+    let Json(body) = body.map_err(json_rejection)?;
+    let list = find_list(&list_repository, slug)
+        .await
+        .map_err(core_error)?;
+    let segment = Segment::parse(&body.segment).map_err(core_error)?;
+
+    let count = zero2prod_core::handlers::count_segment(
+        subscription_repository.as_ref(),
+        topic_repository.as_ref(),
+        list.id,
+        &segment,
+    )
+    .await
+    .map_err(core_error)?;
+
+    Ok(Json(SegmentCount { count }))
+}
@@ -1,45 +1,134 @@
+use std::net::SocketAddr;
 use std::sync::Arc;
 
+use axum::extract::{ConnectInfo, Path};
+use axum::response::{IntoResponse, Response};
 use axum::Extension;
 use axum::{extract::rejection::FormRejection, Form};
+use http::{HeaderMap, Uri};
 use hyper::StatusCode;
+use serde::Deserialize;
 
-use sqlx::types::Uuid;
-
-use zero2prod_core::domain::{Document, DocumentKind, NewSubscriber};
+use email_address::EmailAddress;
+use zero2prod_core::domain::{NewSubscriber, SubscriberName};
+use zero2prod_core::error::CoreError;
 
+use crate::bot_protection::{BotFields, BotProtection};
 use crate::configuration::Configuration;
+use crate::consent::consent_context;
 use crate::error::{core_error, form_rejection};
-use crate::repository::SubscriptionRepositoryImpl;
-use crate::service::EmailServiceImpl;
+use crate::handlers::lists::find_list;
+use crate::idempotency::{Fingerprinted, IdempotencyKey, IdempotencyStore};
+use crate::layer::client_ip;
+use crate::metrics::Metrics;
+use crate::repository::{ConsentRepositoryImpl, ListRepositoryImpl, SubscriptionRepositoryImpl};
+use crate::service::{EmailServiceImpl, RateLimiterImpl, SubscriptionLinks};
+
+#[derive(Deserialize)]
+pub struct SubscribeForm {
+    name: SubscriberName,
+    email: EmailAddress,
+    #[serde(flatten)]
+    bot: BotFields,
+}
 
+/// Subscribes to the list `slug`.
+///
+/// Subscriptions are idempotent when the request carries an
+/// `Idempotency-Key`, the keys of anonymous clients are scoped to their IP.
+/// They are ignored when the IP of the client is unknown.
+#[allow(clippy::too_many_arguments)]
 pub async fn subscribe(
+    Path(slug): Path<String>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    uri: Uri,
+    idempotency_key: IdempotencyKey,
+    Extension(idempotency_store): Extension<Arc<IdempotencyStore>>,
     Extension(config): Extension<Arc<Configuration>>,
     Extension(subscription_repository): Extension<Arc<SubscriptionRepositoryImpl>>,
+    Extension(list_repository): Extension<Arc<ListRepositoryImpl>>,
+    Extension(consent_repository): Extension<Arc<ConsentRepositoryImpl>>,
     Extension(email_client): Extension<Arc<EmailServiceImpl>>,
-    form: Result<Form<NewSubscriber>, FormRejection>,
-) -> Result<StatusCode, (StatusCode, String)> {
-    let form = form.map_err(form_rejection)?;
-
-    let confirmation_link = format!(
-        "http://{}/subscriptions/confirm?sub_id={}",
-        config.app.host,
-        Uuid::new_v4()
-    );
-
-    let confirmation_email = Document::new(
-        "Welcome !".into(),
-        DocumentKind::Confirmation { confirmation_link },
-    );
-
-    zero2prod_core::handlers::subscribe(
-        subscription_repository.as_ref(),
-        email_client.as_ref(),
-        form.0,
-        confirmation_email,
-    )
-    .await
-    .map_err(core_error)?;
-
-    Ok(StatusCode::OK)
+    Extension(email_limiter): Extension<Arc<RateLimiterImpl>>,
+    Extension(links): Extension<Arc<SubscriptionLinks>>,
+    Extension(bot_protection): Extension<Arc<BotProtection>>,
+    Extension(metrics): Extension<Arc<Metrics>>,
+    Fingerprinted(fingerprint, form): Fingerprinted<Result<Form<SubscribeForm>, FormRejection>>,
+) -> Response {
+    let client_ip = connect_info.map(|ConnectInfo(peer)| {
+        client_ip(peer.ip(), &headers, &config.rate_limit.trusted_proxies)
+    });
+    // Without the IP of the client there is nothing to scope its keys to
+    let owner = client_ip.map(|ip| ip.to_string());
+    let consent = consent_context(Some(&config), client_ip, &headers);
+
+    let subscribe = async {
+        let Form(form) = form.map_err(|err| {
+            metrics.subscription("invalid");
+            form_rejection(err)
+        })?;
+
+        if let Err(rejection) = bot_protection.check(&form.bot, client_ip).await {
+            tracing::warn!(
+                reason = rejection.reason(),
+                "Rejected a subscription from a bot"
+            );
+            metrics.bot_rejection(rejection.reason());
+            metrics.subscription("rejected");
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "unable to process the subscription".to_string(),
+            )
+                .into_response());
+        }
+
+        let list = find_list(&list_repository, slug).await.map_err(|err| {
+            metrics.subscription("invalid");
+            core_error(err)
+        })?;
+        let new_subscriber = NewSubscriber {
+            list_id: list.id,
+            name: form.name,
+            email: form.email,
+        };
+
+        let result = zero2prod_core::handlers::subscribe(
+            subscription_repository.as_ref(),
+            consent_repository.as_ref(),
+            email_client.as_ref(),
+            email_limiter.as_ref(),
+            &list,
+            new_subscriber,
+            &consent,
+            |list, subscriber_id, stored| links.confirmation_email(list, subscriber_id, stored),
+        )
+        .await;
+
+        metrics.subscription(match &result {
+            Ok(()) => "created",
+            Err(CoreError::EmailAlreadyExists) => "already_exists",
+            Err(CoreError::EmailSuppressed) => "suppressed",
+            Err(CoreError::InvalidDomain(_)) => "invalid",
+            Err(CoreError::RateLimited { .. }) => {
+                metrics.rate_limited(email_limiter.scope());
+                "rate_limited"
+            }
+            Err(_) => "failed",
+        });
+
+        result.map_err(core_error)?;
+
+        Ok(StatusCode::OK)
+    };
+
+    idempotency_store
+        .run(
+            owner.as_deref(),
+            idempotency_key,
+            uri.path(),
+            &fingerprint,
+            subscribe,
+        )
+        .await
 }
@@ -0,0 +1,154 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::Query,
+    response::{IntoResponse, Response},
+    Extension,
+};
+use chrono::{DateTime, Utc};
+use futures_util::{stream, StreamExt};
+use http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use uuid::Uuid;
+use zero2prod_core::{
+    domain::{SubscriberEntry, SubscriptionStatus},
+    error::{CoreError, CoreResult},
+};
+
+use crate::{
+    auth::Editor, configuration::Configuration, error::core_error,
+    handlers::subscriber_import::csv_cell, repository::SubscriptionRepositoryImpl,
+};
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    /// One JSON object per line.
+    Ndjson,
+}
+
+#[derive(Deserialize)]
+pub struct ExportParams {
+    #[serde(default)]
+    format: ExportFormat,
+    status: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ExportedSubscriber {
+    id: Uuid,
+    list_id: Uuid,
+    email: String,
+    name: String,
+    status: &'static str,
+    subscribed_at: DateTime<Utc>,
+}
+
+impl From<SubscriberEntry> for ExportedSubscriber {
+    fn from(entry: SubscriberEntry) -> Self {
+        Self {
+            id: entry.subscriber.id,
+            list_id: entry.subscriber.list_id,
+            email: entry.subscriber.email.to_string(),
+            name: entry.subscriber.name.as_ref().to_owned(),
+            status: entry.subscriber.status.as_str(),
+            subscribed_at: entry.subscribed_at.into(),
+        }
+    }
+}
+
+const CSV_HEADER: [&str; 6] = ["id", "list_id", "email", "name", "status", "subscribed_at"];
+
+/// Exports the subscribers to every list, optionally only the ones with a
+/// status.
+///
+/// The file is written as the rows are read, whatever the number of
+/// subscribers.
+pub async fn export_subscribers(
+    _: Editor,
+    Extension(config): Extension<Arc<Configuration>>,
+    Extension(subscription_repository): Extension<Arc<SubscriptionRepositoryImpl>>,
+    Query(params): Query<ExportParams>,
+) -> Result<Response, Response> {
+    let status = params
+        .status
+        .map(|status| status.parse::<SubscriptionStatus>())
+        .transpose()
+        .map_err(core_error)?;
+    let format = params.format;
+
+    let header = match format {
+        ExportFormat::Csv => Some(csv_lines([CSV_HEADER]).map_err(core_error)?),
+        ExportFormat::Ndjson => None,
+    };
+    let batches = subscription_repository
+        .export(status, config.subscriptions.export_batch_size)
+        .map(move |batch| {
+            batch
+                .and_then(|batch| encode(format, batch))
+                .map_err(|err| {
+                    // The response has started, the client sees a truncated file
+                    warn!("Failed to export the subscribers: {}", err);
+                    std::io::Error::other(err.to_string())
+                })
+        });
+    let body = Body::from_stream(stream::iter(header.map(Ok)).chain(batches));
+
+    let (content_type, extension) = match format {
+        ExportFormat::Csv => ("text/csv; charset=utf-8", "csv"),
+        ExportFormat::Ndjson => ("application/x-ndjson", "ndjson"),
+    };
+    Ok((
+        [
+            (CONTENT_TYPE, content_type.to_string()),
+            (
+                CONTENT_DISPOSITION,
+                format!("attachment; filename=\"subscribers.{}\"", extension),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+fn encode(format: ExportFormat, batch: Vec<SubscriberEntry>) -> CoreResult<Vec<u8>> {
+    let subscribers = batch.into_iter().map(ExportedSubscriber::from);
+    match format {
+        ExportFormat::Csv => csv_lines(subscribers.map(|subscriber| {
+            [
+                subscriber.id.to_string(),
+                subscriber.list_id.to_string(),
+                csv_cell(&subscriber.email).into_owned(),
+                csv_cell(&subscriber.name).into_owned(),
+                subscriber.status.to_owned(),
+                subscriber.subscribed_at.to_rfc3339(),
+            ]
+        })),
+        ExportFormat::Ndjson => {
+            let mut lines = vec![];
+            for subscriber in subscribers {
+                serde_json::to_writer(&mut lines, &subscriber)?;
+                lines.push(b'\n');
+            }
+            Ok(lines)
+        }
+    }
+}
+
+fn csv_lines<R, F>(records: impl IntoIterator<Item = R>) -> CoreResult<Vec<u8>>
+where
+    R: IntoIterator<Item = F>,
+    F: AsRef<[u8]>,
+{
+    let mut writer = csv::Writer::from_writer(vec![]);
+    for record in records {
+        writer.write_record(record)?;
+    }
+    writer
+        .into_inner()
+        .map_err(|err| CoreError::Unexpected(err.to_string()))
+}
@@ -0,0 +1,368 @@
+use std::{borrow::Cow, io::Read, path::PathBuf, sync::Arc};
+
+use axum::{
+    body::Body,
+    extract::{Path, Query},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use chrono::{DateTime, Utc};
+use csv::ByteRecord;
+use futures_util::TryStreamExt;
+use http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use tokio::{io::AsyncWriteExt, sync::mpsc, task::JoinHandle};
+use tokio_util::io::StreamReader;
+use tracing::{error, warn};
+use uuid::Uuid;
+use zero2prod_core::{
+    domain::{ImportOptions, ImportRow, ImportRowError, ImportStatus, SubscriberImport},
+    error::{CoreError, CoreResult},
+    handlers::{finish_import, import_batch, start_import},
+    repository::SubscriberImportRepository,
+};
+
+use crate::{
+    auth::Editor,
+    configuration::Configuration,
+    error::core_error,
+    handlers::lists::find_list,
+    repository::{ListRepositoryImpl, SubscriberImportRepositoryImpl},
+    shutdown::Shutdown,
+};
+
+#[derive(Deserialize)]
+pub struct ImportParams {
+    list: String,
+    /// Header of the column holding the email addresses.
+    #[serde(default = "default_email_column")]
+    email_column: String,
+    /// Header of the column holding the names.
+    #[serde(default = "default_name_column")]
+    name_column: String,
+    /// Subscribes the rows as confirmed rather than sending them a
+    /// confirmation email.
+    #[serde(default)]
+    confirmed: bool,
+}
+
+fn default_email_column() -> String {
+    "email".into()
+}
+
+fn default_name_column() -> String {
+    "name".into()
+}
+
+#[derive(Serialize)]
+pub struct ImportResponse {
+    id: Uuid,
+    list_id: Uuid,
+    confirmed: bool,
+    status: &'static str,
+    rows: u64,
+    imported: u64,
+    failed: u64,
+    created_at: DateTime<Utc>,
+    finished_at: Option<DateTime<Utc>>,
+    /// Where to download the rows that were not imported.
+    errors: String,
+}
+
+impl From<SubscriberImport> for ImportResponse {
+    fn from(import: SubscriberImport) -> Self {
+        Self {
+            id: import.id,
+            list_id: import.list_id,
+            confirmed: import.options.confirmed,
+            status: import.status.as_str(),
+            rows: import.progress.rows,
+            imported: import.progress.imported,
+            failed: import.progress.failed,
+            created_at: import.created_at.into(),
+            finished_at: import.finished_at.map(DateTime::from),
+            errors: format!("/subscribers/import/{}/errors", import.id),
+        }
+    }
+}
+
+/// Why the rows of a file could not be read.
+enum ReadError {
+    /// Fails the request, before anything is imported.
+    MissingColumn(String),
+    /// Fails the import, the rows read until then are kept.
+    Unreadable(String),
+}
+
+/// Imports the subscribers of a CSV file to a list.
+///
+/// The upload is spooled to a temporary file, the import is then run in the
+/// background and the response sent as soon as it started. Clients follow it
+/// through [`get_import`]. The file is imported in batches, whatever its size.
+/// Invalid rows do not stop the import, they are kept in its error report.
+pub async fn import_subscribers(
+    _: Editor,
+    Extension(config): Extension<Arc<Configuration>>,
+    Extension(list_repository): Extension<Arc<ListRepositoryImpl>>,
+    Extension(import_repository): Extension<Arc<SubscriberImportRepositoryImpl>>,
+    Extension(shutdown): Extension<Shutdown>,
+    Query(params): Query<ImportParams>,
+    body: Body,
+) -> Result<(StatusCode, Json<ImportResponse>), Response> {
+    let list = find_list(&list_repository, params.list)
+        .await
+        .map_err(core_error)?;
+    let upload = Upload::spool(body).await.map_err(|err| {
+        warn!("Failed to receive the imported file: {}", err);
+        (
+            StatusCode::BAD_REQUEST,
+            "failed to read the file".to_string(),
+        )
+            .into_response()
+    })?;
+    let file = std::fs::File::open(&upload.0).map_err(|err| core_error(err.into()))?;
+
+    // Parsing blocks, the rows are handed over in batches as they are read
+    let batch_size = config.subscriptions.import_batch_size.max(1);
+    let (sender, mut batches) = mpsc::channel(1);
+    let reader = tokio::task::spawn_blocking(move || {
+        read_rows(
+            file,
+            &params.email_column,
+            &params.name_column,
+            batch_size,
+            sender,
+        );
+        drop(upload);
+    });
+
+    let next = batches.recv().await;
+    if let Some(Err(ReadError::MissingColumn(column))) = next {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("missing column: {}", column),
+        )
+            .into_response());
+    }
+
+    let options = ImportOptions {
+        confirmed: params.confirmed,
+    };
+    let import = start_import(import_repository.as_ref(), &list, options)
+        .await
+        .map_err(core_error)?;
+    shutdown.spawn(run_import(
+        import_repository,
+        import.clone(),
+        next,
+        batches,
+        reader,
+        shutdown.clone(),
+    ));
+
+    Ok((StatusCode::ACCEPTED, Json(import.into())))
+}
+
+/// An uploaded file, removed once dropped.
+struct Upload(PathBuf);
+
+impl Upload {
+    async fn spool(body: Body) -> std::io::Result<Self> {
+        let upload = Upload(std::env::temp_dir().join(format!("import-{}.csv", Uuid::new_v4())));
+        let mut file = tokio::fs::File::create(&upload.0).await?;
+        let mut body = StreamReader::new(body.into_data_stream().map_err(std::io::Error::other));
+        tokio::io::copy(&mut body, &mut file).await?;
+        file.flush().await?;
+        Ok(upload)
+    }
+}
+
+impl Drop for Upload {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Imports the batches of rows, `next` being the first, then ends the import.
+/// Imports still running on shutdown are failed between two batches.
+async fn run_import(
+    import_repository: Arc<SubscriberImportRepositoryImpl>,
+    import: SubscriberImport,
+    mut next: Option<Result<Vec<ImportRow>, ReadError>>,
+    mut batches: mpsc::Receiver<Result<Vec<ImportRow>, ReadError>>,
+    reader: JoinHandle<()>,
+    shutdown: Shutdown,
+) {
+    let mut status = ImportStatus::Completed;
+    while let Some(batch) = next {
+        let rows = match batch {
+            Ok(rows) => rows,
+            Err(ReadError::MissingColumn(message) | ReadError::Unreadable(message)) => {
+                warn!("Failed to read the imported file: {}", message);
+                status = ImportStatus::Failed;
+                break;
+            }
+        };
+        if let Err(err) = import_batch(import_repository.as_ref(), &import, rows).await {
+            error!(import_id = %import.id, "Failed to import a batch: {}", err);
+            status = ImportStatus::Failed;
+            break;
+        }
+        if shutdown.is_triggered() {
+            warn!(import_id = %import.id, "Stopped the import on shutdown");
+            status = ImportStatus::Failed;
+            break;
+        }
+        next = batches.recv().await;
+    }
+    drop(batches);
+    let _ = reader.await;
+
+    if let Err(err) = finish_import(import_repository.as_ref(), import.id, status).await {
+        error!(import_id = %import.id, "Failed to end the import: {}", err);
+    }
+}
+
+pub async fn get_import(
+    _: Editor,
+    Extension(import_repository): Extension<Arc<SubscriberImportRepositoryImpl>>,
+    Path(import_id): Path<Uuid>,
+) -> Result<Json<ImportResponse>, Response> {
+    let import = find_import(&import_repository, import_id)
+        .await
+        .map_err(core_error)?;
+
+    Ok(Json(import.into()))
+}
+
+/// The rows of an import that were not imported, as a CSV file.
+pub async fn import_errors(
+    _: Editor,
+    Extension(import_repository): Extension<Arc<SubscriberImportRepositoryImpl>>,
+    Path(import_id): Path<Uuid>,
+) -> Result<Response, Response> {
+    let import = find_import(&import_repository, import_id)
+        .await
+        .map_err(core_error)?;
+    let errors = import_repository
+        .errors(import.id)
+        .await
+        .map_err(core_error)?;
+
+    let report = error_report(&errors).map_err(core_error)?;
+
+    Ok((
+        [
+            (CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (
+                CONTENT_DISPOSITION,
+                format!("attachment; filename=\"import-{}-errors.csv\"", import.id),
+            ),
+        ],
+        report,
+    )
+        .into_response())
+}
+
+fn error_report(errors: &[ImportRowError]) -> CoreResult<Vec<u8>> {
+    let mut report = csv::Writer::from_writer(vec![]);
+    report.write_record(["line", "email", "error"])?;
+    for error in errors {
+        report.write_record([
+            error.line.to_string().as_str(),
+            &csv_cell(&error.email),
+            &error.reason,
+        ])?;
+    }
+    report
+        .into_inner()
+        .map_err(|err| CoreError::Unexpected(err.to_string()))
+}
+
+/// Quotes the cells spreadsheets would evaluate as formulas, so that opening
+/// a file cannot run what a subscriber typed in a form.
+pub(crate) fn csv_cell(value: &str) -> Cow<'_, str> {
+    if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        Cow::Owned(format!("'{}", value))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+async fn find_import(
+    import_repository: &SubscriberImportRepositoryImpl,
+    import_id: Uuid,
+) -> CoreResult<SubscriberImport> {
+    import_repository
+        .find_by_id(import_id)
+        .await?
+        .ok_or(CoreError::ImportNotFound)
+}
+
+/// Sends the rows of `file` to `batches`, until the file ends or the
+/// receiver is gone.
+fn read_rows(
+    file: impl Read,
+    email_column: &str,
+    name_column: &str,
+    batch_size: usize,
+    batches: mpsc::Sender<Result<Vec<ImportRow>, ReadError>>,
+) {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(file);
+    let columns = reader
+        .byte_headers()
+        .map_err(|err| ReadError::Unreadable(err.to_string()))
+        .and_then(|headers| {
+            let position = |column: &str| {
+                headers
+                    .iter()
+                    .position(|header| {
+                        String::from_utf8_lossy(header)
+                            .trim()
+                            .eq_ignore_ascii_case(column)
+                    })
+                    .ok_or_else(|| ReadError::MissingColumn(column.to_owned()))
+            };
+            Ok((position(email_column)?, position(name_column)?))
+        });
+    let (email, name) = match columns {
+        Ok(columns) => columns,
+        Err(err) => {
+            let _ = batches.blocking_send(Err(err));
+            return;
+        }
+    };
+
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut record = ByteRecord::new();
+    loop {
+        match reader.read_byte_record(&mut record) {
+            Ok(true) => {
+                let field = |index| {
+                    String::from_utf8_lossy(record.get(index).unwrap_or_default()).into_owned()
+                };
+                batch.push(ImportRow {
+                    line: record.position().map_or(0, |position| position.line()),
+                    name: field(name),
+                    email: field(email),
+                });
+                if batch.len() == batch_size
+                    && batches
+                        .blocking_send(Ok(std::mem::take(&mut batch)))
+                        .is_err()
+                {
+                    return;
+                }
+            }
+            Ok(false) => break,
+            Err(err) => {
+                let _ = batches.blocking_send(Err(ReadError::Unreadable(err.to_string())));
+                return;
+            }
+        }
+    }
+    if !batch.is_empty() {
+        let _ = batches.blocking_send(Ok(batch));
+    }
+}
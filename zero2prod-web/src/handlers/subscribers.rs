@@ -0,0 +1,245 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{rejection::JsonRejection, Path, Query},
+    response::Response,
+    Extension, Json,
+};
+use chrono::{DateTime, Utc};
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use zero2prod_core::{
+    domain::{
+        SubscriberCursor, SubscriberEntry, SubscriberFilter, SubscriberName, SubscriberUpdate,
+        SubscriptionStatus, TopicSlug,
+    },
+    error::CoreResult,
+    handlers::{SubscriberDetails, SubscriberPage},
+};
+
+use crate::{
+    auth::Editor,
+    error::{core_error, json_rejection},
+    handlers::lists::find_list,
+    repository::{
+        ConsentRepositoryImpl, ListRepositoryImpl, SubscriptionRepositoryImpl, TopicRepositoryImpl,
+    },
+};
+
+/// Subscribers listed per page when the request does not say.
+const DEFAULT_PAGE_SIZE: u64 = 50;
+const MAX_PAGE_SIZE: u64 = 500;
+
+#[derive(Deserialize)]
+pub struct SubscriberParams {
+    /// Slug of the only list to list the subscribers of.
+    list: Option<String>,
+    status: Option<String>,
+    subscribed_after: Option<DateTime<Utc>>,
+    subscribed_before: Option<DateTime<Utc>>,
+    /// Start of the email address or of the name of the subscribers.
+    search: Option<String>,
+    /// The `next` cursor of the previous page.
+    after: Option<String>,
+    limit: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub struct SubscriberBody {
+    name: Option<String>,
+    status: Option<String>,
+    /// Slugs of the only topics the subscriber receives.
+    topics: Option<Vec<String>>,
+}
+
+impl TryFrom<SubscriberBody> for SubscriberUpdate {
+    type Error = zero2prod_core::error::CoreError;
+
+    fn try_from(body: SubscriberBody) -> CoreResult<Self> {
+        Ok(SubscriberUpdate {
+            name: body.name.map(SubscriberName::parse).transpose()?,
+            status: body
+                .status
+                .map(|status| status.parse::<SubscriptionStatus>())
+                .transpose()?,
+            topics: body
+                .topics
+                .map(|topics| topics.into_iter().map(TopicSlug::parse).collect())
+                .transpose()?,
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub struct SubscriberPageResponse {
+    subscribers: Vec<SubscriberResponse>,
+    /// Cursor of the next page, as the `after` parameter.
+    next: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SubscriberResponse {
+    id: Uuid,
+    list_id: Uuid,
+    email: String,
+    name: String,
+    status: &'static str,
+    subscribed_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    topics: Option<Vec<TopicChoiceResponse>>,
+}
+
+#[derive(Serialize)]
+pub struct TopicChoiceResponse {
+    topic: String,
+    subscribed: bool,
+}
+
+impl From<SubscriberPage> for SubscriberPageResponse {
+    fn from(page: SubscriberPage) -> Self {
+        Self {
+            subscribers: page
+                .subscribers
+                .into_iter()
+                .map(SubscriberResponse::from)
+                .collect(),
+            next: page.next.map(|cursor| cursor.to_string()),
+        }
+    }
+}
+
+impl From<SubscriberEntry> for SubscriberResponse {
+    fn from(entry: SubscriberEntry) -> Self {
+        Self {
+            id: entry.subscriber.id,
+            list_id: entry.subscriber.list_id,
+            email: entry.subscriber.email.to_string(),
+            name: entry.subscriber.name.as_ref().to_owned(),
+            status: entry.subscriber.status.as_str(),
+            subscribed_at: entry.subscribed_at.into(),
+            topics: None,
+        }
+    }
+}
+
+impl From<SubscriberDetails> for SubscriberResponse {
+    fn from(details: SubscriberDetails) -> Self {
+        Self {
+            topics: Some(
+                details
+                    .topics
+                    .into_iter()
+                    .map(|preference| TopicChoiceResponse {
+                        topic: preference.topic.slug.to_string(),
+                        subscribed: preference.subscribed,
+                    })
+                    .collect(),
+            ),
+            ..details.entry.into()
+        }
+    }
+}
+
+/// Lists the subscribers matching the parameters, newest first.
+pub async fn list_subscribers(
+    _: Editor,
+    Extension(subscription_repository): Extension<Arc<SubscriptionRepositoryImpl>>,
+    Extension(list_repository): Extension<Arc<ListRepositoryImpl>>,
+    Query(params): Query<SubscriberParams>,
+) -> Result<Json<SubscriberPageResponse>, Response> {
+    let list_id = match params.list {
+        Some(slug) => Some(
+            find_list(&list_repository, slug)
+                .await
+                .map_err(core_error)?
+                .id,
+        ),
+        None => None,
+    };
+    let filter = SubscriberFilter {
+        list_id,
+        status: params
+            .status
+            .map(|status| status.parse::<SubscriptionStatus>())
+            .transpose()
+            .map_err(core_error)?,
+        subscribed_after: params.subscribed_after.map(Into::into),
+        subscribed_before: params.subscribed_before.map(Into::into),
+        search: params.search.filter(|search| !search.trim().is_empty()),
+    };
+    let after = params
+        .after
+        .map(|after| after.parse::<SubscriberCursor>())
+        .transpose()
+        .map_err(core_error)?;
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    let page = zero2prod_core::handlers::list_subscribers(
+        subscription_repository.as_ref(),
+        &filter,
+        after,
+        limit,
+    )
+    .await
+    .map_err(core_error)?;
+
+    Ok(Json(page.into()))
+}
+
+pub async fn get_subscriber(
+    _: Editor,
+    Extension(subscription_repository): Extension<Arc<SubscriptionRepositoryImpl>>,
+    Extension(topic_repository): Extension<Arc<TopicRepositoryImpl>>,
+    Path(subscriber_id): Path<Uuid>,
+) -> Result<Json<SubscriberResponse>, Response> {
+    let details = zero2prod_core::handlers::subscriber_details(
+        subscription_repository.as_ref(),
+        topic_repository.as_ref(),
+        subscriber_id,
+    )
+    .await
+    .map_err(core_error)?;
+
+    Ok(Json(details.into()))
+}
+
+/// Changes the name, status or topics of a subscriber.
+pub async fn update_subscriber(
+    _: Editor,
+    Extension(subscription_repository): Extension<Arc<SubscriptionRepositoryImpl>>,
+    Extension(topic_repository): Extension<Arc<TopicRepositoryImpl>>,
+    Extension(consent_repository): Extension<Arc<ConsentRepositoryImpl>>,
+    Path(subscriber_id): Path<Uuid>,
+    body: Result<Json<SubscriberBody>, JsonRejection>,
+) -> Result<Json<SubscriberResponse>, Response> {
+    let Json(body) = body.map_err(json_rejection)?;
+    let update = body.try_into().map_err(core_error)?;
+
+    let details = zero2prod_core::handlers::update_subscriber(
+        subscription_repository.as_ref(),
+        topic_repository.as_ref(),
+        consent_repository.as_ref(),
+        subscriber_id,
+        &update,
+    )
+    .await
+    .map_err(core_error)?;
+
+    Ok(Json(details.into()))
+}
+
+pub async fn delete_subscriber(
+    _: Editor,
+    Extension(subscription_repository): Extension<Arc<SubscriptionRepositoryImpl>>,
+    Path(subscriber_id): Path<Uuid>,
+) -> Result<StatusCode, Response> {
+    zero2prod_core::handlers::delete_subscriber(subscription_repository.as_ref(), subscriber_id)
+        .await
+        .map_err(core_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{rejection::JsonRejection, Path},
+    response::Response,
+    Extension, Json,
+};
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use zero2prod_core::{
+    domain::{ListSlug, NewTopic, Topic},
+    error::CoreError,
+    repository::TopicRepository,
+};
+
+use crate::{
+    auth::Editor,
+    error::{core_error, json_rejection},
+    handlers::lists::find_list,
+    repository::{ListRepositoryImpl, TopicRepositoryImpl},
+};
+
+#[derive(Deserialize)]
+pub struct TopicBody {
+    slug: String,
+    name: String,
+}
+
+#[derive(Serialize)]
+pub struct TopicResponse {
+    id: Uuid,
+    slug: String,
+    name: String,
+}
+
+impl From<Topic> for TopicResponse {
+    fn from(topic: Topic) -> Self {
+        Self {
+            id: topic.id,
+            slug: topic.slug.to_string(),
+            name: topic.name,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct CreatedTopic {
+    id: Uuid,
+}
+
+pub async fn create_topic(
+    _: Editor,
+    Extension(list_repository): Extension<Arc<ListRepositoryImpl>>,
+    Extension(topic_repository): Extension<Arc<TopicRepositoryImpl>>,
+    Path(slug): Path<String>,
+    body: Result<Json<TopicBody>, JsonRejection>,
+) -> Result<(StatusCode, Json<CreatedTopic>), Response> {
+    let Json(body) = body.map_err(json_rejection)?;
+    let slug = ListSlug::parse(slug).map_err(|_| core_error(CoreError::ListNotFound))?;
+    let new_topic = NewTopic::parse(body.slug, body.name).map_err(core_error)?;
+
+    let id = zero2prod_core::handlers::create_topic(
+        list_repository.as_ref(),
+        topic_repository.as_ref(),
+        &slug,
+        new_topic,
+    )
+    .await
+    .map_err(core_error)?;
+
+    Ok((StatusCode::CREATED, Json(CreatedTopic { id })))
+}
+
+pub async fn list_topics(
+    _: Editor,
+    Extension(list_repository): Extension<Arc<ListRepositoryImpl>>,
+    Extension(topic_repository): Extension<Arc<TopicRepositoryImpl>>,
+    Path(slug): Path<String>,
+) -> Result<Json<Vec<TopicResponse>>, Response> {
+    let list = find_list(&list_repository, slug)
+        .await
+        .map_err(core_error)?;
+    let topics = topic_repository.list(list.id).await.map_err(core_error)?;
+
+    Ok(Json(topics.into_iter().map(TopicResponse::from).collect()))
+}
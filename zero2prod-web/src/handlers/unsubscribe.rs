@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use axum::{extract::Query, response::Html, response::Response, Extension};
+use serde::Serialize;
+
+use crate::{
+    clock::unix_now,
+    consent::Consent,
+    error::core_error,
+    handlers::TokenParams,
+    repository::{ConsentRepositoryImpl, SubscriptionRepositoryImpl},
+    service::SubscriptionLinks,
+    template::{Page, TemplateEngine},
+};
+
+#[derive(Serialize)]
+struct UnsubscribePage {
+    token: String,
+    unsubscribed: bool,
+}
+
+/// Asks for a confirmation before unsubscribing, so that mail clients
+/// following links unsubscribe no one.
+pub async fn unsubscribe_page(
+    Extension(template_engine): Extension<Arc<TemplateEngine>>,
+    Query(params): Query<TokenParams>,
+) -> Html<String> {
+    let page = UnsubscribePage {
+        token: params.token,
+        unsubscribed: false,
+    };
+    Html(template_engine.render_page(Page::Unsubscribe, &page))
+}
+
+/// Also the target of the one-click unsubscribe of mail clients (RFC 8058),
+/// which post to the link of the `List-Unsubscribe` header.
+pub async fn unsubscribe(
+    Extension(subscription_repository): Extension<Arc<SubscriptionRepositoryImpl>>,
+    Extension(consent_repository): Extension<Arc<ConsentRepositoryImpl>>,
+    Extension(links): Extension<Arc<SubscriptionLinks>>,
+    Extension(template_engine): Extension<Arc<TemplateEngine>>,
+    Consent(consent): Consent,
+    Query(params): Query<TokenParams>,
+) -> Result<Html<String>, Response> {
+    zero2prod_core::handlers::unsubscribe(
+        subscription_repository.as_ref(),
+        consent_repository.as_ref(),
+        links.keys(),
+        &params.token,
+        unix_now(),
+        &consent,
+    )
+    .await
+    .map_err(core_error)?;
+
+    let page = UnsubscribePage {
+        token: String::new(),
+        unsubscribed: true,
+    };
+    Ok(Html(template_engine.render_page(Page::Unsubscribe, &page)))
+}
@@ -0,0 +1,313 @@
+use std::{future::Future, time::Duration};
+
+use async_trait::async_trait;
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    extract::{FromRequest, FromRequestParts, Request},
+    response::{IntoResponse, Response},
+};
+use http::{request::Parts, HeaderName, HeaderValue};
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{types::Json, PgPool};
+use zero2prod_core::error::CoreResult;
+
+use crate::error::core_error;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+/// Set on the responses replayed from a previous request.
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+const MAX_KEY_LENGTH: usize = 255;
+
+/// The `Idempotency-Key` of a request, absent when the client did not send
+/// one.
+pub struct IdempotencyKey(Option<String>);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for IdempotencyKey
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(value) = parts.headers.get(IDEMPOTENCY_KEY_HEADER) else {
+            return Ok(IdempotencyKey(None));
+        };
+        match value.to_str() {
+            Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LENGTH => {
+                Ok(IdempotencyKey(Some(key.to_owned())))
+            }
+            _ => Err((
+                StatusCode::BAD_REQUEST,
+                "invalid idempotency key".to_string(),
+            )
+                .into_response()),
+        }
+    }
+}
+
+/// Extracts `T` from the body of the request along with a digest of the body,
+/// so that a key reused for another request can be told apart from a retry.
+pub struct Fingerprinted<T>(pub String, pub T);
+
+#[async_trait]
+impl<S, T> FromRequest<S> for Fingerprinted<T>
+where
+    S: Send + Sync,
+    T: FromRequest<S>,
+{
+    type Rejection = Response;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let (parts, body) = request.into_parts();
+        let body = Bytes::from_request(Request::from_parts(parts.clone(), body), state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        let fingerprint = hex::encode(Sha256::digest(&body));
+        let inner = T::from_request(Request::from_parts(parts, Body::from(body)), state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        Ok(Fingerprinted(fingerprint, inner))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedHeader {
+    name: String,
+    value: Vec<u8>,
+}
+
+struct SavedResponse {
+    request_path: String,
+    request_fingerprint: Option<String>,
+    response_status: Option<i16>,
+    response_headers: Option<Json<Vec<SavedHeader>>>,
+    response_body: Option<Vec<u8>>,
+}
+
+/// Saves the first response to every idempotency key in Postgres, so that
+/// retried requests get it back rather than being processed again.
+///
+/// The key is claimed before the request is processed, a duplicate arriving
+/// meanwhile waits for the response to replay it. Server errors are not
+/// saved, the request may then be retried.
+pub struct IdempotencyStore {
+    pool: PgPool,
+    ttl: Duration,
+}
+
+/// Time after which a key still being processed is considered abandoned, for
+/// instance by an instance that stopped, and may be claimed again.
+const PROCESSING_TIMEOUT: Duration = Duration::from_secs(60);
+/// How often a duplicate checks whether the request it waits for is done.
+const PROCESSING_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+impl IdempotencyStore {
+    pub fn new(pool: PgPool, ttl: Duration) -> Self {
+        Self { pool, ttl }
+    }
+
+    /// Runs `handler` unless `key` was already used by `owner`, in which case
+    /// the saved response is returned instead. Requests without a key, or
+    /// whose owner is unknown, are always run.
+    pub async fn run<F, R>(
+        &self,
+        owner: Option<&str>,
+        key: IdempotencyKey,
+        path: &str,
+        fingerprint: &str,
+        handler: F,
+    ) -> Response
+    where
+        F: Future<Output = R>,
+        R: IntoResponse,
+    {
+        let (Some(owner), Some(key)) = (owner, key.0) else {
+            return handler.await.into_response();
+        };
+        self.run_once(owner, &key, path, fingerprint, handler)
+            .await
+            .unwrap_or_else(core_error)
+    }
+
+    async fn run_once<F, R>(
+        &self,
+        owner: &str,
+        key: &str,
+        path: &str,
+        fingerprint: &str,
+        handler: F,
+    ) -> CoreResult<Response>
+    where
+        F: Future<Output = R>,
+        R: IntoResponse,
+    {
+        // Duplicates wait for the request holding the key, it is claimed
+        // again when that request fails or is abandoned
+        while !self.claim(owner, key, path, fingerprint).await? {
+            if let Some(saved) = self.saved(owner, key).await? {
+                if let Some(response) = replay(saved, path, fingerprint)? {
+                    return Ok(response);
+                }
+            }
+            tokio::time::sleep(PROCESSING_POLL_INTERVAL).await;
+        }
+
+        let response = handler.await.into_response();
+        if response.status().is_server_error() {
+            self.release(owner, key).await?;
+            return Ok(response);
+        }
+
+        let (parts, body) = response.into_parts();
+        let body = to_bytes(body, usize::MAX).await?;
+        let headers = parts
+            .headers
+            .iter()
+            .map(|(name, value)| SavedHeader {
+                name: name.to_string(),
+                value: value.as_bytes().to_vec(),
+            })
+            .collect::<Vec<_>>();
+        sqlx::query!(
+            r#"
+            UPDATE idempotency_keys
+            SET response_status = $3, response_headers = $4, response_body = $5
+            WHERE owner = $1 AND key = $2
+            "#,
+            owner,
+            key,
+            parts.status.as_u16() as i16,
+            Json(headers) as _,
+            body.as_ref()
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(Response::from_parts(parts, Body::from(body)))
+    }
+
+    /// Claims `key` for a request, unless it is already used. The claim is
+    /// committed right away so that duplicates see the key as being
+    /// processed, expired and abandoned keys are claimed again.
+    async fn claim(
+        &self,
+        owner: &str,
+        key: &str,
+        path: &str,
+        fingerprint: &str,
+    ) -> CoreResult<bool> {
+        let claimed = sqlx::query!(
+            r#"
+            INSERT INTO idempotency_keys (owner, key, request_path, request_fingerprint)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (owner, key) DO UPDATE
+            SET request_path = EXCLUDED.request_path,
+                request_fingerprint = EXCLUDED.request_fingerprint,
+                response_status = NULL,
+                response_headers = NULL,
+                response_body = NULL,
+                created_at = now()
+            WHERE idempotency_keys.created_at < now() - make_interval(secs => $5)
+               OR (idempotency_keys.response_status IS NULL
+                   AND idempotency_keys.created_at < now() - make_interval(secs => $6))
+            "#,
+            owner,
+            key,
+            path,
+            fingerprint,
+            self.ttl.as_secs_f64(),
+            PROCESSING_TIMEOUT.as_secs_f64()
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected()
+            == 1;
+        Ok(claimed)
+    }
+
+    async fn saved(&self, owner: &str, key: &str) -> CoreResult<Option<SavedResponse>> {
+        let saved = sqlx::query_as!(
+            SavedResponse,
+            r#"
+            SELECT request_path, request_fingerprint, response_status,
+                response_headers AS "response_headers: Json<Vec<SavedHeader>>",
+                response_body
+            FROM idempotency_keys
+            WHERE owner = $1 AND key = $2
+            "#,
+            owner,
+            key
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(saved)
+    }
+
+    /// Gives up the claim on a key whose request failed, so that it can be
+    /// retried.
+    async fn release(&self, owner: &str, key: &str) -> CoreResult<()> {
+        sqlx::query!(
+            r#"
+            DELETE FROM idempotency_keys
+            WHERE owner = $1 AND key = $2 AND response_status IS NULL
+            "#,
+            owner,
+            key
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Deletes the keys older than the window they are valid for.
+    pub async fn prune(&self) -> CoreResult<u64> {
+        let pruned = sqlx::query!(
+            "DELETE FROM idempotency_keys WHERE created_at < now() - make_interval(secs => $1)",
+            self.ttl.as_secs_f64()
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+        Ok(pruned)
+    }
+}
+
+/// The response saved for a key, `None` while its request is being processed.
+fn replay(saved: SavedResponse, path: &str, fingerprint: &str) -> CoreResult<Option<Response>> {
+    let same_body = saved
+        .request_fingerprint
+        .as_deref()
+        .is_none_or(|saved| saved == fingerprint);
+    if saved.request_path != path || !same_body {
+        return Ok(Some(
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "idempotency key already used for another request".to_string(),
+            )
+                .into_response(),
+        ));
+    }
+    let (Some(status), Some(Json(headers)), Some(body)) = (
+        saved.response_status,
+        saved.response_headers,
+        saved.response_body,
+    ) else {
+        return Ok(None);
+    };
+
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = StatusCode::from_u16(status as u16)?;
+    for header in headers {
+        let name = HeaderName::try_from(header.name)?;
+        let value = HeaderValue::from_bytes(&header.value)?;
+        response.headers_mut().append(name, value);
+    }
+    response
+        .headers_mut()
+        .insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+    Ok(Some(response))
+}
@@ -0,0 +1,45 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use zero2prod_core::{
+    domain::Job, error::CoreResult, handlers::expire_pending_subscriptions,
+    service::jobs::JobHandler,
+};
+
+use crate::{metrics::Metrics, repository::SubscriptionRepositoryImpl};
+
+/// Deletes the subscriptions left pending for longer than their time to live.
+pub struct ExpirePendingSubscriptionsJob {
+    repository: Arc<SubscriptionRepositoryImpl>,
+    ttl: Duration,
+    metrics: Arc<Metrics>,
+}
+
+impl ExpirePendingSubscriptionsJob {
+    pub const KIND: &'static str = "expire-pending-subscriptions";
+
+    pub(crate) fn new(
+        repository: Arc<SubscriptionRepositoryImpl>,
+        ttl: Duration,
+        metrics: Arc<Metrics>,
+    ) -> Self {
+        Self {
+            repository,
+            ttl,
+            metrics,
+        }
+    }
+}
+
+#[async_trait]
+impl JobHandler for ExpirePendingSubscriptionsJob {
+    fn kind(&self) -> &'static str {
+        Self::KIND
+    }
+
+    async fn run(&self, _job: &Job) -> CoreResult<()> {
+        let expired = expire_pending_subscriptions(self.repository.as_ref(), self.ttl).await?;
+        self.metrics.subscriptions_expired(expired);
+        Ok(())
+    }
+}
@@ -0,0 +1,224 @@
+mod expire_pending;
+mod prune_form_tokens;
+mod prune_idempotency_keys;
+mod prune_jobs;
+mod queue;
+mod schedule;
+mod send_import_confirmations;
+mod send_issues;
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use tokio::task::{JoinError, JoinHandle};
+use tracing::{error, info_span, warn, Instrument};
+use zero2prod_core::{
+    domain::{Backoff, Job},
+    error::CoreResult,
+    service::jobs::JobHandler,
+};
+
+use crate::{configuration::JobsConfig, metrics::Metrics, shutdown::Shutdown};
+
+pub(crate) use expire_pending::ExpirePendingSubscriptionsJob;
+pub(crate) use prune_form_tokens::PruneFormTokensJob;
+pub(crate) use prune_idempotency_keys::PruneIdempotencyKeysJob;
+pub use prune_jobs::PruneJobsJob;
+pub use queue::JobQueueImpl;
+pub use schedule::JobSchedule;
+pub(crate) use send_import_confirmations::SendImportConfirmationsJob;
+pub(crate) use send_issues::SendIssuesJob;
+
+/// Shortest time between two renewals of the lease of a running job.
+const MIN_LEASE_RENEWAL: Duration = Duration::from_millis(100);
+
+/// Runs the queued jobs with the handler registered for their kind, and
+/// enqueues the recurring jobs when they are due.
+///
+/// Any number of instances may run against the same database, a job is only
+/// handed to one worker at a time.
+pub struct JobRunner {
+    queue: Arc<JobQueueImpl>,
+    handlers: HashMap<&'static str, Arc<dyn JobHandler>>,
+    schedules: Vec<JobSchedule>,
+    schedules_registered: AtomicBool,
+    schedule_overrides: HashMap<String, String>,
+    workers: usize,
+    poll_interval: Duration,
+    lease: Duration,
+    backoff: Backoff,
+    metrics: Option<Arc<Metrics>>,
+}
+
+impl JobRunner {
+    pub fn new(queue: Arc<JobQueueImpl>, config: &JobsConfig) -> Self {
+        Self {
+            queue,
+            handlers: HashMap::new(),
+            schedules: Vec::new(),
+            schedules_registered: AtomicBool::new(false),
+            schedule_overrides: config.schedules.clone(),
+            workers: config.workers,
+            poll_interval: Duration::from_millis(config.poll_interval),
+            lease: Duration::from_secs(config.lease),
+            backoff: Backoff {
+                base: Duration::from_secs(config.backoff_base),
+                max: Duration::from_secs(config.backoff_max),
+            },
+            metrics: None,
+        }
+    }
+
+    pub(crate) fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Runs the jobs of the kind of `handler`.
+    pub fn register(mut self, handler: impl JobHandler + 'static) -> Self {
+        self.handlers.insert(handler.kind(), Arc::new(handler));
+        self
+    }
+
+    /// Runs the jobs of the kind of `handler`, and enqueues one on
+    /// `default_schedule` unless the configuration overrides it.
+    pub fn register_recurring(
+        self,
+        handler: impl JobHandler + 'static,
+        default_schedule: &str,
+    ) -> Result<Self, String> {
+        let kind = handler.kind();
+        let mut runner = self.register(handler);
+        let expression = runner
+            .schedule_overrides
+            .get(kind)
+            .map(String::as_str)
+            .unwrap_or(default_schedule);
+        if !expression.trim().is_empty() {
+            runner.schedules.push(JobSchedule::parse(kind, expression)?);
+        }
+        Ok(runner)
+    }
+
+    /// Starts the workers and the scheduler, they stop once the shutdown is
+    /// triggered and their current job is done.
+    pub fn start(self, shutdown: &Shutdown) {
+        let runner = Arc::new(self);
+        for _ in 0..runner.workers {
+            shutdown.spawn(runner.clone().work(shutdown.clone()));
+        }
+
+        let scheduler = runner.clone();
+        shutdown.spawn_periodic(runner.poll_interval, move || {
+            let scheduler = scheduler.clone();
+            async move {
+                if let Err(err) = scheduler.enqueue_due_schedules().await {
+                    warn!("Failed to enqueue the scheduled jobs: {}", err);
+                }
+            }
+        });
+    }
+
+    async fn work(self: Arc<Self>, shutdown: Shutdown) {
+        while !shutdown.is_triggered() {
+            match self.run_next().await {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(err) => warn!("Failed to fetch the next job: {}", err),
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(self.poll_interval) => {},
+                _ = shutdown.triggered() => break,
+            }
+        }
+    }
+
+    /// Runs the next due job, returns whether there was one.
+    pub async fn run_next(&self) -> CoreResult<bool> {
+        let Some(job) = self.queue.dequeue(self.lease).await? else {
+            return Ok(false);
+        };
+        let span = info_span!("Job", kind = %job.kind, job_id = %job.id, attempt = job.attempt);
+        self.run(job).instrument(span).await?;
+        Ok(true)
+    }
+
+    /// Enqueues the jobs of the due schedules, returns how many were enqueued.
+    pub async fn enqueue_due_schedules(&self) -> CoreResult<usize> {
+        // The database may not be migrated yet when the application starts
+        if !self.schedules_registered.load(Ordering::Relaxed) {
+            schedule::register(&self.queue, &self.schedules).await?;
+            self.schedules_registered.store(true, Ordering::Relaxed);
+        }
+        schedule::enqueue_due(&self.queue, &self.schedules).await
+    }
+
+    async fn run(&self, job: Job) -> CoreResult<()> {
+        let outcome = match self.handlers.get(job.kind.as_str()) {
+            // The previous workers of the job died while running it
+            _ if job.attempt > job.max_attempts => Err("Lost by its workers".to_owned()),
+            None => Err(format!("No handler for {} jobs", job.kind)),
+            Some(handler) => {
+                // Spawned so that a panicking handler fails the job rather
+                // than the worker
+                let handler = handler.clone();
+                let spawned = job.clone();
+                let task = tokio::spawn(async move { handler.run(&spawned).await });
+                match self.hold_lease(&job, task).await {
+                    Ok(result) => result.map_err(|err| err.to_string()),
+                    Err(err) => Err(format!("Panicked: {}", err)),
+                }
+            }
+        };
+
+        let (held, status) = match outcome {
+            Ok(()) => (self.queue.succeed(&job).await?, "succeeded"),
+            Err(err) if job.is_last_attempt() => {
+                error!("Job failed for the last time: {}", err);
+                (self.queue.bury(&job, &err).await?, "dead")
+            }
+            Err(err) => {
+                let delay = self.backoff.delay(job.attempt);
+                warn!("Job failed, retrying in {:?}: {}", delay, err);
+                (self.queue.retry(&job, delay, &err).await?, "retried")
+            }
+        };
+        let status = match held {
+            true => status,
+            false => {
+                warn!("Job was taken over by another worker, its outcome is dropped");
+                "lost"
+            }
+        };
+        if let Some(metrics) = &self.metrics {
+            metrics.job(&job.kind, status);
+        }
+        Ok(())
+    }
+
+    /// Waits for the task running `job`, renewing its lease meanwhile so that
+    /// long jobs are not handed to another worker.
+    async fn hold_lease<T>(&self, job: &Job, mut task: JoinHandle<T>) -> Result<T, JoinError> {
+        let mut renewals = tokio::time::interval((self.lease / 3).max(MIN_LEASE_RENEWAL));
+        // The first tick is immediate, the lease was just taken
+        renewals.tick().await;
+        loop {
+            tokio::select! {
+                result = &mut task => return result,
+                _ = renewals.tick() => {
+                    // A lease taken over by another worker is noticed once
+                    // the job is done
+                    if let Err(err) = self.queue.renew(job, self.lease).await {
+                        warn!("Failed to renew the lease of the job: {}", err);
+                    }
+                }
+            }
+        }
+    }
+}
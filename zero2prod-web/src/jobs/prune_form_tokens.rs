@@ -0,0 +1,36 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tracing::info;
+use zero2prod_core::{domain::Job, error::CoreResult, service::jobs::JobHandler};
+
+use crate::bot_protection::BotProtection;
+
+/// Deletes the nonces of the used form tokens that have expired, the tokens
+/// are rejected for their age from then on.
+pub(crate) struct PruneFormTokensJob {
+    bot_protection: Arc<BotProtection>,
+}
+
+impl PruneFormTokensJob {
+    pub const KIND: &'static str = "prune-form-tokens";
+
+    pub fn new(bot_protection: Arc<BotProtection>) -> Self {
+        Self { bot_protection }
+    }
+}
+
+#[async_trait]
+impl JobHandler for PruneFormTokensJob {
+    fn kind(&self) -> &'static str {
+        Self::KIND
+    }
+
+    async fn run(&self, _job: &Job) -> CoreResult<()> {
+        let pruned = self.bot_protection.prune_form_tokens().await?;
+        if pruned > 0 {
+            info!(pruned, "Pruned expired form tokens");
+        }
+        Ok(())
+    }
+}
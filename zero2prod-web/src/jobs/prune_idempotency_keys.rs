@@ -0,0 +1,36 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tracing::info;
+use zero2prod_core::{domain::Job, error::CoreResult, service::jobs::JobHandler};
+
+use crate::idempotency::IdempotencyStore;
+
+/// Deletes the idempotency keys that have expired along with their saved
+/// responses.
+pub(crate) struct PruneIdempotencyKeysJob {
+    store: Arc<IdempotencyStore>,
+}
+
+impl PruneIdempotencyKeysJob {
+    pub const KIND: &'static str = "prune-idempotency-keys";
+
+    pub fn new(store: Arc<IdempotencyStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl JobHandler for PruneIdempotencyKeysJob {
+    fn kind(&self) -> &'static str {
+        Self::KIND
+    }
+
+    async fn run(&self, _job: &Job) -> CoreResult<()> {
+        let pruned = self.store.prune().await?;
+        if pruned > 0 {
+            info!(pruned, "Pruned expired idempotency keys");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use tracing::info;
+use zero2prod_core::{domain::Job, error::CoreResult, service::jobs::JobHandler};
+
+use super::JobQueueImpl;
+
+/// Deletes the jobs that succeeded longer ago than the retention, dead jobs
+/// are kept until they are dealt with.
+pub struct PruneJobsJob {
+    queue: Arc<JobQueueImpl>,
+    retention: Duration,
+}
+
+impl PruneJobsJob {
+    pub const KIND: &'static str = "prune-jobs";
+
+    pub fn new(queue: Arc<JobQueueImpl>, retention: Duration) -> Self {
+        Self { queue, retention }
+    }
+}
+
+#[async_trait]
+impl JobHandler for PruneJobsJob {
+    fn kind(&self) -> &'static str {
+        Self::KIND
+    }
+
+    async fn run(&self, _job: &Job) -> CoreResult<()> {
+        let pruned = self.queue.prune(self.retention).await?;
+        if pruned > 0 {
+            info!(pruned, "Pruned succeeded jobs");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,188 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use sqlx::{
+    types::chrono::{DateTime, Utc},
+    PgExecutor, PgPool,
+};
+use uuid::Uuid;
+use zero2prod_core::{
+    domain::{Job, NewJob},
+    error::CoreResult,
+    service::jobs::JobQueue,
+};
+
+/// Job queue stored in the `jobs` table, safe to share between instances.
+pub struct JobQueueImpl {
+    pool: PgPool,
+    max_attempts: u32,
+}
+
+impl JobQueueImpl {
+    pub fn new(pool: PgPool, max_attempts: u32) -> Self {
+        Self { pool, max_attempts }
+    }
+
+    pub(crate) fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    /// Queues a job through `executor`, so that it can be part of a
+    /// transaction.
+    pub(crate) async fn insert<'e>(
+        &self,
+        executor: impl PgExecutor<'e>,
+        job: NewJob,
+    ) -> CoreResult<Uuid> {
+        let id = Uuid::new_v4();
+        let run_at = job
+            .run_at
+            .map(DateTime::<Utc>::from)
+            .unwrap_or_else(Utc::now);
+        sqlx::query!(
+            r#"
+            INSERT INTO jobs (id, kind, payload, status, max_attempts, run_at, created_at)
+            VALUES ($1, $2, $3, 'queued', $4, $5, now())
+            "#,
+            id,
+            job.kind,
+            job.payload,
+            self.max_attempts as i32,
+            run_at
+        )
+        .execute(executor)
+        .await?;
+        Ok(id)
+    }
+
+    /// Takes the next due job, or a running one whose lease has expired, and
+    /// leases it for `lease`. Jobs taken by other workers are skipped rather
+    /// than waited for.
+    pub async fn dequeue(&self, lease: Duration) -> CoreResult<Option<Job>> {
+        let row = sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET status = 'running',
+                attempts = attempts + 1,
+                locked_until = now() + make_interval(secs => $1)
+            WHERE id = (
+                SELECT id FROM jobs
+                WHERE (status = 'queued' AND run_at <= now())
+                   OR (status = 'running' AND locked_until < now())
+                ORDER BY run_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, kind, payload, attempts, max_attempts
+            "#,
+            lease.as_secs_f64()
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| Job {
+            id: row.id,
+            kind: row.kind,
+            payload: row.payload,
+            attempt: row.attempts as u32,
+            max_attempts: row.max_attempts as u32,
+        }))
+    }
+
+    /// Extends the lease of a running job by `lease`, returns whether the
+    /// worker still holds the job.
+    pub async fn renew(&self, job: &Job, lease: Duration) -> CoreResult<bool> {
+        let renewed = sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET locked_until = now() + make_interval(secs => $3)
+            WHERE id = $1 AND status = 'running' AND attempts = $2
+            "#,
+            job.id,
+            job.attempt as i32,
+            lease.as_secs_f64()
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(renewed.rows_affected() > 0)
+    }
+
+    // The completions below only apply to the run of `job`, they return
+    // false when its lease expired and another worker took the job over.
+
+    pub async fn succeed(&self, job: &Job) -> CoreResult<bool> {
+        let updated = sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET status = 'succeeded', locked_until = NULL, last_error = NULL, finished_at = now()
+            WHERE id = $1 AND status = 'running' AND attempts = $2
+            "#,
+            job.id,
+            job.attempt as i32
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(updated.rows_affected() > 0)
+    }
+
+    /// Queues a failed job again, to run after `delay`.
+    pub async fn retry(&self, job: &Job, delay: Duration, error: &str) -> CoreResult<bool> {
+        let updated = sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET status = 'queued',
+                run_at = now() + make_interval(secs => $3),
+                locked_until = NULL,
+                last_error = $4
+            WHERE id = $1 AND status = 'running' AND attempts = $2
+            "#,
+            job.id,
+            job.attempt as i32,
+            delay.as_secs_f64(),
+            error
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(updated.rows_affected() > 0)
+    }
+
+    /// Leaves a job that ran out of attempts dead, dead jobs are kept for
+    /// inspection and never run again.
+    pub async fn bury(&self, job: &Job, error: &str) -> CoreResult<bool> {
+        let updated = sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET status = 'dead', locked_until = NULL, last_error = $3, finished_at = now()
+            WHERE id = $1 AND status = 'running' AND attempts = $2
+            "#,
+            job.id,
+            job.attempt as i32,
+            error
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(updated.rows_affected() > 0)
+    }
+
+    /// Deletes the jobs that succeeded more than `retention` ago, returns how
+    /// many were deleted.
+    pub async fn prune(&self, retention: Duration) -> CoreResult<u64> {
+        let deleted = sqlx::query!(
+            r#"
+            DELETE FROM jobs
+            WHERE status = 'succeeded' AND finished_at < now() - make_interval(secs => $1)
+            "#,
+            retention.as_secs_f64()
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(deleted.rows_affected())
+    }
+}
+
+#[async_trait]
+impl JobQueue for JobQueueImpl {
+    async fn enqueue(&self, job: NewJob) -> CoreResult<Uuid> {
+        self.insert(&self.pool, job).await
+    }
+}
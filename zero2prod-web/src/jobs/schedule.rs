@@ -0,0 +1,182 @@
+use std::{str::FromStr, time::Duration};
+
+use cron::Schedule;
+use serde_json::json;
+use sqlx::types::chrono::{DateTime, Utc};
+use tracing::warn;
+use zero2prod_core::{domain::NewJob, error::CoreResult};
+
+use super::JobQueueImpl;
+
+/// A job enqueued on a cron schedule or at a fixed interval. Every instance
+/// keeps the schedules, the first one to notice that a schedule is due
+/// enqueues its job.
+pub struct JobSchedule {
+    kind: &'static str,
+    expression: String,
+    timing: Timing,
+}
+
+enum Timing {
+    Cron(Box<Schedule>),
+    Every(Duration),
+}
+
+impl JobSchedule {
+    /// Accepts the usual five fields of crontab, the extended expressions
+    /// starting with the seconds, and intervals such as `@every 3600s`.
+    pub fn parse(kind: &'static str, expression: &str) -> Result<Self, String> {
+        let expression = expression.trim().to_owned();
+        let invalid = |err: &dyn std::fmt::Display| {
+            format!("Invalid schedule '{}' for {}: {}", expression, kind, err)
+        };
+        let timing = match expression.strip_prefix("@every") {
+            Some(interval) => {
+                let seconds = interval
+                    .trim()
+                    .strip_suffix('s')
+                    .ok_or_else(|| invalid(&"the interval must be in seconds"))?
+                    .parse::<u64>()
+                    .map_err(|err| invalid(&err))?;
+                if seconds == 0 {
+                    return Err(invalid(&"the interval must not be empty"));
+                }
+                Timing::Every(Duration::from_secs(seconds))
+            }
+            None => {
+                let extended = match expression.split_whitespace().count() {
+                    5 => format!("0 {}", expression),
+                    _ => expression.clone(),
+                };
+                Timing::Cron(Box::new(
+                    Schedule::from_str(&extended).map_err(|err| invalid(&err))?,
+                ))
+            }
+        };
+        Ok(Self {
+            kind,
+            expression,
+            timing,
+        })
+    }
+
+    pub fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    fn next_run_at(&self) -> Option<DateTime<Utc>> {
+        match &self.timing {
+            Timing::Cron(schedule) => schedule.upcoming(Utc).next(),
+            Timing::Every(interval) => Some(Utc::now() + *interval),
+        }
+    }
+}
+
+/// Records the schedules, a schedule whose expression changed is due again at
+/// its next occurrence.
+pub(crate) async fn register(queue: &JobQueueImpl, schedules: &[JobSchedule]) -> CoreResult<()> {
+    for schedule in schedules {
+        let Some(next_run_at) = schedule.next_run_at() else {
+            warn!("The schedule of {} has no upcoming run", schedule.kind);
+            continue;
+        };
+        sqlx::query!(
+            r#"
+            INSERT INTO job_schedules (kind, cron, next_run_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (kind) DO UPDATE
+            SET cron = EXCLUDED.cron, next_run_at = EXCLUDED.next_run_at
+            WHERE job_schedules.cron <> EXCLUDED.cron
+            "#,
+            schedule.kind,
+            schedule.expression,
+            next_run_at
+        )
+        .execute(queue.pool())
+        .await?;
+    }
+    Ok(())
+}
+
+/// Enqueues the jobs of the due schedules, returns how many were enqueued.
+pub(crate) async fn enqueue_due(
+    queue: &JobQueueImpl,
+    schedules: &[JobSchedule],
+) -> CoreResult<usize> {
+    let kinds = schedules
+        .iter()
+        .map(|s| s.kind.to_owned())
+        .collect::<Vec<_>>();
+
+    let mut transaction = queue.pool().begin().await?;
+    let due = sqlx::query!(
+        r#"
+        SELECT kind FROM job_schedules
+        WHERE kind = ANY($1) AND next_run_at <= now()
+        FOR UPDATE SKIP LOCKED
+        "#,
+        &kinds
+    )
+    .fetch_all(&mut *transaction)
+    .await?;
+
+    for row in &due {
+        let Some(schedule) = schedules.iter().find(|s| s.kind == row.kind) else {
+            continue;
+        };
+        queue
+            .insert(&mut *transaction, NewJob::new(schedule.kind, json!({})))
+            .await?;
+        match schedule.next_run_at() {
+            Some(next_run_at) => {
+                sqlx::query!(
+                    "UPDATE job_schedules SET next_run_at = $2 WHERE kind = $1",
+                    schedule.kind,
+                    next_run_at
+                )
+                .execute(&mut *transaction)
+                .await?
+            }
+            None => {
+                sqlx::query!("DELETE FROM job_schedules WHERE kind = $1", schedule.kind)
+                    .execute(&mut *transaction)
+                    .await?
+            }
+        };
+    }
+    transaction.commit().await?;
+
+    Ok(due.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crontab_expressions_are_accepted() {
+        let schedule = JobSchedule::parse("test", "30 3 * * *").unwrap();
+        assert_eq!(schedule.expression, "30 3 * * *");
+        assert!(schedule.next_run_at().is_some());
+    }
+
+    #[test]
+    fn expressions_with_seconds_are_accepted() {
+        assert!(JobSchedule::parse("test", "*/5 * * * * *").is_ok());
+    }
+
+    #[test]
+    fn intervals_are_accepted() {
+        let schedule = JobSchedule::parse("test", "@every 90s").unwrap();
+        let next_run_at = schedule.next_run_at().unwrap();
+        assert!(next_run_at > Utc::now() + Duration::from_secs(80));
+        assert!(next_run_at <= Utc::now() + Duration::from_secs(90));
+    }
+
+    #[test]
+    fn invalid_expressions_are_rejected() {
+        assert!(JobSchedule::parse("test", "every tuesday").is_err());
+        assert!(JobSchedule::parse("test", "@every 5m").is_err());
+        assert!(JobSchedule::parse("test", "@every 0s").is_err());
+    }
+}
@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use zero2prod_core::{
+    domain::{ImportConfirmations, Job},
+    error::CoreResult,
+    handlers::send_import_confirmations,
+    service::jobs::JobHandler,
+};
+
+use crate::{
+    repository::{ListRepositoryImpl, SubscriptionRepositoryImpl},
+    service::{EmailServiceImpl, SubscriptionLinks},
+};
+
+/// Sends their confirmation email to a batch of imported subscribers. A retry
+/// sends it again to the ones still pending.
+pub struct SendImportConfirmationsJob {
+    subscription_repository: Arc<SubscriptionRepositoryImpl>,
+    list_repository: Arc<ListRepositoryImpl>,
+    email_client: Arc<EmailServiceImpl>,
+    links: Arc<SubscriptionLinks>,
+}
+
+impl SendImportConfirmationsJob {
+    pub(crate) fn new(
+        subscription_repository: Arc<SubscriptionRepositoryImpl>,
+        list_repository: Arc<ListRepositoryImpl>,
+        email_client: Arc<EmailServiceImpl>,
+        links: Arc<SubscriptionLinks>,
+    ) -> Self {
+        Self {
+            subscription_repository,
+            list_repository,
+            email_client,
+            links,
+        }
+    }
+}
+
+#[async_trait]
+impl JobHandler for SendImportConfirmationsJob {
+    fn kind(&self) -> &'static str {
+        ImportConfirmations::KIND
+    }
+
+    async fn run(&self, job: &Job) -> CoreResult<()> {
+        let confirmations: ImportConfirmations = serde_json::from_value(job.payload.clone())?;
+        send_import_confirmations(
+            self.subscription_repository.as_ref(),
+            self.list_repository.as_ref(),
+            self.email_client.as_ref(),
+            &confirmations,
+            |list, subscriber_id, stored| {
+                self.links.confirmation_email(list, subscriber_id, stored)
+            },
+        )
+        .await
+    }
+}
@@ -0,0 +1,62 @@
+use std::{sync::Arc, time::SystemTime};
+
+use async_trait::async_trait;
+use zero2prod_core::{
+    domain::Job,
+    error::CoreResult,
+    handlers::{send_due_issues, DeliveryPolicy},
+    service::jobs::JobHandler,
+};
+
+use crate::{
+    repository::{ListRepositoryImpl, NewsletterIssueRepositoryImpl},
+    service::{EmailServiceImpl, SubscriptionLinks},
+};
+
+/// Starts sending the due newsletter issues and delivers the ones being sent.
+pub struct SendIssuesJob {
+    repository: Arc<NewsletterIssueRepositoryImpl>,
+    list_repository: Arc<ListRepositoryImpl>,
+    email_client: Arc<EmailServiceImpl>,
+    links: Arc<SubscriptionLinks>,
+    policy: DeliveryPolicy,
+}
+
+impl SendIssuesJob {
+    pub const KIND: &'static str = "send-newsletter-issues";
+
+    pub(crate) fn new(
+        repository: Arc<NewsletterIssueRepositoryImpl>,
+        list_repository: Arc<ListRepositoryImpl>,
+        email_client: Arc<EmailServiceImpl>,
+        links: Arc<SubscriptionLinks>,
+        policy: DeliveryPolicy,
+    ) -> Self {
+        Self {
+            repository,
+            list_repository,
+            email_client,
+            links,
+            policy,
+        }
+    }
+}
+
+#[async_trait]
+impl JobHandler for SendIssuesJob {
+    fn kind(&self) -> &'static str {
+        Self::KIND
+    }
+
+    async fn run(&self, _job: &Job) -> CoreResult<()> {
+        send_due_issues(
+            self.repository.as_ref(),
+            self.list_repository.as_ref(),
+            self.email_client.as_ref(),
+            SystemTime::now(),
+            self.policy,
+            |list, issue, subscriber_id| self.links.issue_email(list, issue, subscriber_id),
+        )
+        .await
+    }
+}
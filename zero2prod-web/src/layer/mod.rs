@@ -1,3 +1,8 @@
+mod metrics;
+mod rate_limit;
 mod trace_id;
 
-pub use trace_id::TraceIdLayer;
+pub use metrics::MetricsLayer;
+pub(crate) use rate_limit::client_ip;
+pub use rate_limit::RateLimitLayer;
+pub use trace_id::{TraceId, TraceIdLayer, REQUEST_ID_HEADER};
@@ -0,0 +1,187 @@
+use std::{
+    convert::Infallible,
+    future::Future,
+    net::{IpAddr, SocketAddr},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    response::{IntoResponse, Response},
+};
+use http::{HeaderMap, Request};
+use ipnet::IpNet;
+use tower::Service;
+use tower_layer::Layer;
+use zero2prod_core::{error::CoreError, service::rate_limiter::RateLimiter};
+
+use crate::{error::core_error, metrics::Metrics, service::RateLimiterImpl};
+
+pub const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+/// Limits the requests of every client IP, the address of the client is read
+/// from `X-Forwarded-For` when the peer is a trusted proxy.
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    limiter: Arc<RateLimiterImpl>,
+    trusted_proxies: Arc<[IpNet]>,
+    metrics: Arc<Metrics>,
+}
+
+impl RateLimitLayer {
+    pub fn new(
+        limiter: Arc<RateLimiterImpl>,
+        trusted_proxies: &[IpNet],
+        metrics: Arc<Metrics>,
+    ) -> Self {
+        Self {
+            limiter,
+            trusted_proxies: trusted_proxies.into(),
+            metrics,
+        }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    layer: RateLimitLayer,
+}
+
+impl<S> Service<Request<Body>> for RateLimitService<S>
+where
+    S: Service<Request<Body>, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, Infallible>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        // The ready service is used for this request, the clone is kept for the
+        // next one
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let layer = self.layer.clone();
+
+        let client_ip = req
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(peer)| client_ip(peer.ip(), req.headers(), &layer.trusted_proxies));
+
+        Box::pin(async move {
+            if let Some(client_ip) = client_ip {
+                match layer.limiter.acquire(&client_ip.to_string()).await {
+                    Ok(()) => {}
+                    Err(err @ CoreError::RateLimited { .. }) => {
+                        layer.metrics.rate_limited(layer.limiter.scope());
+                        return Ok(core_error(err));
+                    }
+                    // Failing open, an unavailable storage must not take the
+                    // endpoint down with it
+                    Err(err) => tracing::error!("Failed to apply the rate limit: {}", err),
+                }
+            }
+            inner.call(req).await.map(IntoResponse::into_response)
+        })
+    }
+}
+
+/// Walks `X-Forwarded-For` from the closest hop while the hops are trusted
+/// proxies, the first untrusted address is the client.
+pub(crate) fn client_ip(peer: IpAddr, headers: &HeaderMap, trusted_proxies: &[IpNet]) -> IpAddr {
+    let is_trusted = |ip: &IpAddr| trusted_proxies.iter().any(|net| net.contains(ip));
+
+    let mut client = peer;
+    let hops = headers
+        .get_all(FORWARDED_FOR_HEADER)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .collect::<Vec<_>>();
+
+    for hop in hops.into_iter().rev() {
+        if !is_trusted(&client) {
+            break;
+        }
+        match hop.trim().parse() {
+            Ok(ip) => client = ip,
+            Err(_) => break,
+        }
+    }
+    client
+}
+
+#[cfg(test)]
+mod tests {
+    use http::HeaderValue;
+
+    use super::*;
+
+    fn trusted() -> Vec<IpNet> {
+        vec!["10.0.0.0/8".parse().unwrap()]
+    }
+
+    fn forwarded_for(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(FORWARDED_FOR_HEADER, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn the_peer_is_the_client_without_proxies() {
+        let peer = "203.0.113.7".parse().unwrap();
+        assert_eq!(client_ip(peer, &HeaderMap::new(), &trusted()), peer);
+    }
+
+    #[test]
+    fn forwarded_for_is_ignored_from_untrusted_peers() {
+        let peer = "203.0.113.7".parse().unwrap();
+        let headers = forwarded_for("198.51.100.1");
+        assert_eq!(client_ip(peer, &headers, &trusted()), peer);
+    }
+
+    #[test]
+    fn forwarded_for_is_honoured_from_trusted_proxies() {
+        let headers = forwarded_for("198.51.100.1, 10.0.0.2");
+        assert_eq!(
+            client_ip("10.0.0.1".parse().unwrap(), &headers, &trusted()),
+            "198.51.100.1".parse::<IpAddr>().unwrap()
+        );
+    }
+
+    #[test]
+    fn spoofed_hops_before_the_client_are_ignored() {
+        let headers = forwarded_for("1.2.3.4, 198.51.100.1");
+        assert_eq!(
+            client_ip("10.0.0.1".parse().unwrap(), &headers, &trusted()),
+            "198.51.100.1".parse::<IpAddr>().unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_hops_stop_the_walk() {
+        let headers = forwarded_for("198.51.100.1, garbage");
+        assert_eq!(
+            client_ip("10.0.0.1".parse().unwrap(), &headers, &trusted()),
+            "10.0.0.1".parse::<IpAddr>().unwrap()
+        );
+    }
+}
@@ -1,22 +1,95 @@
 use std::{
     fmt::Display,
+    future::Future,
+    pin::Pin,
     sync::Arc,
     task::{Context, Poll},
 };
 
 use axum::body::Body;
-use http::Request;
+use http::{HeaderMap, HeaderValue, Request, Response};
+use opentelemetry::propagation::{Extractor, TextMapPropagator};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use pin_project_lite::pin_project;
+use tokio::task::futures::TaskLocalFuture;
 use tower::Service;
 use tower_layer::Layer;
 use tracing::{instrument::Instrumented, trace_span, Instrument};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
 use uuid::Uuid;
 
-#[derive(Clone, Debug)]
-pub struct TraceId(pub Arc<Uuid>);
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+tokio::task_local! {
+    static CURRENT_TRACE_ID: TraceId;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceId(Arc<str>);
 
 impl TraceId {
     fn generate() -> Self {
-        Self(Arc::new(Uuid::new_v4()))
+        Self(Uuid::new_v4().to_string().into())
+    }
+
+    /// Picks the trace id of an inbound request, `X-Request-Id` takes precedence
+    /// over `traceparent`. Invalid values are ignored so that callers cannot
+    /// inject arbitrary data into our logs.
+    fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(Self::from_request_id)
+            .or_else(|| {
+                headers
+                    .get(TRACEPARENT_HEADER)
+                    .and_then(Self::from_traceparent)
+            })
+    }
+
+    fn from_request_id(value: &HeaderValue) -> Option<Self> {
+        let value = value.to_str().ok()?;
+        let valid = !value.is_empty()
+            && value.len() <= MAX_REQUEST_ID_LENGTH
+            && value
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'));
+        valid.then(|| Self(value.into()))
+    }
+
+    /// Extracts the trace id of a W3C `traceparent` header
+    /// (`{version}-{trace-id}-{parent-id}-{trace-flags}`).
+    fn from_traceparent(value: &HeaderValue) -> Option<Self> {
+        let value = value.to_str().ok()?;
+        let parts: Vec<&str> = value.split('-').collect();
+        let [version, trace_id, parent_id, flags] = parts.as_slice() else {
+            return None;
+        };
+
+        let is_hex = |s: &str, len: usize| {
+            s.len() == len && s.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f'))
+        };
+        let is_zero = |s: &str| s.chars().all(|c| c == '0');
+
+        let valid = is_hex(version, 2)
+            && *version != "ff"
+            && is_hex(trace_id, 32)
+            && !is_zero(trace_id)
+            && is_hex(parent_id, 16)
+            && !is_zero(parent_id)
+            && is_hex(flags, 2);
+        valid.then(|| Self((*trace_id).into()))
+    }
+
+    /// Returns the trace id of the request being handled by the current task.
+    pub fn current() -> Option<Self> {
+        CURRENT_TRACE_ID.try_with(|trace_id| trace_id.clone()).ok()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
     }
 }
 
@@ -31,23 +104,68 @@ pub struct TraceIdService<S> {
     inner: S,
 }
 
-impl<S> Service<Request<Body>> for TraceIdService<S>
+impl<S, B> Service<Request<Body>> for TraceIdService<S>
 where
-    S: Service<Request<Body>>,
+    S: Service<Request<Body>, Response = Response<B>>,
 {
     type Response = S::Response;
     type Error = S::Error;
-    type Future = Instrumented<S::Future>;
+    type Future = TraceIdFuture<S::Future>;
 
     fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         self.inner.poll_ready(cx)
     }
 
     fn call(&mut self, mut req: Request<Body>) -> Self::Future {
-        let trace_id = TraceId::generate();
-        let span = trace_span!("request", trace_id = trace_id.to_string());
-        req.extensions_mut().insert(trace_id);
-        self.inner.call(req).instrument(span)
+        let trace_id = TraceId::from_headers(req.headers()).unwrap_or_else(TraceId::generate);
+        let span = trace_span!("request", trace_id = trace_id.as_str());
+        span.set_parent(TraceContextPropagator::new().extract(&HeaderExtractor(req.headers())));
+        req.extensions_mut().insert(trace_id.clone());
+
+        TraceIdFuture {
+            inner: CURRENT_TRACE_ID.scope(trace_id.clone(), self.inner.call(req).instrument(span)),
+            trace_id,
+        }
+    }
+}
+
+/// Lets the OpenTelemetry propagator read the `traceparent` of the inbound
+/// request so that exported spans join the caller's trace.
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+pin_project! {
+    pub struct TraceIdFuture<F> {
+        #[pin]
+        inner: TaskLocalFuture<TraceId, Instrumented<F>>,
+        trace_id: TraceId,
+    }
+}
+
+impl<F, B, E> Future for TraceIdFuture<F>
+where
+    F: Future<Output = Result<Response<B>, E>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let mut result = std::task::ready!(this.inner.poll(cx));
+        if let Ok(response) = &mut result {
+            if let Ok(value) = HeaderValue::from_str(this.trace_id.as_str()) {
+                response.headers_mut().insert(REQUEST_ID_HEADER, value);
+            }
+        }
+        Poll::Ready(result)
     }
 }
 
@@ -60,3 +178,74 @@ impl<S> Layer<S> for TraceIdLayer {
         TraceIdService { inner }
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn headers(name: &'static str, value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn request_id_is_accepted() {
+        let trace_id = TraceId::from_headers(&headers(REQUEST_ID_HEADER, "abc-123_x.y:z"));
+        assert_eq!(trace_id.unwrap().as_str(), "abc-123_x.y:z");
+    }
+
+    #[test]
+    fn request_id_with_forbidden_characters_is_rejected() {
+        let trace_id = TraceId::from_headers(&headers(REQUEST_ID_HEADER, "abc def"));
+        assert!(trace_id.is_none());
+    }
+
+    #[test]
+    fn request_id_too_long_is_rejected() {
+        let value = HeaderValue::from_str(&"a".repeat(MAX_REQUEST_ID_LENGTH + 1)).unwrap();
+        assert!(TraceId::from_request_id(&value).is_none());
+    }
+
+    #[test]
+    fn traceparent_trace_id_is_extracted() {
+        let trace_id = TraceId::from_headers(&headers(
+            TRACEPARENT_HEADER,
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        ));
+        assert_eq!(
+            trace_id.unwrap().as_str(),
+            "4bf92f3577b34da6a3ce929d0e0e4736"
+        );
+    }
+
+    #[test]
+    fn invalid_traceparents_are_rejected() {
+        let cases = [
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e47-00f067aa0ba902b7-01",
+        ];
+        for case in cases {
+            let value = HeaderValue::from_static(case);
+            assert!(TraceId::from_traceparent(&value).is_none(), "{}", case);
+        }
+    }
+
+    #[test]
+    fn request_id_takes_precedence_over_traceparent() {
+        let mut headers = headers(REQUEST_ID_HEADER, "my-request");
+        headers.insert(
+            TRACEPARENT_HEADER,
+            HeaderValue::from_static("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"),
+        );
+        assert_eq!(
+            TraceId::from_headers(&headers).unwrap().as_str(),
+            "my-request"
+        );
+    }
+}
@@ -1,13 +1,23 @@
+mod auth;
+mod bot_protection;
+mod clock;
+mod consent;
 mod error;
 mod handlers;
+mod idempotency;
 mod layer;
+mod metrics;
 mod repository;
 mod service;
 mod template;
 
+pub mod cli;
 pub mod client;
 pub mod configuration;
 pub mod domain;
+pub mod jobs;
+pub mod migration;
 pub mod server;
+pub mod shutdown;
 pub mod telemetry;
 pub mod testing;
@@ -0,0 +1,188 @@
+use std::time::Duration;
+
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts, Registry,
+    TextEncoder,
+};
+use sqlx::PgPool;
+
+/// Application metrics, exposed in the Prometheus text format.
+///
+/// Every instance owns its registry so that several applications can live in the
+/// same process (as they do in the integration tests).
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    db_pool_size: IntGauge,
+    db_pool_idle: IntGauge,
+    db_pool_in_use: IntGauge,
+    emails: IntCounterVec,
+    subscriptions: IntCounterVec,
+    subscriptions_expired: IntCounter,
+    rate_limited: IntCounterVec,
+    bot_rejections: IntCounterVec,
+    jobs: IntCounterVec,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new_custom(Some("zero2prod".into()), None)
+            .expect("Failed to create metrics registry");
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "Number of HTTP requests handled"),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "Latency of HTTP requests in seconds",
+            ),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let db_pool_size = IntGauge::new(
+            "db_pool_size",
+            "Number of connections currently held by the pool",
+        )
+        .unwrap();
+        let db_pool_idle =
+            IntGauge::new("db_pool_idle", "Number of idle connections in the pool").unwrap();
+        let db_pool_in_use = IntGauge::new(
+            "db_pool_in_use",
+            "Number of connections checked out of the pool, requests wait for one once it reaches the maximum size",
+        )
+        .unwrap();
+        let emails = IntCounterVec::new(
+            Opts::new(
+                "emails_total",
+                "Number of emails handed to the email provider",
+            ),
+            &["provider", "kind", "outcome"],
+        )
+        .unwrap();
+        let subscriptions = IntCounterVec::new(
+            Opts::new("subscriptions_total", "Number of subscription attempts"),
+            &["outcome"],
+        )
+        .unwrap();
+        let subscriptions_expired = IntCounter::new(
+            "subscriptions_expired_total",
+            "Number of pending subscriptions deleted once expired",
+        )
+        .unwrap();
+        let rate_limited = IntCounterVec::new(
+            Opts::new(
+                "rate_limited_total",
+                "Number of attempts rejected by a rate limit",
+            ),
+            &["scope"],
+        )
+        .unwrap();
+        let bot_rejections = IntCounterVec::new(
+            Opts::new(
+                "bot_rejections_total",
+                "Number of forms rejected as submitted by a bot",
+            ),
+            &["reason"],
+        )
+        .unwrap();
+        let jobs = IntCounterVec::new(
+            Opts::new("jobs_total", "Number of background job runs"),
+            &["kind", "outcome"],
+        )
+        .unwrap();
+
+        registry.register(Box::new(http_requests.clone())).unwrap();
+        registry
+            .register(Box::new(http_request_duration.clone()))
+            .unwrap();
+        registry.register(Box::new(db_pool_size.clone())).unwrap();
+        registry.register(Box::new(db_pool_idle.clone())).unwrap();
+        registry.register(Box::new(db_pool_in_use.clone())).unwrap();
+        registry.register(Box::new(emails.clone())).unwrap();
+        registry.register(Box::new(subscriptions.clone())).unwrap();
+        registry
+            .register(Box::new(subscriptions_expired.clone()))
+            .unwrap();
+        registry.register(Box::new(rate_limited.clone())).unwrap();
+        registry.register(Box::new(bot_rejections.clone())).unwrap();
+        registry.register(Box::new(jobs.clone())).unwrap();
+
+        Self {
+            registry,
+            http_requests,
+            http_request_duration,
+            db_pool_size,
+            db_pool_idle,
+            db_pool_in_use,
+            emails,
+            subscriptions,
+            subscriptions_expired,
+            rate_limited,
+            bot_rejections,
+            jobs,
+        }
+    }
+
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        let status = status.to_string();
+        let labels = [method, route, status.as_str()];
+        self.http_requests.with_label_values(&labels).inc();
+        self.http_request_duration
+            .with_label_values(&labels)
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn email(&self, provider: &str, kind: &str, outcome: &str) {
+        self.emails
+            .with_label_values(&[provider, kind, outcome])
+            .inc();
+    }
+
+    pub fn subscription(&self, outcome: &str) {
+        self.subscriptions.with_label_values(&[outcome]).inc();
+    }
+
+    pub fn subscriptions_expired(&self, count: u64) {
+        self.subscriptions_expired.inc_by(count);
+    }
+
+    pub fn rate_limited(&self, scope: &str) {
+        self.rate_limited.with_label_values(&[scope]).inc();
+    }
+
+    pub fn bot_rejection(&self, reason: &str) {
+        self.bot_rejections.with_label_values(&[reason]).inc();
+    }
+
+    pub fn job(&self, kind: &str, outcome: &str) {
+        self.jobs.with_label_values(&[kind, outcome]).inc();
+    }
+
+    /// Samples the state of the connection pool without taking a connection,
+    /// so that scrapes still answer when the pool is exhausted.
+    pub fn observe_pool(&self, pool: &PgPool) {
+        let size = i64::from(pool.size());
+        let idle = pool.num_idle() as i64;
+        self.db_pool_size.set(size);
+        self.db_pool_idle.set(idle);
+        self.db_pool_in_use.set(size - idle);
+    }
+
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("Failed to encode metrics");
+        String::from_utf8(buffer).expect("Metrics are not valid UTF-8")
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,88 @@
+use sqlx::{
+    migrate::{Migrate, MigrateError, Migrator},
+    PgConnection, PgPool,
+};
+use tracing::info;
+
+pub static MIGRATOR: Migrator = sqlx::migrate!("../migrations");
+
+/// Key of the advisory lock serializing migrations across instances.
+const MIGRATION_LOCK_KEY: i64 = 0x7a32_705f_6d69_6772;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+}
+
+/// Applies the pending migrations.
+///
+/// The whole run happens under a Postgres advisory lock, so that instances
+/// booting concurrently wait for the first one to migrate the database and then
+/// find nothing left to apply.
+pub async fn run(pool: &PgPool) -> Result<(), MigrateError> {
+    let mut conn = pool.acquire().await?;
+
+    info!("Acquiring migration lock");
+    sqlx::query("SELECT pg_advisory_lock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *conn)
+        .await?;
+
+    let result = MIGRATOR.run_direct(&mut *conn).await;
+
+    sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *conn)
+        .await?;
+
+    if result.is_ok() {
+        info!("Database migrations are up to date");
+    }
+    result
+}
+
+/// Lists the embedded migrations along with whether they have been applied.
+pub async fn status(pool: &PgPool) -> Result<Vec<MigrationStatus>, MigrateError> {
+    let mut conn = pool.acquire().await?;
+    let applied = applied_versions(&mut conn).await?;
+
+    Ok(MIGRATOR
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .map(|migration| MigrationStatus {
+            version: migration.version,
+            description: migration.description.to_string(),
+            applied: applied.contains(&migration.version),
+        })
+        .collect())
+}
+
+/// Returns the versions of the embedded migrations that have not been applied
+/// to the database yet.
+pub async fn pending_migrations(pool: &PgPool) -> Result<Vec<i64>, MigrateError> {
+    Ok(status(pool)
+        .await?
+        .into_iter()
+        .filter(|migration| !migration.applied)
+        .map(|migration| migration.version)
+        .collect())
+}
+
+async fn applied_versions(conn: &mut PgConnection) -> Result<Vec<i64>, MigrateError> {
+    let initialized: bool =
+        sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+            .fetch_one(&mut *conn)
+            .await?;
+    if !initialized {
+        return Ok(Vec::new());
+    }
+
+    Ok(conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect())
+}
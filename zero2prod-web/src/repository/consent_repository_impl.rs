@@ -0,0 +1,82 @@
+use async_trait::async_trait;
+use sqlx::{PgConnection, PgPool, QueryBuilder};
+use uuid::Uuid;
+
+use zero2prod_core::{domain::ConsentEvent, error::CoreResult, repository::ConsentRepository};
+
+pub struct ConsentRepositoryImpl {
+    db_pool: PgPool,
+}
+
+/// Events inserted per statement, well below the limit on bound parameters.
+const INSERT_CHUNK: usize = 1000;
+
+impl ConsentRepositoryImpl {
+    pub fn new(db_pool: PgPool) -> Self {
+        Self { db_pool }
+    }
+}
+
+fn topics(event: &ConsentEvent) -> Vec<String> {
+    event.topics.iter().map(|topic| topic.to_string()).collect()
+}
+
+/// Inserts `events` through `connection`, so that they can be part of a
+/// larger transaction.
+pub(crate) async fn insert_all(
+    connection: &mut PgConnection,
+    events: &[ConsentEvent],
+) -> CoreResult<()> {
+    for chunk in events.chunks(INSERT_CHUNK) {
+        let mut query = QueryBuilder::new(
+            "INSERT INTO consent_events \
+            (id, subscriber_id, action, source, ip, user_agent, policy_version, topics, occurred_at) ",
+        );
+        query.push_values(chunk, |mut row, event| {
+            row.push_bind(Uuid::new_v4())
+                .push_bind(event.subscriber_id)
+                .push_bind(event.action.as_str())
+                .push_bind(event.context.source.as_str())
+                .push_bind(event.context.ip.clone())
+                .push_bind(event.context.user_agent.clone())
+                .push_bind(event.context.policy_version.clone())
+                .push_bind(topics(event))
+                .push("now()");
+        });
+        query.build().execute(&mut *connection).await?;
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl ConsentRepository for ConsentRepositoryImpl {
+    async fn record(&self, event: &ConsentEvent) -> CoreResult<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO consent_events
+                (id, subscriber_id, action, source, ip, user_agent, policy_version, topics, occurred_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, now())
+            "#,
+            Uuid::new_v4(),
+            event.subscriber_id,
+            event.action.as_str(),
+            event.context.source.as_str(),
+            event.context.ip,
+            event.context.user_agent,
+            event.context.policy_version,
+            &topics(event)
+        )
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn record_all(&self, events: &[ConsentEvent]) -> CoreResult<()> {
+        let mut transaction = self.db_pool.begin().await?;
+        insert_all(&mut transaction, events).await?;
+        transaction.commit().await?;
+
+        Ok(())
+    }
+}
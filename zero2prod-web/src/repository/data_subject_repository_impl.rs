@@ -0,0 +1,228 @@
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use email_address::EmailAddress;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use zero2prod_core::{
+    domain::{
+        ConsentContext, ConsentEvent, DeliveryData, EmailHash, ListSlug, RecordedConsent,
+        SubjectData, SubscriptionData, TopicSlug,
+    },
+    error::CoreResult,
+    repository::DataSubjectRepository,
+};
+
+use crate::service::stored_key;
+
+pub struct DataSubjectRepositoryImpl {
+    db_pool: PgPool,
+}
+
+impl DataSubjectRepositoryImpl {
+    pub fn new(db_pool: PgPool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl DataSubjectRepository for DataSubjectRepositoryImpl {
+    async fn export(&self, email: &EmailAddress) -> CoreResult<SubjectData> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT subscriptions.id, lists.slug, subscriptions.name, subscriptions.status,
+                subscriptions.subscribed_at
+            FROM subscriptions JOIN lists ON lists.id = subscriptions.list_id
+            WHERE lower(subscriptions.email) = lower($1)
+            ORDER BY subscriptions.subscribed_at
+            "#,
+            email.as_str()
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+        let ids = rows.iter().map(|row| row.id).collect::<Vec<_>>();
+
+        let topics = sqlx::query!(
+            r#"
+            SELECT topic_preferences.subscriber_id, topics.slug, topic_preferences.subscribed
+            FROM topic_preferences JOIN topics ON topics.id = topic_preferences.topic_id
+            WHERE topic_preferences.subscriber_id = ANY($1)
+            ORDER BY topics.slug
+            "#,
+            &ids
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+        let tokens = sqlx::query!(
+            r#"
+            SELECT subscriber_id, subscription_token FROM subscription_tokens
+            WHERE subscriber_id = ANY($1)
+            "#,
+            &ids
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+        let deliveries = sqlx::query!(
+            r#"
+            SELECT issue_deliveries.subscriber_id, issue_deliveries.issue_id,
+                newsletter_issues.title, issue_deliveries.status, issue_deliveries.attempts,
+                issue_deliveries.updated_at, issue_deliveries.opened_at
+            FROM issue_deliveries
+            JOIN newsletter_issues ON newsletter_issues.id = issue_deliveries.issue_id
+            WHERE issue_deliveries.subscriber_id = ANY($1)
+            ORDER BY issue_deliveries.updated_at
+            "#,
+            &ids
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+        let consents = sqlx::query!(
+            r#"
+            SELECT subscriber_id, action, source, ip, user_agent, policy_version, topics,
+                occurred_at
+            FROM consent_events
+            WHERE subscriber_id = ANY($1)
+            ORDER BY occurred_at
+            "#,
+            &ids
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let subscriptions = rows
+            .into_iter()
+            .map(|row| {
+                Ok(SubscriptionData {
+                    id: row.id,
+                    list: ListSlug::parse(row.slug)?,
+                    name: row.name.parse()?,
+                    status: row.status.parse()?,
+                    subscribed_at: row.subscribed_at.into(),
+                    topics: topics
+                        .iter()
+                        .filter(|topic| topic.subscriber_id == row.id)
+                        .map(|topic| Ok((TopicSlug::parse(topic.slug.clone())?, topic.subscribed)))
+                        .collect::<CoreResult<_>>()?,
+                    tokens: tokens
+                        .iter()
+                        .filter(|token| token.subscriber_id == row.id)
+                        .map(|token| token.subscription_token.clone())
+                        .collect(),
+                    deliveries: deliveries
+                        .iter()
+                        .filter(|delivery| delivery.subscriber_id == row.id)
+                        .map(|delivery| DeliveryData {
+                            issue_id: delivery.issue_id,
+                            title: delivery.title.clone(),
+                            status: delivery.status.clone(),
+                            attempts: delivery.attempts as u32,
+                            updated_at: delivery.updated_at.into(),
+                            opened_at: delivery.opened_at.map(SystemTime::from),
+                        })
+                        .collect(),
+                    consents: consents
+                        .iter()
+                        .filter(|consent| consent.subscriber_id == row.id)
+                        .map(|consent| {
+                            Ok(RecordedConsent {
+                                event: ConsentEvent {
+                                    subscriber_id: consent.subscriber_id,
+                                    action: consent.action.parse()?,
+                                    context: ConsentContext {
+                                        source: consent.source.parse()?,
+                                        ip: consent.ip.clone(),
+                                        user_agent: consent.user_agent.clone(),
+                                        policy_version: consent.policy_version.clone(),
+                                    },
+                                    topics: consent
+                                        .topics
+                                        .iter()
+                                        .map(|topic| TopicSlug::parse(topic.clone()))
+                                        .collect::<CoreResult<_>>()?,
+                                },
+                                occurred_at: consent.occurred_at.into(),
+                            })
+                        })
+                        .collect::<CoreResult<_>>()?,
+                })
+            })
+            .collect::<CoreResult<_>>()?;
+
+        Ok(SubjectData {
+            email: email.clone(),
+            subscriptions,
+        })
+    }
+
+    async fn erase(&self, email: &EmailAddress, email_hash: &EmailHash) -> CoreResult<u64> {
+        let mut transaction = self.db_pool.begin().await?;
+        let ids = sqlx::query!(
+            "SELECT id FROM subscriptions WHERE lower(email) = lower($1) FOR UPDATE",
+            email.as_str()
+        )
+        .fetch_all(&mut *transaction)
+        .await?
+        .into_iter()
+        .map(|row| row.id)
+        .collect::<Vec<_>>();
+
+        // Preferences and deliveries go with the subscriptions
+        sqlx::query!(
+            "DELETE FROM subscription_tokens WHERE subscriber_id = ANY($1)",
+            &ids
+        )
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query!("DELETE FROM subscriptions WHERE id = ANY($1)", &ids)
+            .execute(&mut *transaction)
+            .await?;
+        // Consents outlive their subscription, including the ones deleted
+        // before
+        sqlx::query!(
+            "DELETE FROM consent_events WHERE subscriber_id = ANY($1) OR email_hash = $2",
+            &ids,
+            email_hash.as_ref()
+        )
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query!(
+            "DELETE FROM subscriber_import_errors WHERE lower(email) = lower($1)",
+            email.as_str()
+        )
+        .execute(&mut *transaction)
+        .await?;
+        // The email limiter keys its buckets by the lowercase address
+        sqlx::query!(
+            "DELETE FROM rate_limit_buckets WHERE key = $1",
+            stored_key(&email.as_str().to_lowercase())
+        )
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO suppressed_emails (email_hash, suppressed_at)
+            VALUES ($1, now())
+            ON CONFLICT DO NOTHING
+            "#,
+            email_hash.as_ref()
+        )
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO audit_log (id, action, subject, details, occurred_at)
+            VALUES ($1, 'data_erased', $2, $3, now())
+            "#,
+            Uuid::new_v4(),
+            email_hash.as_ref(),
+            json!({ "subscriptions": ids.len() })
+        )
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+
+        Ok(ids.len() as u64)
+    }
+}
@@ -0,0 +1,163 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use zero2prod_core::{
+    domain::{ListSettings, ListSlug, ListTemplates, MailingList, NewList},
+    error::{CoreError, CoreResult},
+    repository::ListRepository,
+};
+
+pub struct ListRepositoryImpl {
+    db_pool: PgPool,
+}
+
+impl ListRepositoryImpl {
+    pub fn new(db_pool: PgPool) -> Self {
+        Self { db_pool }
+    }
+}
+
+struct ListRow {
+    id: Uuid,
+    slug: String,
+    name: String,
+    sender_email: Option<String>,
+    require_confirmation: bool,
+    confirmation_ttl: Option<i64>,
+    confirmation_template_html: Option<String>,
+    issue_template_html: Option<String>,
+    issue_template_text: Option<String>,
+}
+
+impl TryFrom<ListRow> for MailingList {
+    type Error = CoreError;
+
+    fn try_from(row: ListRow) -> Result<Self, Self::Error> {
+        Ok(MailingList {
+            id: row.id,
+            slug: ListSlug::parse(row.slug)?,
+            settings: ListSettings {
+                name: row.name,
+                sender: row.sender_email.map(|email| email.parse()).transpose()?,
+                require_confirmation: row.require_confirmation,
+                confirmation_ttl: row.confirmation_ttl.map(|ttl| ttl as u64),
+                templates: ListTemplates {
+                    confirmation_html: row.confirmation_template_html,
+                    issue_html: row.issue_template_html,
+                    issue_text: row.issue_template_text,
+                },
+            },
+        })
+    }
+}
+
+#[async_trait]
+impl ListRepository for ListRepositoryImpl {
+    async fn create(&self, new_list: &NewList) -> CoreResult<Uuid> {
+        let list_id = Uuid::new_v4();
+        let settings = &new_list.settings;
+        sqlx::query!(
+            r#"
+            INSERT INTO lists (id, slug, name, sender_email, require_confirmation,
+                confirmation_ttl, confirmation_template_html, issue_template_html,
+                issue_template_text)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+            list_id,
+            new_list.slug.as_ref(),
+            settings.name,
+            settings.sender.as_ref().map(|sender| sender.as_str()),
+            settings.require_confirmation,
+            settings.confirmation_ttl.map(|ttl| ttl as i64),
+            settings.templates.confirmation_html,
+            settings.templates.issue_html,
+            settings.templates.issue_text
+        )
+        .execute(&self.db_pool)
+        .await
+        .map_err(|err| match err {
+            sqlx::Error::Database(err) if err.is_unique_violation() => CoreError::ListAlreadyExists,
+            err => CoreError::Unexpected(err.to_string()),
+        })?;
+        Ok(list_id)
+    }
+
+    async fn find_by_id(&self, list_id: Uuid) -> CoreResult<Option<MailingList>> {
+        sqlx::query_as!(
+            ListRow,
+            r#"
+            SELECT id, slug, name, sender_email, require_confirmation, confirmation_ttl,
+                confirmation_template_html, issue_template_html, issue_template_text
+            FROM lists
+            WHERE id = $1
+            "#,
+            list_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?
+        .map(MailingList::try_from)
+        .transpose()
+    }
+
+    async fn find_by_slug(&self, slug: &ListSlug) -> CoreResult<Option<MailingList>> {
+        sqlx::query_as!(
+            ListRow,
+            r#"
+            SELECT id, slug, name, sender_email, require_confirmation, confirmation_ttl,
+                confirmation_template_html, issue_template_html, issue_template_text
+            FROM lists
+            WHERE slug = $1
+            "#,
+            slug.as_ref()
+        )
+        .fetch_optional(&self.db_pool)
+        .await?
+        .map(MailingList::try_from)
+        .transpose()
+    }
+
+    async fn list(&self) -> CoreResult<Vec<MailingList>> {
+        sqlx::query_as!(
+            ListRow,
+            r#"
+            SELECT id, slug, name, sender_email, require_confirmation, confirmation_ttl,
+                confirmation_template_html, issue_template_html, issue_template_text
+            FROM lists
+            ORDER BY slug
+            "#
+        )
+        .fetch_all(&self.db_pool)
+        .await?
+        .into_iter()
+        .map(MailingList::try_from)
+        .collect()
+    }
+
+    async fn update(&self, list_id: Uuid, settings: &ListSettings) -> CoreResult<()> {
+        let updated = sqlx::query!(
+            r#"
+            UPDATE lists
+            SET name = $2, sender_email = $3, require_confirmation = $4, confirmation_ttl = $5,
+                confirmation_template_html = $6, issue_template_html = $7,
+                issue_template_text = $8
+            WHERE id = $1
+            "#,
+            list_id,
+            settings.name,
+            settings.sender.as_ref().map(|sender| sender.as_str()),
+            settings.require_confirmation,
+            settings.confirmation_ttl.map(|ttl| ttl as i64),
+            settings.templates.confirmation_html,
+            settings.templates.issue_html,
+            settings.templates.issue_text
+        )
+        .execute(&self.db_pool)
+        .await?;
+
+        match updated.rows_affected() {
+            0 => Err(CoreError::ListNotFound),
+            _ => Ok(()),
+        }
+    }
+}
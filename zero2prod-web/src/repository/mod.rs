@@ -1,3 +1,18 @@
+mod consent_repository_impl;
+mod data_subject_repository_impl;
+mod list_repository_impl;
+mod newsletter_issue_repository_impl;
+mod segment_sql;
+mod subscriber_import_repository_impl;
 mod subscription_repository_impl;
+mod topic_repository_impl;
+mod user_repository_impl;
 
+pub use consent_repository_impl::ConsentRepositoryImpl;
+pub use data_subject_repository_impl::DataSubjectRepositoryImpl;
+pub use list_repository_impl::ListRepositoryImpl;
+pub use newsletter_issue_repository_impl::NewsletterIssueRepositoryImpl;
+pub use subscriber_import_repository_impl::SubscriberImportRepositoryImpl;
 pub use subscription_repository_impl::SubscriptionRepositoryImpl;
+pub use topic_repository_impl::TopicRepositoryImpl;
+pub use user_repository_impl::UserRepositoryImpl;
@@ -0,0 +1,428 @@
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+use sqlx::types::chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, QueryBuilder, Transaction};
+use uuid::Uuid;
+
+use zero2prod_core::{
+    domain::{
+        Delivery, DeliveryOutcome, DeliveryStats, IssueAudience, IssueContent, IssueStatus,
+        NewsletterIssue, Segment,
+    },
+    error::{CoreError, CoreResult},
+    repository::NewsletterIssueRepository,
+};
+
+use super::segment_sql::push_segment;
+
+pub struct NewsletterIssueRepositoryImpl {
+    db_pool: PgPool,
+}
+
+impl NewsletterIssueRepositoryImpl {
+    pub fn new(db_pool: PgPool) -> Self {
+        Self { db_pool }
+    }
+
+    /// Tells why an issue was not changed.
+    async fn not_changed(&self, issue_id: Uuid) -> CoreError {
+        match self.find_by_id(issue_id).await {
+            Ok(Some(_)) => CoreError::IssueNotEditable,
+            Ok(None) => CoreError::IssueNotFound,
+            Err(err) => err,
+        }
+    }
+}
+
+struct IssueRow {
+    id: Uuid,
+    list_id: Uuid,
+    title: String,
+    text_content: String,
+    html_content: String,
+    status: String,
+    send_at: Option<DateTime<Utc>>,
+    created_by: Option<Uuid>,
+    created_at: DateTime<Utc>,
+    sent_at: Option<DateTime<Utc>>,
+    topic_ids: Vec<Uuid>,
+    segment: Option<String>,
+}
+
+impl TryFrom<IssueRow> for NewsletterIssue {
+    type Error = CoreError;
+
+    fn try_from(row: IssueRow) -> Result<Self, Self::Error> {
+        Ok(NewsletterIssue {
+            id: row.id,
+            list_id: row.list_id,
+            content: IssueContent {
+                title: row.title,
+                text_content: row.text_content,
+                html_content: row.html_content,
+            },
+            status: row.status.parse()?,
+            send_at: row.send_at.map(SystemTime::from),
+            created_by: row.created_by,
+            created_at: row.created_at.into(),
+            sent_at: row.sent_at.map(SystemTime::from),
+            audience: IssueAudience {
+                topic_ids: row.topic_ids,
+                segment: row.segment.as_deref().map(Segment::parse).transpose()?,
+            },
+        })
+    }
+}
+
+#[async_trait]
+impl NewsletterIssueRepository for NewsletterIssueRepositoryImpl {
+    async fn create(
+        &self,
+        list_id: Uuid,
+        content: &IssueContent,
+        audience: &IssueAudience,
+        created_by: Uuid,
+    ) -> CoreResult<Uuid> {
+        let issue_id = Uuid::new_v4();
+        let mut transaction = self.db_pool.begin().await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO newsletter_issues
+                (id, list_id, title, text_content, html_content, segment, status, created_by,
+                created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, 'draft', $7, now(), now())
+            "#,
+            issue_id,
+            list_id,
+            content.title,
+            content.text_content,
+            content.html_content,
+            audience.segment.as_ref().map(Segment::to_string),
+            created_by
+        )
+        .execute(&mut *transaction)
+        .await?;
+        set_topics(&mut transaction, issue_id, &audience.topic_ids).await?;
+        transaction.commit().await?;
+        Ok(issue_id)
+    }
+
+    async fn find_by_id(&self, issue_id: Uuid) -> CoreResult<Option<NewsletterIssue>> {
+        sqlx::query_as!(
+            IssueRow,
+            r#"
+            SELECT id, list_id, title, text_content, html_content, status, send_at, created_by,
+                created_at, sent_at, segment,
+                ARRAY(
+                    SELECT topic_id FROM newsletter_issue_topics
+                    WHERE issue_id = newsletter_issues.id
+                    ORDER BY topic_id
+                ) AS "topic_ids!"
+            FROM newsletter_issues
+            WHERE id = $1
+            "#,
+            issue_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?
+        .map(NewsletterIssue::try_from)
+        .transpose()
+    }
+
+    async fn list(&self, status: Option<IssueStatus>) -> CoreResult<Vec<NewsletterIssue>> {
+        sqlx::query_as!(
+            IssueRow,
+            r#"
+            SELECT id, list_id, title, text_content, html_content, status, send_at, created_by,
+                created_at, sent_at, segment,
+                ARRAY(
+                    SELECT topic_id FROM newsletter_issue_topics
+                    WHERE issue_id = newsletter_issues.id
+                    ORDER BY topic_id
+                ) AS "topic_ids!"
+            FROM newsletter_issues
+            WHERE $1::text IS NULL OR status = $1
+            ORDER BY created_at DESC
+            "#,
+            status.map(|status| status.as_str())
+        )
+        .fetch_all(&self.db_pool)
+        .await?
+        .into_iter()
+        .map(NewsletterIssue::try_from)
+        .collect()
+    }
+
+    async fn update(
+        &self,
+        issue_id: Uuid,
+        content: &IssueContent,
+        audience: &IssueAudience,
+    ) -> CoreResult<()> {
+        let mut transaction = self.db_pool.begin().await?;
+        let updated = sqlx::query!(
+            r#"
+            UPDATE newsletter_issues
+            SET title = $2, text_content = $3, html_content = $4, segment = $5,
+                updated_at = now()
+            WHERE id = $1 AND status IN ('draft', 'scheduled')
+            "#,
+            issue_id,
+            content.title,
+            content.text_content,
+            content.html_content,
+            audience.segment.as_ref().map(Segment::to_string)
+        )
+        .execute(&mut *transaction)
+        .await?;
+        if updated.rows_affected() == 0 {
+            return Err(self.not_changed(issue_id).await);
+        }
+
+        set_topics(&mut transaction, issue_id, &audience.topic_ids).await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn delete(&self, issue_id: Uuid) -> CoreResult<()> {
+        let deleted = sqlx::query!(
+            "DELETE FROM newsletter_issues WHERE id = $1 AND status IN ('draft', 'scheduled')",
+            issue_id
+        )
+        .execute(&self.db_pool)
+        .await?;
+
+        match deleted.rows_affected() {
+            0 => Err(self.not_changed(issue_id).await),
+            _ => Ok(()),
+        }
+    }
+
+    async fn schedule(&self, issue_id: Uuid, send_at: Option<SystemTime>) -> CoreResult<()> {
+        let status = match send_at {
+            Some(_) => IssueStatus::Scheduled,
+            None => IssueStatus::Draft,
+        };
+        let updated = sqlx::query!(
+            r#"
+            UPDATE newsletter_issues
+            SET status = $2, send_at = $3, updated_at = now()
+            WHERE id = $1 AND status IN ('draft', 'scheduled')
+            "#,
+            issue_id,
+            status.as_str(),
+            send_at.map(DateTime::<Utc>::from)
+        )
+        .execute(&self.db_pool)
+        .await?;
+
+        match updated.rows_affected() {
+            0 => Err(self.not_changed(issue_id).await),
+            _ => Ok(()),
+        }
+    }
+
+    async fn start_due(&self, now: SystemTime) -> CoreResult<()> {
+        // A single transaction, so that an issue never starts without all of
+        // its deliveries
+        let mut transaction = self.db_pool.begin().await?;
+        let due = sqlx::query!(
+            r#"
+            UPDATE newsletter_issues
+            SET status = 'sending', updated_at = now()
+            WHERE id IN (
+                SELECT id FROM newsletter_issues
+                WHERE status = 'scheduled' AND send_at <= $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, list_id, segment
+            "#,
+            DateTime::<Utc>::from(now)
+        )
+        .fetch_all(&mut *transaction)
+        .await?;
+
+        for issue in due {
+            // Issues about topics skip the subscribers who opted out of all
+            // of them
+            let mut query = QueryBuilder::new(
+                "INSERT INTO issue_deliveries (issue_id, subscriber_id, email, status, updated_at) \
+                SELECT ",
+            );
+            query.push_bind(issue.id);
+            query.push(
+                ", subscriptions.id, subscriptions.email, 'pending', now() FROM subscriptions \
+                WHERE subscriptions.status = 'confirmed' AND subscriptions.list_id = ",
+            );
+            query.push_bind(issue.list_id);
+            query.push(" AND (NOT EXISTS (SELECT 1 FROM newsletter_issue_topics WHERE issue_id = ");
+            query.push_bind(issue.id);
+            query.push(
+                ") OR EXISTS (SELECT 1 FROM newsletter_issue_topics LEFT JOIN topic_preferences \
+                ON topic_preferences.topic_id = newsletter_issue_topics.topic_id \
+                AND topic_preferences.subscriber_id = subscriptions.id \
+                WHERE newsletter_issue_topics.issue_id = ",
+            );
+            query.push_bind(issue.id);
+            query.push(" AND COALESCE(topic_preferences.subscribed, TRUE)))");
+            if let Some(segment) = issue.segment {
+                query.push(" AND ");
+                push_segment(&mut query, &Segment::parse(&segment)?);
+            }
+            query.push(" ON CONFLICT DO NOTHING");
+            query.build().execute(&mut *transaction).await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn next_delivery(&self, issue_id: Uuid) -> CoreResult<Option<Delivery>> {
+        let delivery = sqlx::query!(
+            r#"
+            UPDATE issue_deliveries
+            SET status = 'sending', attempts = attempts + 1, updated_at = now()
+            WHERE (issue_id, subscriber_id) = (
+                SELECT issue_id, subscriber_id FROM issue_deliveries
+                WHERE issue_id = $1 AND status = 'pending'
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING subscriber_id, email, attempts
+            "#,
+            issue_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?
+        .map(|row| Delivery {
+            subscriber_id: row.subscriber_id,
+            email: row.email,
+            attempt: row.attempts as u32,
+        });
+        Ok(delivery)
+    }
+
+    async fn record_delivery(
+        &self,
+        issue_id: Uuid,
+        subscriber_id: Uuid,
+        outcome: DeliveryOutcome,
+    ) -> CoreResult<()> {
+        let (status, error) = match outcome {
+            DeliveryOutcome::Sent => ("sent", None),
+            DeliveryOutcome::Retry(error) => ("pending", Some(error)),
+            DeliveryOutcome::Failed(error) => ("failed", Some(error)),
+        };
+        sqlx::query!(
+            r#"
+            UPDATE issue_deliveries
+            SET status = $3, last_error = $4, updated_at = now()
+            WHERE issue_id = $1 AND subscriber_id = $2
+            "#,
+            issue_id,
+            subscriber_id,
+            status,
+            error
+        )
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn fail_interrupted(&self, issue_id: Uuid, older_than: Duration) -> CoreResult<u64> {
+        let failed = sqlx::query!(
+            r#"
+            UPDATE issue_deliveries
+            SET status = 'failed', last_error = 'Interrupted while sending', updated_at = now()
+            WHERE issue_id = $1
+                AND status = 'sending'
+                AND updated_at < now() - make_interval(secs => $2)
+            "#,
+            issue_id,
+            older_than.as_secs_f64()
+        )
+        .execute(&self.db_pool)
+        .await?;
+        Ok(failed.rows_affected())
+    }
+
+    async fn complete(&self, issue_id: Uuid) -> CoreResult<bool> {
+        let completed = sqlx::query!(
+            r#"
+            UPDATE newsletter_issues
+            SET status = 'sent', sent_at = now(), updated_at = now()
+            WHERE id = $1
+                AND status = 'sending'
+                AND NOT EXISTS (
+                    SELECT 1 FROM issue_deliveries
+                    WHERE issue_id = $1 AND status IN ('pending', 'sending')
+                )
+            "#,
+            issue_id
+        )
+        .execute(&self.db_pool)
+        .await?;
+        Ok(completed.rows_affected() > 0)
+    }
+
+    async fn delivery_stats(&self, issue_id: Uuid) -> CoreResult<DeliveryStats> {
+        let stats = sqlx::query!(
+            r#"
+            SELECT
+                COUNT(*) FILTER (WHERE status IN ('pending', 'sending')) AS "pending!",
+                COUNT(*) FILTER (WHERE status = 'sent') AS "sent!",
+                COUNT(*) FILTER (WHERE status = 'failed') AS "failed!"
+            FROM issue_deliveries
+            WHERE issue_id = $1
+            "#,
+            issue_id
+        )
+        .fetch_one(&self.db_pool)
+        .await?;
+        Ok(DeliveryStats {
+            pending: stats.pending as u64,
+            sent: stats.sent as u64,
+            failed: stats.failed as u64,
+        })
+    }
+
+    async fn record_open(&self, issue_id: Uuid, subscriber_id: Uuid) -> CoreResult<()> {
+        sqlx::query!(
+            r#"
+            UPDATE issue_deliveries
+            SET opened_at = COALESCE(opened_at, now())
+            WHERE issue_id = $1 AND subscriber_id = $2
+            "#,
+            issue_id,
+            subscriber_id
+        )
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+}
+
+/// Replaces the topics of an issue.
+async fn set_topics(
+    transaction: &mut Transaction<'_, Postgres>,
+    issue_id: Uuid,
+    topic_ids: &[Uuid],
+) -> CoreResult<()> {
+    sqlx::query!(
+        "DELETE FROM newsletter_issue_topics WHERE issue_id = $1",
+        issue_id
+    )
+    .execute(&mut **transaction)
+    .await?;
+    sqlx::query!(
+        r#"
+        INSERT INTO newsletter_issue_topics (issue_id, topic_id)
+        SELECT $1, topic_id FROM UNNEST($2::uuid[]) AS topic_id
+        "#,
+        issue_id,
+        topic_ids
+    )
+    .execute(&mut **transaction)
+    .await?;
+    Ok(())
+}
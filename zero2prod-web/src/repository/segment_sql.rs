@@ -0,0 +1,75 @@
+use sqlx::types::chrono::{DateTime, NaiveDate, Utc};
+use sqlx::{Postgres, QueryBuilder};
+
+use zero2prod_core::domain::{Condition, Segment, SegmentDate};
+
+/// Appends a condition on `subscriptions` matching the segment. Every value
+/// is bound, so segments never end up in the SQL text.
+pub(crate) fn push_segment(query: &mut QueryBuilder<'_, Postgres>, segment: &Segment) {
+    match segment {
+        Segment::Condition(condition) => push_condition(query, condition),
+        Segment::Not(segment) => {
+            query.push("NOT (");
+            push_segment(query, segment);
+            query.push(")");
+        }
+        Segment::And(segments) => push_all(query, segments, " AND "),
+        Segment::Or(segments) => push_all(query, segments, " OR "),
+    }
+}
+
+fn push_all(query: &mut QueryBuilder<'_, Postgres>, segments: &[Segment], separator: &str) {
+    query.push("(");
+    for (i, segment) in segments.iter().enumerate() {
+        if i > 0 {
+            query.push(separator);
+        }
+        push_segment(query, segment);
+    }
+    query.push(")");
+}
+
+fn push_condition(query: &mut QueryBuilder<'_, Postgres>, condition: &Condition) {
+    match condition {
+        Condition::Status(status) => {
+            query.push("subscriptions.status = ");
+            query.push_bind(status.as_str());
+        }
+        Condition::SubscribedAfter(date) => {
+            query.push("subscriptions.subscribed_at >= ");
+            query.push_bind(midnight(date));
+        }
+        Condition::SubscribedBefore(date) => {
+            query.push("subscriptions.subscribed_at < ");
+            query.push_bind(midnight(date));
+        }
+        Condition::Topic(topic) => {
+            // Subscribers receive every topic until they opt out
+            query.push(
+                "EXISTS (SELECT 1 FROM topics LEFT JOIN topic_preferences \
+                ON topic_preferences.topic_id = topics.id \
+                AND topic_preferences.subscriber_id = subscriptions.id \
+                WHERE topics.list_id = subscriptions.list_id AND topics.slug = ",
+            );
+            query.push_bind(topic.as_ref().to_owned());
+            query.push(" AND COALESCE(topic_preferences.subscribed, TRUE))");
+        }
+        Condition::OpenedWithin { days } => {
+            query.push(
+                "EXISTS (SELECT 1 FROM issue_deliveries \
+                WHERE issue_deliveries.subscriber_id = subscriptions.id \
+                AND issue_deliveries.opened_at >= now() - make_interval(days => ",
+            );
+            query.push_bind(*days as i32);
+            query.push("))");
+        }
+    }
+}
+
+/// The start of the day in UTC.
+fn midnight(date: &SegmentDate) -> DateTime<Utc> {
+    NaiveDate::from_ymd_opt(date.year, date.month, date.day)
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .expect("Segment dates are valid")
+        .and_utc()
+}
@@ -0,0 +1,277 @@
+use std::{sync::Arc, time::SystemTime};
+
+use async_trait::async_trait;
+use serde_json::json;
+use sqlx::{
+    types::chrono::{DateTime, Utc},
+    PgConnection, PgPool,
+};
+use uuid::Uuid;
+
+use zero2prod_core::{
+    domain::{
+        EmailHash, ImportBatch, ImportConfirmations, ImportOptions, ImportProgress, ImportRowError,
+        ImportStatus, NewJob, SubscriberImport, UpsertedSubscriber,
+    },
+    error::{CoreError, CoreResult},
+    repository::SubscriberImportRepository,
+};
+
+use crate::jobs::JobQueueImpl;
+
+use super::consent_repository_impl;
+
+pub struct SubscriberImportRepositoryImpl {
+    db_pool: PgPool,
+    job_queue: Arc<JobQueueImpl>,
+}
+
+impl SubscriberImportRepositoryImpl {
+    pub fn new(db_pool: PgPool, job_queue: Arc<JobQueueImpl>) -> Self {
+        Self { db_pool, job_queue }
+    }
+}
+
+struct SubscriberImportRow {
+    id: Uuid,
+    list_id: Uuid,
+    confirmed: bool,
+    status: String,
+    rows: i64,
+    imported: i64,
+    failed: i64,
+    created_at: DateTime<Utc>,
+    finished_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<SubscriberImportRow> for SubscriberImport {
+    type Error = CoreError;
+
+    fn try_from(row: SubscriberImportRow) -> CoreResult<Self> {
+        Ok(SubscriberImport {
+            id: row.id,
+            list_id: row.list_id,
+            options: ImportOptions {
+                confirmed: row.confirmed,
+            },
+            status: row.status.parse()?,
+            progress: ImportProgress {
+                rows: row.rows as u64,
+                imported: row.imported as u64,
+                failed: row.failed as u64,
+            },
+            created_at: row.created_at.into(),
+            finished_at: row.finished_at.map(SystemTime::from),
+        })
+    }
+}
+
+#[async_trait]
+impl SubscriberImportRepository for SubscriberImportRepositoryImpl {
+    async fn create(&self, list_id: Uuid, options: &ImportOptions) -> CoreResult<Uuid> {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            r#"
+            INSERT INTO subscriber_imports (id, list_id, confirmed, status, created_at)
+            VALUES ($1, $2, $3, $4, now())
+            "#,
+            id,
+            list_id,
+            options.confirmed,
+            ImportStatus::Running.as_str()
+        )
+        .execute(&self.db_pool)
+        .await?;
+        Ok(id)
+    }
+
+    async fn find_by_id(&self, import_id: Uuid) -> CoreResult<Option<SubscriberImport>> {
+        sqlx::query_as!(
+            SubscriberImportRow,
+            r#"
+            SELECT id, list_id, confirmed, status, rows, imported, failed, created_at, finished_at
+            FROM subscriber_imports WHERE id = $1
+            "#,
+            import_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?
+        .map(SubscriberImport::try_from)
+        .transpose()
+    }
+
+    async fn import_batch(
+        &self,
+        import_id: Uuid,
+        list_id: Uuid,
+        batch: &ImportBatch,
+    ) -> CoreResult<ImportProgress> {
+        let mut transaction = self.db_pool.begin().await?;
+        let upserted = upsert(&mut transaction, list_id, batch).await?;
+        let outcome = batch.outcome(&upserted);
+        consent_repository_impl::insert_all(&mut transaction, &outcome.consents).await?;
+        if let Some(confirmations) = outcome.confirmations {
+            self.job_queue
+                .insert(
+                    &mut *transaction,
+                    NewJob::new(ImportConfirmations::KIND, json!(confirmations)),
+                )
+                .await?;
+        }
+        record_batch(
+            &mut transaction,
+            import_id,
+            &outcome.progress,
+            &outcome.errors,
+        )
+        .await?;
+        transaction.commit().await?;
+
+        Ok(outcome.progress)
+    }
+
+    async fn finish(&self, import_id: Uuid, status: ImportStatus) -> CoreResult<()> {
+        sqlx::query!(
+            "UPDATE subscriber_imports SET status = $2, finished_at = now() WHERE id = $1",
+            import_id,
+            status.as_str()
+        )
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn errors(&self, import_id: Uuid) -> CoreResult<Vec<ImportRowError>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT line, email, reason FROM subscriber_import_errors
+            WHERE import_id = $1
+            ORDER BY line
+            "#,
+            import_id
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ImportRowError {
+                line: row.line as u64,
+                email: row.email,
+                reason: row.reason,
+            })
+            .collect())
+    }
+}
+
+async fn upsert(
+    connection: &mut PgConnection,
+    list_id: Uuid,
+    batch: &ImportBatch,
+) -> CoreResult<Vec<UpsertedSubscriber>> {
+    let mut ids = vec![];
+    let mut emails = vec![];
+    let mut names = vec![];
+    let mut email_hashes = vec![];
+    for imported in &batch.subscribers {
+        ids.push(Uuid::new_v4());
+        emails.push(imported.subscriber.email.to_string());
+        names.push(imported.subscriber.name.as_ref().to_owned());
+        email_hashes.push(
+            EmailHash::of(&imported.subscriber.email)
+                .as_ref()
+                .to_owned(),
+        );
+    }
+
+    // Only pending subscribers change status, so that the ones who left are
+    // not subscribed again
+    let rows = sqlx::query!(
+        r#"
+        WITH previous AS (
+            SELECT lower(email) AS email, status FROM subscriptions
+            WHERE list_id = $1
+                AND lower(email) IN (SELECT lower(address) FROM UNNEST($3::text[]) AS address)
+        ), upserted AS (
+            INSERT INTO subscriptions (id, list_id, email, name, subscribed_at, status)
+            SELECT input.id, $1, input.email, input.name, now(), $6
+            FROM UNNEST($2::uuid[], $3::text[], $4::text[], $5::text[])
+                AS input (id, email, name, email_hash)
+            WHERE NOT EXISTS (
+                SELECT 1 FROM suppressed_emails WHERE email_hash = input.email_hash
+            )
+            ON CONFLICT (list_id, lower(email)) DO UPDATE
+            SET name = EXCLUDED.name,
+                status = CASE WHEN subscriptions.status = 'pending'
+                    THEN EXCLUDED.status ELSE subscriptions.status END
+            RETURNING id, email, status, (xmax = 0) AS created
+        )
+        SELECT upserted.id, upserted.email, upserted.created AS "created!",
+            COALESCE(previous.status <> upserted.status, false) AS "promoted!"
+        FROM upserted LEFT JOIN previous ON previous.email = lower(upserted.email)
+        "#,
+        list_id,
+        &ids,
+        &emails,
+        &names,
+        &email_hashes,
+        batch.status().as_str()
+    )
+    .fetch_all(connection)
+    .await?;
+
+    rows.into_iter()
+        .map(|row| {
+            Ok(UpsertedSubscriber {
+                id: row.id,
+                email: row.email.parse()?,
+                created: row.created,
+                promoted: row.promoted,
+            })
+        })
+        .collect()
+}
+
+async fn record_batch(
+    connection: &mut PgConnection,
+    import_id: Uuid,
+    progress: &ImportProgress,
+    errors: &[ImportRowError],
+) -> CoreResult<()> {
+    let mut lines = vec![];
+    let mut emails = vec![];
+    let mut reasons = vec![];
+    for error in errors {
+        lines.push(error.line as i64);
+        emails.push(error.email.clone());
+        reasons.push(error.reason.clone());
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO subscriber_import_errors (import_id, line, email, reason)
+        SELECT $1, * FROM UNNEST($2::bigint[], $3::text[], $4::text[])
+        "#,
+        import_id,
+        &lines,
+        &emails,
+        &reasons
+    )
+    .execute(&mut *connection)
+    .await?;
+    sqlx::query!(
+        r#"
+        UPDATE subscriber_imports
+        SET rows = rows + $2, imported = imported + $3, failed = failed + $4
+        WHERE id = $1
+        "#,
+        import_id,
+        progress.rows as i64,
+        progress.imported as i64,
+        progress.failed as i64
+    )
+    .execute(&mut *connection)
+    .await?;
+
+    Ok(())
+}
@@ -1,15 +1,26 @@
+use std::time::Duration;
+
 use async_trait::async_trait;
-use sqlx::types::chrono::Utc;
-use sqlx::PgPool;
+use email_address::EmailAddress;
+use futures_util::{stream, Stream};
+use sqlx::postgres::PgRow;
+use sqlx::types::chrono::{DateTime, Utc};
+use sqlx::{PgPool, QueryBuilder, Row};
+use tokio::sync::mpsc;
 
 use zero2prod_core::{
-    domain::NewSubscriber,
+    domain::{
+        EmailHash, NewSubscriber, Segment, StoredToken, Subscriber, SubscriberCursor,
+        SubscriberEntry, SubscriberFilter, SubscriberName, SubscriptionStatus,
+    },
     error::{CoreError, CoreResult},
     repository::SubscriptionRepository,
 };
 
 use uuid::Uuid;
 
+use super::segment_sql::push_segment;
+
 pub struct SubscriptionRepositoryImpl {
     db_pool: PgPool,
 }
@@ -18,27 +29,401 @@ impl SubscriptionRepositoryImpl {
     pub fn new(db_pool: PgPool) -> Self {
         Self { db_pool }
     }
+
+    /// Streams the subscribers to every list with the status, or all of them,
+    /// in batches of `batch_size`.
+    ///
+    /// The rows are read through a cursor, a batch is only fetched once the
+    /// previous one has been consumed.
+    ///
+    /// This is not part of [`SubscriptionRepository`]: the export is streamed
+    /// straight into the response body by the web handler, with no logic for
+    /// the core to hold, and the stream outlives the call, which the async
+    /// methods of the trait cannot express. The cursor statements are runtime
+    /// queries, `DECLARE` has no result columns to check and the `FETCH` size
+    /// is only known at runtime.
+    pub fn export(
+        &self,
+        status: Option<SubscriptionStatus>,
+        batch_size: usize,
+    ) -> impl Stream<Item = CoreResult<Vec<SubscriberEntry>>> {
+        let (sender, batches) = mpsc::channel(1);
+        let db_pool = self.db_pool.clone();
+        tokio::spawn(async move {
+            if let Err(err) = export_batches(&db_pool, status, batch_size, &sender).await {
+                let _ = sender.send(Err(err)).await;
+            }
+        });
+
+        stream::unfold(batches, |mut batches| async move {
+            batches.recv().await.map(|batch| (batch, batches))
+        })
+    }
+}
+
+async fn export_batches(
+    db_pool: &PgPool,
+    status: Option<SubscriptionStatus>,
+    batch_size: usize,
+    batches: &mpsc::Sender<CoreResult<Vec<SubscriberEntry>>>,
+) -> CoreResult<()> {
+    // Cursors only live as long as the transaction that declares them
+    let mut transaction = db_pool.begin().await.map_err(db_error)?;
+    sqlx::query(
+        r#"
+        DECLARE subscribers_export NO SCROLL CURSOR FOR
+        SELECT id, list_id, email, name, status, subscribed_at FROM subscriptions
+        WHERE $1::text IS NULL OR status = $1
+        ORDER BY subscribed_at, id
+        "#,
+    )
+    .bind(status.map(|status| status.as_str()))
+    .execute(&mut *transaction)
+    .await
+    .map_err(db_error)?;
+
+    let fetch = format!("FETCH {} FROM subscribers_export", batch_size.max(1));
+    loop {
+        let rows = sqlx::query(&fetch)
+            .fetch_all(&mut *transaction)
+            .await
+            .map_err(db_error)?;
+        if rows.is_empty() {
+            break;
+        }
+        let batch = rows
+            .iter()
+            .map(subscriber_entry)
+            .collect::<CoreResult<_>>()?;
+        if batches.send(Ok(batch)).await.is_err() {
+            // Nobody is reading anymore
+            break;
+        }
+    }
+    transaction.commit().await.map_err(db_error)?;
+
+    Ok(())
+}
+
+fn subscriber_entry(row: &PgRow) -> CoreResult<SubscriberEntry> {
+    let subscribed_at: DateTime<Utc> = row.try_get("subscribed_at").map_err(db_error)?;
+    Ok(SubscriberEntry {
+        subscriber: Subscriber {
+            id: row.try_get("id").map_err(db_error)?,
+            list_id: row.try_get("list_id").map_err(db_error)?,
+            email: row.try_get::<&str, _>("email").map_err(db_error)?.parse()?,
+            name: row.try_get::<&str, _>("name").map_err(db_error)?.parse()?,
+            status: row
+                .try_get::<&str, _>("status")
+                .map_err(db_error)?
+                .parse()?,
+        },
+        subscribed_at: subscribed_at.into(),
+    })
 }
 
 #[async_trait]
 impl SubscriptionRepository for SubscriptionRepositoryImpl {
-    async fn create(&self, new_subscriber: &NewSubscriber) -> CoreResult<()> {
-        sqlx::query!(
+    async fn create(&self, new_subscriber: &NewSubscriber) -> CoreResult<Uuid> {
+        let email_hash = EmailHash::of(&new_subscriber.email);
+        let suppressed = sqlx::query!(
+            r#"SELECT EXISTS (SELECT 1 FROM suppressed_emails WHERE email_hash = $1) AS "suppressed!""#,
+            email_hash.as_ref()
+        )
+        .fetch_one(&self.db_pool)
+        .await
+        .map_err(db_error)?
+        .suppressed;
+        if suppressed {
+            return Err(CoreError::EmailSuppressed);
+        }
+
+        // Subscribers who left may subscribe again. Pending ones are returned
+        // untouched so that a new confirmation can be sent, the form cannot
+        // be used to rename them or to keep them from expiring.
+        let created = sqlx::query!(
             r#"
-            INSERT INTO subscriptions (id, email, name, subscribed_at, status) 
-            VALUES ($1, $2, $3, $4, 'confirmed')
+            INSERT INTO subscriptions (id, list_id, email, name, subscribed_at, status)
+            VALUES ($1, $2, $3, $4, $5, 'pending')
+            ON CONFLICT (list_id, lower(email)) DO UPDATE
+            SET name = CASE subscriptions.status
+                    WHEN 'pending' THEN subscriptions.name ELSE EXCLUDED.name END,
+                subscribed_at = CASE subscriptions.status
+                    WHEN 'pending' THEN subscriptions.subscribed_at ELSE EXCLUDED.subscribed_at END,
+                status = 'pending'
+            WHERE subscriptions.status IN ('pending', 'unsubscribed')
+            RETURNING id
         "#,
             Uuid::new_v4(),
+            new_subscriber.list_id,
             new_subscriber.email.as_ref(),
             new_subscriber.name.as_ref(),
             Utc::now()
         )
-        .execute(&self.db_pool)
+        .fetch_optional(&self.db_pool)
+        .await
+        .map_err(db_error)?;
+
+        created
+            .map(|row| row.id)
+            .ok_or(CoreError::EmailAlreadyExists)
+    }
+
+    async fn find_by_id(&self, subscriber_id: Uuid) -> CoreResult<Option<Subscriber>> {
+        let row = sqlx::query!(
+            "SELECT id, list_id, email, name, status FROM subscriptions WHERE id = $1",
+            subscriber_id
+        )
+        .fetch_optional(&self.db_pool)
         .await
         .map_err(db_error)?;
 
+        row.map(|row| {
+            Ok(Subscriber {
+                id: row.id,
+                list_id: row.list_id,
+                email: row.email.parse()?,
+                name: row.name.parse()?,
+                status: row.status.parse()?,
+            })
+        })
+        .transpose()
+    }
+
+    async fn store_token(&self, subscriber_id: Uuid, token: &StoredToken) -> CoreResult<()> {
+        sqlx::query!(
+            "INSERT INTO subscription_tokens (subscription_token, subscriber_id) VALUES ($1, $2)",
+            token.as_ref(),
+            subscriber_id
+        )
+        .execute(&self.db_pool)
+        .await
+        .map_err(db_error)?;
         Ok(())
     }
+
+    async fn find_by_token(&self, token: &str) -> CoreResult<Option<Uuid>> {
+        let row = sqlx::query!(
+            "SELECT subscriber_id FROM subscription_tokens WHERE subscription_token = $1",
+            token
+        )
+        .fetch_optional(&self.db_pool)
+        .await
+        .map_err(db_error)?;
+        Ok(row.map(|row| row.subscriber_id))
+    }
+
+    async fn find_entry(&self, subscriber_id: Uuid) -> CoreResult<Option<SubscriberEntry>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id, list_id, email, name, status, subscribed_at FROM subscriptions
+            WHERE id = $1
+            "#,
+            subscriber_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await
+        .map_err(db_error)?;
+
+        row.map(|row| {
+            Ok(SubscriberEntry {
+                subscriber: Subscriber {
+                    id: row.id,
+                    list_id: row.list_id,
+                    email: row.email.parse()?,
+                    name: row.name.parse()?,
+                    status: row.status.parse()?,
+                },
+                subscribed_at: row.subscribed_at.into(),
+            })
+        })
+        .transpose()
+    }
+
+    async fn list(
+        &self,
+        filter: &SubscriberFilter,
+        after: Option<SubscriberCursor>,
+        limit: u64,
+    ) -> CoreResult<Vec<SubscriberEntry>> {
+        let search = filter
+            .search
+            .as_ref()
+            .map(|search| format!("{}%", escape_like(&search.to_lowercase())));
+        sqlx::query!(
+            r#"
+            SELECT id, list_id, email, name, status, subscribed_at FROM subscriptions
+            WHERE ($1::uuid IS NULL OR list_id = $1)
+            AND ($2::text IS NULL OR status = $2)
+            AND ($3::timestamptz IS NULL OR subscribed_at >= $3)
+            AND ($4::timestamptz IS NULL OR subscribed_at < $4)
+            AND ($5::text IS NULL OR lower(email) LIKE $5 OR lower(name) LIKE $5)
+            AND ($6::timestamptz IS NULL OR (subscribed_at, id) < ($6, $7))
+            ORDER BY subscribed_at DESC, id DESC
+            LIMIT $8
+            "#,
+            filter.list_id,
+            filter.status.map(|status| status.as_str()),
+            filter.subscribed_after.map(DateTime::<Utc>::from),
+            filter.subscribed_before.map(DateTime::<Utc>::from),
+            search,
+            after.map(|after| DateTime::<Utc>::from(after.subscribed_at)),
+            after.map(|after| after.id),
+            limit as i64
+        )
+        .fetch_all(&self.db_pool)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .map(|row| {
+            Ok(SubscriberEntry {
+                subscriber: Subscriber {
+                    id: row.id,
+                    list_id: row.list_id,
+                    email: row.email.parse()?,
+                    name: row.name.parse()?,
+                    status: row.status.parse()?,
+                },
+                subscribed_at: row.subscribed_at.into(),
+            })
+        })
+        .collect()
+    }
+
+    async fn find_by_email(&self, email: &EmailAddress) -> CoreResult<Vec<Subscriber>> {
+        sqlx::query!(
+            r#"
+            SELECT id, list_id, email, name, status FROM subscriptions
+            WHERE lower(email) = lower($1)
+            ORDER BY subscribed_at
+            "#,
+            email.as_str()
+        )
+        .fetch_all(&self.db_pool)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .map(|row| {
+            Ok(Subscriber {
+                id: row.id,
+                list_id: row.list_id,
+                email: row.email.parse()?,
+                name: row.name.parse()?,
+                status: row.status.parse()?,
+            })
+        })
+        .collect()
+    }
+
+    async fn set_status(&self, subscriber_id: Uuid, status: SubscriptionStatus) -> CoreResult<()> {
+        let updated = sqlx::query!(
+            "UPDATE subscriptions SET status = $2 WHERE id = $1",
+            subscriber_id,
+            status.as_str()
+        )
+        .execute(&self.db_pool)
+        .await
+        .map_err(db_error)?;
+
+        match updated.rows_affected() {
+            0 => Err(CoreError::SubscriberNotFound),
+            _ => Ok(()),
+        }
+    }
+
+    async fn set_name(&self, subscriber_id: Uuid, name: &SubscriberName) -> CoreResult<()> {
+        let updated = sqlx::query!(
+            "UPDATE subscriptions SET name = $2 WHERE id = $1",
+            subscriber_id,
+            name.as_ref()
+        )
+        .execute(&self.db_pool)
+        .await
+        .map_err(db_error)?;
+
+        match updated.rows_affected() {
+            0 => Err(CoreError::SubscriberNotFound),
+            _ => Ok(()),
+        }
+    }
+
+    async fn delete(&self, subscriber_id: Uuid) -> CoreResult<()> {
+        let mut transaction = self.db_pool.begin().await.map_err(db_error)?;
+        sqlx::query!(
+            "DELETE FROM subscription_tokens WHERE subscriber_id = $1",
+            subscriber_id
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(db_error)?;
+        let deleted = sqlx::query!("DELETE FROM subscriptions WHERE id = $1", subscriber_id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(db_error)?;
+        transaction.commit().await.map_err(db_error)?;
+
+        match deleted.rows_affected() {
+            0 => Err(CoreError::SubscriberNotFound),
+            _ => Ok(()),
+        }
+    }
+
+    async fn delete_stale_pending(&self, older_than: Duration) -> CoreResult<u64> {
+        let mut transaction = self.db_pool.begin().await.map_err(db_error)?;
+        let stale = sqlx::query!(
+            r#"
+            SELECT id FROM subscriptions
+            WHERE status = 'pending' AND subscribed_at < now() - make_interval(secs => $1)
+            FOR UPDATE
+            "#,
+            older_than.as_secs_f64()
+        )
+        .fetch_all(&mut *transaction)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .map(|row| row.id)
+        .collect::<Vec<_>>();
+
+        sqlx::query!(
+            "DELETE FROM subscription_tokens WHERE subscriber_id = ANY($1)",
+            &stale
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(db_error)?;
+        let deleted = sqlx::query!("DELETE FROM subscriptions WHERE id = ANY($1)", &stale)
+            .execute(&mut *transaction)
+            .await
+            .map_err(db_error)?;
+        transaction.commit().await.map_err(db_error)?;
+
+        Ok(deleted.rows_affected())
+    }
+
+    async fn count_segment(&self, list_id: Uuid, segment: &Segment) -> CoreResult<u64> {
+        let mut query =
+            QueryBuilder::new("SELECT COUNT(*) FROM subscriptions WHERE subscriptions.list_id = ");
+        query.push_bind(list_id);
+        query.push(" AND ");
+        push_segment(&mut query, segment);
+        let count: i64 = query
+            .build()
+            .fetch_one(&self.db_pool)
+            .await
+            .map_err(db_error)?
+            .try_get(0)
+            .map_err(db_error)?;
+        Ok(count as u64)
+    }
+}
+
+/// Escapes the wildcards of LIKE patterns.
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
 }
 
 pub fn db_error(err: sqlx::Error) -> CoreError {
@@ -0,0 +1,125 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use zero2prod_core::{
+    domain::{NewTopic, Topic, TopicPreference, TopicSlug},
+    error::{CoreError, CoreResult},
+    repository::TopicRepository,
+};
+
+pub struct TopicRepositoryImpl {
+    db_pool: PgPool,
+}
+
+impl TopicRepositoryImpl {
+    pub fn new(db_pool: PgPool) -> Self {
+        Self { db_pool }
+    }
+}
+
+struct TopicRow {
+    id: Uuid,
+    list_id: Uuid,
+    slug: String,
+    name: String,
+}
+
+impl TryFrom<TopicRow> for Topic {
+    type Error = CoreError;
+
+    fn try_from(row: TopicRow) -> Result<Self, Self::Error> {
+        Ok(Topic {
+            id: row.id,
+            list_id: row.list_id,
+            slug: TopicSlug::parse(row.slug)?,
+            name: row.name,
+        })
+    }
+}
+
+#[async_trait]
+impl TopicRepository for TopicRepositoryImpl {
+    async fn create(&self, list_id: Uuid, new_topic: &NewTopic) -> CoreResult<Uuid> {
+        let topic_id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO topics (id, list_id, slug, name) VALUES ($1, $2, $3, $4)",
+            topic_id,
+            list_id,
+            new_topic.slug.as_ref(),
+            new_topic.name
+        )
+        .execute(&self.db_pool)
+        .await
+        .map_err(|err| match err {
+            sqlx::Error::Database(err) if err.is_unique_violation() => {
+                CoreError::TopicAlreadyExists
+            }
+            err => CoreError::Unexpected(err.to_string()),
+        })?;
+        Ok(topic_id)
+    }
+
+    async fn list(&self, list_id: Uuid) -> CoreResult<Vec<Topic>> {
+        sqlx::query_as!(
+            TopicRow,
+            "SELECT id, list_id, slug, name FROM topics WHERE list_id = $1 ORDER BY slug",
+            list_id
+        )
+        .fetch_all(&self.db_pool)
+        .await?
+        .into_iter()
+        .map(Topic::try_from)
+        .collect()
+    }
+
+    async fn preferences(&self, subscriber_id: Uuid) -> CoreResult<Vec<TopicPreference>> {
+        sqlx::query!(
+            r#"
+            SELECT topics.id, topics.list_id, topics.slug, topics.name,
+                COALESCE(topic_preferences.subscribed, TRUE) AS "subscribed!"
+            FROM subscriptions
+            JOIN topics ON topics.list_id = subscriptions.list_id
+            LEFT JOIN topic_preferences ON topic_preferences.topic_id = topics.id
+                AND topic_preferences.subscriber_id = subscriptions.id
+            WHERE subscriptions.id = $1
+            ORDER BY topics.slug
+            "#,
+            subscriber_id
+        )
+        .fetch_all(&self.db_pool)
+        .await?
+        .into_iter()
+        .map(|row| {
+            Ok(TopicPreference {
+                topic: Topic::try_from(TopicRow {
+                    id: row.id,
+                    list_id: row.list_id,
+                    slug: row.slug,
+                    name: row.name,
+                })?,
+                subscribed: row.subscribed,
+            })
+        })
+        .collect()
+    }
+
+    async fn set_preferences(&self, subscriber_id: Uuid, topic_ids: &[Uuid]) -> CoreResult<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO topic_preferences (subscriber_id, topic_id, subscribed)
+            SELECT subscriptions.id, topics.id, topics.id = ANY($2)
+            FROM subscriptions
+            JOIN topics ON topics.list_id = subscriptions.list_id
+            WHERE subscriptions.id = $1
+            ON CONFLICT (subscriber_id, topic_id) DO UPDATE
+            SET subscribed = EXCLUDED.subscribed, updated_at = now()
+            "#,
+            subscriber_id,
+            topic_ids
+        )
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+}
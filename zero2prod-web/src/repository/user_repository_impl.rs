@@ -0,0 +1,68 @@
+use async_trait::async_trait;
+use secrecy::{ExposeSecret, SecretString};
+use sqlx::types::chrono::Utc;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use zero2prod_core::{
+    domain::{NewUser, User},
+    error::{CoreError, CoreResult},
+    repository::UserRepository,
+};
+
+pub struct UserRepositoryImpl {
+    db_pool: PgPool,
+}
+
+impl UserRepositoryImpl {
+    pub fn new(db_pool: PgPool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl UserRepository for UserRepositoryImpl {
+    async fn create(&self, new_user: &NewUser) -> CoreResult<Uuid> {
+        let user_id = Uuid::new_v4();
+        sqlx::query!(
+            r#"
+            INSERT INTO users (user_id, username, password_hash, created_at)
+            VALUES ($1, $2, $3, $4)
+        "#,
+            user_id,
+            new_user.username,
+            new_user.password_hash.expose_secret(),
+            Utc::now()
+        )
+        .execute(&self.db_pool)
+        .await
+        .map_err(|err| match err {
+            sqlx::Error::Database(err) if err.is_unique_violation() => {
+                CoreError::UsernameAlreadyExists
+            }
+            err => CoreError::Unexpected(err.to_string()),
+        })?;
+
+        Ok(user_id)
+    }
+
+    async fn find_by_username(&self, username: &str) -> CoreResult<Option<User>> {
+        let user = sqlx::query!(
+            r#"
+            SELECT user_id, username, password_hash
+            FROM users
+            WHERE username = $1
+        "#,
+            username
+        )
+        .fetch_optional(&self.db_pool)
+        .await?
+        .map(|row| User {
+            id: row.user_id,
+            username: row.username,
+            password_hash: SecretString::new(row.password_hash),
+        });
+
+        Ok(user)
+    }
+}
@@ -1,23 +1,59 @@
 use std::{
     fmt::{self, Display, Formatter},
+    future::IntoFuture,
+    net::SocketAddr,
     sync::Arc,
+    time::Duration,
 };
 
-use crate::{configuration::Configuration, service::EmailServiceImpl, template::TemplateEngine};
+use crate::{
+    auth::SessionStore,
+    bot_protection::BotProtection,
+    configuration::Configuration,
+    idempotency::IdempotencyStore,
+    metrics::Metrics,
+    repository::{
+        ConsentRepositoryImpl, DataSubjectRepositoryImpl, ListRepositoryImpl,
+        NewsletterIssueRepositoryImpl, SubscriberImportRepositoryImpl, SubscriptionRepositoryImpl,
+        TopicRepositoryImpl, UserRepositoryImpl,
+    },
+    service::{EmailServiceImpl, RateLimiterImpl, SubscriptionLinks},
+    template::TemplateEngine,
+};
 use axum::{
-    routing::{get, post, IntoMakeService},
+    extract::{connect_info::IntoMakeServiceWithConnectInfo, ConnectInfo},
+    middleware::AddExtension,
+    routing::{get, post},
     serve::Serve,
     Extension, Router,
 };
+use hyper::StatusCode;
 use sqlx::{postgres::PgPoolOptions, PgPool};
-use tracing::info;
+use tracing::{info, warn};
+use zero2prod_core::handlers::DeliveryPolicy;
 
 use tokio::net::TcpListener;
 
 use crate::{
     configuration::WithDb,
-    handlers::{health_check, subscribe},
-    layer::TraceIdLayer,
+    handlers::{
+        compose_issue, confirm, count_segment, create_issue, create_list, create_topic, dashboard,
+        delete_issue, delete_subscriber, erase_data, erase_page, export_data, export_subscribers,
+        form_token, get_import, get_issue, get_list, get_log_filter, get_subscriber, health_live,
+        health_ready, import_errors, import_subscribers, issue_opened, issue_page, issues_page,
+        list_issues, list_lists, list_subscribers, list_topics, login, login_page, logout, metrics,
+        new_issue_page, preferences, preview_issue, publish_issue, remove_subscriber,
+        request_data_access, resend_confirmation, save_subscriber, schedule_issue, send_issue,
+        set_log_filter, subscribe, subscriber_page, subscribers_page, unschedule_issue,
+        unsubscribe, unsubscribe_page, update_issue, update_list, update_preferences,
+        update_subscriber,
+    },
+    jobs::{
+        ExpirePendingSubscriptionsJob, JobQueueImpl, JobRunner, PruneFormTokensJob,
+        PruneIdempotencyKeysJob, PruneJobsJob, SendImportConfirmationsJob, SendIssuesJob,
+    },
+    layer::{MetricsLayer, RateLimitLayer, TraceIdLayer},
+    shutdown::{self, Shutdown},
 };
 
 #[derive(Default, Clone)]
@@ -26,7 +62,16 @@ pub struct Address {
     pub port: u16,
 }
 
-pub type Server = Serve<IntoMakeService<Router>, Router>;
+pub type Server = Serve<
+    IntoMakeServiceWithConnectInfo<Router, SocketAddr>,
+    AddExtension<Router, ConnectInfo<SocketAddr>>,
+>;
+
+/// Interval between two evictions of the refilled rate limit buckets.
+const RATE_LIMIT_PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+/// Closing the pool waits for every connection to be returned, which tasks
+/// abandoned by the drain timeout may never do.
+const POOL_CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
 
 impl Display for Address {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
@@ -34,7 +79,91 @@ impl Display for Address {
     }
 }
 
-pub async fn start(configuration: &Configuration) -> (Server, Address, PgPool) {
+/// A started application, it does not serve requests until
+/// [`Application::run_until_stopped`] is awaited.
+pub struct Application {
+    server: Server,
+    address: Address,
+    pool: PgPool,
+    shutdown: Shutdown,
+    drain_timeout: Duration,
+}
+
+impl Application {
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    /// Handle used to stop the application, or to spawn background work that
+    /// must complete before it stops.
+    pub fn shutdown_handle(&self) -> Shutdown {
+        self.shutdown.clone()
+    }
+
+    /// Serves requests until SIGINT/SIGTERM is received or the shutdown handle
+    /// is triggered.
+    ///
+    /// On shutdown, the listener stops accepting connections, then in-flight
+    /// requests and background tasks are given `app.shutdown_timeout`
+    /// milliseconds to complete before the database pool is closed.
+    pub async fn run_until_stopped(self) -> std::io::Result<()> {
+        let Application {
+            server,
+            pool,
+            shutdown,
+            drain_timeout,
+            ..
+        } = self;
+
+        let on_signal = shutdown.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = shutdown::signal() => on_signal.trigger(),
+                _ = on_signal.triggered() => {},
+            }
+        });
+
+        let graceful = shutdown.clone();
+        let server = server
+            .with_graceful_shutdown(async move { graceful.triggered().await })
+            .into_future();
+        tokio::pin!(server);
+
+        let result = tokio::select! {
+            result = &mut server => result,
+            _ = shutdown.triggered() => {
+                info!("Shutting down, draining in-flight requests and background tasks");
+                let drain = async {
+                    let result = (&mut server).await;
+                    shutdown.wait_for_tasks().await;
+                    result
+                };
+                match tokio::time::timeout(drain_timeout, drain).await {
+                    Ok(result) => result,
+                    Err(_) => {
+                        warn!("Drain timeout elapsed, dropping remaining work");
+                        Ok(())
+                    }
+                }
+            }
+        };
+
+        if tokio::time::timeout(POOL_CLOSE_TIMEOUT, pool.close())
+            .await
+            .is_err()
+        {
+            warn!("Database connections still in use, stopping without closing them");
+        }
+        info!("Application stopped");
+        result
+    }
+}
+
+pub async fn start(configuration: &Configuration) -> Application {
     let address = format!("{}:{}", configuration.app.host, configuration.app.port);
     let listener = TcpListener::bind(address)
         .await
@@ -45,26 +174,212 @@ pub async fn start(configuration: &Configuration) -> (Server, Address, PgPool) {
         .acquire_timeout(std::time::Duration::from_millis(configuration.db.timeout))
         .connect_lazy_with(configuration.db.connection_options(WithDb::Yes));
 
+    let shutdown = Shutdown::new();
     let template_engine = Arc::new(TemplateEngine::init());
+    let metrics_registry = Arc::new(Metrics::new());
 
     let email_client = Arc::new(EmailServiceImpl::from_config(
         &configuration.email_client,
         template_engine.clone(),
+        metrics_registry.clone(),
+    ));
+
+    let subscription_repository = Arc::new(SubscriptionRepositoryImpl::new(pool.clone()));
+    let issue_repository = Arc::new(NewsletterIssueRepositoryImpl::new(pool.clone()));
+    let list_repository = Arc::new(ListRepositoryImpl::new(pool.clone()));
+    let topic_repository = Arc::new(TopicRepositoryImpl::new(pool.clone()));
+    let user_repository = Arc::new(UserRepositoryImpl::new(pool.clone()));
+    let subject_repository = Arc::new(DataSubjectRepositoryImpl::new(pool.clone()));
+    let consent_repository = Arc::new(ConsentRepositoryImpl::new(pool.clone()));
+    let links = Arc::new(
+        SubscriptionLinks::from_config(configuration).expect("Invalid token configuration"),
+    );
+    let idempotency_store = Arc::new(IdempotencyStore::new(
+        pool.clone(),
+        Duration::from_secs(configuration.idempotency.ttl),
     ));
 
-    let subscription_repository = Arc::new(crate::repository::SubscriptionRepositoryImpl::new(
+    let sessions = Arc::new(SessionStore::new(
         pool.clone(),
+        Duration::from_secs(configuration.admin.session_ttl),
     ));
 
-    let app = Router::new()
-        .route("/health_check", get(health_check))
-        .route("/subscriptions", post(subscribe))
+    let job_queue = Arc::new(JobQueueImpl::new(
+        pool.clone(),
+        configuration.jobs.max_attempts,
+    ));
+    let import_repository = Arc::new(SubscriberImportRepositoryImpl::new(
+        pool.clone(),
+        job_queue.clone(),
+    ));
+    let bot_protection = Arc::new(BotProtection::from_config(
+        &configuration.bot_protection,
+        pool.clone(),
+    ));
+
+    job_runner(
+        configuration,
+        job_queue.clone(),
+        subscription_repository.clone(),
+        issue_repository.clone(),
+        list_repository.clone(),
+        email_client.clone(),
+        links.clone(),
+        idempotency_store.clone(),
+        bot_protection.clone(),
+        metrics_registry.clone(),
+    )
+    .expect("Invalid job schedule")
+    .start(&shutdown);
+
+    let rate_limit = &configuration.rate_limit;
+    let ip_limiter = Arc::new(RateLimiterImpl::new(
+        "ip",
+        rate_limit.per_ip,
+        rate_limit.storage,
+        pool.clone(),
+    ));
+    let email_limiter = Arc::new(RateLimiterImpl::new(
+        "email",
+        rate_limit.per_email,
+        rate_limit.storage,
+        pool.clone(),
+    ));
+    spawn_rate_limit_pruning(&shutdown, vec![ip_limiter.clone(), email_limiter.clone()]);
+
+    let ip_rate_limit = RateLimitLayer::new(
+        ip_limiter,
+        &rate_limit.trusted_proxies,
+        metrics_registry.clone(),
+    );
+
+    let configuration_extension = Extension(Arc::new(configuration.clone()));
+
+    let admin = Router::new()
+        .route("/metrics", get(metrics))
+        .route("/admin/log-filter", get(get_log_filter).put(set_log_filter))
+        .with_state(pool.clone())
+        .layer(Extension(metrics_registry.clone()))
+        .layer(configuration_extension.clone());
+
+    let mut app = Router::new()
+        .route("/health_check", get(health_live))
+        .route("/health/live", get(health_live))
+        .route("/health/ready", get(health_ready))
+        .route("/subscriptions/form-token", get(form_token))
+        .route("/lists", get(list_lists).post(create_list))
+        .route("/lists/:slug", get(get_list).put(update_list))
+        .route("/lists/:slug/topics", get(list_topics).post(create_topic))
+        .route("/lists/:slug/segments/count", post(count_segment))
+        .route(
+            "/lists/:slug/subscriptions",
+            post(subscribe).layer(ip_rate_limit.clone()),
+        )
+        .route("/subscriptions/confirm", get(confirm))
+        .route(
+            "/subscriptions/confirm/resend",
+            post(resend_confirmation).layer(ip_rate_limit.clone()),
+        )
+        .route(
+            "/subscriptions/unsubscribe",
+            get(unsubscribe_page).post(unsubscribe),
+        )
+        .route("/preferences", get(preferences).post(update_preferences))
+        .route("/subscribers", get(list_subscribers))
+        .route("/subscribers/export", get(export_subscribers))
+        .route("/subscribers/import", post(import_subscribers))
+        .route("/subscribers/import/:import_id", get(get_import))
+        .route("/subscribers/import/:import_id/errors", get(import_errors))
+        .route(
+            "/subscribers/:subscriber_id",
+            get(get_subscriber)
+                .patch(update_subscriber)
+                .delete(delete_subscriber),
+        )
+        .route(
+            "/me",
+            post(request_data_access).layer(ip_rate_limit.clone()),
+        )
+        .route("/me/export", get(export_data))
+        .route("/me/erase", get(erase_page).post(erase_data))
+        .route("/newsletters/issues", get(list_issues).post(create_issue))
+        .route(
+            "/newsletters/issues/:issue_id",
+            get(get_issue).put(update_issue).delete(delete_issue),
+        )
+        .route("/newsletters/issues/:issue_id/preview", get(preview_issue))
+        .route(
+            "/newsletters/issues/:issue_id/schedule",
+            post(schedule_issue).delete(unschedule_issue),
+        )
+        .route("/newsletters/issues/:issue_id/publish", post(publish_issue))
+        .route("/newsletters/issues/:issue_id/opened", get(issue_opened))
+        .route("/admin", get(dashboard))
+        .route(
+            "/admin/login",
+            post(login).layer(ip_rate_limit).get(login_page),
+        )
+        .route("/admin/logout", post(logout))
+        .route("/admin/subscribers", get(subscribers_page))
+        .route(
+            "/admin/subscribers/:subscriber_id",
+            get(subscriber_page).post(save_subscriber),
+        )
+        .route(
+            "/admin/subscribers/:subscriber_id/delete",
+            post(remove_subscriber),
+        )
+        .route("/admin/issues", get(issues_page).post(compose_issue))
+        .route("/admin/issues/new", get(new_issue_page))
+        .route("/admin/issues/:issue_id", get(issue_page))
+        .route("/admin/issues/:issue_id/publish", post(send_issue))
+        .fallback(|| async { StatusCode::NOT_FOUND })
         .with_state(pool.clone())
         .layer(Extension(email_client))
         .layer(Extension(subscription_repository))
-        .layer(Extension(Arc::new(configuration.clone())))
+        .layer(Extension(issue_repository))
+        .layer(Extension(list_repository))
+        .layer(Extension(topic_repository))
+        .layer(Extension(user_repository))
+        .layer(Extension(subject_repository))
+        .layer(Extension(consent_repository))
+        .layer(Extension(import_repository))
+        .layer(Extension(job_queue))
+        .layer(Extension(email_limiter))
+        .layer(Extension(bot_protection))
+        .layer(Extension(links))
+        .layer(Extension(idempotency_store))
+        .layer(Extension(sessions))
+        .layer(Extension(shutdown.clone()))
+        .layer(Extension(template_engine))
+        .layer(configuration_extension)
+        .layer(Extension(metrics_registry.clone()))
+        .layer(MetricsLayer::new(metrics_registry))
         .layer(TraceIdLayer);
 
+    match configuration.app.admin_port {
+        Some(admin_port) => {
+            let admin_address = format!("{}:{}", configuration.app.host, admin_port);
+            let admin_listener = TcpListener::bind(admin_address)
+                .await
+                .expect("Failed to bind admin listener");
+            info!(
+                "admin endpoints listening on {}",
+                admin_listener
+                    .local_addr()
+                    .expect("Failed to get admin listener address")
+            );
+            let admin_shutdown = shutdown.clone();
+            shutdown.spawn(async move {
+                axum::serve(admin_listener, admin.into_make_service())
+                    .with_graceful_shutdown(async move { admin_shutdown.triggered().await })
+                    .await
+                    .expect("Admin server failed")
+            });
+        }
+        None => app = app.merge(admin),
+    }
+
     let app_address = Address {
         host: listener.local_addr().unwrap().ip().to_string(),
         port: listener.local_addr().unwrap().port(),
@@ -75,9 +390,83 @@ pub async fn start(configuration: &Configuration) -> (Server, Address, PgPool) {
         .expect("Failed to get listener address");
 
     info!("listening on {}", addr);
-    (
-        axum::serve(listener, app.into_make_service()),
-        app_address,
+    Application {
+        server: axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        ),
+        address: app_address,
         pool,
-    )
+        shutdown,
+        drain_timeout: Duration::from_millis(configuration.app.shutdown_timeout),
+    }
+}
+
+fn spawn_rate_limit_pruning(shutdown: &Shutdown, limiters: Vec<Arc<RateLimiterImpl>>) {
+    let limiters = Arc::new(limiters);
+    shutdown.spawn_periodic(RATE_LIMIT_PRUNE_INTERVAL, move || {
+        let limiters = limiters.clone();
+        async move {
+            for limiter in limiters.iter() {
+                if let Err(err) = limiter.prune().await {
+                    warn!(
+                        "Failed to prune {} rate limit buckets: {}",
+                        limiter.scope(),
+                        err
+                    );
+                }
+            }
+        }
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn job_runner(
+    configuration: &Configuration,
+    queue: Arc<JobQueueImpl>,
+    subscription_repository: Arc<SubscriptionRepositoryImpl>,
+    issue_repository: Arc<NewsletterIssueRepositoryImpl>,
+    list_repository: Arc<ListRepositoryImpl>,
+    email_client: Arc<EmailServiceImpl>,
+    links: Arc<SubscriptionLinks>,
+    idempotency_store: Arc<IdempotencyStore>,
+    bot_protection: Arc<BotProtection>,
+    metrics: Arc<Metrics>,
+) -> Result<JobRunner, String> {
+    let pending_ttl = Duration::from_secs(configuration.subscriptions.pending_ttl);
+    let retention = Duration::from_secs(configuration.jobs.retention);
+    let delivery_policy = DeliveryPolicy {
+        max_attempts: configuration.newsletter.max_delivery_attempts,
+        interrupted_after: Duration::from_secs(configuration.newsletter.interrupted_after),
+    };
+
+    let runner = JobRunner::new(queue.clone(), &configuration.jobs)
+        .with_metrics(metrics.clone())
+        .register(SendImportConfirmationsJob::new(
+            subscription_repository.clone(),
+            list_repository.clone(),
+            email_client.clone(),
+            links.clone(),
+        ))
+        .register_recurring(
+            ExpirePendingSubscriptionsJob::new(subscription_repository, pending_ttl, metrics),
+            &format!("@every {}s", configuration.subscriptions.expiry_interval),
+        )?
+        .register_recurring(
+            SendIssuesJob::new(
+                issue_repository,
+                list_repository,
+                email_client,
+                links,
+                delivery_policy,
+            ),
+            "* * * * *",
+        )?
+        .register_recurring(PruneJobsJob::new(queue, retention), "30 3 * * *")?
+        .register_recurring(
+            PruneIdempotencyKeysJob::new(idempotency_store),
+            "15 * * * *",
+        )?
+        .register_recurring(PruneFormTokensJob::new(bot_protection), "45 * * * *")?;
+    Ok(runner)
 }
@@ -0,0 +1,106 @@
+mod hcaptcha;
+mod turnstile;
+
+use std::net::IpAddr;
+
+use async_trait::async_trait;
+use reqwest::Client;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use zero2prod_core::error::CoreResult;
+
+use crate::configuration::{CaptchaConfig, CaptchaProvider};
+
+pub use hcaptcha::HCaptchaVerifier;
+pub use turnstile::TurnstileVerifier;
+
+#[async_trait]
+pub trait CaptchaVerifier {
+    /// Checks the response produced by the captcha widget, `remote_ip` is the
+    /// address of the client that solved it when known.
+    async fn verify(&self, response: &str, remote_ip: Option<IpAddr>) -> CoreResult<bool>;
+}
+
+/// The captcha verifier selected by the configuration.
+pub enum CaptchaVerifierImpl {
+    HCaptcha(HCaptchaVerifier),
+    Turnstile(TurnstileVerifier),
+}
+
+impl CaptchaVerifierImpl {
+    pub fn from_config(config: &CaptchaConfig) -> Self {
+        match config.provider {
+            CaptchaProvider::HCaptcha => Self::HCaptcha(HCaptchaVerifier::from_config(config)),
+            CaptchaProvider::Turnstile => Self::Turnstile(TurnstileVerifier::from_config(config)),
+        }
+    }
+}
+
+#[async_trait]
+impl CaptchaVerifier for CaptchaVerifierImpl {
+    async fn verify(&self, response: &str, remote_ip: Option<IpAddr>) -> CoreResult<bool> {
+        match self {
+            Self::HCaptcha(verifier) => verifier.verify(response, remote_ip).await,
+            Self::Turnstile(verifier) => verifier.verify(response, remote_ip).await,
+        }
+    }
+}
+
+/// Verification endpoint shared by hCaptcha and Turnstile, both follow the
+/// `siteverify` protocol of reCAPTCHA.
+struct SiteVerify {
+    http_client: Client,
+    verify_url: String,
+    secret: SecretString,
+}
+
+#[derive(Deserialize)]
+struct SiteVerifyResponse {
+    success: bool,
+    #[serde(default, rename = "error-codes")]
+    error_codes: Vec<String>,
+}
+
+impl SiteVerify {
+    fn new(config: &CaptchaConfig, default_url: &str) -> Self {
+        Self {
+            http_client: Client::builder()
+                .timeout(std::time::Duration::from_millis(config.timeout))
+                .build()
+                .unwrap(),
+            verify_url: config
+                .verify_url
+                .clone()
+                .unwrap_or_else(|| default_url.to_owned()),
+            secret: config.secret.clone(),
+        }
+    }
+
+    async fn verify(&self, response: &str, remote_ip: Option<IpAddr>) -> CoreResult<bool> {
+        let mut form = vec![
+            ("secret", self.secret.expose_secret().to_owned()),
+            ("response", response.to_owned()),
+        ];
+        if let Some(remote_ip) = remote_ip {
+            form.push(("remoteip", remote_ip.to_string()));
+        }
+
+        let verification = self
+            .http_client
+            .post(&self.verify_url)
+            .form(&form)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<SiteVerifyResponse>()
+            .await?;
+
+        if !verification.success {
+            tracing::info!(
+                error_codes = ?verification.error_codes,
+                "Captcha verification failed"
+            );
+        }
+        Ok(verification.success)
+    }
+}
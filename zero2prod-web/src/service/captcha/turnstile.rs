@@ -0,0 +1,29 @@
+use std::net::IpAddr;
+
+use async_trait::async_trait;
+use zero2prod_core::error::CoreResult;
+
+use crate::configuration::CaptchaConfig;
+
+use super::{CaptchaVerifier, SiteVerify};
+
+const VERIFY_URL: &str = "https://challenges.cloudflare.com/turnstile/v0/siteverify";
+
+pub struct TurnstileVerifier {
+    site_verify: SiteVerify,
+}
+
+impl TurnstileVerifier {
+    pub fn from_config(config: &CaptchaConfig) -> Self {
+        Self {
+            site_verify: SiteVerify::new(config, VERIFY_URL),
+        }
+    }
+}
+
+#[async_trait]
+impl CaptchaVerifier for TurnstileVerifier {
+    async fn verify(&self, response: &str, remote_ip: Option<IpAddr>) -> CoreResult<bool> {
+        self.site_verify.verify(response, remote_ip).await
+    }
+}
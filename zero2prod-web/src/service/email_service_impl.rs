@@ -7,20 +7,29 @@ use secrecy::{ExposeSecret, SecretString};
 use serde_json::json;
 use zero2prod_core::{domain::Document, error::CoreResult, service::email_service::EmailService};
 
-use crate::template::TemplateEngine;
+use crate::{
+    layer::{TraceId, REQUEST_ID_HEADER},
+    metrics::Metrics,
+    template::TemplateEngine,
+};
+
+const PROVIDER: &str = "postmark";
 
 pub struct EmailServiceImpl {
     sender: EmailAddress,
     http_client: Client,
     base_url: String,
     auth_token: SecretString,
+    health_check_path: Option<String>,
     template_engine: Arc<TemplateEngine>,
+    metrics: Arc<Metrics>,
 }
 
 impl EmailServiceImpl {
     pub fn from_config(
         config: &crate::configuration::EmailClientConfig,
         template_engine: Arc<TemplateEngine>,
+        metrics: Arc<Metrics>,
     ) -> Self {
         Self {
             sender: config.sender_email.clone(),
@@ -30,9 +39,32 @@ impl EmailServiceImpl {
                 .unwrap(),
             base_url: config.base_url.clone(),
             auth_token: config.auth_token.clone(),
+            health_check_path: config.health_check_path.clone(),
             template_engine,
+            metrics,
         }
     }
+
+    pub fn probe_enabled(&self) -> bool {
+        self.health_check_path.is_some()
+    }
+
+    /// Checks that the email provider is reachable and accepts our credentials.
+    pub async fn probe(&self) -> reqwest::Result<()> {
+        let Some(path) = &self.health_check_path else {
+            return Ok(());
+        };
+
+        self.http_client
+            .get(format!("{}{}", self.base_url, path))
+            .header("Accept", "application/json")
+            .header("X-Postmark-Server-Token", self.auth_token.expose_secret())
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -42,21 +74,45 @@ impl EmailService for EmailServiceImpl {
 
         let html = self.template_engine.render(&document);
 
-        let json = json!({
-            "from": self.sender.as_ref(),
+        let mut json = json!({
+            "from": document.sender.as_ref().unwrap_or(&self.sender).as_str(),
             "to": recipient,
             "subject": document.title,
             "HtmlBody": html
         });
+        if let Some(text) = self.template_engine.render_text(&document) {
+            json["TextBody"] = text.into();
+        }
+        // Lets mail clients unsubscribe in one click, with a POST to the link
+        if let Some(link) = document.kind.unsubscribe_link() {
+            json["Headers"] = json!([
+                { "Name": "List-Unsubscribe", "Value": format!("<{}>", link) },
+                { "Name": "List-Unsubscribe-Post", "Value": "List-Unsubscribe=One-Click" },
+            ]);
+        }
 
-        self.http_client
+        let mut request = self
+            .http_client
             .post(&url)
             .json(&json)
-            .header("X-Postmark-Server-Token", self.auth_token.expose_secret())
+            .header("X-Postmark-Server-Token", self.auth_token.expose_secret());
+
+        if let Some(trace_id) = TraceId::current() {
+            request = request.header(REQUEST_ID_HEADER, trace_id.as_str());
+        }
+
+        let result = request
             .send()
-            .await?
-            .error_for_status()?;
+            .await
+            .and_then(|response| response.error_for_status());
+
+        let outcome = match result {
+            Ok(_) => "sent",
+            Err(_) => "failed",
+        };
+        self.metrics.email(PROVIDER, document.kind.name(), outcome);
 
+        result?;
         Ok(())
     }
 }
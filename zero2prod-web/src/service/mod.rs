@@ -1,3 +1,12 @@
+mod captcha;
 mod email_service_impl;
+mod password;
+mod rate_limiter_impl;
+mod subscription_links;
 
+pub use captcha::{CaptchaVerifier, CaptchaVerifierImpl};
 pub use email_service_impl::EmailServiceImpl;
+pub use password::{hash_password, verify_password};
+pub(crate) use rate_limiter_impl::stored_key;
+pub use rate_limiter_impl::RateLimiterImpl;
+pub use subscription_links::SubscriptionLinks;
@@ -0,0 +1,32 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, SaltString},
+    Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version,
+};
+use secrecy::{ExposeSecret, SecretString};
+use zero2prod_core::error::{CoreError, CoreResult};
+
+fn argon2() -> Argon2<'static> {
+    Argon2::new(
+        Algorithm::Argon2id,
+        Version::V0x13,
+        Params::new(15000, 2, 1, None).expect("Invalid argon2 parameters"),
+    )
+}
+
+/// Hashes a password with Argon2id, the salt and parameters are stored along
+/// with the hash in the PHC string format.
+pub fn hash_password(password: &SecretString) -> CoreResult<SecretString> {
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = argon2()
+        .hash_password(password.expose_secret().as_bytes(), &salt)
+        .map_err(|e| CoreError::Unexpected(e.to_string()))?;
+    Ok(SecretString::new(hash.to_string()))
+}
+
+/// Checks a password against a hash produced by [`hash_password`], the
+/// parameters stored with the hash are used.
+pub fn verify_password(password: &SecretString, hash: &SecretString) -> bool {
+    PasswordHash::new(hash.expose_secret())
+        .and_then(|hash| argon2().verify_password(password.expose_secret().as_bytes(), &hash))
+        .is_ok()
+}
@@ -0,0 +1,263 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use zero2prod_core::{
+    error::{CoreError, CoreResult},
+    service::rate_limiter::RateLimiter,
+};
+
+use crate::configuration::{RateLimitQuota, RateLimitStorage};
+
+/// The key a bucket is stored under in Postgres, keys may be personal data
+/// such as email addresses so only their digest is stored.
+pub(crate) fn stored_key(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+/// Number of buckets kept in memory before the full ones are evicted.
+const MEMORY_PRUNE_THRESHOLD: usize = 10_000;
+
+/// Token bucket rate limiter, every scope (client IP, recipient...) uses its
+/// own limiter.
+pub struct RateLimiterImpl {
+    scope: &'static str,
+    quota: RateLimitQuota,
+    storage: Storage,
+}
+
+enum Storage {
+    Memory(Mutex<HashMap<String, MemoryBucket>>),
+    Postgres(PgPool),
+}
+
+struct MemoryBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl RateLimiterImpl {
+    pub fn new(
+        scope: &'static str,
+        quota: RateLimitQuota,
+        storage: RateLimitStorage,
+        pool: PgPool,
+    ) -> Self {
+        let storage = match storage {
+            RateLimitStorage::Memory => Storage::Memory(Mutex::default()),
+            RateLimitStorage::Postgres => Storage::Postgres(pool),
+        };
+        Self {
+            scope,
+            quota,
+            storage,
+        }
+    }
+
+    pub fn scope(&self) -> &'static str {
+        self.scope
+    }
+
+    /// Forgets the buckets that have been refilled, they are equivalent to
+    /// missing ones.
+    pub async fn prune(&self) -> CoreResult<()> {
+        let period = self.quota.period as f64;
+        match &self.storage {
+            Storage::Memory(buckets) => {
+                let now = Instant::now();
+                buckets.lock().unwrap().retain(|_, bucket| {
+                    now.duration_since(bucket.updated_at).as_secs_f64() < period
+                });
+            }
+            Storage::Postgres(pool) => {
+                sqlx::query!(
+                    r#"
+                    DELETE FROM rate_limit_buckets
+                    WHERE scope = $1 AND updated_at < now() - make_interval(secs => $2)
+                    "#,
+                    self.scope,
+                    period
+                )
+                .execute(pool)
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    fn acquire_in_memory(&self, buckets: &Mutex<HashMap<String, MemoryBucket>>, key: &str) -> Take {
+        let now = Instant::now();
+        let mut buckets = buckets.lock().unwrap();
+        if buckets.len() >= MEMORY_PRUNE_THRESHOLD && !buckets.contains_key(key) {
+            let period = self.quota.period as f64;
+            buckets
+                .retain(|_, bucket| now.duration_since(bucket.updated_at).as_secs_f64() < period);
+        }
+
+        let bucket = buckets.entry(key.to_owned()).or_insert(MemoryBucket {
+            tokens: self.quota.burst as f64,
+            updated_at: now,
+        });
+        let take = take(
+            &self.quota,
+            bucket.tokens,
+            now.duration_since(bucket.updated_at),
+        );
+        bucket.tokens = take.tokens;
+        bucket.updated_at = now;
+        take
+    }
+
+    async fn acquire_in_postgres(&self, pool: &PgPool, key: &str) -> CoreResult<Take> {
+        let key = stored_key(key);
+
+        let mut transaction = pool.begin().await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO rate_limit_buckets (scope, key, tokens, updated_at)
+            VALUES ($1, $2, $3, clock_timestamp())
+            ON CONFLICT (scope, key) DO NOTHING
+            "#,
+            self.scope,
+            key,
+            self.quota.burst as f64
+        )
+        .execute(&mut *transaction)
+        .await?;
+
+        let bucket = sqlx::query!(
+            r#"
+            SELECT tokens, EXTRACT(EPOCH FROM clock_timestamp() - updated_at)::float8 AS "elapsed!"
+            FROM rate_limit_buckets
+            WHERE scope = $1 AND key = $2
+            FOR UPDATE
+            "#,
+            self.scope,
+            key
+        )
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        let elapsed = bucket.elapsed.max(0.0);
+        let take = take(&self.quota, bucket.tokens, Duration::from_secs_f64(elapsed));
+        sqlx::query!(
+            r#"
+            UPDATE rate_limit_buckets
+            SET tokens = $3, updated_at = updated_at + make_interval(secs => $4)
+            WHERE scope = $1 AND key = $2
+            "#,
+            self.scope,
+            key,
+            take.tokens,
+            elapsed
+        )
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+
+        Ok(take)
+    }
+}
+
+#[async_trait]
+impl RateLimiter for RateLimiterImpl {
+    async fn acquire(&self, key: &str) -> CoreResult<()> {
+        let take = match &self.storage {
+            Storage::Memory(buckets) => self.acquire_in_memory(buckets, key),
+            Storage::Postgres(pool) => self.acquire_in_postgres(pool, key).await?,
+        };
+        match take.retry_after {
+            None => Ok(()),
+            Some(retry_after) => Err(CoreError::RateLimited { retry_after }),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Take {
+    /// Tokens left in the bucket.
+    tokens: f64,
+    /// Seconds to wait for a token when the bucket is empty.
+    retry_after: Option<u64>,
+}
+
+/// Refills a bucket holding `tokens` for the time `elapsed` since it was last
+/// updated, then takes a token from it.
+fn take(quota: &RateLimitQuota, tokens: f64, elapsed: Duration) -> Take {
+    let burst = quota.burst as f64;
+    let rate = burst / quota.period.max(1) as f64;
+    let tokens = (tokens + elapsed.as_secs_f64() * rate).min(burst);
+
+    match tokens >= 1.0 {
+        true => Take {
+            tokens: tokens - 1.0,
+            retry_after: None,
+        },
+        false => Take {
+            tokens,
+            retry_after: Some(((1.0 - tokens) / rate).ceil().max(1.0) as u64),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUOTA: RateLimitQuota = RateLimitQuota {
+        burst: 2,
+        period: 60,
+    };
+
+    #[test]
+    fn take_consumes_a_token() {
+        assert_eq!(
+            take(&QUOTA, 2.0, Duration::ZERO),
+            Take {
+                tokens: 1.0,
+                retry_after: None
+            }
+        );
+    }
+
+    #[test]
+    fn take_reports_when_the_next_token_is_available() {
+        assert_eq!(
+            take(&QUOTA, 0.5, Duration::ZERO),
+            Take {
+                tokens: 0.5,
+                retry_after: Some(15)
+            }
+        );
+    }
+
+    #[test]
+    fn take_refills_up_to_the_burst() {
+        assert_eq!(
+            take(&QUOTA, 0.0, Duration::from_secs(3600)),
+            Take {
+                tokens: 1.0,
+                retry_after: None
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn memory_storage_limits_each_key() {
+        let pool = PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        let limiter = RateLimiterImpl::new("test", QUOTA, RateLimitStorage::Memory, pool);
+
+        assert!(limiter.acquire("a").await.is_ok());
+        assert!(limiter.acquire("a").await.is_ok());
+        assert!(matches!(
+            limiter.acquire("a").await,
+            Err(CoreError::RateLimited { retry_after: 30 })
+        ));
+        assert!(limiter.acquire("b").await.is_ok());
+    }
+}
@@ -0,0 +1,166 @@
+use zero2prod_core::domain::{
+    Document, DocumentKind, MailingList, NewsletterIssue, StoredToken, SubscriptionToken,
+    TokenKeys, TokenPurpose,
+};
+
+use crate::{
+    clock::unix_now,
+    configuration::{Configuration, ConfirmationTokens},
+};
+use uuid::Uuid;
+
+/// Builds the links sent to subscribers, they carry signed tokens.
+pub struct SubscriptionLinks {
+    base_url: String,
+    keys: TokenKeys,
+    confirmation_ttl: u64,
+    unsubscribe_ttl: u64,
+    confirmation_tokens: ConfirmationTokens,
+}
+
+impl SubscriptionLinks {
+    pub fn from_config(config: &Configuration) -> Result<Self, String> {
+        let tokens = &config.tokens;
+        if !tokens.keys.contains_key(&tokens.kid) {
+            return Err(format!(
+                "No key for the active key id '{}', set Z2P_TOKENS_KEYS_{}",
+                tokens.kid,
+                tokens.kid.to_uppercase()
+            ));
+        }
+        Ok(Self {
+            base_url: config.app.url.trim_end_matches('/').to_owned(),
+            keys: TokenKeys::new(tokens.kid.clone(), tokens.keys.clone())?,
+            confirmation_ttl: config.tokens.confirmation_ttl,
+            unsubscribe_ttl: config.tokens.unsubscribe_ttl,
+            confirmation_tokens: config.tokens.confirmation,
+        })
+    }
+
+    pub fn keys(&self) -> &TokenKeys {
+        &self.keys
+    }
+
+    /// The confirmation email of a subscriber of `list`. Its link carries
+    /// `stored` or a signed token expiring after the confirmation ttl of the
+    /// list, as configured.
+    pub fn confirmation_email(
+        &self,
+        list: &MailingList,
+        subscriber_id: Uuid,
+        stored: &StoredToken,
+    ) -> Document {
+        let confirmation_link = match self.confirmation_tokens {
+            ConfirmationTokens::Signed => {
+                let ttl = list
+                    .settings
+                    .confirmation_ttl
+                    .unwrap_or(self.confirmation_ttl);
+                self.confirmation_link(subscriber_id, ttl)
+            }
+            ConfirmationTokens::Stored => format!(
+                "{}/subscriptions/confirm?token={}",
+                self.base_url,
+                stored.as_ref()
+            ),
+        };
+        Document::new(
+            format!("Welcome to {} !", list.settings.name),
+            DocumentKind::Confirmation {
+                confirmation_link,
+                unsubscribe_link: self.unsubscribe_link(subscriber_id),
+            },
+        )
+        .for_list(list)
+    }
+
+    /// The issue as sent to one of the subscribers.
+    pub fn issue_email(
+        &self,
+        list: &MailingList,
+        issue: &NewsletterIssue,
+        subscriber_id: Uuid,
+    ) -> Document {
+        Document::new(
+            issue.content.title.clone(),
+            DocumentKind::Issue {
+                html_content: issue.content.html_content.clone(),
+                text_content: issue.content.text_content.clone(),
+                unsubscribe_link: self.unsubscribe_link(subscriber_id),
+                preferences_link: self.preferences_link(subscriber_id),
+                tracking_pixel_link: Some(self.tracking_pixel_link(issue.id, subscriber_id)),
+            },
+        )
+        .for_list(list)
+    }
+
+    /// Lets the owner of the subscriber's email address export or erase
+    /// their data, the links expire after the default confirmation ttl.
+    pub fn data_access_email(&self, subscriber_id: Uuid) -> Document {
+        Document::new(
+            "Your data at zero2prod".into(),
+            DocumentKind::DataAccess {
+                export_link: self.link(
+                    "me/export",
+                    TokenPurpose::Access,
+                    subscriber_id,
+                    self.confirmation_ttl,
+                ),
+                erase_link: self.link(
+                    "me/erase",
+                    TokenPurpose::Access,
+                    subscriber_id,
+                    self.confirmation_ttl,
+                ),
+            },
+        )
+    }
+
+    pub fn confirmation_link(&self, subscriber_id: Uuid, ttl: u64) -> String {
+        self.link(
+            "subscriptions/confirm",
+            TokenPurpose::Confirm,
+            subscriber_id,
+            ttl,
+        )
+    }
+
+    pub fn unsubscribe_link(&self, subscriber_id: Uuid) -> String {
+        self.link(
+            "subscriptions/unsubscribe",
+            TokenPurpose::Unsubscribe,
+            subscriber_id,
+            self.unsubscribe_ttl,
+        )
+    }
+
+    /// Valid as long as the unsubscribe links, both are sent in every issue.
+    pub fn preferences_link(&self, subscriber_id: Uuid) -> String {
+        self.link(
+            "preferences",
+            TokenPurpose::Preferences,
+            subscriber_id,
+            self.unsubscribe_ttl,
+        )
+    }
+
+    /// Records that the subscriber opened the issue when loaded.
+    pub fn tracking_pixel_link(&self, issue_id: Uuid, subscriber_id: Uuid) -> String {
+        self.link(
+            &format!("newsletters/issues/{}/opened", issue_id),
+            TokenPurpose::Open,
+            subscriber_id,
+            self.unsubscribe_ttl,
+        )
+    }
+
+    fn link(&self, path: &str, purpose: TokenPurpose, subscriber_id: Uuid, ttl: u64) -> String {
+        let token = SubscriptionToken::new(purpose, subscriber_id, unix_now() + ttl);
+        format!(
+            "{}/{}?token={}",
+            self.base_url,
+            path,
+            self.keys.sign(&token)
+        )
+    }
+}
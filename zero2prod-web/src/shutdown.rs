@@ -0,0 +1,93 @@
+use std::{future::Future, time::Duration};
+
+use tokio::task::JoinHandle;
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+
+/// Coordinates the graceful shutdown of the application.
+///
+/// Background work must be spawned through [`Shutdown::spawn`] so that the
+/// application waits for it before exiting. Long running workers are expected
+/// to watch [`Shutdown::triggered`] between two items and return once it fires.
+#[derive(Clone, Default)]
+pub struct Shutdown {
+    token: CancellationToken,
+    tasks: TaskTracker,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn trigger(&self) {
+        self.token.cancel();
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Completes once the shutdown has been triggered.
+    pub async fn triggered(&self) {
+        self.token.cancelled().await
+    }
+
+    pub fn spawn<F>(&self, task: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.tasks.spawn(task)
+    }
+
+    /// Runs `task` every `period`, starting one period from now, until the
+    /// shutdown is triggered. A run in progress is completed before stopping.
+    pub fn spawn_periodic<T, F>(&self, period: Duration, mut task: T) -> JoinHandle<()>
+    where
+        T: FnMut() -> F + Send + 'static,
+        F: Future<Output = ()> + Send,
+    {
+        let shutdown = self.clone();
+        self.spawn(async move {
+            let mut interval =
+                tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => task().await,
+                    _ = shutdown.triggered() => break,
+                }
+            }
+        })
+    }
+
+    /// Waits for every task spawned through this handle to complete.
+    pub async fn wait_for_tasks(&self) {
+        self.tasks.close();
+        self.tasks.wait().await
+    }
+}
+
+/// Completes when the process receives SIGINT or SIGTERM.
+pub async fn signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install SIGINT handler")
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
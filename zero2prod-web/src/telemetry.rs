@@ -1,18 +1,147 @@
+use std::fmt::{self, Display, Formatter};
+
+use once_cell::sync::OnceCell;
+use opentelemetry::{trace::TraceError, KeyValue};
+use opentelemetry_otlp::{SpanExporterBuilder, WithExportConfig};
+use opentelemetry_sdk::{
+    runtime,
+    trace::{self, Sampler, Tracer},
+    Resource,
+};
 use tracing_bunyan_formatter::{BunyanFormattingLayer, JsonStorageLayer};
-use tracing_subscriber::{fmt::MakeWriter, layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_subscriber::{
+    fmt::MakeWriter, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Layer,
+    Registry,
+};
+use zero2prod_core::domain::set_redactor;
+
+use crate::configuration::{LogFormat, OtlpConfig, OtlpProtocol, TelemetryConfig};
+
+type FilterHandle = reload::Handle<EnvFilter, Registry>;
+
+/// The subscriber is global to the process, and so is the handle used to change
+/// its filter at runtime.
+static FILTER_HANDLE: OnceCell<FilterHandle> = OnceCell::new();
+
+#[derive(Debug, PartialEq)]
+pub enum FilterError {
+    /// The directives could not be parsed.
+    Invalid(String),
+    /// No reloadable subscriber has been installed.
+    Unavailable,
+}
+
+impl Display for FilterError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterError::Invalid(msg) => write!(f, "Invalid filter: {}", msg),
+            FilterError::Unavailable => write!(f, "Log filter cannot be changed"),
+        }
+    }
+}
 
-pub fn setup_subscriber<Sink>(app_name: &str, env_filter: &str, sink: Sink)
+pub fn setup_subscriber<Sink>(config: &TelemetryConfig, sink: Sink)
 where
     Sink: for<'a> MakeWriter<'a> + Send + Sync + 'static,
 {
+    set_redactor(
+        config
+            .redaction
+            .redactor()
+            .expect("telemetry.redaction.key must be set to hash personal data"),
+    );
+
     let env_filter =
-        tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| env_filter.into());
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| config.filter.as_str().into());
+    let (filter_layer, filter_handle) = reload::Layer::new(env_filter);
+
+    let formatting_layer = match config.format {
+        LogFormat::Json => JsonStorageLayer
+            .and_then(BunyanFormattingLayer::new(
+                config.service_name.clone(),
+                sink,
+            ))
+            .boxed(),
+        LogFormat::Pretty => tracing_subscriber::fmt::layer()
+            .pretty()
+            .with_writer(sink)
+            .boxed(),
+        LogFormat::Compact => tracing_subscriber::fmt::layer()
+            .compact()
+            .with_writer(sink)
+            .boxed(),
+    };
 
-    let formatting_layer = BunyanFormattingLayer::new(app_name.into(), sink);
+    let otlp_layer = config.otlp.as_ref().map(|otlp| {
+        let tracer =
+            otlp_tracer(&config.service_name, otlp).expect("Failed to install OTLP exporter");
+        tracing_opentelemetry::layer().with_tracer(tracer)
+    });
 
     tracing_subscriber::registry()
-        .with(env_filter)
-        .with(JsonStorageLayer)
+        .with(filter_layer)
         .with(formatting_layer)
+        .with(otlp_layer)
         .init();
+
+    FILTER_HANDLE
+        .set(filter_handle)
+        .expect("Subscriber has already been set up");
+}
+
+/// Returns the directives of the filter currently in use.
+pub fn current_filter() -> Result<String, FilterError> {
+    FILTER_HANDLE
+        .get()
+        .ok_or(FilterError::Unavailable)?
+        .with_current(|filter| filter.to_string())
+        .map_err(|_| FilterError::Unavailable)
+}
+
+/// Replaces the filter of the global subscriber, e.g. to turn on debug logs
+/// without restarting the application.
+pub fn set_filter(directives: &str) -> Result<(), FilterError> {
+    let filter = EnvFilter::try_new(directives).map_err(|e| FilterError::Invalid(e.to_string()))?;
+    FILTER_HANDLE
+        .get()
+        .ok_or(FilterError::Unavailable)?
+        .reload(filter)
+        .map_err(|_| FilterError::Unavailable)
+}
+
+/// Builds a tracer exporting spans in batches to an OTLP collector.
+///
+/// The batch processor runs on the tokio runtime, this must therefore be called
+/// from within a runtime.
+pub fn otlp_tracer(service_name: &str, config: &OtlpConfig) -> Result<Tracer, TraceError> {
+    let exporter: SpanExporterBuilder = match config.protocol {
+        OtlpProtocol::Grpc => opentelemetry_otlp::new_exporter()
+            .tonic()
+            .with_endpoint(&config.endpoint)
+            .into(),
+        OtlpProtocol::Http => opentelemetry_otlp::new_exporter()
+            .http()
+            .with_endpoint(&config.endpoint)
+            .into(),
+    };
+
+    opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(exporter)
+        .with_trace_config(
+            trace::config()
+                .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+                    config.sampling_ratio,
+                ))))
+                .with_resource(Resource::new(vec![KeyValue::new(
+                    "service.name",
+                    service_name.to_owned(),
+                )])),
+        )
+        .install_batch(runtime::Tokio)
+}
+
+/// Flushes the spans that have not been exported yet.
+pub fn shutdown() {
+    opentelemetry::global::shutdown_tracer_provider();
 }
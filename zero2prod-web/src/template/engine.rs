@@ -1,17 +1,136 @@
 use std::sync::Arc;
 
 use axum::extract::FromRef;
-use handlebars::Handlebars;
-use zero2prod_core::domain::Document;
+use handlebars::{Handlebars, Template};
+use serde::Serialize;
+use zero2prod_core::domain::{Document, DocumentKind};
 
 pub static CONFIRMATION_HTML: &str = include_str!(concat!(
     env!("CARGO_MANIFEST_DIR"),
     "/src/template/resources/email/confirmation.html"
 ));
 
+pub static ISSUE_HTML: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/template/resources/email/issue.html"
+));
+
+pub static ISSUE_TXT: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/template/resources/email/issue.txt"
+));
+
+pub static DATA_ACCESS_HTML: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/template/resources/email/data_access.html"
+));
+
+pub static DATA_ACCESS_TXT: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/template/resources/email/data_access.txt"
+));
+
+pub static CONFIRMATION_EXPIRED_HTML: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/template/resources/pages/confirmation_expired.html"
+));
+
+pub static PREFERENCES_HTML: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/template/resources/pages/preferences.html"
+));
+
+pub static ERASE_HTML: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/template/resources/pages/erase.html"
+));
+
+pub static UNSUBSCRIBE_HTML: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/template/resources/pages/unsubscribe.html"
+));
+
+pub static ADMIN_LAYOUT_HTML: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/template/resources/pages/admin/layout.html"
+));
+
+pub static ADMIN_LOGIN_HTML: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/template/resources/pages/admin/login.html"
+));
+
+pub static ADMIN_SUBSCRIBERS_HTML: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/template/resources/pages/admin/subscribers.html"
+));
+
+pub static ADMIN_SUBSCRIBER_HTML: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/template/resources/pages/admin/subscriber.html"
+));
+
+pub static ADMIN_ISSUES_HTML: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/template/resources/pages/admin/issues.html"
+));
+
+pub static ADMIN_NEW_ISSUE_HTML: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/template/resources/pages/admin/new_issue.html"
+));
+
+pub static ADMIN_ISSUE_HTML: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/src/template/resources/pages/admin/issue.html"
+));
+
+/// Pages served to browsers.
+#[derive(Debug, Clone, Copy)]
+pub enum Page {
+    ConfirmationExpired,
+    Preferences,
+    Erase,
+    Unsubscribe,
+    AdminLogin,
+    AdminSubscribers,
+    AdminSubscriber,
+    AdminIssues,
+    AdminNewIssue,
+    AdminIssue,
+}
+
+impl Page {
+    fn key(&self) -> &'static str {
+        match self {
+            Page::ConfirmationExpired => "pages/confirmation_expired.html",
+            Page::Preferences => "pages/preferences.html",
+            Page::Erase => "pages/erase.html",
+            Page::Unsubscribe => "pages/unsubscribe.html",
+            Page::AdminLogin => "pages/admin/login.html",
+            Page::AdminSubscribers => "pages/admin/subscribers.html",
+            Page::AdminSubscriber => "pages/admin/subscriber.html",
+            Page::AdminIssues => "pages/admin/issues.html",
+            Page::AdminNewIssue => "pages/admin/new_issue.html",
+            Page::AdminIssue => "pages/admin/issue.html",
+        }
+    }
+}
+
 fn key(document: &Document) -> &'static str {
     match document.kind {
-        zero2prod_core::domain::DocumentKind::Confirmation { .. } => "confirmation.html",
+        DocumentKind::Confirmation { .. } => "confirmation.html",
+        DocumentKind::Issue { .. } => "issue.html",
+        DocumentKind::DataAccess { .. } => "data_access.html",
+    }
+}
+
+/// Documents sent with a plain text alternative.
+fn text_key(document: &Document) -> Option<&'static str> {
+    match document.kind {
+        DocumentKind::Confirmation { .. } => None,
+        DocumentKind::Issue { .. } => Some("issue.txt"),
+        DocumentKind::DataAccess { .. } => Some("data_access.txt"),
     }
 }
 
@@ -26,14 +145,95 @@ impl TemplateEngine {
         engine
             .register_template_string("confirmation.html", CONFIRMATION_HTML)
             .expect("Failed to register confirmation.html template");
+        engine
+            .register_template_string("issue.html", ISSUE_HTML)
+            .expect("Failed to register issue.html template");
+        engine
+            .register_template_string("issue.txt", ISSUE_TXT)
+            .expect("Failed to register issue.txt template");
+        engine
+            .register_template_string("data_access.html", DATA_ACCESS_HTML)
+            .expect("Failed to register data_access.html template");
+        engine
+            .register_template_string("data_access.txt", DATA_ACCESS_TXT)
+            .expect("Failed to register data_access.txt template");
+        engine
+            .register_template_string(Page::ConfirmationExpired.key(), CONFIRMATION_EXPIRED_HTML)
+            .expect("Failed to register confirmation_expired.html template");
+        engine
+            .register_template_string(Page::Preferences.key(), PREFERENCES_HTML)
+            .expect("Failed to register preferences.html template");
+        engine
+            .register_template_string(Page::Erase.key(), ERASE_HTML)
+            .expect("Failed to register erase.html template");
+        engine
+            .register_template_string(Page::Unsubscribe.key(), UNSUBSCRIBE_HTML)
+            .expect("Failed to register unsubscribe.html template");
+        // The pages of the dashboard are rendered within this layout
+        engine
+            .register_partial("admin_layout", ADMIN_LAYOUT_HTML)
+            .expect("Failed to register admin/layout.html partial");
+        engine
+            .register_template_string(Page::AdminLogin.key(), ADMIN_LOGIN_HTML)
+            .expect("Failed to register admin/login.html template");
+        engine
+            .register_template_string(Page::AdminSubscribers.key(), ADMIN_SUBSCRIBERS_HTML)
+            .expect("Failed to register admin/subscribers.html template");
+        engine
+            .register_template_string(Page::AdminSubscriber.key(), ADMIN_SUBSCRIBER_HTML)
+            .expect("Failed to register admin/subscriber.html template");
+        engine
+            .register_template_string(Page::AdminIssues.key(), ADMIN_ISSUES_HTML)
+            .expect("Failed to register admin/issues.html template");
+        engine
+            .register_template_string(Page::AdminNewIssue.key(), ADMIN_NEW_ISSUE_HTML)
+            .expect("Failed to register admin/new_issue.html template");
+        engine
+            .register_template_string(Page::AdminIssue.key(), ADMIN_ISSUE_HTML)
+            .expect("Failed to register admin/issue.html template");
         Self {
             engine: Arc::new(engine),
         }
     }
 
     pub fn render(&self, document: &Document) -> String {
+        self.render_document(document, document.templates.html.as_deref(), key(document))
+    }
+
+    pub fn render_text(&self, document: &Document) -> Option<String> {
+        text_key(document)
+            .map(|key| self.render_document(document, document.templates.text.as_deref(), key))
+    }
+
+    /// Checks that `source` is a valid template, before it is used to render
+    /// documents.
+    pub fn check(source: &str) -> Result<(), String> {
+        Template::compile(source)
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    }
+
+    /// Renders the document with its own template when it has one, falling
+    /// back to the default template `key`.
+    fn render_document(&self, document: &Document, source: Option<&str>, key: &str) -> String {
+        if let Some(source) = source {
+            match self.engine.render_template(source, &document.kind) {
+                Ok(rendered) => return rendered,
+                Err(err) => tracing::error!(
+                    "Failed to render the {} template of a list, using the default one: {}",
+                    document.kind.name(),
+                    err
+                ),
+            }
+        }
         self.engine
-            .render(key(document), &document.kind)
+            .render(key, &document.kind)
             .expect("Failed to render template")
     }
+
+    pub fn render_page<T: Serialize>(&self, page: Page, data: &T) -> String {
+        self.engine
+            .render(page.key(), data)
+            .expect("Failed to render page")
+    }
 }
@@ -1,3 +1,3 @@
 mod engine;
 
-pub use engine::TemplateEngine;
+pub use engine::{Page, TemplateEngine};
@@ -0,0 +1,73 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+use crate::configuration::Configuration;
+
+/// A subscriber inserted straight into the database, confirmed on the
+/// default list unless told otherwise.
+pub struct TestSubscriber {
+    pub list_id: Uuid,
+    pub email: String,
+    pub name: String,
+    pub status: String,
+    pub subscribed_at: DateTime<Utc>,
+}
+
+impl TestSubscriber {
+    pub fn new(email: &str) -> Self {
+        Self {
+            list_id: Uuid::from_u128(1),
+            email: email.into(),
+            name: "John Doe".into(),
+            status: "confirmed".into(),
+            subscribed_at: Utc::now(),
+        }
+    }
+
+    pub fn list(self, list_id: Uuid) -> Self {
+        Self { list_id, ..self }
+    }
+
+    pub fn name(self, name: &str) -> Self {
+        Self {
+            name: name.into(),
+            ..self
+        }
+    }
+
+    pub fn status(self, status: &str) -> Self {
+        Self {
+            status: status.into(),
+            ..self
+        }
+    }
+
+    pub fn subscribed_at(self, subscribed_at: DateTime<Utc>) -> Self {
+        Self {
+            subscribed_at,
+            ..self
+        }
+    }
+}
+
+/// Accepts every email the application sends.
+pub async fn mount_email_provider(email_server: &MockServer) {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(email_server)
+        .await;
+}
+
+/// Sends the scheduled issues as soon as they are due.
+pub fn send_issues_every_second(config: &mut Configuration) {
+    config.jobs.poll_interval = 100;
+    config
+        .jobs
+        .schedules
+        .insert("send-newsletter-issues".into(), "* * * * * *".into());
+}
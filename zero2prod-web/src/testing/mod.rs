@@ -1,2 +1,4 @@
+mod fixtures;
 mod test_app;
-pub use test_app::{run_test, TestApp, TestStack};
+pub use fixtures::{mount_email_provider, send_issues_every_second, TestSubscriber};
+pub use test_app::{run_test, run_test_with, TestApp, TestStack};
@@ -1,23 +1,56 @@
-use std::{future::Future, panic, pin::Pin};
+use std::{
+    collections::HashMap,
+    future::Future,
+    panic,
+    pin::Pin,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
+use super::TestSubscriber;
 use crate::{
-    client::Z2PClient,
-    configuration::{self, Configuration, WithDb},
+    client::{Credentials, Z2PClient},
+    configuration::{
+        self, Configuration, LogFormat, OtlpConfig, RedactionConfig, TelemetryConfig, WithDb,
+    },
+    migration::MIGRATOR,
+    repository::UserRepositoryImpl,
     server::{self, Address},
+    service::hash_password,
+    shutdown::Shutdown,
     telemetry::setup_subscriber,
 };
 
-use once_cell::sync::Lazy;
+use once_cell::sync::OnceCell;
+use secrecy::SecretString;
 use sqlx::{Connection, Executor, PgConnection, PgPool};
+use uuid::Uuid;
 use wiremock::MockServer;
+use zero2prod_core::{
+    domain::{NewUser, RedactionMode, SubscriptionToken, TokenKeys, TokenPurpose},
+    repository::UserRepository,
+};
 
-static TRACING: Lazy<()> = Lazy::new(|| {
+static TRACING: OnceCell<()> = OnceCell::new();
+
+/// Installs the subscriber shared by the tests of a binary, the first app
+/// spawned decides whether spans are exported to `otlp`.
+fn setup_tracing(otlp: Option<OtlpConfig>) {
+    let config = TelemetryConfig {
+        service_name: "integration-test".into(),
+        format: LogFormat::Json,
+        filter: "zero2prod_web::layer::trace_id=trace,debug".into(),
+        otlp,
+        redaction: RedactionConfig {
+            mode: RedactionMode::Hash,
+            key: Some(SecretString::new("redaction-key".into())),
+        },
+    };
     if std::env::var("TEST_LOG").is_ok() {
-        setup_subscriber("integration-test", "debug", std::io::stdout);
+        setup_subscriber(&config, std::io::stdout);
     } else {
-        setup_subscriber("integration-test", "debug", std::io::sink);
+        setup_subscriber(&config, std::io::sink);
     }
-});
+}
 
 pub struct TestStack {
     pub app: TestApp,
@@ -30,27 +63,98 @@ pub struct TestApp {
     pub config: Configuration,
     pub address: Address,
     pub pool: PgPool,
+    pub shutdown: Shutdown,
 }
 
-pub async fn spawn_app() -> (TestApp, Z2PClient, MockServer) {
-    Lazy::force(&TRACING);
+impl TestApp {
+    /// Creates a user able to sign in with `username` and `password`.
+    pub async fn create_user(&self, username: &str, password: &str) -> Uuid {
+        let new_user = NewUser {
+            username: username.into(),
+            password_hash: hash_password(&SecretString::new(password.into())).unwrap(),
+        };
+        UserRepositoryImpl::new(self.pool.clone())
+            .create(&new_user)
+            .await
+            .expect("Failed to create user")
+    }
+
+    /// Creates a user named `username`, returns the credentials to sign in
+    /// with.
+    pub async fn create_editor(&self, username: &str) -> Credentials {
+        let credentials = Credentials {
+            username: username.into(),
+            password: "correct horse battery staple".into(),
+        };
+        self.create_user(&credentials.username, &credentials.password)
+            .await;
+        credentials
+    }
+
+    /// Signs a token valid for a minute with the key the test configuration
+    /// uses by default.
+    pub fn sign_token(&self, purpose: TokenPurpose, subscriber_id: Uuid) -> String {
+        let keys = TokenKeys::new(
+            "test".into(),
+            HashMap::from([("test".to_owned(), self.config.tokens.keys["test"].clone())]),
+        )
+        .unwrap();
+        let expires_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 60;
+        keys.sign(&SubscriptionToken::new(purpose, subscriber_id, expires_at))
+    }
+
+    /// Inserts `subscriber` without going through the subscription form.
+    pub async fn insert_subscriber(&self, subscriber: TestSubscriber) -> Uuid {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            r#"
+            INSERT INTO subscriptions (id, list_id, email, name, subscribed_at, status)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            id,
+            subscriber.list_id,
+            subscriber.email,
+            subscriber.name,
+            subscriber.subscribed_at,
+            subscriber.status
+        )
+        .execute(&self.pool)
+        .await
+        .expect("Failed to insert subscriber");
+        id
+    }
+}
 
+pub async fn spawn_app<C>(configure: C) -> (TestApp, Z2PClient, MockServer)
+where
+    C: FnOnce(&mut Configuration),
+{
     let email_server = MockServer::start().await;
 
     let mut config = configuration::get_test_configuration().expect("Failed to read configuration");
     config.email_client.base_url = email_server.uri();
+    configure(&mut config);
+    TRACING.get_or_init(|| setup_tracing(config.telemetry.otlp.clone()));
 
-    let (server, address, pool) = server::start(&config).await;
+    let application = server::start(&config).await;
     configure_database(&config).await;
 
+    let address = application.address().clone();
+    let pool = application.pool().clone();
+    let shutdown = application.shutdown_handle();
     let client = Z2PClient::new(address.to_string());
 
-    tokio::spawn(async { server.await.unwrap() });
+    tokio::spawn(async { application.run_until_stopped().await.unwrap() });
     (
         TestApp {
             config,
             address,
             pool,
+            shutdown,
         },
         client,
         email_server,
@@ -71,7 +175,7 @@ pub async fn configure_database(configuration: &Configuration) -> (String, PgPoo
         .await
         .expect("Failed to connect to Postgres");
 
-    sqlx::migrate!("../migrations")
+    MIGRATOR
         .run(&db_pool)
         .await
         .expect("Failed to migrate database");
@@ -108,6 +212,15 @@ pub fn run_test<T>(test: T)
 where
     T: panic::UnwindSafe,
     T: FnOnce(TestStack) -> Pin<Box<dyn Future<Output = ()> + 'static + Send>>,
+{
+    run_test_with(|_| {}, test)
+}
+
+pub fn run_test_with<C, T>(configure: C, test: T)
+where
+    C: FnOnce(&mut Configuration) + panic::UnwindSafe,
+    T: panic::UnwindSafe,
+    T: FnOnce(TestStack) -> Pin<Box<dyn Future<Output = ()> + 'static + Send>>,
 {
     let result = std::panic::catch_unwind(|| {
         tokio::runtime::Builder::new_current_thread()
@@ -115,7 +228,7 @@ where
             .build()
             .unwrap()
             .block_on(async {
-                let (test_app, client, email_server) = spawn_app().await;
+                let (test_app, client, email_server) = spawn_app(configure).await;
 
                 test(TestStack {
                     client,
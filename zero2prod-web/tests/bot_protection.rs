@@ -0,0 +1,283 @@
+use reqwest::StatusCode;
+use secrecy::SecretString;
+use serde_json::json;
+use wiremock::{
+    matchers::{body_string_contains, method, path},
+    Mock, ResponseTemplate,
+};
+use zero2prod_macros::integration_test;
+use zero2prod_web::configuration::{CaptchaConfig, CaptchaProvider, Configuration};
+
+const BODY: &str = "name=John%20Doe&email=john.doe@gmail.com";
+
+fn require_form_tokens(config: &mut Configuration) {
+    config.bot_protection.form_secret = Some(SecretString::new("form-secret".into()));
+    config.bot_protection.min_fill_time = 0;
+}
+
+fn require_slow_form_tokens(config: &mut Configuration) {
+    require_form_tokens(config);
+    config.bot_protection.min_fill_time = 60;
+}
+
+fn captcha(provider: CaptchaProvider, config: &mut Configuration) {
+    // The captcha provider is mocked by the same server as the email provider
+    config.bot_protection.captcha = Some(CaptchaConfig {
+        provider,
+        secret: SecretString::new("captcha-secret".into()),
+        verify_url: Some(format!("{}/siteverify", config.email_client.base_url)),
+        timeout: 1000,
+    });
+}
+
+fn require_hcaptcha(config: &mut Configuration) {
+    captcha(CaptchaProvider::HCaptcha, config);
+}
+
+fn require_turnstile(config: &mut Configuration) {
+    captcha(CaptchaProvider::Turnstile, config);
+}
+
+async fn form_token(test_stack: &zero2prod_web::testing::TestStack) -> String {
+    let body: serde_json::Value = test_stack
+        .client
+        .form_token()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    body["token"].as_str().unwrap().to_owned()
+}
+
+#[integration_test]
+fn filled_honeypots_are_rejected_silently(test_stack: TestStack) {
+    Mock::given(path("/email"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&test_stack.email_server)
+        .await;
+
+    let response = test_stack
+        .client
+        .subscribe(
+            "newsletter",
+            format!("{}&website=http%3A%2F%2Fspam.example", BODY),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.text().await.unwrap(),
+        "unable to process the subscription"
+    );
+    let metrics = test_stack
+        .client
+        .metrics()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(metrics.contains(r#"zero2prod_bot_rejections_total{reason="honeypot"} 1"#));
+}
+
+#[integration_test]
+fn form_tokens_are_not_issued_when_disabled(test_stack: TestStack) {
+    let response = test_stack.client.form_token().await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[integration_test(configure = require_form_tokens)]
+fn forms_without_token_are_rejected(test_stack: TestStack) {
+    let response = test_stack
+        .client
+        .subscribe("newsletter", BODY)
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.text().await.unwrap(),
+        "unable to process the subscription"
+    );
+}
+
+#[integration_test(configure = require_form_tokens)]
+fn forms_with_a_valid_token_are_accepted(test_stack: TestStack) {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&test_stack.email_server)
+        .await;
+    let token = form_token(&test_stack).await;
+
+    let response = test_stack
+        .client
+        .subscribe("newsletter", format!("{}&form_token={}", BODY, token))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[integration_test(configure = require_form_tokens)]
+fn reused_form_tokens_are_rejected(test_stack: TestStack) {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&test_stack.email_server)
+        .await;
+    let token = form_token(&test_stack).await;
+
+    let first = test_stack
+        .client
+        .subscribe("newsletter", format!("{}&form_token={}", BODY, token))
+        .await
+        .unwrap();
+    let second = test_stack
+        .client
+        .subscribe(
+            "newsletter",
+            format!(
+                "name=Jane%20Doe&email=jane.doe@gmail.com&form_token={}",
+                token
+            ),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(first.status(), StatusCode::OK);
+    assert_eq!(second.status(), StatusCode::BAD_REQUEST);
+    let metrics = test_stack
+        .client
+        .metrics()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(metrics.contains(r#"zero2prod_bot_rejections_total{reason="reused_form_token"} 1"#));
+}
+
+#[integration_test(configure = require_slow_form_tokens)]
+fn forms_submitted_too_fast_are_rejected(test_stack: TestStack) {
+    let token = form_token(&test_stack).await;
+
+    let response = test_stack
+        .client
+        .subscribe("newsletter", format!("{}&form_token={}", BODY, token))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let metrics = test_stack
+        .client
+        .metrics()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(metrics.contains(r#"zero2prod_bot_rejections_total{reason="too_fast"} 1"#));
+}
+
+#[integration_test(configure = require_hcaptcha)]
+fn hcaptcha_responses_are_verified(test_stack: TestStack) {
+    Mock::given(path("/siteverify"))
+        .and(method("POST"))
+        .and(body_string_contains("secret=captcha-secret"))
+        .and(body_string_contains("response=solved"))
+        .and(body_string_contains("remoteip=127.0.0.1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "success": true })))
+        .expect(1)
+        .mount(&test_stack.email_server)
+        .await;
+    Mock::given(path("/email"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&test_stack.email_server)
+        .await;
+
+    let response = test_stack
+        .client
+        .subscribe("newsletter", format!("{}&h-captcha-response=solved", BODY))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[integration_test(configure = require_turnstile)]
+fn failed_turnstile_challenges_are_rejected(test_stack: TestStack) {
+    Mock::given(path("/siteverify"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "success": false,
+            "error-codes": ["invalid-input-response"]
+        })))
+        .expect(1)
+        .mount(&test_stack.email_server)
+        .await;
+    Mock::given(path("/email"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&test_stack.email_server)
+        .await;
+
+    let response = test_stack
+        .client
+        .subscribe(
+            "newsletter",
+            format!("{}&cf-turnstile-response=forged", BODY),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let metrics = test_stack
+        .client
+        .metrics()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(metrics.contains(r#"zero2prod_bot_rejections_total{reason="failed_captcha"} 1"#));
+}
+
+#[integration_test(configure = require_turnstile)]
+fn forms_without_captcha_are_rejected(test_stack: TestStack) {
+    Mock::given(path("/siteverify"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "success": true })))
+        .expect(0)
+        .mount(&test_stack.email_server)
+        .await;
+
+    let response = test_stack
+        .client
+        .subscribe("newsletter", BODY)
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[integration_test(configure = require_hcaptcha)]
+fn unavailable_captcha_providers_reject_the_form(test_stack: TestStack) {
+    Mock::given(path("/siteverify"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&test_stack.email_server)
+        .await;
+
+    let response = test_stack
+        .client
+        .subscribe("newsletter", format!("{}&h-captcha-response=solved", BODY))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
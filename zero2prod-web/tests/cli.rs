@@ -0,0 +1,126 @@
+use std::process::ExitCode;
+
+use argon2::{Argon2, PasswordHash, PasswordVerifier};
+use wiremock::{
+    matchers::{method, path},
+    Mock, ResponseTemplate,
+};
+use zero2prod_macros::integration_test;
+use zero2prod_web::cli::{
+    create_admin, render_config, send_test_email, CreateAdminArgs, SendTestEmailArgs, TestEmailKind,
+};
+
+#[integration_test]
+fn create_admin_stores_an_argon2_hash(test_stack: TestStack) {
+    let args = CreateAdminArgs {
+        username: "admin".into(),
+        password_stdin: true,
+    };
+
+    let exit_code = create_admin(
+        test_stack.app.config.clone(),
+        args,
+        "correct-horse-battery\n".as_bytes(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+    let saved = sqlx::query!("SELECT username, password_hash FROM users")
+        .fetch_one(&test_stack.app.pool)
+        .await
+        .expect("Failed to fetch saved user.");
+    assert_eq!(saved.username, "admin");
+    let hash = PasswordHash::new(&saved.password_hash).unwrap();
+    assert_eq!(hash.algorithm.as_str(), "argon2id");
+    assert!(Argon2::default()
+        .verify_password(b"correct-horse-battery", &hash)
+        .is_ok());
+    assert!(Argon2::default()
+        .verify_password(b"not-the-password", &hash)
+        .is_err());
+}
+
+#[integration_test]
+fn create_admin_rejects_duplicate_usernames(test_stack: TestStack) {
+    let args = CreateAdminArgs {
+        username: "admin".into(),
+        password_stdin: false,
+    };
+
+    create_admin(
+        test_stack.app.config.clone(),
+        args.clone(),
+        std::io::empty(),
+    )
+    .await
+    .unwrap();
+    let second = create_admin(test_stack.app.config.clone(), args, std::io::empty()).await;
+
+    assert!(second.is_err());
+}
+
+#[integration_test]
+fn create_admin_rejects_invalid_usernames(test_stack: TestStack) {
+    let args = CreateAdminArgs {
+        username: "john doe".into(),
+        password_stdin: false,
+    };
+
+    let exit_code = create_admin(test_stack.app.config.clone(), args, std::io::empty())
+        .await
+        .unwrap();
+
+    assert_eq!(exit_code, ExitCode::FAILURE);
+}
+
+#[integration_test]
+fn send_test_email_uses_the_email_provider(test_stack: TestStack) {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&test_stack.email_server)
+        .await;
+    let args = SendTestEmailArgs {
+        recipient: "john.doe@gmail.com".parse().unwrap(),
+        kind: TestEmailKind::Confirmation,
+        dry_run: false,
+    };
+
+    let exit_code = send_test_email(test_stack.app.config.clone(), args)
+        .await
+        .unwrap();
+
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+}
+
+#[integration_test]
+fn send_test_email_dry_run_does_not_send(test_stack: TestStack) {
+    Mock::given(path("/email"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&test_stack.email_server)
+        .await;
+    let args = SendTestEmailArgs {
+        recipient: "john.doe@gmail.com".parse().unwrap(),
+        kind: TestEmailKind::Confirmation,
+        dry_run: true,
+    };
+
+    let exit_code = send_test_email(test_stack.app.config.clone(), args)
+        .await
+        .unwrap();
+
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+}
+
+#[integration_test]
+fn config_check_redacts_secrets(test_stack: TestStack) {
+    let rendered = render_config(&test_stack.app.config).unwrap();
+
+    assert!(rendered.contains("[REDACTED]"));
+    assert!(!rendered.contains("my-secret-token"));
+    assert!(!rendered.contains("test-admin-token"));
+    assert!(!rendered.contains(": \"password\""));
+}
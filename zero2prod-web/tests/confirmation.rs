@@ -0,0 +1,535 @@
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use chrono::{Duration, Utc};
+use reqwest::StatusCode;
+use secrecy::SecretString;
+use sqlx::PgPool;
+use uuid::Uuid;
+use wiremock::MockServer;
+use zero2prod_core::domain::{SubscriptionToken, TokenKeys, TokenPurpose};
+use zero2prod_macros::integration_test;
+use zero2prod_web::{
+    configuration::{Configuration, ConfirmationTokens},
+    testing::{mount_email_provider, TestApp, TestSubscriber},
+};
+
+const BODY: &str = "name=John%20Doe&email=john.doe@gmail.com";
+
+struct Links {
+    confirmation: String,
+    unsubscribe: String,
+}
+
+fn rotate_keys(config: &mut Configuration) {
+    config
+        .tokens
+        .keys
+        .insert("next".into(), SecretString::new("next-signing-key".into()));
+    config.tokens.kid = "next".into();
+}
+
+fn send_stored_tokens(config: &mut Configuration) {
+    config.tokens.confirmation = ConfirmationTokens::Stored;
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Signs a token with the key the test configuration uses by default.
+fn sign(
+    config: &Configuration,
+    purpose: TokenPurpose,
+    subscriber_id: Uuid,
+    expires_at: u64,
+) -> String {
+    let keys = TokenKeys::new(
+        "test".into(),
+        HashMap::from([("test".to_owned(), config.tokens.keys["test"].clone())]),
+    )
+    .unwrap();
+    keys.sign(&SubscriptionToken::new(purpose, subscriber_id, expires_at))
+}
+
+fn token_after(body: &str, marker: &str) -> String {
+    let start = body.find(marker).expect("Link not found") + marker.len();
+    body[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '.')
+        .collect()
+}
+
+/// Links of the last email sent.
+async fn last_links(email_server: &MockServer) -> Links {
+    let email_request = email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let body: serde_json::Value = email_request.body_json().unwrap();
+    let html = body["HtmlBody"].as_str().unwrap().replace("&#x3D;", "=");
+    Links {
+        confirmation: token_after(&html, "/subscriptions/confirm?token="),
+        unsubscribe: token_after(&html, "/subscriptions/unsubscribe?token="),
+    }
+}
+
+async fn subscription_status(pool: &PgPool) -> String {
+    sqlx::query!("SELECT status FROM subscriptions")
+        .fetch_one(pool)
+        .await
+        .unwrap()
+        .status
+}
+
+async fn subscriber_id(pool: &PgPool) -> Uuid {
+    sqlx::query!("SELECT id FROM subscriptions")
+        .fetch_one(pool)
+        .await
+        .unwrap()
+        .id
+}
+
+#[integration_test]
+fn new_subscriptions_are_pending(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+
+    test_stack
+        .client
+        .subscribe("newsletter", BODY)
+        .await
+        .unwrap();
+
+    assert_eq!(subscription_status(&test_stack.app.pool).await, "pending");
+}
+
+#[integration_test]
+fn the_confirmation_link_confirms_the_subscription(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    test_stack
+        .client
+        .subscribe("newsletter", BODY)
+        .await
+        .unwrap();
+    let links = last_links(&test_stack.email_server).await;
+
+    let first = test_stack
+        .client
+        .confirm(&links.confirmation)
+        .await
+        .unwrap();
+    let second = test_stack
+        .client
+        .confirm(&links.confirmation)
+        .await
+        .unwrap();
+
+    assert_eq!(first.status(), StatusCode::OK);
+    assert_eq!(second.status(), StatusCode::OK);
+    assert_eq!(subscription_status(&test_stack.app.pool).await, "confirmed");
+}
+
+#[integration_test]
+fn forged_confirmation_tokens_are_rejected(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    test_stack
+        .client
+        .subscribe("newsletter", BODY)
+        .await
+        .unwrap();
+    let links = last_links(&test_stack.email_server).await;
+    let forged = links.confirmation.replacen("test.", "test.x", 1);
+
+    let response = test_stack.client.confirm(&forged).await.unwrap();
+    let unsubscribe_token = test_stack.client.confirm(&links.unsubscribe).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(unsubscribe_token.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(subscription_status(&test_stack.app.pool).await, "pending");
+}
+
+#[integration_test]
+fn expired_confirmation_links_offer_to_resend_the_email(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    test_stack
+        .client
+        .subscribe("newsletter", BODY)
+        .await
+        .unwrap();
+    let subscriber_id = subscriber_id(&test_stack.app.pool).await;
+    let expired = sign(
+        &test_stack.app.config,
+        TokenPurpose::Confirm,
+        subscriber_id,
+        unix_now() - 1,
+    );
+
+    let response = test_stack.client.confirm(&expired).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::GONE);
+    let page = response.text().await.unwrap();
+    assert!(page.contains(r#"action="/subscriptions/confirm/resend""#));
+    assert!(page.contains(&expired.replace('=', "&#x3D;")));
+
+    let resend = test_stack
+        .client
+        .resend_confirmation(&expired)
+        .await
+        .unwrap();
+
+    assert_eq!(resend.status(), StatusCode::OK);
+    assert_eq!(
+        test_stack
+            .email_server
+            .received_requests()
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+    let links = last_links(&test_stack.email_server).await;
+    let confirmed = test_stack
+        .client
+        .confirm(&links.confirmation)
+        .await
+        .unwrap();
+    assert_eq!(confirmed.status(), StatusCode::OK);
+    assert_eq!(subscription_status(&test_stack.app.pool).await, "confirmed");
+}
+
+#[integration_test]
+fn confirmation_emails_are_not_resent_once_confirmed(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    test_stack
+        .client
+        .subscribe("newsletter", BODY)
+        .await
+        .unwrap();
+    let links = last_links(&test_stack.email_server).await;
+    test_stack
+        .client
+        .confirm(&links.confirmation)
+        .await
+        .unwrap();
+
+    let resend = test_stack
+        .client
+        .resend_confirmation(&links.confirmation)
+        .await
+        .unwrap();
+
+    assert_eq!(resend.status(), StatusCode::OK);
+    assert_eq!(
+        test_stack
+            .email_server
+            .received_requests()
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[integration_test(configure = rotate_keys)]
+fn tokens_signed_with_a_previous_key_are_accepted(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    test_stack
+        .client
+        .subscribe("newsletter", BODY)
+        .await
+        .unwrap();
+    let links = last_links(&test_stack.email_server).await;
+    let subscriber_id = subscriber_id(&test_stack.app.pool).await;
+    let previous = sign(
+        &test_stack.app.config,
+        TokenPurpose::Confirm,
+        subscriber_id,
+        unix_now() + 60,
+    );
+
+    let response = test_stack.client.confirm(&previous).await.unwrap();
+
+    assert!(links.confirmation.starts_with("next."));
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[integration_test]
+fn the_unsubscribe_link_ends_the_subscription(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    test_stack
+        .client
+        .subscribe("newsletter", BODY)
+        .await
+        .unwrap();
+    let links = last_links(&test_stack.email_server).await;
+    test_stack
+        .client
+        .confirm(&links.confirmation)
+        .await
+        .unwrap();
+
+    let response = test_stack
+        .client
+        .unsubscribe(&links.unsubscribe)
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        subscription_status(&test_stack.app.pool).await,
+        "unsubscribed"
+    );
+    let confirm_again = test_stack
+        .client
+        .confirm(&links.confirmation)
+        .await
+        .unwrap();
+    assert_eq!(confirm_again.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[integration_test(configure = send_stored_tokens)]
+fn stored_confirmation_tokens_confirm_the_subscription(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    test_stack
+        .client
+        .subscribe("newsletter", BODY)
+        .await
+        .unwrap();
+    let links = last_links(&test_stack.email_server).await;
+    let stored = sqlx::query!("SELECT subscription_token FROM subscription_tokens")
+        .fetch_one(&test_stack.app.pool)
+        .await
+        .unwrap();
+
+    let response = test_stack
+        .client
+        .confirm(&links.confirmation)
+        .await
+        .unwrap();
+
+    assert_eq!(links.confirmation, stored.subscription_token);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(subscription_status(&test_stack.app.pool).await, "confirmed");
+}
+
+#[integration_test]
+fn stored_tokens_are_accepted_when_signed_tokens_are_sent(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    test_stack
+        .client
+        .subscribe("newsletter", BODY)
+        .await
+        .unwrap();
+    let stored = sqlx::query!("SELECT subscription_token FROM subscription_tokens")
+        .fetch_one(&test_stack.app.pool)
+        .await
+        .unwrap();
+
+    let response = test_stack
+        .client
+        .confirm(&stored.subscription_token)
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(subscription_status(&test_stack.app.pool).await, "confirmed");
+}
+
+#[integration_test]
+fn following_the_unsubscribe_link_asks_for_a_confirmation(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    test_stack
+        .client
+        .subscribe("newsletter", BODY)
+        .await
+        .unwrap();
+    let links = last_links(&test_stack.email_server).await;
+
+    let response = test_stack
+        .client
+        .unsubscribe_page(&links.unsubscribe)
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.text().await.unwrap().contains("method=\"post\""));
+    assert_eq!(subscription_status(&test_stack.app.pool).await, "pending");
+}
+
+#[integration_test]
+fn emails_offer_to_unsubscribe_in_one_click(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    test_stack
+        .client
+        .subscribe("newsletter", BODY)
+        .await
+        .unwrap();
+
+    let email_request = &test_stack.email_server.received_requests().await.unwrap()[0];
+    let body: serde_json::Value = email_request.body_json().unwrap();
+    let links = last_links(&test_stack.email_server).await;
+    let headers = body["Headers"].as_array().unwrap();
+    assert!(headers[0]["Value"].as_str().unwrap().contains(&format!(
+        "/subscriptions/unsubscribe?token={}",
+        links.unsubscribe
+    )));
+    assert_eq!(headers[1]["Name"], "List-Unsubscribe-Post");
+    assert_eq!(headers[1]["Value"], "List-Unsubscribe=One-Click");
+}
+
+#[integration_test]
+fn unsubscribed_subscribers_can_subscribe_again(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    test_stack
+        .client
+        .subscribe("newsletter", BODY)
+        .await
+        .unwrap();
+    let links = last_links(&test_stack.email_server).await;
+    test_stack
+        .client
+        .unsubscribe(&links.unsubscribe)
+        .await
+        .unwrap();
+
+    let response = test_stack
+        .client
+        .subscribe("newsletter", BODY)
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(subscription_status(&test_stack.app.pool).await, "pending");
+}
+
+#[integration_test]
+fn pending_subscribers_get_a_new_confirmation_email_when_subscribing_again(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    test_stack
+        .client
+        .subscribe("newsletter", BODY)
+        .await
+        .unwrap();
+    let first = sqlx::query!("SELECT id, subscribed_at FROM subscriptions")
+        .fetch_one(&test_stack.app.pool)
+        .await
+        .unwrap();
+
+    let response = test_stack
+        .client
+        .subscribe("newsletter", "name=Someone%20Else&email=john.doe@gmail.com")
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let emails = test_stack.email_server.received_requests().await.unwrap();
+    assert_eq!(emails.len(), 2);
+    let pending = sqlx::query!("SELECT id, name, subscribed_at FROM subscriptions")
+        .fetch_one(&test_stack.app.pool)
+        .await
+        .unwrap();
+    assert_eq!(pending.id, first.id);
+    assert_eq!(pending.name, "John Doe");
+    assert_eq!(pending.subscribed_at, first.subscribed_at);
+
+    let links = last_links(&test_stack.email_server).await;
+    let confirmed = test_stack
+        .client
+        .confirm(&links.confirmation)
+        .await
+        .unwrap();
+    assert_eq!(confirmed.status(), StatusCode::OK);
+}
+
+#[integration_test]
+fn confirmed_subscribers_cannot_subscribe_again(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    test_stack
+        .client
+        .subscribe("newsletter", BODY)
+        .await
+        .unwrap();
+    let links = last_links(&test_stack.email_server).await;
+    test_stack
+        .client
+        .confirm(&links.confirmation)
+        .await
+        .unwrap();
+
+    let response = test_stack
+        .client
+        .subscribe("newsletter", BODY)
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let emails = test_stack.email_server.received_requests().await.unwrap();
+    assert_eq!(emails.len(), 1);
+}
+
+fn expire_pending_every_second(config: &mut Configuration) {
+    config.subscriptions.pending_ttl = 3600;
+    config.subscriptions.expiry_interval = 1;
+    config.jobs.poll_interval = 100;
+}
+
+/// Inserts a subscriber who subscribed `age_in_hours` ago, along with a
+/// stored confirmation token.
+async fn insert_subscriber(app: &TestApp, email: &str, status: &str, age_in_hours: i64) -> Uuid {
+    let subscriber = TestSubscriber::new(email)
+        .status(status)
+        .subscribed_at(Utc::now() - Duration::hours(age_in_hours));
+    let id = app.insert_subscriber(subscriber).await;
+    sqlx::query!(
+        "INSERT INTO subscription_tokens (subscription_token, subscriber_id) VALUES ($1, $2)",
+        id.simple().to_string(),
+        id
+    )
+    .execute(&app.pool)
+    .await
+    .unwrap();
+    id
+}
+
+#[integration_test(configure = expire_pending_every_second)]
+fn stale_pending_subscriptions_expire(test_stack: TestStack) {
+    let pool = &test_stack.app.pool;
+    let stale = insert_subscriber(&test_stack.app, "stale@gmail.com", "pending", 2).await;
+    let fresh = insert_subscriber(&test_stack.app, "fresh@gmail.com", "pending", 0).await;
+    let confirmed = insert_subscriber(&test_stack.app, "confirmed@gmail.com", "confirmed", 2).await;
+
+    let mut remaining = Vec::new();
+    for _ in 0..100 {
+        remaining = sqlx::query!("SELECT id FROM subscriptions")
+            .fetch_all(pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|row| row.id)
+            .collect::<Vec<_>>();
+        if !remaining.contains(&stale) {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+
+    assert!(!remaining.contains(&stale));
+    assert!(remaining.contains(&fresh));
+    assert!(remaining.contains(&confirmed));
+    let stale_tokens = sqlx::query!(
+        "SELECT COUNT(*) AS \"count!\" FROM subscription_tokens WHERE subscriber_id = $1",
+        stale
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap()
+    .count;
+    assert_eq!(stale_tokens, 0);
+}
@@ -0,0 +1,271 @@
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use wiremock::MockServer;
+use zero2prod_core::domain::{EmailHash, TokenPurpose};
+use zero2prod_macros::integration_test;
+use zero2prod_web::{
+    configuration::Configuration,
+    testing::{mount_email_provider, TestStack, TestSubscriber},
+};
+
+const BODY: &str = "name=John%20Doe&email=john.doe@gmail.com";
+const USER_AGENT: &str = "Mozilla/5.0 (X11; Linux x86_64)";
+
+struct ConsentRow {
+    action: String,
+    source: String,
+    ip: Option<String>,
+    user_agent: Option<String>,
+    policy_version: Option<String>,
+    topics: Vec<String>,
+}
+
+fn with_policy(config: &mut Configuration) {
+    config.subscriptions.policy_version = Some("2026-10".into());
+}
+
+fn with_hashed_client_info(config: &mut Configuration) {
+    config.subscriptions.hash_client_info = true;
+}
+
+async fn confirmation_token(email_server: &MockServer) -> String {
+    let email_request = email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let body: Value = email_request.body_json().unwrap();
+    let html = body["HtmlBody"].as_str().unwrap().replace("&#x3D;", "=");
+    let marker = "/subscriptions/confirm?token=";
+    let start = html.find(marker).expect("Link not found") + marker.len();
+    html[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '.')
+        .collect()
+}
+
+/// Subscribes John from a browser, as identified by its user agent.
+async fn subscribe_from_browser(test_stack: &TestStack) {
+    let response = reqwest::Client::new()
+        .post(format!(
+            "{}/lists/newsletter/subscriptions",
+            test_stack.app.address
+        ))
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .header("User-Agent", USER_AGENT)
+        .body(BODY)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+async fn consents(pool: &PgPool) -> Vec<ConsentRow> {
+    sqlx::query_as!(
+        ConsentRow,
+        r#"
+        SELECT action, source, ip, user_agent, policy_version, topics
+        FROM consent_events ORDER BY occurred_at
+        "#
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+#[integration_test(configure = with_policy)]
+fn subscribing_and_confirming_are_recorded(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    subscribe_from_browser(&test_stack).await;
+    let token = confirmation_token(&test_stack.email_server).await;
+
+    test_stack.client.confirm(&token).await.unwrap();
+
+    let consents = consents(&test_stack.app.pool).await;
+    let actions = consents
+        .iter()
+        .map(|consent| consent.action.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(actions, ["subscribe", "confirm"]);
+    let subscribe = &consents[0];
+    assert_eq!(subscribe.source, "form");
+    assert_eq!(subscribe.ip.as_deref(), Some("127.0.0.1"));
+    assert_eq!(subscribe.user_agent.as_deref(), Some(USER_AGENT));
+    assert_eq!(subscribe.policy_version.as_deref(), Some("2026-10"));
+}
+
+#[integration_test]
+fn confirming_twice_is_recorded_once(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    test_stack
+        .client
+        .subscribe("newsletter", BODY)
+        .await
+        .unwrap();
+    let token = confirmation_token(&test_stack.email_server).await;
+
+    test_stack.client.confirm(&token).await.unwrap();
+    test_stack.client.confirm(&token).await.unwrap();
+
+    let consents = consents(&test_stack.app.pool).await;
+    assert_eq!(consents.len(), 2);
+    assert!(consents[1].policy_version.is_none());
+}
+
+#[integration_test]
+fn consents_outlive_deleted_subscribers(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    let credentials = test_stack.app.create_editor("editor").await;
+    test_stack
+        .client
+        .subscribe("newsletter", BODY)
+        .await
+        .unwrap();
+    let pool = &test_stack.app.pool;
+    let subscriber = sqlx::query!("SELECT id, list_id FROM subscriptions")
+        .fetch_one(pool)
+        .await
+        .unwrap();
+
+    let response = test_stack
+        .client
+        .delete_subscriber(&credentials, subscriber.id)
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let consent =
+        sqlx::query!("SELECT subscriber_id, list_id, email_hash, action FROM consent_events")
+            .fetch_one(pool)
+            .await
+            .unwrap();
+    assert_eq!(consent.subscriber_id, subscriber.id);
+    assert_eq!(consent.list_id, subscriber.list_id);
+    assert_eq!(
+        consent.email_hash,
+        EmailHash::of(&"john.doe@gmail.com".parse().unwrap()).as_ref()
+    );
+    assert_eq!(consent.action, "subscribe");
+}
+
+#[integration_test(configure = with_hashed_client_info)]
+fn client_info_can_be_hashed(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+
+    subscribe_from_browser(&test_stack).await;
+
+    let consents = consents(&test_stack.app.pool).await;
+    let ip = consents[0].ip.as_deref().unwrap();
+    let user_agent = consents[0].user_agent.as_deref().unwrap();
+    assert_eq!(ip.len(), 64);
+    assert_ne!(ip, "127.0.0.1");
+    assert_eq!(user_agent.len(), 64);
+    assert_ne!(user_agent, USER_AGENT);
+}
+
+#[integration_test]
+fn unsubscribing_is_recorded(test_stack: TestStack) {
+    let subscriber_id = test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("john.doe@gmail.com"))
+        .await;
+    let token = test_stack
+        .app
+        .sign_token(TokenPurpose::Unsubscribe, subscriber_id);
+
+    test_stack.client.unsubscribe(&token).await.unwrap();
+
+    let consents = consents(&test_stack.app.pool).await;
+    assert_eq!(consents.len(), 1);
+    assert_eq!(consents[0].action, "unsubscribe");
+}
+
+#[integration_test]
+fn preference_changes_are_recorded_with_their_topics(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    let response = test_stack
+        .client
+        .create_topic(
+            &credentials,
+            "newsletter",
+            &json!({"slug": "releases", "name": "Releases"}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let subscriber_id = test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("john.doe@gmail.com"))
+        .await;
+    let token = test_stack
+        .app
+        .sign_token(TokenPurpose::Preferences, subscriber_id);
+
+    test_stack
+        .client
+        .update_preferences(&token, &["releases"])
+        .await
+        .unwrap();
+
+    let consents = consents(&test_stack.app.pool).await;
+    assert_eq!(consents.len(), 1);
+    assert_eq!(consents[0].action, "preferences");
+    assert_eq!(consents[0].topics, ["releases"]);
+}
+
+#[integration_test]
+fn consent_events_cannot_be_changed(test_stack: TestStack) {
+    let pool = &test_stack.app.pool;
+    let subscriber_id = test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("john.doe@gmail.com"))
+        .await;
+    let token = test_stack
+        .app
+        .sign_token(TokenPurpose::Unsubscribe, subscriber_id);
+    test_stack.client.unsubscribe(&token).await.unwrap();
+
+    let result = sqlx::query!("UPDATE consent_events SET action = 'subscribe'")
+        .execute(pool)
+        .await;
+
+    assert!(result.is_err());
+    assert_eq!(consents(pool).await[0].action, "unsubscribe");
+}
+
+#[integration_test]
+fn exports_include_the_consents(test_stack: TestStack) {
+    let subscriber_id = test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("john.doe@gmail.com"))
+        .await;
+    test_stack
+        .client
+        .unsubscribe(
+            &test_stack
+                .app
+                .sign_token(TokenPurpose::Unsubscribe, subscriber_id),
+        )
+        .await
+        .unwrap();
+
+    let data: Value = test_stack
+        .client
+        .export_data(
+            &test_stack
+                .app
+                .sign_token(TokenPurpose::Access, subscriber_id),
+        )
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    let consent = &data["subscriptions"][0]["consents"][0];
+    assert_eq!(consent["action"], "unsubscribe");
+    assert_eq!(consent["source"], "form");
+    assert_eq!(consent["topics"], json!([]));
+}
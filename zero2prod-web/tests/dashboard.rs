@@ -0,0 +1,461 @@
+use reqwest::{Response, StatusCode};
+use serde_json::json;
+use zero2prod_macros::integration_test;
+use zero2prod_web::{
+    client::Credentials,
+    testing::{TestStack, TestSubscriber},
+};
+
+/// The value of the cookie the response sets.
+fn set_cookie(response: &Response, name: &str) -> Option<String> {
+    response
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .find_map(|value| value.strip_prefix(&format!("{}=", name)))
+        .map(|value| value.split(';').next().unwrap().to_owned())
+}
+
+/// The value of the hidden `field` of the first form of the page.
+fn hidden_field(html: &str, field: &str) -> String {
+    let prefix = format!("name=\"{}\" value=\"", field);
+    let start = html.find(&prefix).unwrap() + prefix.len();
+    html[start..].split('"').next().unwrap().to_owned()
+}
+
+fn location(response: &Response) -> &str {
+    response.headers()["location"].to_str().unwrap()
+}
+
+async fn sign_in_with(test_stack: &TestStack, username: &str, password: &str) -> Response {
+    let page = test_stack
+        .client
+        .dashboard_page("/admin/login", "")
+        .await
+        .unwrap();
+    let login_cookie = set_cookie(&page, "z2p_login").unwrap();
+    let login_token = hidden_field(&page.text().await.unwrap(), "login_token");
+
+    test_stack
+        .client
+        .dashboard_form(
+            "/admin/login",
+            &format!("z2p_login={}", login_cookie),
+            &[
+                ("login_token", &login_token),
+                ("username", username),
+                ("password", password),
+            ],
+        )
+        .await
+        .unwrap()
+}
+
+/// Signs the editor in, returns their session cookie and CSRF token.
+async fn sign_in(test_stack: &TestStack, credentials: &Credentials) -> (String, String) {
+    let response = sign_in_with(test_stack, &credentials.username, &credentials.password).await;
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    let cookies = format!(
+        "z2p_session={}",
+        set_cookie(&response, "z2p_session").unwrap()
+    );
+
+    let page = test_stack
+        .client
+        .dashboard_page("/admin/subscribers", &cookies)
+        .await
+        .unwrap();
+    let csrf_token = hidden_field(&page.text().await.unwrap(), "csrf_token");
+    (cookies, csrf_token)
+}
+
+#[integration_test]
+fn editors_sign_in_with_their_password(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+
+    let response = sign_in_with(&test_stack, &credentials.username, &credentials.password).await;
+
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_eq!(location(&response), "/admin/subscribers");
+    let session_cookie = response
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .map(|value| value.to_str().unwrap())
+        .find(|value| value.starts_with("z2p_session="))
+        .unwrap();
+    assert!(session_cookie.contains("; Path=/admin;"));
+    assert!(session_cookie.contains("; HttpOnly; SameSite=Strict"));
+    let token = set_cookie(&response, "z2p_session").unwrap();
+    let page = test_stack
+        .client
+        .dashboard_page("/admin/subscribers", &format!("z2p_session={}", token))
+        .await
+        .unwrap();
+    assert_eq!(page.status(), StatusCode::OK);
+    assert!(page.text().await.unwrap().contains("<h1>Subscribers</h1>"));
+    // Only the digest of the token is stored
+    let stored = sqlx::query!("SELECT id FROM admin_sessions")
+        .fetch_one(&test_stack.app.pool)
+        .await
+        .unwrap();
+    assert_ne!(stored.id, token);
+}
+
+#[integration_test]
+fn invalid_credentials_are_rejected(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+
+    let response = sign_in_with(&test_stack, &credentials.username, "wrong").await;
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(set_cookie(&response, "z2p_session"), None);
+    let page = response.text().await.unwrap();
+    assert!(page.contains("Invalid username or password"));
+    assert!(page.contains("value=\"editor\""));
+}
+
+#[integration_test]
+fn login_forms_must_match_their_cookie(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+
+    let response = test_stack
+        .client
+        .dashboard_form(
+            "/admin/login",
+            "z2p_login=forged",
+            &[
+                ("login_token", "other"),
+                ("username", &credentials.username),
+                ("password", &credentials.password),
+            ],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(set_cookie(&response, "z2p_session"), None);
+}
+
+#[integration_test]
+fn pages_redirect_to_the_login_page_without_a_session(test_stack: TestStack) {
+    let id = test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("john.doe@gmail.com").status("pending"))
+        .await;
+
+    for cookies in ["", "z2p_session=unknown"] {
+        for path in [
+            "/admin".to_owned(),
+            "/admin/subscribers".to_owned(),
+            format!("/admin/subscribers/{}", id),
+            "/admin/issues".to_owned(),
+            "/admin/issues/new".to_owned(),
+        ] {
+            let response = test_stack
+                .client
+                .dashboard_page(&path, cookies)
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::SEE_OTHER, "{}", path);
+            assert_eq!(location(&response), "/admin/login");
+        }
+    }
+}
+
+#[integration_test]
+fn forms_without_the_csrf_token_are_rejected(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    let (cookies, _) = sign_in(&test_stack, &credentials).await;
+    let id = test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("john.doe@gmail.com").status("pending"))
+        .await;
+
+    let response = test_stack
+        .client
+        .dashboard_form(
+            &format!("/admin/subscribers/{}/delete", id),
+            &cookies,
+            &[("csrf_token", "forged")],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let remaining = sqlx::query!("SELECT count(*) AS \"count!\" FROM subscriptions")
+        .fetch_one(&test_stack.app.pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining.count, 1);
+}
+
+#[integration_test]
+fn subscribers_are_listed_and_searched(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    let (cookies, _) = sign_in(&test_stack, &credentials).await;
+    test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("john.doe@gmail.com").status("pending"))
+        .await;
+    test_stack
+        .app
+        .insert_subscriber(
+            TestSubscriber::new("jane.roe@gmail.com")
+                .name("Jane Roe")
+                .status("pending"),
+        )
+        .await;
+
+    let all = test_stack
+        .client
+        .dashboard_page("/admin/subscribers", &cookies)
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    let searched = test_stack
+        .client
+        .dashboard_page("/admin/subscribers?search=jane&status=", &cookies)
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    assert!(all.contains("john.doe@gmail.com"));
+    assert!(all.contains("jane.roe@gmail.com"));
+    assert!(!searched.contains("john.doe@gmail.com"));
+    assert!(searched.contains("jane.roe@gmail.com"));
+}
+
+#[integration_test]
+fn subscribers_can_be_edited(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    let (cookies, csrf_token) = sign_in(&test_stack, &credentials).await;
+    for (slug, name) in [("releases", "Releases"), ("blog", "Blog posts")] {
+        test_stack
+            .client
+            .create_topic(
+                &credentials,
+                "newsletter",
+                &json!({"slug": slug, "name": name}),
+            )
+            .await
+            .unwrap();
+    }
+    let id = test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("john.doe@gmail.com").status("pending"))
+        .await;
+    let path = format!("/admin/subscribers/{}", id);
+
+    let page = test_stack
+        .client
+        .dashboard_page(&path, &cookies)
+        .await
+        .unwrap();
+    let saved = test_stack
+        .client
+        .dashboard_form(
+            &path,
+            &cookies,
+            &[
+                ("csrf_token", &csrf_token),
+                ("name", "Johnny Doe"),
+                ("status", "confirmed"),
+                ("topics", "blog"),
+            ],
+        )
+        .await
+        .unwrap();
+    let invalid = test_stack
+        .client
+        .dashboard_form(
+            &path,
+            &cookies,
+            &[
+                ("csrf_token", &csrf_token),
+                ("name", "<script>"),
+                ("status", "confirmed"),
+            ],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(page.status(), StatusCode::OK);
+    let page = page.text().await.unwrap();
+    assert!(page.contains("john.doe@gmail.com"));
+    assert!(page.contains("value=\"releases\" checked"));
+    assert_eq!(saved.status(), StatusCode::SEE_OTHER);
+    assert_eq!(location(&saved), path);
+    assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+    assert!(invalid.text().await.unwrap().contains("class=\"error\""));
+    let subscriber = sqlx::query!("SELECT name, status FROM subscriptions WHERE id = $1", id)
+        .fetch_one(&test_stack.app.pool)
+        .await
+        .unwrap();
+    assert_eq!(subscriber.name, "Johnny Doe");
+    assert_eq!(subscriber.status, "confirmed");
+    let unsubscribed = sqlx::query!(
+        r#"
+        SELECT topics.slug FROM topic_preferences
+        JOIN topics ON topics.id = topic_preferences.topic_id
+        WHERE subscriber_id = $1 AND NOT subscribed
+        "#,
+        id
+    )
+    .fetch_all(&test_stack.app.pool)
+    .await
+    .unwrap();
+    assert_eq!(unsubscribed.len(), 1);
+    assert_eq!(unsubscribed[0].slug, "releases");
+}
+
+#[integration_test]
+fn subscribers_can_be_deleted(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    let (cookies, csrf_token) = sign_in(&test_stack, &credentials).await;
+    let id = test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("john.doe@gmail.com").status("pending"))
+        .await;
+
+    let response = test_stack
+        .client
+        .dashboard_form(
+            &format!("/admin/subscribers/{}/delete", id),
+            &cookies,
+            &[("csrf_token", &csrf_token)],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_eq!(location(&response), "/admin/subscribers");
+    let remaining = sqlx::query!("SELECT count(*) AS \"count!\" FROM subscriptions")
+        .fetch_one(&test_stack.app.pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining.count, 0);
+}
+
+#[integration_test]
+fn issues_are_composed_previewed_and_sent(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    let (cookies, csrf_token) = sign_in(&test_stack, &credentials).await;
+
+    let form = test_stack
+        .client
+        .dashboard_page("/admin/issues/new", &cookies)
+        .await
+        .unwrap();
+    let created = test_stack
+        .client
+        .dashboard_form(
+            "/admin/issues",
+            &cookies,
+            &[
+                ("csrf_token", &csrf_token),
+                ("list", "newsletter"),
+                ("title", "Our first issue"),
+                ("html_content", "<p>Hello there</p>"),
+                ("text_content", "Hello there"),
+            ],
+        )
+        .await
+        .unwrap();
+    assert_eq!(created.status(), StatusCode::SEE_OTHER);
+    let path = location(&created).to_owned();
+    let issue = test_stack
+        .client
+        .dashboard_page(&path, &cookies)
+        .await
+        .unwrap();
+    let sent = test_stack
+        .client
+        .dashboard_form(
+            &format!("{}/publish", path),
+            &cookies,
+            &[("csrf_token", &csrf_token)],
+        )
+        .await
+        .unwrap();
+    let issues = test_stack
+        .client
+        .dashboard_page("/admin/issues", &cookies)
+        .await
+        .unwrap();
+
+    assert!(form.text().await.unwrap().contains("value=\"newsletter\""));
+    assert!(path.starts_with("/admin/issues/"));
+    assert_eq!(issue.status(), StatusCode::OK);
+    let issue = issue.text().await.unwrap();
+    assert!(issue.contains("<h1>Our first issue</h1>"));
+    assert!(issue.contains("<dd>draft</dd>"));
+    // The preview is escaped into the frame
+    assert!(issue.contains("srcdoc=\""));
+    assert!(issue.contains("&lt;p&gt;Hello there&lt;/p&gt;"));
+    assert_eq!(sent.status(), StatusCode::SEE_OTHER);
+    assert_eq!(location(&sent), path);
+    let issues = issues.text().await.unwrap();
+    assert!(issues.contains("Our first issue"));
+    assert!(issues.contains("<td>scheduled</td>"));
+}
+
+#[integration_test]
+fn invalid_issues_are_shown_again(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    let (cookies, csrf_token) = sign_in(&test_stack, &credentials).await;
+
+    let response = test_stack
+        .client
+        .dashboard_form(
+            "/admin/issues",
+            &cookies,
+            &[
+                ("csrf_token", &csrf_token),
+                ("list", "newsletter"),
+                ("title", ""),
+                ("html_content", "<p>Hello there</p>"),
+                ("text_content", "Hello there"),
+            ],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let page = response.text().await.unwrap();
+    assert!(page.contains("class=\"error\""));
+    assert!(page.contains("Hello there</textarea>"));
+    let issues = sqlx::query!("SELECT count(*) AS \"count!\" FROM newsletter_issues")
+        .fetch_one(&test_stack.app.pool)
+        .await
+        .unwrap();
+    assert_eq!(issues.count, 0);
+}
+
+#[integration_test]
+fn editors_can_sign_out(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    let (cookies, csrf_token) = sign_in(&test_stack, &credentials).await;
+
+    let response = test_stack
+        .client
+        .dashboard_form("/admin/logout", &cookies, &[("csrf_token", &csrf_token)])
+        .await
+        .unwrap();
+    let page = test_stack
+        .client
+        .dashboard_page("/admin/subscribers", &cookies)
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_eq!(location(&response), "/admin/login");
+    assert_eq!(set_cookie(&response, "z2p_session"), Some("".into()));
+    assert_eq!(page.status(), StatusCode::SEE_OTHER);
+    assert_eq!(location(&page), "/admin/login");
+}
@@ -0,0 +1,329 @@
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+use wiremock::MockServer;
+use zero2prod_core::domain::TokenPurpose;
+use zero2prod_macros::integration_test;
+use zero2prod_web::{
+    configuration::{Configuration, RateLimitStorage},
+    testing::{mount_email_provider, TestStack, TestSubscriber},
+};
+
+async fn sent_emails(email_server: &MockServer) -> Vec<Value> {
+    email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|request| request.body_json().unwrap())
+        .collect()
+}
+
+/// John is subscribed to the newsletter and, with another case, to Rust
+/// Weekly, where he opted out of the releases and was sent an issue.
+async fn john_on_two_lists(test_stack: &TestStack) -> (Uuid, Uuid) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    let response = test_stack
+        .client
+        .create_list(
+            &credentials,
+            &json!({"slug": "rust-weekly", "name": "Rust Weekly"}),
+        )
+        .await
+        .unwrap();
+    let rust_weekly = response.json::<Value>().await.unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    test_stack
+        .client
+        .create_topic(
+            &credentials,
+            "rust-weekly",
+            &json!({"slug": "releases", "name": "Releases"}),
+        )
+        .await
+        .unwrap();
+    let issue: Value = test_stack
+        .client
+        .create_issue(
+            &credentials,
+            &json!({
+                "list": "rust-weekly",
+                "title": "Issue #1",
+                "text_content": "text",
+                "html_content": "html",
+            }),
+        )
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let issue_id: Uuid = issue["id"].as_str().unwrap().parse().unwrap();
+
+    let pool = &test_stack.app.pool;
+    let first = test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("john.doe@gmail.com"))
+        .await;
+    let second = test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("John.Doe@gmail.com").list(rust_weekly))
+        .await;
+    sqlx::query!(
+        r#"
+        INSERT INTO topic_preferences (subscriber_id, topic_id, subscribed)
+        SELECT $1, id, FALSE FROM topics
+        "#,
+        second
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+    sqlx::query!(
+        r#"
+        INSERT INTO issue_deliveries (issue_id, subscriber_id, email, status, attempts, updated_at)
+        VALUES ($1, $2, 'John.Doe@gmail.com', 'sent', 1, now())
+        "#,
+        issue_id,
+        second
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+    (first, second)
+}
+
+#[integration_test]
+fn subscribers_are_emailed_a_data_access_link(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("john.doe@gmail.com"))
+        .await;
+
+    let response = test_stack
+        .client
+        .request_data_access("John.Doe@gmail.com")
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let emails = sent_emails(&test_stack.email_server).await;
+    assert_eq!(emails.len(), 1);
+    assert_eq!(emails[0]["to"], "john.doe@gmail.com");
+    let text = emails[0]["TextBody"].as_str().unwrap();
+    let marker = "/me/export?token=";
+    let start = text.find(marker).expect("Link not found") + marker.len();
+    let token = text[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '.')
+        .collect::<String>();
+    let export = test_stack.client.export_data(&token).await.unwrap();
+    assert_eq!(export.status(), StatusCode::OK);
+    assert!(text.contains("/me/erase?token="));
+}
+
+#[integration_test]
+fn unknown_addresses_are_not_revealed(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("john.doe@gmail.com"))
+        .await;
+
+    let known = test_stack
+        .client
+        .request_data_access("john.doe@gmail.com")
+        .await
+        .unwrap();
+    let unknown = test_stack
+        .client
+        .request_data_access("jane.doe@gmail.com")
+        .await
+        .unwrap();
+
+    assert_eq!(known.status(), unknown.status());
+    assert_eq!(known.text().await.unwrap(), unknown.text().await.unwrap());
+    assert_eq!(sent_emails(&test_stack.email_server).await.len(), 1);
+}
+
+#[integration_test]
+fn subscribers_can_export_their_data(test_stack: TestStack) {
+    let (first, second) = john_on_two_lists(&test_stack).await;
+    let token = test_stack.app.sign_token(TokenPurpose::Access, first);
+
+    let response = test_stack.client.export_data(&token).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let data: Value = response.json().await.unwrap();
+    assert_eq!(data["email"], "john.doe@gmail.com");
+    let subscriptions = data["subscriptions"].as_array().unwrap();
+    assert_eq!(subscriptions.len(), 2);
+    let rust_weekly = subscriptions
+        .iter()
+        .find(|subscription| subscription["id"] == second.to_string())
+        .unwrap();
+    assert_eq!(rust_weekly["list"], "rust-weekly");
+    assert_eq!(rust_weekly["name"], "John Doe");
+    assert_eq!(rust_weekly["status"], "confirmed");
+    assert_eq!(
+        rust_weekly["topics"],
+        json!([{"topic": "releases", "subscribed": false}])
+    );
+    assert_eq!(rust_weekly["deliveries"][0]["title"], "Issue #1");
+    assert_eq!(rust_weekly["deliveries"][0]["status"], "sent");
+}
+
+#[integration_test]
+fn subscribers_can_erase_their_data(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    let (first, _) = john_on_two_lists(&test_stack).await;
+    test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("jane.doe@gmail.com"))
+        .await;
+    sqlx::query!(
+        r#"
+        INSERT INTO consent_events (id, subscriber_id, action, source, occurred_at)
+        VALUES ($1, $2, 'subscribe', 'form', now())
+        "#,
+        Uuid::new_v4(),
+        first
+    )
+    .execute(&test_stack.app.pool)
+    .await
+    .unwrap();
+    let token = test_stack.app.sign_token(TokenPurpose::Access, first);
+
+    let response = test_stack.client.erase_data(&token).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response
+        .text()
+        .await
+        .unwrap()
+        .contains("Your data has been erased"));
+    let pool = &test_stack.app.pool;
+    let emails = sqlx::query!("SELECT email FROM subscriptions")
+        .fetch_all(pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|row| row.email)
+        .collect::<Vec<_>>();
+    assert_eq!(emails, vec!["jane.doe@gmail.com"]);
+    let deliveries = sqlx::query!(r#"SELECT COUNT(*) AS "count!" FROM issue_deliveries"#)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+    assert_eq!(deliveries.count, 0);
+    let consents = sqlx::query!(r#"SELECT COUNT(*) AS "count!" FROM consent_events"#)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+    assert_eq!(consents.count, 0);
+    let audit = sqlx::query!("SELECT action, subject, details::text AS details FROM audit_log")
+        .fetch_one(pool)
+        .await
+        .unwrap();
+    assert_eq!(audit.action, "data_erased");
+    assert!(!audit.subject.contains("john"));
+    assert!(!audit.details.unwrap().contains("john"));
+    let suppressed = sqlx::query!("SELECT email_hash FROM suppressed_emails")
+        .fetch_one(pool)
+        .await
+        .unwrap();
+    assert_eq!(suppressed.email_hash, audit.subject);
+
+    let export = test_stack.client.export_data(&token).await.unwrap();
+    let subscribe = test_stack
+        .client
+        .subscribe("newsletter", "name=John%20Doe&email=JOHN.DOE@gmail.com")
+        .await
+        .unwrap();
+    assert_eq!(export.status(), StatusCode::NOT_FOUND);
+    assert_eq!(subscribe.status(), StatusCode::BAD_REQUEST);
+}
+
+fn rate_limit_in_postgres(config: &mut Configuration) {
+    config.rate_limit.storage = RateLimitStorage::Postgres;
+}
+
+async fn email_buckets(pool: &PgPool) -> i64 {
+    sqlx::query!(r#"SELECT COUNT(*) AS "count!" FROM rate_limit_buckets WHERE scope = 'email'"#)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+        .count
+}
+
+#[integration_test(configure = rate_limit_in_postgres)]
+fn erasing_data_forgets_the_rate_limit_of_the_address(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    test_stack
+        .client
+        .subscribe("newsletter", "name=John%20Doe&email=John.Doe@gmail.com")
+        .await
+        .unwrap();
+    let pool = &test_stack.app.pool;
+    assert_eq!(email_buckets(pool).await, 1);
+    let subscriber_id = sqlx::query!("SELECT id FROM subscriptions")
+        .fetch_one(pool)
+        .await
+        .unwrap()
+        .id;
+    let token = test_stack
+        .app
+        .sign_token(TokenPurpose::Access, subscriber_id);
+
+    let response = test_stack.client.erase_data(&token).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(email_buckets(pool).await, 0);
+}
+
+#[integration_test]
+fn the_erase_link_asks_for_a_confirmation(test_stack: TestStack) {
+    let subscriber_id = test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("john.doe@gmail.com"))
+        .await;
+    let token = test_stack
+        .app
+        .sign_token(TokenPurpose::Access, subscriber_id);
+
+    let response = test_stack.client.erase_page(&token).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let page = response.text().await.unwrap();
+    assert!(page.contains(r#"<form action="/me/erase" method="post">"#));
+    assert!(page.contains(&token));
+    let count = sqlx::query!(r#"SELECT COUNT(*) AS "count!" FROM subscriptions"#)
+        .fetch_one(&test_stack.app.pool)
+        .await
+        .unwrap();
+    assert_eq!(count.count, 1);
+}
+
+#[integration_test]
+fn data_access_requires_an_access_token(test_stack: TestStack) {
+    let subscriber_id = test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("john.doe@gmail.com"))
+        .await;
+    let token = test_stack
+        .app
+        .sign_token(TokenPurpose::Unsubscribe, subscriber_id);
+
+    let export = test_stack.client.export_data(&token).await.unwrap();
+    let erase = test_stack.client.erase_data(&token).await.unwrap();
+    let forged = test_stack.client.export_data("forged").await.unwrap();
+
+    assert_eq!(export.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(erase.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(forged.status(), StatusCode::UNAUTHORIZED);
+}
@@ -1,4 +1,10 @@
+use reqwest::StatusCode;
+use wiremock::{
+    matchers::{method, path},
+    Mock, ResponseTemplate,
+};
 use zero2prod_macros::integration_test;
+use zero2prod_web::configuration::Configuration;
 
 #[integration_test]
 fn health_check_works(test_stack: TestStack) {
@@ -11,3 +17,128 @@ fn health_check_works(test_stack: TestStack) {
     assert!(response.status().is_success());
     assert_eq!(Some(0), response.content_length());
 }
+
+#[integration_test]
+fn health_live_works(test_stack: TestStack) {
+    let response = test_stack
+        .client
+        .health_live()
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[integration_test]
+fn health_ready_reports_every_component(test_stack: TestStack) {
+    let response = test_stack
+        .client
+        .health_ready()
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "up");
+    assert_eq!(body["components"]["database"]["status"], "up");
+    assert_eq!(body["components"]["migrations"]["status"], "up");
+    assert!(body["components"]["database"]["latency_ms"].is_u64());
+    assert!(body["components"].get("email").is_none());
+}
+
+#[integration_test]
+fn health_ready_fails_when_the_database_is_down(test_stack: TestStack) {
+    test_stack.app.pool.close().await;
+
+    let ready = test_stack
+        .client
+        .health_ready()
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(ready.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body: serde_json::Value = ready.json().await.unwrap();
+    assert_eq!(body["status"], "down");
+    assert_eq!(body["components"]["database"]["status"], "down");
+    assert_eq!(
+        body["components"]["database"]["detail"],
+        "database unreachable"
+    );
+
+    let live = test_stack
+        .client
+        .health_live()
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(live.status(), StatusCode::OK);
+}
+
+#[integration_test]
+fn health_ready_fails_when_migrations_are_pending(test_stack: TestStack) {
+    sqlx::query(
+        "DELETE FROM _sqlx_migrations WHERE version = (SELECT MAX(version) FROM _sqlx_migrations)",
+    )
+    .execute(&test_stack.app.pool)
+    .await
+    .unwrap();
+
+    let response = test_stack
+        .client
+        .health_ready()
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["components"]["migrations"]["status"], "down");
+    assert_eq!(
+        body["components"]["migrations"]["detail"],
+        "pending migrations"
+    );
+}
+
+fn probe_email_provider(config: &mut Configuration) {
+    config.email_client.health_check_path = Some("/server".into());
+}
+
+#[integration_test(configure = probe_email_provider)]
+fn health_ready_probes_the_email_provider(test_stack: TestStack) {
+    Mock::given(path("/server"))
+        .and(method("GET"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&test_stack.email_server)
+        .await;
+
+    let response = test_stack
+        .client
+        .health_ready()
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["components"]["email"]["status"], "up");
+}
+
+#[integration_test(configure = probe_email_provider)]
+fn health_ready_fails_when_the_email_provider_is_down(test_stack: TestStack) {
+    Mock::given(path("/server"))
+        .and(method("GET"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&test_stack.email_server)
+        .await;
+
+    let response = test_stack
+        .client
+        .health_ready()
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["components"]["email"]["status"], "down");
+    assert_eq!(body["components"]["database"]["status"], "up");
+}
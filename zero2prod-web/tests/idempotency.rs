@@ -0,0 +1,304 @@
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+use zero2prod_macros::integration_test;
+use zero2prod_web::{
+    client::Credentials,
+    configuration::Configuration,
+    testing::{mount_email_provider, TestStack},
+};
+
+const BODY: &str = "name=John%20Doe&email=john.doe@gmail.com";
+
+fn expire_keys_immediately(config: &mut Configuration) {
+    config.idempotency.ttl = 0;
+}
+
+async fn emails_sent(email_server: &MockServer) -> usize {
+    email_server.received_requests().await.unwrap().len()
+}
+
+/// Publishing an issue scheduled to be sent is allowed, unlike publishing an
+/// issue being sent.
+async fn start_sending(test_stack: &TestStack, issue_id: Uuid) {
+    sqlx::query!(
+        "UPDATE newsletter_issues SET status = 'sending' WHERE id = $1",
+        issue_id
+    )
+    .execute(&test_stack.app.pool)
+    .await
+    .unwrap();
+}
+
+async fn create_issue(test_stack: &TestStack, credentials: &Credentials) -> Uuid {
+    let issue = json!({
+        "list": "newsletter",
+        "title": "Issue #1",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+    });
+    let body: Value = test_stack
+        .client
+        .create_issue(credentials, &issue)
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    body["id"].as_str().unwrap().parse().unwrap()
+}
+
+#[integration_test]
+fn retried_subscriptions_are_replayed(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+
+    let first = test_stack
+        .client
+        .subscribe_idempotently("newsletter", BODY, "subscribe-1")
+        .await
+        .unwrap();
+    let retry = test_stack
+        .client
+        .subscribe_idempotently("newsletter", BODY, "subscribe-1")
+        .await
+        .unwrap();
+
+    assert_eq!(first.status(), StatusCode::OK);
+    assert!(first.headers().get("Idempotent-Replayed").is_none());
+    assert_eq!(retry.status(), StatusCode::OK);
+    assert_eq!(retry.headers()["Idempotent-Replayed"], "true");
+    assert_eq!(emails_sent(&test_stack.email_server).await, 1);
+}
+
+#[integration_test]
+fn concurrent_duplicates_are_processed_once(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+
+    let (first, second) = tokio::join!(
+        test_stack
+            .client
+            .subscribe_idempotently("newsletter", BODY, "subscribe-1"),
+        test_stack
+            .client
+            .subscribe_idempotently("newsletter", BODY, "subscribe-1"),
+    );
+
+    let (first, second) = (first.unwrap(), second.unwrap());
+    let replayed = [&first, &second]
+        .iter()
+        .filter(|response| response.headers().contains_key("Idempotent-Replayed"))
+        .count();
+    assert_eq!(first.status(), StatusCode::OK);
+    assert_eq!(second.status(), StatusCode::OK);
+    assert_eq!(replayed, 1);
+    assert_eq!(first.text().await.unwrap(), second.text().await.unwrap());
+    let subscriptions = sqlx::query!("SELECT count(*) AS \"count!\" FROM subscriptions")
+        .fetch_one(&test_stack.app.pool)
+        .await
+        .unwrap();
+    assert_eq!(subscriptions.count, 1);
+    assert_eq!(emails_sent(&test_stack.email_server).await, 1);
+}
+
+#[integration_test]
+fn duplicates_wait_for_the_request_being_processed(test_stack: TestStack) {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(1000)))
+        .mount(&test_stack.email_server)
+        .await;
+
+    let (first, duplicate) = tokio::join!(
+        test_stack
+            .client
+            .subscribe_idempotently("newsletter", BODY, "subscribe-1"),
+        async {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            test_stack
+                .client
+                .subscribe_idempotently("newsletter", BODY, "subscribe-1")
+                .await
+        },
+    );
+
+    let duplicate = duplicate.unwrap();
+    assert_eq!(first.unwrap().status(), StatusCode::OK);
+    assert_eq!(duplicate.status(), StatusCode::OK);
+    assert_eq!(duplicate.headers()["Idempotent-Replayed"], "true");
+    assert_eq!(emails_sent(&test_stack.email_server).await, 1);
+}
+
+#[integration_test]
+fn client_errors_are_replayed(test_stack: TestStack) {
+    let first = test_stack
+        .client
+        .subscribe_idempotently("newsletter", "name=John%20Doe", "subscribe-1")
+        .await
+        .unwrap();
+    let first_body = first.text().await.unwrap();
+    let retry = test_stack
+        .client
+        .subscribe_idempotently("newsletter", "name=John%20Doe", "subscribe-1")
+        .await
+        .unwrap();
+
+    assert_eq!(retry.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(retry.text().await.unwrap(), first_body);
+}
+
+#[integration_test]
+fn server_errors_are_not_saved(test_stack: TestStack) {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .up_to_n_times(1)
+        .mount(&test_stack.email_server)
+        .await;
+    mount_email_provider(&test_stack.email_server).await;
+
+    let first = test_stack
+        .client
+        .subscribe_idempotently("newsletter", BODY, "subscribe-1")
+        .await
+        .unwrap();
+    let retry = test_stack
+        .client
+        .subscribe_idempotently("newsletter", BODY, "subscribe-1")
+        .await
+        .unwrap();
+
+    assert_eq!(first.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(retry.status(), StatusCode::OK);
+    assert!(retry.headers().get("Idempotent-Replayed").is_none());
+    assert_eq!(emails_sent(&test_stack.email_server).await, 2);
+}
+
+#[integration_test(configure = expire_keys_immediately)]
+fn expired_keys_are_processed_again(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+
+    test_stack
+        .client
+        .subscribe_idempotently("newsletter", BODY, "subscribe-1")
+        .await
+        .unwrap();
+    let retry = test_stack
+        .client
+        .subscribe_idempotently("newsletter", BODY, "subscribe-1")
+        .await
+        .unwrap();
+
+    assert_eq!(retry.status(), StatusCode::OK);
+    assert!(retry.headers().get("Idempotent-Replayed").is_none());
+    assert_eq!(emails_sent(&test_stack.email_server).await, 2);
+}
+
+#[integration_test]
+fn invalid_keys_are_rejected(test_stack: TestStack) {
+    let response = test_stack
+        .client
+        .subscribe_idempotently("newsletter", BODY, &"k".repeat(256))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(emails_sent(&test_stack.email_server).await, 0);
+}
+
+#[integration_test]
+fn retried_publications_are_replayed(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    let issue_id = create_issue(&test_stack, &credentials).await;
+
+    let first = test_stack
+        .client
+        .publish_issue_idempotently(&credentials, issue_id, "publish-1")
+        .await
+        .unwrap();
+    start_sending(&test_stack, issue_id).await;
+    let retry = test_stack
+        .client
+        .publish_issue_idempotently(&credentials, issue_id, "publish-1")
+        .await
+        .unwrap();
+    let without_key = test_stack
+        .client
+        .publish_issue(&credentials, issue_id)
+        .await
+        .unwrap();
+
+    assert_eq!(first.status(), StatusCode::ACCEPTED);
+    assert_eq!(retry.status(), StatusCode::ACCEPTED);
+    assert_eq!(retry.headers()["Idempotent-Replayed"], "true");
+    assert_eq!(without_key.status(), StatusCode::CONFLICT);
+}
+
+#[integration_test]
+fn keys_are_scoped_to_their_user(test_stack: TestStack) {
+    let alice = test_stack.app.create_editor("alice").await;
+    let bob = test_stack.app.create_editor("bob").await;
+    let issue_id = create_issue(&test_stack, &alice).await;
+
+    test_stack
+        .client
+        .publish_issue_idempotently(&alice, issue_id, "publish-1")
+        .await
+        .unwrap();
+    start_sending(&test_stack, issue_id).await;
+    let response = test_stack
+        .client
+        .publish_issue_idempotently(&bob, issue_id, "publish-1")
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
+#[integration_test]
+fn keys_cannot_be_reused_for_another_request(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    let first_issue = create_issue(&test_stack, &credentials).await;
+    let second_issue = create_issue(&test_stack, &credentials).await;
+
+    test_stack
+        .client
+        .publish_issue_idempotently(&credentials, first_issue, "publish-1")
+        .await
+        .unwrap();
+    let response = test_stack
+        .client
+        .publish_issue_idempotently(&credentials, second_issue, "publish-1")
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[integration_test]
+fn keys_cannot_be_reused_for_another_body(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+
+    test_stack
+        .client
+        .subscribe_idempotently("newsletter", BODY, "subscribe-1")
+        .await
+        .unwrap();
+    let response = test_stack
+        .client
+        .subscribe_idempotently(
+            "newsletter",
+            "name=Jane%20Doe&email=jane.doe@gmail.com",
+            "subscribe-1",
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(emails_sent(&test_stack.email_server).await, 1);
+}
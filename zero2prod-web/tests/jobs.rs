@@ -0,0 +1,250 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use async_trait::async_trait;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+use zero2prod_core::{
+    domain::{Job, NewJob},
+    error::{CoreError, CoreResult},
+    service::jobs::{JobHandler, JobQueue},
+};
+use zero2prod_macros::integration_test;
+use zero2prod_web::{
+    configuration::{Configuration, JobsConfig},
+    jobs::{JobQueueImpl, JobRunner},
+};
+
+/// The jobs are run by the tests rather than by the application.
+fn without_workers(config: &mut Configuration) {
+    config.jobs.workers = 0;
+}
+
+/// Records the jobs it runs, and fails the first `failures` runs.
+#[derive(Clone, Default)]
+struct TestJob {
+    runs: Arc<Mutex<Vec<Job>>>,
+    failures: u32,
+    duration: Duration,
+}
+
+#[async_trait]
+impl JobHandler for TestJob {
+    fn kind(&self) -> &'static str {
+        "test"
+    }
+
+    async fn run(&self, job: &Job) -> CoreResult<()> {
+        tokio::time::sleep(self.duration).await;
+        let runs = {
+            let mut runs = self.runs.lock().unwrap();
+            runs.push(job.clone());
+            runs.len() as u32
+        };
+        match runs <= self.failures {
+            true => Err(CoreError::Unexpected(format!("failure #{}", runs))),
+            false => Ok(()),
+        }
+    }
+}
+
+fn runner(pool: &PgPool, config: JobsConfig, handler: TestJob) -> (Arc<JobQueueImpl>, JobRunner) {
+    let queue = Arc::new(JobQueueImpl::new(pool.clone(), config.max_attempts));
+    let runner = JobRunner::new(queue.clone(), &config).register(handler);
+    (queue, runner)
+}
+
+fn config(max_attempts: u32, backoff_base: u64) -> JobsConfig {
+    JobsConfig {
+        max_attempts,
+        backoff_base,
+        ..JobsConfig::default()
+    }
+}
+
+struct JobRow {
+    status: String,
+    attempts: i32,
+    last_error: Option<String>,
+    delayed_by: f64,
+}
+
+async fn job_row(pool: &PgPool, id: Uuid) -> JobRow {
+    sqlx::query_as!(
+        JobRow,
+        r#"
+        SELECT status, attempts, last_error,
+            EXTRACT(EPOCH FROM run_at - now())::float8 AS "delayed_by!"
+        FROM jobs WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+#[integration_test(configure = without_workers)]
+fn queued_jobs_are_run_once_with_their_payload(test_stack: TestStack) {
+    let handler = TestJob::default();
+    let (queue, runner) = runner(&test_stack.app.pool, config(3, 0), handler.clone());
+    let id = queue
+        .enqueue(NewJob::new("test", json!({"issue": 42})))
+        .await
+        .unwrap();
+
+    assert!(runner.run_next().await.unwrap());
+    assert!(!runner.run_next().await.unwrap());
+
+    let runs = handler.runs.lock().unwrap().clone();
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0].id, id);
+    assert_eq!(runs[0].payload, json!({"issue": 42}));
+    assert_eq!(runs[0].attempt, 1);
+    assert_eq!(job_row(&test_stack.app.pool, id).await.status, "succeeded");
+}
+
+#[integration_test(configure = without_workers)]
+fn failing_jobs_are_retried_after_a_backoff(test_stack: TestStack) {
+    let handler = TestJob {
+        failures: 1,
+        ..TestJob::default()
+    };
+    let (queue, runner) = runner(&test_stack.app.pool, config(3, 60), handler.clone());
+    let id = queue.enqueue(NewJob::new("test", json!({}))).await.unwrap();
+
+    assert!(runner.run_next().await.unwrap());
+
+    let job = job_row(&test_stack.app.pool, id).await;
+    assert_eq!(job.status, "queued");
+    assert_eq!(job.attempts, 1);
+    assert_eq!(
+        job.last_error.as_deref(),
+        Some("Unexpected error: failure #1")
+    );
+    assert!(job.delayed_by > 50.0);
+    assert!(!runner.run_next().await.unwrap());
+}
+
+#[integration_test(configure = without_workers)]
+fn jobs_are_dead_after_their_last_attempt(test_stack: TestStack) {
+    let handler = TestJob {
+        failures: u32::MAX,
+        ..TestJob::default()
+    };
+    let (queue, runner) = runner(&test_stack.app.pool, config(2, 0), handler.clone());
+    let id = queue.enqueue(NewJob::new("test", json!({}))).await.unwrap();
+
+    assert!(runner.run_next().await.unwrap());
+    assert!(runner.run_next().await.unwrap());
+    assert!(!runner.run_next().await.unwrap());
+
+    let job = job_row(&test_stack.app.pool, id).await;
+    assert_eq!(job.status, "dead");
+    assert_eq!(job.attempts, 2);
+    assert_eq!(handler.runs.lock().unwrap().len(), 2);
+}
+
+#[integration_test(configure = without_workers)]
+fn jobs_without_a_handler_are_retried(test_stack: TestStack) {
+    let (queue, runner) = runner(&test_stack.app.pool, config(3, 60), TestJob::default());
+    let id = queue
+        .enqueue(NewJob::new("unknown", json!({})))
+        .await
+        .unwrap();
+
+    assert!(runner.run_next().await.unwrap());
+
+    let job = job_row(&test_stack.app.pool, id).await;
+    assert_eq!(job.status, "queued");
+    assert_eq!(
+        job.last_error.as_deref(),
+        Some("No handler for unknown jobs")
+    );
+}
+
+#[integration_test(configure = without_workers)]
+fn jobs_are_not_run_before_they_are_due(test_stack: TestStack) {
+    let (queue, runner) = runner(&test_stack.app.pool, config(3, 0), TestJob::default());
+    let later = SystemTime::now() + Duration::from_secs(3600);
+    queue
+        .enqueue(NewJob::new("test", json!({})).at(later))
+        .await
+        .unwrap();
+
+    assert!(!runner.run_next().await.unwrap());
+}
+
+#[integration_test(configure = without_workers)]
+fn a_job_is_handed_to_a_single_worker(test_stack: TestStack) {
+    let handler = TestJob {
+        duration: Duration::from_millis(300),
+        ..TestJob::default()
+    };
+    let (queue, first) = runner(&test_stack.app.pool, config(3, 0), handler.clone());
+    let (_, second) = runner(&test_stack.app.pool, config(3, 0), handler.clone());
+    queue.enqueue(NewJob::new("test", json!({}))).await.unwrap();
+
+    let (first, second) = tokio::join!(first.run_next(), second.run_next());
+
+    assert_ne!(first.unwrap(), second.unwrap());
+    assert_eq!(handler.runs.lock().unwrap().len(), 1);
+}
+
+#[integration_test(configure = without_workers)]
+fn jobs_lost_by_their_worker_are_run_again(test_stack: TestStack) {
+    let handler = TestJob::default();
+    let (queue, runner) = runner(&test_stack.app.pool, config(3, 0), handler.clone());
+    let id = queue.enqueue(NewJob::new("test", json!({}))).await.unwrap();
+    // A worker takes the job and dies before its lease expires
+    queue.dequeue(Duration::ZERO).await.unwrap().unwrap();
+
+    assert!(runner.run_next().await.unwrap());
+
+    assert_eq!(handler.runs.lock().unwrap()[0].attempt, 2);
+    assert_eq!(job_row(&test_stack.app.pool, id).await.status, "succeeded");
+}
+
+#[integration_test(configure = without_workers)]
+fn long_jobs_keep_their_lease(test_stack: TestStack) {
+    let handler = TestJob {
+        duration: Duration::from_millis(2500),
+        ..TestJob::default()
+    };
+    let config = JobsConfig {
+        lease: 1,
+        ..config(3, 0)
+    };
+    let (queue, first) = runner(&test_stack.app.pool, config.clone(), handler.clone());
+    let (_, second) = runner(&test_stack.app.pool, config, handler.clone());
+    let id = queue.enqueue(NewJob::new("test", json!({}))).await.unwrap();
+
+    let (first, second) = tokio::join!(first.run_next(), async {
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        second.run_next().await
+    });
+
+    assert!(first.unwrap());
+    assert!(!second.unwrap());
+    assert_eq!(handler.runs.lock().unwrap().len(), 1);
+    assert_eq!(job_row(&test_stack.app.pool, id).await.status, "succeeded");
+}
+
+#[integration_test(configure = without_workers)]
+fn workers_that_lost_their_job_cannot_complete_it(test_stack: TestStack) {
+    let (queue, runner) = runner(&test_stack.app.pool, config(3, 0), TestJob::default());
+    let id = queue.enqueue(NewJob::new("test", json!({}))).await.unwrap();
+    let stale = queue.dequeue(Duration::ZERO).await.unwrap().unwrap();
+    assert!(runner.run_next().await.unwrap());
+
+    assert!(!queue.succeed(&stale).await.unwrap());
+    assert!(!queue.retry(&stale, Duration::ZERO, "late").await.unwrap());
+    assert!(!queue.bury(&stale, "late").await.unwrap());
+
+    let job = job_row(&test_stack.app.pool, id).await;
+    assert_eq!(job.status, "succeeded");
+    assert_eq!(job.last_error, None);
+}
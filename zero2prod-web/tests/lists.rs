@@ -0,0 +1,373 @@
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+use wiremock::MockServer;
+use zero2prod_macros::integration_test;
+use zero2prod_web::{
+    client::Credentials,
+    testing::{mount_email_provider, send_issues_every_second, TestStack},
+};
+
+const BODY: &str = "name=John%20Doe&email=john.doe@gmail.com";
+
+async fn sent_emails(email_server: &MockServer) -> Vec<Value> {
+    email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|request| request.body_json().unwrap())
+        .collect()
+}
+
+async fn create_list(test_stack: &TestStack, credentials: &Credentials, list: Value) {
+    let response = test_stack
+        .client
+        .create_list(credentials, &list)
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+}
+
+async fn subscription_statuses(test_stack: &TestStack) -> Vec<(String, String)> {
+    sqlx::query!(
+        r#"
+        SELECT lists.slug, subscriptions.status
+        FROM subscriptions JOIN lists ON lists.id = subscriptions.list_id
+        ORDER BY lists.slug
+        "#
+    )
+    .fetch_all(&test_stack.app.pool)
+    .await
+    .unwrap()
+    .into_iter()
+    .map(|row| (row.slug, row.status))
+    .collect()
+}
+
+#[integration_test]
+fn lists_can_be_created_and_updated(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    create_list(
+        &test_stack,
+        &credentials,
+        json!({"slug": "rust-weekly", "name": "Rust Weekly"}),
+    )
+    .await;
+
+    let response = test_stack
+        .client
+        .update_list(
+            &credentials,
+            "rust-weekly",
+            &json!({
+                "name": "Rust Weekly News",
+                "sender": "rust@example.com",
+                "require_confirmation": false,
+            }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let list: Value = test_stack
+        .client
+        .get_list(&credentials, "rust-weekly")
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(list["name"], "Rust Weekly News");
+    assert_eq!(list["sender"], "rust@example.com");
+    assert_eq!(list["require_confirmation"], false);
+
+    let lists: Value = test_stack
+        .client
+        .list_lists(&credentials)
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let slugs = lists
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|list| list["slug"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(slugs, vec!["newsletter", "rust-weekly"]);
+}
+
+#[integration_test]
+fn lists_require_valid_credentials(test_stack: TestStack) {
+    let credentials = Credentials {
+        username: "editor".into(),
+        password: "wrong password".into(),
+    };
+
+    let response = test_stack
+        .client
+        .create_list(&credentials, &json!({"slug": "rust", "name": "Rust"}))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[integration_test]
+fn invalid_lists_are_rejected(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    let test_cases = vec![
+        (
+            json!({"slug": "Rust Weekly", "name": "Rust"}),
+            "invalid slug",
+        ),
+        (json!({"slug": "rust", "name": " "}), "blank name"),
+        (
+            json!({"slug": "rust", "name": "Rust", "sender": "not an email"}),
+            "invalid sender",
+        ),
+        (
+            json!({"slug": "rust", "name": "Rust", "templates": {"issue_html": "{{#if}}"}}),
+            "invalid template",
+        ),
+        (
+            json!({"slug": "newsletter", "name": "Newsletter"}),
+            "existing slug",
+        ),
+    ];
+
+    for (body, description) in test_cases {
+        let response = test_stack
+            .client
+            .create_list(&credentials, &body)
+            .await
+            .unwrap();
+
+        assert!(
+            response.status().is_client_error(),
+            "The API did not reject the list when it had an {}",
+            description
+        );
+    }
+}
+
+#[integration_test]
+fn subscribing_to_an_unknown_list_returns_404(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+
+    let response = test_stack.client.subscribe("unknown", BODY).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[integration_test]
+fn confirmations_use_the_sender_and_template_of_the_list(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    let credentials = test_stack.app.create_editor("editor").await;
+    create_list(
+        &test_stack,
+        &credentials,
+        json!({
+            "slug": "rust-weekly",
+            "name": "Rust Weekly",
+            "sender": "rust@example.com",
+            "templates": {
+                "confirmation_html": "<p>Rust Weekly: {{confirmation_link}}</p>",
+            },
+        }),
+    )
+    .await;
+
+    let response = test_stack
+        .client
+        .subscribe("rust-weekly", BODY)
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let emails = sent_emails(&test_stack.email_server).await;
+    assert_eq!(emails.len(), 1);
+    assert_eq!(emails[0]["from"], "rust@example.com");
+    assert!(emails[0]["HtmlBody"]
+        .as_str()
+        .unwrap()
+        .starts_with("<p>Rust Weekly: "));
+}
+
+#[integration_test]
+fn an_email_can_subscribe_to_several_lists(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    let credentials = test_stack.app.create_editor("editor").await;
+    create_list(
+        &test_stack,
+        &credentials,
+        json!({"slug": "rust-weekly", "name": "Rust Weekly"}),
+    )
+    .await;
+
+    let first = test_stack
+        .client
+        .subscribe("newsletter", BODY)
+        .await
+        .unwrap();
+    let second = test_stack
+        .client
+        .subscribe("rust-weekly", BODY)
+        .await
+        .unwrap();
+
+    assert_eq!(first.status(), StatusCode::OK);
+    assert_eq!(second.status(), StatusCode::OK);
+    assert_eq!(
+        subscription_statuses(&test_stack).await,
+        vec![
+            ("newsletter".to_owned(), "pending".to_owned()),
+            ("rust-weekly".to_owned(), "pending".to_owned()),
+        ]
+    );
+}
+
+#[integration_test]
+fn an_email_subscribes_once_to_a_list_whatever_its_case(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+
+    let first = test_stack
+        .client
+        .subscribe("newsletter", BODY)
+        .await
+        .unwrap();
+    let second = test_stack
+        .client
+        .subscribe("newsletter", "name=John%20Doe&email=John.Doe@Gmail.com")
+        .await
+        .unwrap();
+
+    assert_eq!(first.status(), StatusCode::OK);
+    assert_eq!(second.status(), StatusCode::OK);
+    assert_eq!(
+        subscription_statuses(&test_stack).await,
+        vec![("newsletter".to_owned(), "pending".to_owned())]
+    );
+}
+
+#[integration_test]
+fn single_opt_in_lists_confirm_right_away(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    let credentials = test_stack.app.create_editor("editor").await;
+    create_list(
+        &test_stack,
+        &credentials,
+        json!({"slug": "rust-weekly", "name": "Rust Weekly", "require_confirmation": false}),
+    )
+    .await;
+
+    let response = test_stack
+        .client
+        .subscribe("rust-weekly", BODY)
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(sent_emails(&test_stack.email_server).await.is_empty());
+    assert_eq!(
+        subscription_statuses(&test_stack).await,
+        vec![("rust-weekly".to_owned(), "confirmed".to_owned())]
+    );
+}
+
+#[integration_test(configure = send_issues_every_second)]
+fn issues_are_only_sent_to_the_subscribers_of_their_list(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    let credentials = test_stack.app.create_editor("editor").await;
+    create_list(
+        &test_stack,
+        &credentials,
+        json!({
+            "slug": "rust-weekly",
+            "name": "Rust Weekly",
+            "sender": "rust@example.com",
+            "require_confirmation": false,
+        }),
+    )
+    .await;
+    test_stack
+        .client
+        .subscribe("rust-weekly", "name=Jane%20Doe&email=jane.doe@gmail.com")
+        .await
+        .unwrap();
+    sqlx::query!(
+        r#"
+        INSERT INTO subscriptions (id, list_id, email, name, subscribed_at, status)
+        VALUES ($1, '00000000-0000-0000-0000-000000000001', 'john.doe@gmail.com', 'John Doe', now(), 'confirmed')
+        "#,
+        Uuid::new_v4()
+    )
+    .execute(&test_stack.app.pool)
+    .await
+    .unwrap();
+
+    let response = test_stack
+        .client
+        .create_issue(
+            &credentials,
+            &json!({
+                "list": "rust-weekly",
+                "title": "Issue #1",
+                "text_content": "Newsletter body as plain text",
+                "html_content": "<p>Newsletter body as HTML</p>",
+            }),
+        )
+        .await
+        .unwrap();
+    let issue: Value = response.json().await.unwrap();
+    let issue_id: Uuid = issue["id"].as_str().unwrap().parse().unwrap();
+    test_stack
+        .client
+        .publish_issue(&credentials, issue_id)
+        .await
+        .unwrap();
+
+    for _ in 0..100 {
+        let issue: Value = test_stack
+            .client
+            .get_issue(&credentials, issue_id)
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        if issue["status"] == "sent" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let emails = sent_emails(&test_stack.email_server).await;
+    assert_eq!(emails.len(), 1);
+    assert_eq!(emails[0]["to"], "jane.doe@gmail.com");
+    assert_eq!(emails[0]["from"], "rust@example.com");
+}
+
+#[integration_test]
+fn issues_of_unknown_lists_are_rejected(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+
+    let response = test_stack
+        .client
+        .create_issue(
+            &credentials,
+            &json!({
+                "list": "unknown",
+                "title": "Issue #1",
+                "text_content": "text",
+                "html_content": "html",
+            }),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,79 @@
+use reqwest::StatusCode;
+use zero2prod_macros::integration_test;
+use zero2prod_web::configuration::Configuration;
+
+const ADMIN_TOKEN: &str = "test-admin-token";
+
+#[integration_test]
+fn log_filter_can_be_changed_at_runtime(test_stack: TestStack) {
+    let response = test_stack
+        .client
+        .set_log_filter(ADMIN_TOKEN, "zero2prod_web=trace,warn")
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = test_stack
+        .client
+        .get_log_filter(ADMIN_TOKEN)
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let filter = response.text().await.unwrap();
+    assert!(filter.contains("zero2prod_web=trace"));
+    assert!(filter.contains("warn"));
+
+    test_stack
+        .client
+        .set_log_filter(ADMIN_TOKEN, "debug")
+        .await
+        .unwrap();
+}
+
+#[integration_test]
+fn log_filter_rejects_invalid_directives(test_stack: TestStack) {
+    let response = test_stack
+        .client
+        .set_log_filter(ADMIN_TOKEN, "zero2prod_web=[{")
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[integration_test]
+fn log_filter_requires_the_admin_token(test_stack: TestStack) {
+    let response = test_stack
+        .client
+        .set_log_filter("wrong-token", "trace")
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers()["www-authenticate"], "Bearer");
+
+    let response = test_stack
+        .client
+        .get_log_filter("wrong-token")
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+fn without_admin_token(config: &mut Configuration) {
+    config.admin.token = None;
+}
+
+#[integration_test(configure = without_admin_token)]
+fn log_filter_is_disabled_without_an_admin_token(test_stack: TestStack) {
+    let response = test_stack
+        .client
+        .get_log_filter("")
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
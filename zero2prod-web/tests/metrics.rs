@@ -0,0 +1,159 @@
+use reqwest::StatusCode;
+use wiremock::{
+    matchers::{method, path},
+    Mock, ResponseTemplate,
+};
+use zero2prod_macros::integration_test;
+use zero2prod_web::{client::Z2PClient, configuration::Configuration};
+
+#[integration_test]
+fn metrics_are_exposed_in_prometheus_format(test_stack: TestStack) {
+    test_stack.client.health_live().await.unwrap();
+
+    let response = test_stack
+        .client
+        .metrics()
+        .await
+        .expect("Failed to execute request");
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/plain"));
+
+    let body = response.text().await.unwrap();
+    assert!(body.contains(
+        r#"zero2prod_http_requests_total{method="GET",route="/health/live",status="200"} 1"#
+    ));
+    assert!(body.contains("zero2prod_http_request_duration_seconds_bucket"));
+    assert!(body.contains("zero2prod_db_pool_size"));
+    assert!(body.contains("zero2prod_db_pool_idle"));
+    assert!(body.contains("zero2prod_db_pool_in_use"));
+}
+
+#[integration_test]
+fn metrics_use_a_single_label_for_unmatched_routes(test_stack: TestStack) {
+    reqwest::get(format!("{}/does/not/exist", test_stack.app.address))
+        .await
+        .unwrap();
+
+    let body = test_stack
+        .client
+        .metrics()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    assert!(body.contains(r#"route="unmatched",status="404""#));
+    assert!(!body.contains("/does/not/exist"));
+}
+
+#[integration_test]
+fn metrics_count_subscriptions_and_emails(test_stack: TestStack) {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&test_stack.email_server)
+        .await;
+
+    let body = "name=John%20Doe&email=john.doe@gmail.com";
+    test_stack
+        .client
+        .subscribe("newsletter", body)
+        .await
+        .unwrap();
+    sqlx::query!("UPDATE subscriptions SET status = 'confirmed'")
+        .execute(&test_stack.app.pool)
+        .await
+        .unwrap();
+    test_stack
+        .client
+        .subscribe("newsletter", body)
+        .await
+        .unwrap();
+    test_stack
+        .client
+        .subscribe("newsletter", "name=John")
+        .await
+        .unwrap();
+
+    let body = test_stack
+        .client
+        .metrics()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    assert!(body.contains(r#"zero2prod_subscriptions_total{outcome="created"} 1"#));
+    assert!(body.contains(r#"zero2prod_subscriptions_total{outcome="already_exists"} 1"#));
+    assert!(body.contains(r#"zero2prod_subscriptions_total{outcome="invalid"} 1"#));
+    assert!(body.contains(
+        r#"zero2prod_emails_total{kind="confirmation",outcome="sent",provider="postmark"} 1"#
+    ));
+}
+
+#[integration_test]
+fn metrics_count_failed_emails(test_stack: TestStack) {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&test_stack.email_server)
+        .await;
+
+    test_stack
+        .client
+        .subscribe("newsletter", "name=John%20Doe&email=john.doe@gmail.com")
+        .await
+        .unwrap();
+
+    let body = test_stack
+        .client
+        .metrics()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    assert!(body.contains(
+        r#"zero2prod_emails_total{kind="confirmation",outcome="failed",provider="postmark"} 1"#
+    ));
+    assert!(body.contains(r#"zero2prod_subscriptions_total{outcome="failed"} 1"#));
+}
+
+fn free_port() -> u16 {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+fn separate_admin_port(config: &mut Configuration) {
+    config.app.admin_port = Some(free_port());
+}
+
+#[integration_test(configure = separate_admin_port)]
+fn metrics_are_served_on_the_admin_port_when_configured(test_stack: TestStack) {
+    let public = test_stack.client.metrics().await.unwrap();
+    assert_eq!(public.status(), StatusCode::NOT_FOUND);
+
+    let admin = Z2PClient::new(format!(
+        "http://{}:{}",
+        test_stack.app.address.host,
+        test_stack.app.config.app.admin_port.unwrap()
+    ));
+    let response = admin.metrics().await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response
+        .text()
+        .await
+        .unwrap()
+        .contains("zero2prod_db_pool_size"));
+}
@@ -0,0 +1,94 @@
+use sqlx::PgPool;
+use zero2prod_macros::integration_test;
+use zero2prod_web::{
+    cli::{prepare_database, MigrationPolicy},
+    migration::{self, MIGRATOR},
+};
+
+async fn forget_last_migration(pool: &PgPool) {
+    sqlx::query(
+        "DELETE FROM _sqlx_migrations WHERE version = (SELECT MAX(version) FROM _sqlx_migrations)",
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+#[integration_test]
+fn status_lists_every_migration_as_applied(test_stack: TestStack) {
+    let status = migration::status(&test_stack.app.pool).await.unwrap();
+
+    assert_eq!(status.len(), MIGRATOR.iter().count());
+    assert!(status.iter().all(|migration| migration.applied));
+    assert!(migration::pending_migrations(&test_stack.app.pool)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[integration_test]
+fn status_reports_pending_migrations(test_stack: TestStack) {
+    forget_last_migration(&test_stack.app.pool).await;
+
+    let pending = migration::pending_migrations(&test_stack.app.pool)
+        .await
+        .unwrap();
+
+    assert_eq!(pending, vec![MIGRATOR.iter().last().unwrap().version]);
+}
+
+#[integration_test]
+fn status_handles_an_empty_database(test_stack: TestStack) {
+    sqlx::query("DROP TABLE _sqlx_migrations")
+        .execute(&test_stack.app.pool)
+        .await
+        .unwrap();
+
+    let status = migration::status(&test_stack.app.pool).await.unwrap();
+
+    assert!(status.iter().all(|migration| !migration.applied));
+}
+
+#[integration_test]
+fn concurrent_runs_are_serialized(test_stack: TestStack) {
+    sqlx::query("DROP SCHEMA public CASCADE")
+        .execute(&test_stack.app.pool)
+        .await
+        .unwrap();
+    sqlx::query("CREATE SCHEMA public")
+        .execute(&test_stack.app.pool)
+        .await
+        .unwrap();
+
+    let (first, second) = tokio::join!(
+        migration::run(&test_stack.app.pool),
+        migration::run(&test_stack.app.pool)
+    );
+
+    first.unwrap();
+    second.unwrap();
+    assert!(migration::pending_migrations(&test_stack.app.pool)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[integration_test]
+fn serve_refuses_to_start_with_pending_migrations_when_required(test_stack: TestStack) {
+    forget_last_migration(&test_stack.app.pool).await;
+
+    let ready = prepare_database(&test_stack.app.config, MigrationPolicy::Require)
+        .await
+        .unwrap();
+
+    assert!(!ready);
+}
+
+#[integration_test]
+fn serve_starts_when_required_migrations_are_applied(test_stack: TestStack) {
+    let ready = prepare_database(&test_stack.app.config, MigrationPolicy::Require)
+        .await
+        .unwrap();
+
+    assert!(ready);
+}
@@ -0,0 +1,366 @@
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+use zero2prod_macros::integration_test;
+use zero2prod_web::{
+    client::Credentials,
+    testing::{mount_email_provider, send_issues_every_second, TestStack, TestSubscriber},
+};
+
+fn issue() -> Value {
+    json!({
+        "list": "newsletter",
+        "title": "Issue #1",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+    })
+}
+
+async fn recipients(email_server: &MockServer) -> Vec<String> {
+    let mut recipients = email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|request| {
+            let body: Value = request.body_json().unwrap();
+            body["to"].as_str().unwrap().to_owned()
+        })
+        .collect::<Vec<_>>();
+    recipients.sort();
+    recipients
+}
+
+async fn create_issue(test_stack: &TestStack, credentials: &Credentials) -> Uuid {
+    let response = test_stack
+        .client
+        .create_issue(credentials, &issue())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body: Value = response.json().await.unwrap();
+    body["id"].as_str().unwrap().parse().unwrap()
+}
+
+async fn get_issue(test_stack: &TestStack, credentials: &Credentials, issue_id: Uuid) -> Value {
+    test_stack
+        .client
+        .get_issue(credentials, issue_id)
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap()
+}
+
+/// Polls the issue until it has been sent.
+async fn wait_until_sent(
+    test_stack: &TestStack,
+    credentials: &Credentials,
+    issue_id: Uuid,
+) -> Value {
+    let mut issue = Value::Null;
+    for _ in 0..100 {
+        issue = get_issue(test_stack, credentials, issue_id).await;
+        if issue["status"] == "sent" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    issue
+}
+
+#[integration_test]
+fn issues_require_valid_credentials(test_stack: TestStack) {
+    test_stack.app.create_editor("editor").await;
+    let credentials = Credentials {
+        username: "editor".into(),
+        password: "wrong password".into(),
+    };
+
+    let response = test_stack
+        .client
+        .create_issue(&credentials, &issue())
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        response.headers()["WWW-Authenticate"],
+        r#"Basic realm="zero2prod""#
+    );
+    let issues = sqlx::query!("SELECT id FROM newsletter_issues")
+        .fetch_all(&test_stack.app.pool)
+        .await
+        .unwrap();
+    assert!(issues.is_empty());
+}
+
+#[integration_test]
+fn drafts_can_be_edited_and_deleted(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    let issue_id = create_issue(&test_stack, &credentials).await;
+
+    let draft = get_issue(&test_stack, &credentials, issue_id).await;
+    assert_eq!(draft["status"], "draft");
+    assert_eq!(draft["title"], "Issue #1");
+
+    let mut edited = issue();
+    edited["title"] = "Issue #2".into();
+    let response = test_stack
+        .client
+        .update_issue(&credentials, issue_id, &edited)
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let draft = get_issue(&test_stack, &credentials, issue_id).await;
+    assert_eq!(draft["title"], "Issue #2");
+
+    let drafts: Value = test_stack
+        .client
+        .list_issues(&credentials, Some("draft"))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(drafts.as_array().unwrap().len(), 1);
+
+    let response = test_stack
+        .client
+        .delete_issue(&credentials, issue_id)
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = test_stack
+        .client
+        .get_issue(&credentials, issue_id)
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[integration_test]
+fn invalid_issues_are_rejected(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    let test_cases = vec![
+        (
+            json!({"list": "newsletter", "text_content": "text", "html_content": "html"}),
+            "missing title",
+        ),
+        (
+            json!({"list": "newsletter", "title": " ", "text_content": "text", "html_content": "html"}),
+            "blank title",
+        ),
+        (
+            json!({"list": "newsletter", "title": "Issue", "text_content": "", "html_content": "html"}),
+            "empty text content",
+        ),
+    ];
+
+    for (body, description) in test_cases {
+        let response = test_stack
+            .client
+            .create_issue(&credentials, &body)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response.status(),
+            StatusCode::BAD_REQUEST,
+            "The API did not fail with 400 Bad Request when the payload was {}.",
+            description
+        );
+    }
+}
+
+#[integration_test]
+fn issues_can_be_previewed(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    let issue_id = create_issue(&test_stack, &credentials).await;
+
+    let response = test_stack
+        .client
+        .preview_issue(&credentials, issue_id)
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()["Content-Type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/html"));
+    assert!(response
+        .text()
+        .await
+        .unwrap()
+        .contains("<p>Newsletter body as HTML</p>"));
+}
+
+#[integration_test(configure = send_issues_every_second)]
+fn issues_scheduled_in_the_future_are_not_sent(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("confirmed@gmail.com"))
+        .await;
+    let credentials = test_stack.app.create_editor("editor").await;
+    let issue_id = create_issue(&test_stack, &credentials).await;
+
+    let response = test_stack
+        .client
+        .schedule_issue(&credentials, issue_id, "2100-01-01T00:00:00Z")
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    tokio::time::sleep(Duration::from_millis(2500)).await;
+
+    let issue = get_issue(&test_stack, &credentials, issue_id).await;
+    assert_eq!(issue["status"], "scheduled");
+    assert_eq!(issue["send_at"], "2100-01-01T00:00:00Z");
+    assert!(recipients(&test_stack.email_server).await.is_empty());
+
+    let response = test_stack
+        .client
+        .unschedule_issue(&credentials, issue_id)
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let issue = get_issue(&test_stack, &credentials, issue_id).await;
+    assert_eq!(issue["status"], "draft");
+}
+
+#[integration_test(configure = send_issues_every_second)]
+fn published_issues_are_sent_once_to_confirmed_subscribers(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("first@gmail.com"))
+        .await;
+    test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("second@gmail.com"))
+        .await;
+    test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("pending@gmail.com").status("pending"))
+        .await;
+    let credentials = test_stack.app.create_editor("editor").await;
+    let issue_id = create_issue(&test_stack, &credentials).await;
+
+    let response = test_stack
+        .client
+        .publish_issue(&credentials, issue_id)
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+
+    let issue = wait_until_sent(&test_stack, &credentials, issue_id).await;
+    assert_eq!(issue["status"], "sent");
+    assert_eq!(
+        issue["deliveries"],
+        json!({"pending": 0, "sent": 2, "failed": 0})
+    );
+    // Later runs of the scheduler must not send the issue again
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert_eq!(
+        recipients(&test_stack.email_server).await,
+        vec!["first@gmail.com", "second@gmail.com"]
+    );
+}
+
+#[integration_test(configure = send_issues_every_second)]
+fn deliveries_resume_after_a_provider_failure(test_stack: TestStack) {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .up_to_n_times(1)
+        .mount(&test_stack.email_server)
+        .await;
+    mount_email_provider(&test_stack.email_server).await;
+    test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("first@gmail.com"))
+        .await;
+    test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("second@gmail.com"))
+        .await;
+    let credentials = test_stack.app.create_editor("editor").await;
+    let issue_id = create_issue(&test_stack, &credentials).await;
+
+    test_stack
+        .client
+        .publish_issue(&credentials, issue_id)
+        .await
+        .unwrap();
+
+    let issue = wait_until_sent(&test_stack, &credentials, issue_id).await;
+    assert_eq!(issue["status"], "sent");
+    assert_eq!(
+        issue["deliveries"],
+        json!({"pending": 0, "sent": 2, "failed": 0})
+    );
+    // The failed attempt is the only email sent twice
+    let recipients = recipients(&test_stack.email_server).await;
+    assert_eq!(recipients.len(), 3);
+    assert!(recipients.contains(&"first@gmail.com".to_owned()));
+    assert!(recipients.contains(&"second@gmail.com".to_owned()));
+}
+
+#[integration_test]
+fn published_issues_are_no_longer_editable(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    let issue_id = create_issue(&test_stack, &credentials).await;
+    test_stack
+        .client
+        .publish_issue(&credentials, issue_id)
+        .await
+        .unwrap();
+    sqlx::query!(
+        "UPDATE newsletter_issues SET status = 'sending' WHERE id = $1",
+        issue_id
+    )
+    .execute(&test_stack.app.pool)
+    .await
+    .unwrap();
+
+    let update = test_stack
+        .client
+        .update_issue(&credentials, issue_id, &issue())
+        .await
+        .unwrap();
+    let delete = test_stack
+        .client
+        .delete_issue(&credentials, issue_id)
+        .await
+        .unwrap();
+    let publish = test_stack
+        .client
+        .publish_issue(&credentials, issue_id)
+        .await
+        .unwrap();
+
+    assert_eq!(update.status(), StatusCode::CONFLICT);
+    assert_eq!(delete.status(), StatusCode::CONFLICT);
+    assert_eq!(publish.status(), StatusCode::CONFLICT);
+}
+
+#[integration_test]
+fn unknown_issues_are_not_found(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+
+    let response = test_stack
+        .client
+        .publish_issue(&credentials, Uuid::new_v4())
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
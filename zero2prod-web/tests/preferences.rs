@@ -0,0 +1,282 @@
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+use wiremock::MockServer;
+use zero2prod_core::domain::TokenPurpose;
+use zero2prod_macros::integration_test;
+use zero2prod_web::{
+    client::Credentials,
+    testing::{mount_email_provider, send_issues_every_second, TestStack, TestSubscriber},
+};
+
+/// Adds the `releases` and `blog` topics to the default list.
+async fn create_topics(test_stack: &TestStack, credentials: &Credentials) {
+    for (slug, name) in [("releases", "Releases"), ("blog", "Blog posts")] {
+        let response = test_stack
+            .client
+            .create_topic(
+                credentials,
+                "newsletter",
+                &json!({"slug": slug, "name": name}),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+}
+
+async fn sent_emails(email_server: &MockServer) -> Vec<Value> {
+    email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|request| request.body_json().unwrap())
+        .collect()
+}
+
+async fn publish(test_stack: &TestStack, credentials: &Credentials, topics: &[&str]) -> Uuid {
+    let response = test_stack
+        .client
+        .create_issue(
+            credentials,
+            &json!({
+                "list": "newsletter",
+                "title": "Issue #1",
+                "text_content": "Newsletter body as plain text",
+                "html_content": "<p>Newsletter body as HTML</p>",
+                "topics": topics,
+            }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let issue: Value = response.json().await.unwrap();
+    let issue_id = issue["id"].as_str().unwrap().parse().unwrap();
+    test_stack
+        .client
+        .publish_issue(credentials, issue_id)
+        .await
+        .unwrap();
+    issue_id
+}
+
+async fn wait_until_sent(test_stack: &TestStack, credentials: &Credentials, issue_id: Uuid) {
+    for _ in 0..100 {
+        let issue: Value = test_stack
+            .client
+            .get_issue(credentials, issue_id)
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        if issue["status"] == "sent" {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("The issue was not sent");
+}
+
+#[integration_test]
+fn topics_can_be_added_to_lists(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    create_topics(&test_stack, &credentials).await;
+
+    let duplicate = test_stack
+        .client
+        .create_topic(
+            &credentials,
+            "newsletter",
+            &json!({"slug": "blog", "name": "Blog"}),
+        )
+        .await
+        .unwrap();
+    let topics: Value = test_stack
+        .client
+        .list_topics(&credentials, "newsletter")
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    assert_eq!(duplicate.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(topics[0]["slug"], "blog");
+    assert_eq!(topics[0]["name"], "Blog posts");
+    assert_eq!(topics[1]["slug"], "releases");
+}
+
+#[integration_test]
+fn the_preference_center_shows_the_topics_of_the_list(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    create_topics(&test_stack, &credentials).await;
+    let subscriber_id = test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("john.doe@gmail.com"))
+        .await;
+    let token = test_stack
+        .app
+        .sign_token(TokenPurpose::Preferences, subscriber_id);
+
+    let response = test_stack.client.preferences(&token).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let page = response.text().await.unwrap();
+    assert!(page.contains("john.doe@gmail.com"));
+    assert!(page.contains(r#"value="releases" checked"#));
+    assert!(page.contains(r#"value="blog" checked"#));
+}
+
+#[integration_test]
+fn the_preference_center_requires_a_preferences_token(test_stack: TestStack) {
+    let subscriber_id = test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("john.doe@gmail.com"))
+        .await;
+    let token = test_stack
+        .app
+        .sign_token(TokenPurpose::Unsubscribe, subscriber_id);
+
+    let forged = test_stack.client.preferences("forged").await.unwrap();
+    let unsubscribe = test_stack.client.preferences(&token).await.unwrap();
+    let update = test_stack
+        .client
+        .update_preferences(&token, &[])
+        .await
+        .unwrap();
+
+    assert_eq!(forged.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(unsubscribe.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(update.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[integration_test]
+fn subscribers_can_opt_out_of_topics(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    create_topics(&test_stack, &credentials).await;
+    let subscriber_id = test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("john.doe@gmail.com"))
+        .await;
+    let token = test_stack
+        .app
+        .sign_token(TokenPurpose::Preferences, subscriber_id);
+
+    let response = test_stack
+        .client
+        .update_preferences(&token, &["releases"])
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let page = response.text().await.unwrap();
+    assert!(page.contains("Your preferences have been saved."));
+    assert!(page.contains(r#"value="releases" checked"#));
+    assert!(page.contains(r#"value="blog">"#));
+    let page = test_stack
+        .client
+        .preferences(&token)
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(page.contains(r#"value="blog">"#));
+}
+
+#[integration_test]
+fn unknown_topics_are_rejected(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    create_topics(&test_stack, &credentials).await;
+    let subscriber_id = test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("john.doe@gmail.com"))
+        .await;
+    let token = test_stack
+        .app
+        .sign_token(TokenPurpose::Preferences, subscriber_id);
+
+    let preferences = test_stack
+        .client
+        .update_preferences(&token, &["podcasts"])
+        .await
+        .unwrap();
+    let issue = test_stack
+        .client
+        .create_issue(
+            &credentials,
+            &json!({
+                "list": "newsletter",
+                "title": "Issue #1",
+                "text_content": "text",
+                "html_content": "html",
+                "topics": ["podcasts"],
+            }),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(preferences.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(issue.status(), StatusCode::BAD_REQUEST);
+}
+
+#[integration_test(configure = send_issues_every_second)]
+fn issues_about_topics_skip_the_subscribers_who_opted_out(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    let credentials = test_stack.app.create_editor("editor").await;
+    create_topics(&test_stack, &credentials).await;
+    let john = test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("john.doe@gmail.com"))
+        .await;
+    test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("jane.doe@gmail.com"))
+        .await;
+    let token = test_stack.app.sign_token(TokenPurpose::Preferences, john);
+    test_stack
+        .client
+        .update_preferences(&token, &["blog"])
+        .await
+        .unwrap();
+
+    let issue_id = publish(&test_stack, &credentials, &["releases"]).await;
+    wait_until_sent(&test_stack, &credentials, issue_id).await;
+
+    let emails = sent_emails(&test_stack.email_server).await;
+    assert_eq!(emails.len(), 1);
+    assert_eq!(emails[0]["to"], "jane.doe@gmail.com");
+}
+
+#[integration_test(configure = send_issues_every_second)]
+fn issues_link_to_the_preference_center(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    let credentials = test_stack.app.create_editor("editor").await;
+    test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("john.doe@gmail.com"))
+        .await;
+
+    let issue_id = publish(&test_stack, &credentials, &[]).await;
+    wait_until_sent(&test_stack, &credentials, issue_id).await;
+
+    let emails = sent_emails(&test_stack.email_server).await;
+    let text = emails[0]["TextBody"].as_str().unwrap();
+    let marker = "/preferences?token=";
+    let start = text.find(marker).expect("Link not found") + marker.len();
+    let token = text[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '.')
+        .collect::<String>();
+    let response = test_stack.client.preferences(&token).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response
+        .text()
+        .await
+        .unwrap()
+        .contains("This list has no topics"));
+}
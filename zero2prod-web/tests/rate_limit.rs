@@ -0,0 +1,200 @@
+use reqwest::{header::RETRY_AFTER, StatusCode};
+use sqlx::PgPool;
+use wiremock::{
+    matchers::{method, path},
+    Mock, ResponseTemplate,
+};
+use zero2prod_macros::integration_test;
+use zero2prod_web::configuration::{Configuration, RateLimitQuota, RateLimitStorage};
+
+const ONE_PER_HOUR: RateLimitQuota = RateLimitQuota {
+    burst: 1,
+    period: 3600,
+};
+
+fn one_request_per_ip(config: &mut Configuration) {
+    config.rate_limit.per_ip = ONE_PER_HOUR;
+}
+
+fn one_request_per_ip_behind_a_proxy(config: &mut Configuration) {
+    config.rate_limit.per_ip = ONE_PER_HOUR;
+    config.rate_limit.trusted_proxies = vec!["127.0.0.1/32".parse().unwrap()];
+}
+
+fn one_email_per_recipient(config: &mut Configuration) {
+    config.rate_limit.per_email = ONE_PER_HOUR;
+}
+
+fn one_request_per_ip_in_postgres(config: &mut Configuration) {
+    config.rate_limit.per_ip = ONE_PER_HOUR;
+    config.rate_limit.storage = RateLimitStorage::Postgres;
+}
+
+async fn subscribe_from(
+    address: &impl std::fmt::Display,
+    forwarded_for: &str,
+    email: &str,
+) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("{}/lists/newsletter/subscriptions", address))
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .header("X-Forwarded-For", forwarded_for)
+        .body(format!("name=John%20Doe&email={}", email))
+        .send()
+        .await
+        .expect("Failed to execute request")
+}
+
+async fn count_buckets(pool: &PgPool) -> i64 {
+    sqlx::query!(r#"SELECT COUNT(*) AS "count!" FROM rate_limit_buckets"#)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+        .count
+}
+
+#[integration_test(configure = one_request_per_ip)]
+fn subscribe_returns_a_429_once_the_ip_limit_is_reached(test_stack: TestStack) {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&test_stack.email_server)
+        .await;
+
+    let first = test_stack
+        .client
+        .subscribe("newsletter", "name=John%20Doe&email=john.doe@gmail.com")
+        .await
+        .unwrap();
+    let second = test_stack
+        .client
+        .subscribe("newsletter", "name=Jane%20Doe&email=jane.doe@gmail.com")
+        .await
+        .unwrap();
+
+    assert_eq!(first.status(), StatusCode::OK);
+    assert_eq!(second.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = second.headers()[RETRY_AFTER]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(retry_after > 0 && retry_after <= 3600);
+
+    let metrics = test_stack
+        .client
+        .metrics()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(metrics.contains(r#"zero2prod_rate_limited_total{scope="ip"} 1"#));
+}
+
+#[integration_test(configure = one_request_per_ip)]
+fn forwarded_for_is_ignored_from_untrusted_peers(test_stack: TestStack) {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&test_stack.email_server)
+        .await;
+
+    let first = subscribe_from(
+        &test_stack.app.address,
+        "198.51.100.1",
+        "john.doe@gmail.com",
+    )
+    .await;
+    let second = subscribe_from(
+        &test_stack.app.address,
+        "198.51.100.2",
+        "jane.doe@gmail.com",
+    )
+    .await;
+
+    assert_eq!(first.status(), StatusCode::OK);
+    assert_eq!(second.status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[integration_test(configure = one_request_per_ip_behind_a_proxy)]
+fn forwarded_for_is_honoured_from_trusted_proxies(test_stack: TestStack) {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&test_stack.email_server)
+        .await;
+
+    let first = subscribe_from(
+        &test_stack.app.address,
+        "198.51.100.1",
+        "john.doe@gmail.com",
+    )
+    .await;
+    let other_client = subscribe_from(
+        &test_stack.app.address,
+        "198.51.100.2",
+        "jane.doe@gmail.com",
+    )
+    .await;
+    let again = subscribe_from(&test_stack.app.address, "198.51.100.1", "jim.doe@gmail.com").await;
+
+    assert_eq!(first.status(), StatusCode::OK);
+    assert_eq!(other_client.status(), StatusCode::OK);
+    assert_eq!(again.status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[integration_test(configure = one_email_per_recipient)]
+fn subscribe_limits_the_confirmation_emails_per_recipient(test_stack: TestStack) {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&test_stack.email_server)
+        .await;
+
+    let first = test_stack
+        .client
+        .subscribe("newsletter", "name=John%20Doe&email=john.doe@gmail.com")
+        .await
+        .unwrap();
+    let second = test_stack
+        .client
+        .subscribe("newsletter", "name=John%20Doe&email=John.Doe@gmail.com")
+        .await
+        .unwrap();
+
+    assert_eq!(first.status(), StatusCode::OK);
+    assert_eq!(second.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(second.headers().contains_key(RETRY_AFTER));
+}
+
+#[integration_test(configure = one_request_per_ip_in_postgres)]
+fn buckets_can_be_shared_through_postgres(test_stack: TestStack) {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&test_stack.email_server)
+        .await;
+
+    let first = test_stack
+        .client
+        .subscribe("newsletter", "name=John%20Doe&email=john.doe@gmail.com")
+        .await
+        .unwrap();
+    let second = test_stack
+        .client
+        .subscribe("newsletter", "name=Jane%20Doe&email=jane.doe@gmail.com")
+        .await
+        .unwrap();
+
+    assert_eq!(first.status(), StatusCode::OK);
+    assert_eq!(second.status(), StatusCode::TOO_MANY_REQUESTS);
+    // One bucket for the client IP, one for the only recipient let through
+    assert_eq!(count_buckets(&test_stack.app.pool).await, 2);
+    let stored_keys = sqlx::query!("SELECT key FROM rate_limit_buckets")
+        .fetch_all(&test_stack.app.pool)
+        .await
+        .unwrap();
+    assert!(stored_keys.iter().all(|row| !row.key.contains('@')));
+}
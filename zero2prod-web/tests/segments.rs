@@ -0,0 +1,274 @@
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+use wiremock::MockServer;
+use zero2prod_macros::integration_test;
+use zero2prod_web::{
+    client::Credentials,
+    testing::{mount_email_provider, send_issues_every_second, TestApp, TestStack, TestSubscriber},
+};
+
+/// John subscribed in 2019, Jane in 2024 and Joe, who is still pending, in
+/// 2025.
+async fn insert_subscribers(app: &TestApp) {
+    for (email, status, subscribed_at) in [
+        ("john.doe@gmail.com", "confirmed", "2019-06-01T12:00:00Z"),
+        ("jane.doe@gmail.com", "confirmed", "2024-01-01T00:00:00Z"),
+        ("joe.doe@gmail.com", "pending", "2025-03-15T08:30:00Z"),
+    ] {
+        let subscriber = TestSubscriber::new(email)
+            .status(status)
+            .subscribed_at(subscribed_at.parse().unwrap());
+        app.insert_subscriber(subscriber).await;
+    }
+}
+
+async fn count(test_stack: &TestStack, credentials: &Credentials, segment: &str) -> u64 {
+    let response = test_stack
+        .client
+        .count_segment(credentials, "newsletter", segment)
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK, "{}", segment);
+    let body: Value = response.json().await.unwrap();
+    body["count"].as_u64().unwrap()
+}
+
+async fn sent_emails(email_server: &MockServer) -> Vec<Value> {
+    email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|request| request.body_json().unwrap())
+        .collect()
+}
+
+async fn create_issue(test_stack: &TestStack, credentials: &Credentials) -> Uuid {
+    let response = test_stack
+        .client
+        .create_issue(
+            credentials,
+            &json!({
+                "list": "newsletter",
+                "title": "Issue #1",
+                "text_content": "Newsletter body as plain text",
+                "html_content": "<p>Newsletter body as HTML</p>",
+            }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let issue: Value = response.json().await.unwrap();
+    issue["id"].as_str().unwrap().parse().unwrap()
+}
+
+async fn wait_until_sent(test_stack: &TestStack, credentials: &Credentials, issue_id: Uuid) {
+    for _ in 0..100 {
+        let issue: Value = test_stack
+            .client
+            .get_issue(credentials, issue_id)
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        if issue["status"] == "sent" {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("The issue was not sent");
+}
+
+#[integration_test]
+fn segments_count_the_subscribers_they_match(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    insert_subscribers(&test_stack.app).await;
+
+    let test_cases = vec![
+        ("confirmed", 2),
+        ("status = pending", 1),
+        ("subscribed after 2024-01-01", 2),
+        ("subscribed before 2024-01-01", 1),
+        ("confirmed, subscribed after 2024-01-01", 1),
+        ("not confirmed or subscribed before 2020-01-01", 2),
+        ("NOT (pending OR subscribed before 2020-01-01)", 1),
+        ("opened in 90 days", 0),
+    ];
+
+    for (segment, expected) in test_cases {
+        assert_eq!(
+            count(&test_stack, &credentials, segment).await,
+            expected,
+            "{}",
+            segment
+        );
+    }
+}
+
+#[integration_test]
+fn invalid_segments_are_rejected(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    let test_cases = vec![
+        ("status = maybe", "unknown status"),
+        ("confirmed and", "missing condition"),
+        ("subscribed after 2024-02-30", "invalid date"),
+        ("(confirmed", "unbalanced parentheses"),
+        ("topic = podcasts", "unknown topic"),
+        ("confirmed'; DROP TABLE subscriptions; --", "SQL"),
+    ];
+
+    for (segment, description) in test_cases {
+        let response = test_stack
+            .client
+            .count_segment(&credentials, "newsletter", segment)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response.status(),
+            StatusCode::BAD_REQUEST,
+            "The API did not reject the segment with an {}",
+            description
+        );
+    }
+
+    let issue = test_stack
+        .client
+        .create_issue(
+            &credentials,
+            &json!({
+                "list": "newsletter",
+                "title": "Issue #1",
+                "text_content": "text",
+                "html_content": "html",
+                "segment": "confirmed and",
+            }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(issue.status(), StatusCode::BAD_REQUEST);
+}
+
+#[integration_test]
+fn segments_match_the_topics_subscribers_receive(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    insert_subscribers(&test_stack.app).await;
+    let response = test_stack
+        .client
+        .create_topic(
+            &credentials,
+            "newsletter",
+            &json!({"slug": "releases", "name": "Releases"}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    sqlx::query!(
+        r#"
+        INSERT INTO topic_preferences (subscriber_id, topic_id, subscribed)
+        SELECT subscriptions.id, topics.id, FALSE
+        FROM subscriptions, topics
+        WHERE subscriptions.email = 'john.doe@gmail.com'
+        "#
+    )
+    .execute(&test_stack.app.pool)
+    .await
+    .unwrap();
+
+    assert_eq!(
+        count(&test_stack, &credentials, "topic = releases").await,
+        2
+    );
+    assert_eq!(
+        count(&test_stack, &credentials, "confirmed, not topic = releases").await,
+        1
+    );
+}
+
+#[integration_test(configure = send_issues_every_second)]
+fn published_issues_are_only_sent_to_their_segment(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    let credentials = test_stack.app.create_editor("editor").await;
+    insert_subscribers(&test_stack.app).await;
+    let issue_id = create_issue(&test_stack, &credentials).await;
+
+    let response = test_stack
+        .client
+        .publish_issue_to_segment(&credentials, issue_id, "subscribed after 2020-01-01")
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    wait_until_sent(&test_stack, &credentials, issue_id).await;
+
+    let emails = sent_emails(&test_stack.email_server).await;
+    assert_eq!(emails.len(), 1);
+    assert_eq!(emails[0]["to"], "jane.doe@gmail.com");
+    let issue: Value = test_stack
+        .client
+        .get_issue(&credentials, issue_id)
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(issue["segment"], "subscribed after 2020-01-01");
+}
+
+#[integration_test(configure = send_issues_every_second)]
+fn opens_are_recorded_by_the_tracking_pixel(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    let credentials = test_stack.app.create_editor("editor").await;
+    insert_subscribers(&test_stack.app).await;
+    let issue_id = create_issue(&test_stack, &credentials).await;
+    test_stack
+        .client
+        .publish_issue(&credentials, issue_id)
+        .await
+        .unwrap();
+    wait_until_sent(&test_stack, &credentials, issue_id).await;
+
+    let emails = sent_emails(&test_stack.email_server).await;
+    let email = emails
+        .iter()
+        .find(|email| email["to"] == "john.doe@gmail.com")
+        .unwrap();
+    // Handlebars escapes the `=` of the link
+    let html = email["HtmlBody"].as_str().unwrap().replace("&#x3D;", "=");
+    let marker = "/opened?token=";
+    let start = html.find(marker).expect("Tracking pixel not found") + marker.len();
+    let token = html[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '.')
+        .collect::<String>();
+    let response = test_stack
+        .client
+        .issue_opened(issue_id, &token)
+        .await
+        .unwrap();
+    let forged = test_stack
+        .client
+        .issue_opened(issue_id, "forged")
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "image/gif");
+    assert_eq!(forged.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        count(&test_stack, &credentials, "opened in 90 days").await,
+        1
+    );
+    assert_eq!(
+        count(
+            &test_stack,
+            &credentials,
+            "confirmed, not opened in 90 days"
+        )
+        .await,
+        1
+    );
+}
@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use wiremock::{
+    matchers::{method, path},
+    Mock, ResponseTemplate,
+};
+use zero2prod_macros::integration_test;
+use zero2prod_web::configuration::Configuration;
+
+const BODY: &str = "name=John%20Doe&email=john.doe@gmail.com";
+
+async fn wait_until_stopped(address: &str) {
+    for _ in 0..50 {
+        if reqwest::get(format!("{}/health/live", address))
+            .await
+            .is_err()
+        {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("The application did not stop");
+}
+
+#[integration_test]
+fn shutdown_lets_in_flight_requests_complete(test_stack: TestStack) {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+        .expect(1)
+        .mount(&test_stack.email_server)
+        .await;
+
+    let client = test_stack.client;
+    let in_flight = tokio::spawn(async move { client.subscribe("newsletter", BODY).await });
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    test_stack.app.shutdown.trigger();
+
+    let response = in_flight
+        .await
+        .unwrap()
+        .expect("In-flight request was dropped");
+    assert_eq!(response.status(), StatusCode::OK);
+
+    wait_until_stopped(&test_stack.app.address.to_string()).await;
+    assert!(test_stack.app.pool.is_closed());
+}
+
+#[integration_test]
+fn shutdown_waits_for_background_tasks(test_stack: TestStack) {
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    let worker = test_stack.app.shutdown.clone();
+    test_stack.app.shutdown.spawn(async move {
+        worker.triggered().await;
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        sender.send(()).unwrap();
+    });
+
+    test_stack.app.shutdown.trigger();
+    wait_until_stopped(&test_stack.app.address.to_string()).await;
+
+    tokio::time::timeout(Duration::from_secs(1), receiver)
+        .await
+        .expect("The background task did not complete")
+        .unwrap();
+}
+
+fn short_drain_timeout(config: &mut Configuration) {
+    config.app.shutdown_timeout = 200;
+}
+
+#[integration_test(configure = short_drain_timeout)]
+fn shutdown_stops_waiting_after_the_drain_timeout(test_stack: TestStack) {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(3)))
+        .mount(&test_stack.email_server)
+        .await;
+
+    let client = test_stack.client;
+    let in_flight = tokio::spawn(async move { client.subscribe("newsletter", BODY).await });
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    test_stack.app.shutdown.trigger();
+
+    for _ in 0..20 {
+        if test_stack.app.pool.is_closed() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    assert!(test_stack.app.pool.is_closed());
+    assert!(!in_flight.is_finished());
+}
@@ -0,0 +1,199 @@
+use chrono::{Duration, Utc};
+use reqwest::StatusCode;
+use serde_json::Value;
+use zero2prod_macros::integration_test;
+use zero2prod_web::{
+    client::Credentials,
+    configuration::Configuration,
+    testing::{TestApp, TestStack, TestSubscriber},
+};
+
+fn small_batches(config: &mut Configuration) {
+    config.subscriptions.export_batch_size = 2;
+}
+
+/// Inserts subscribers to the default list, one minute apart in the given
+/// order.
+async fn insert_subscribers(app: &TestApp, subscribers: &[(&str, &str, &str)]) {
+    for (minutes, (email, name, status)) in subscribers.iter().enumerate() {
+        let subscribed_at = Utc::now() - Duration::minutes((subscribers.len() - minutes) as i64);
+        let subscriber = TestSubscriber::new(email)
+            .name(name)
+            .status(status)
+            .subscribed_at(subscribed_at);
+        app.insert_subscriber(subscriber).await;
+    }
+}
+
+async fn export(
+    test_stack: &TestStack,
+    credentials: &Credentials,
+    params: &[(&str, &str)],
+) -> String {
+    let response = test_stack
+        .client
+        .export_subscribers(credentials, params)
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    response.text().await.unwrap()
+}
+
+#[integration_test(configure = small_batches)]
+fn subscribers_are_exported_as_csv(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    insert_subscribers(
+        &test_stack.app,
+        &[
+            ("john.doe@gmail.com", "John Doe", "confirmed"),
+            ("jane.doe@gmail.com", "Jane Doe", "pending"),
+            ("richard.doe@gmail.com", "Doe, Richard", "unsubscribed"),
+        ],
+    )
+    .await;
+
+    let response = test_stack
+        .client
+        .export_subscribers(&credentials, &[("format", "csv")])
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()["content-type"],
+        "text/csv; charset=utf-8"
+    );
+    let csv = response.text().await.unwrap();
+    let lines = csv.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "id,list_id,email,name,status,subscribed_at");
+    assert_eq!(lines.len(), 4);
+    assert!(lines[1].contains(",john.doe@gmail.com,John Doe,confirmed,"));
+    assert!(lines[2].contains(",jane.doe@gmail.com,Jane Doe,pending,"));
+    assert!(lines[3].contains(",richard.doe@gmail.com,\"Doe, Richard\",unsubscribed,"));
+}
+
+#[integration_test(configure = small_batches)]
+fn subscribers_are_exported_as_ndjson(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    insert_subscribers(
+        &test_stack.app,
+        &[
+            ("john.doe@gmail.com", "John Doe", "confirmed"),
+            ("jane.doe@gmail.com", "Jane Doe", "pending"),
+            ("richard.doe@gmail.com", "Richard Doe", "confirmed"),
+        ],
+    )
+    .await;
+
+    let response = test_stack
+        .client
+        .export_subscribers(&credentials, &[("format", "ndjson")])
+        .await
+        .unwrap();
+
+    assert_eq!(response.headers()["content-type"], "application/x-ndjson");
+    let ndjson = response.text().await.unwrap();
+    let subscribers = ndjson
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    let emails = subscribers
+        .iter()
+        .map(|subscriber| subscriber["email"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        emails,
+        [
+            "john.doe@gmail.com",
+            "jane.doe@gmail.com",
+            "richard.doe@gmail.com"
+        ]
+    );
+    assert_eq!(subscribers[0]["name"], "John Doe");
+    assert_eq!(subscribers[0]["status"], "confirmed");
+    assert_eq!(
+        subscribers[0]["list_id"],
+        "00000000-0000-0000-0000-000000000001"
+    );
+}
+
+#[integration_test]
+fn exports_can_be_filtered_by_status(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    insert_subscribers(
+        &test_stack.app,
+        &[
+            ("john.doe@gmail.com", "John Doe", "confirmed"),
+            ("jane.doe@gmail.com", "Jane Doe", "pending"),
+        ],
+    )
+    .await;
+
+    let csv = export(&test_stack, &credentials, &[("status", "pending")]).await;
+
+    let lines = csv.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[1].contains("jane.doe@gmail.com"));
+}
+
+#[integration_test]
+fn csv_cells_are_not_read_as_formulas(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    insert_subscribers(
+        &test_stack.app,
+        &[
+            ("john.doe@gmail.com", "=1+2", "confirmed"),
+            ("jane.doe@gmail.com", "+1 Jane", "confirmed"),
+            ("=1+1@gmail.com", "Richard Doe", "confirmed"),
+        ],
+    )
+    .await;
+
+    let csv = export(&test_stack, &credentials, &[]).await;
+
+    let lines = csv.lines().collect::<Vec<_>>();
+    assert!(lines[1].contains(",'=1+2,"));
+    assert!(lines[2].contains(",'+1 Jane,"));
+    assert!(lines[3].contains(",'=1+1@gmail.com,"));
+}
+
+#[integration_test]
+fn empty_exports_only_have_a_header(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+
+    let csv = export(&test_stack, &credentials, &[]).await;
+    let ndjson = export(&test_stack, &credentials, &[("format", "ndjson")]).await;
+
+    assert_eq!(csv, "id,list_id,email,name,status,subscribed_at\n");
+    assert_eq!(ndjson, "");
+}
+
+#[integration_test]
+fn unknown_statuses_are_rejected(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+
+    let response = test_stack
+        .client
+        .export_subscribers(&credentials, &[("status", "bouncing")])
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[integration_test]
+fn exporting_requires_authentication(test_stack: TestStack) {
+    let response = test_stack
+        .client
+        .export_subscribers(
+            &Credentials {
+                username: "editor".into(),
+                password: "wrong".into(),
+            },
+            &[],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,467 @@
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use serde_json::Value;
+use sqlx::PgPool;
+use uuid::Uuid;
+use zero2prod_core::domain::{EmailHash, TokenPurpose};
+use zero2prod_macros::integration_test;
+use zero2prod_web::{
+    client::Credentials,
+    configuration::Configuration,
+    testing::{mount_email_provider, TestStack},
+};
+
+const CSV: &str = "\
+name,email
+John Doe,john.doe@gmail.com
+Jane Doe,not an email
+,nameless@gmail.com
+\"Doe, Richard\",richard.doe@gmail.com
+John Again,john.doe@gmail.com
+";
+
+fn small_batches(config: &mut Configuration) {
+    config.subscriptions.import_batch_size = 2;
+}
+
+fn run_jobs_every_100ms(config: &mut Configuration) {
+    config.jobs.poll_interval = 100;
+}
+
+/// Imports `csv` and waits for the import to end.
+async fn import(
+    test_stack: &TestStack,
+    credentials: &Credentials,
+    params: &[(&str, &str)],
+    csv: &str,
+) -> Value {
+    let response = test_stack
+        .client
+        .import_subscribers(credentials, params, csv.to_owned())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let import: Value = response.json().await.unwrap();
+    wait_for(test_stack, credentials, &import, |import| {
+        import["status"] != "running"
+    })
+    .await
+}
+
+/// Polls `import` until `until` holds.
+async fn wait_for(
+    test_stack: &TestStack,
+    credentials: &Credentials,
+    import: &Value,
+    until: impl Fn(&Value) -> bool,
+) -> Value {
+    let import_id = import["id"].as_str().unwrap().parse().unwrap();
+    for _ in 0..50 {
+        let import: Value = test_stack
+            .client
+            .get_import(credentials, import_id)
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        if until(&import) {
+            return import;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("The import did not reach the expected state");
+}
+
+async fn subscriptions(pool: &PgPool) -> Vec<(String, String, String)> {
+    sqlx::query!("SELECT email, name, status FROM subscriptions ORDER BY email")
+        .fetch_all(pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|row| (row.email, row.name, row.status))
+        .collect()
+}
+
+#[integration_test(configure = small_batches)]
+fn valid_rows_are_imported_and_invalid_ones_reported(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+
+    let response = test_stack
+        .client
+        .import_subscribers(
+            &credentials,
+            &[("list", "newsletter"), ("confirmed", "true")],
+            CSV,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let import: Value = response.json().await.unwrap();
+    assert_eq!(import["status"], "running");
+    let import = wait_for(&test_stack, &credentials, &import, |import| {
+        import["status"] != "running"
+    })
+    .await;
+    assert_eq!(import["status"], "completed");
+    assert_eq!(import["rows"], 5);
+    assert_eq!(import["imported"], 3);
+    assert_eq!(import["failed"], 2);
+    assert_eq!(
+        subscriptions(&test_stack.app.pool).await,
+        [
+            (
+                "john.doe@gmail.com".into(),
+                "John Again".into(),
+                "confirmed".into()
+            ),
+            (
+                "richard.doe@gmail.com".into(),
+                "Doe, Richard".into(),
+                "confirmed".into()
+            ),
+        ]
+    );
+
+    let import_id = import["id"].as_str().unwrap().parse().unwrap();
+    let report = test_stack
+        .client
+        .import_errors(&credentials, import_id)
+        .await
+        .unwrap();
+    assert_eq!(report.status(), StatusCode::OK);
+    assert_eq!(report.headers()["content-type"], "text/csv; charset=utf-8");
+    let report = report.text().await.unwrap();
+    let lines = report.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "line,email,error");
+    assert!(lines[1].starts_with("3,not an email,Invalid email address"));
+    assert!(lines[2].starts_with("4,nameless@gmail.com,Invalid name"));
+    assert_eq!(lines.len(), 3);
+}
+
+#[integration_test(configure = small_batches)]
+fn progress_is_reported_while_importing(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    // Holds up the second batch until rolled back
+    let mut blocker = test_stack.app.pool.begin().await.unwrap();
+    sqlx::query!(
+        r#"
+        INSERT INTO subscriptions (id, list_id, email, name, subscribed_at, status)
+        VALUES ($1, '00000000-0000-0000-0000-000000000001', 'eve@gmail.com', 'Eve', now(), 'pending')
+        "#,
+        Uuid::new_v4()
+    )
+    .execute(&mut *blocker)
+    .await
+    .unwrap();
+
+    let response = test_stack
+        .client
+        .import_subscribers(
+            &credentials,
+            &[("list", "newsletter"), ("confirmed", "true")],
+            "name,email\nJohn,john@gmail.com\nJane,jane@gmail.com\nEve,eve@gmail.com\nBob,bob@gmail.com\n",
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let import: Value = response.json().await.unwrap();
+    let running = wait_for(&test_stack, &credentials, &import, |import| {
+        import["rows"] == 2
+    })
+    .await;
+    assert_eq!(running["status"], "running");
+    assert_eq!(running["imported"], 2);
+    blocker.rollback().await.unwrap();
+    let finished = wait_for(&test_stack, &credentials, &import, |import| {
+        import["status"] != "running"
+    })
+    .await;
+    assert_eq!(finished["status"], "completed");
+    assert_eq!(finished["rows"], 4);
+    assert_eq!(finished["imported"], 4);
+}
+
+#[integration_test]
+fn imports_are_recorded_as_consents(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    import(
+        &test_stack,
+        &credentials,
+        &[("list", "newsletter"), ("confirmed", "true")],
+        "name,email\nJohn Doe,john.doe@gmail.com\n",
+    )
+    .await;
+
+    let consents = sqlx::query!("SELECT action, source FROM consent_events ORDER BY action")
+        .fetch_all(&test_stack.app.pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|row| (row.action, row.source))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        consents,
+        [
+            ("confirm".into(), "import".into()),
+            ("subscribe".into(), "import".into()),
+        ]
+    );
+}
+
+#[integration_test(configure = run_jobs_every_100ms)]
+fn pending_rows_are_sent_a_confirmation_email(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    mount_email_provider(&test_stack.email_server).await;
+
+    import(
+        &test_stack,
+        &credentials,
+        &[("list", "newsletter")],
+        "name,email\nJohn Doe,john.doe@gmail.com\nJane Doe,jane.doe@gmail.com\n",
+    )
+    .await;
+
+    let mut received = vec![];
+    for _ in 0..50 {
+        received = test_stack.email_server.received_requests().await.unwrap();
+        if received.len() == 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let mut recipients = received
+        .iter()
+        .map(|request| request.body_json::<Value>().unwrap()["to"].clone())
+        .collect::<Vec<_>>();
+    recipients.sort_by_key(|recipient| recipient.to_string());
+    assert_eq!(recipients, ["jane.doe@gmail.com", "john.doe@gmail.com"]);
+    let body: Value = received[0].body_json().unwrap();
+    assert!(body["HtmlBody"]
+        .as_str()
+        .unwrap()
+        .replace("&#x3D;", "=")
+        .contains("/subscriptions/confirm?token="));
+    let statuses = subscriptions(&test_stack.app.pool).await;
+    assert!(statuses.iter().all(|(_, _, status)| status == "pending"));
+}
+
+#[integration_test]
+fn columns_can_be_mapped(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    let import = import(
+        &test_stack,
+        &credentials,
+        &[
+            ("list", "newsletter"),
+            ("confirmed", "true"),
+            ("email_column", "E-mail"),
+            ("name_column", "Full name"),
+        ],
+        "Id,Full name,E-mail\n1,John Doe,john.doe@gmail.com\n",
+    )
+    .await;
+
+    assert_eq!(import["imported"], 1);
+    assert_eq!(
+        subscriptions(&test_stack.app.pool).await[0].0,
+        "john.doe@gmail.com"
+    );
+}
+
+#[integration_test]
+fn files_missing_a_column_are_rejected(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+
+    let response = test_stack
+        .client
+        .import_subscribers(
+            &credentials,
+            &[("list", "newsletter")],
+            "name,mail\nJohn Doe,john.doe@gmail.com\n",
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response.text().await.unwrap(), "missing column: email");
+    let imports = sqlx::query!("SELECT count(*) AS \"count!\" FROM subscriber_imports")
+        .fetch_one(&test_stack.app.pool)
+        .await
+        .unwrap();
+    assert_eq!(imports.count, 0);
+}
+
+#[integration_test]
+fn existing_subscribers_keep_their_consent(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    let pool = &test_stack.app.pool;
+    for (email, status) in [
+        ("pending@gmail.com", "pending"),
+        ("left@gmail.com", "unsubscribed"),
+    ] {
+        sqlx::query!(
+            r#"
+            INSERT INTO subscriptions (id, list_id, email, name, subscribed_at, status)
+            VALUES ($1, '00000000-0000-0000-0000-000000000001', $2, 'Old Name', now(), $3)
+            "#,
+            Uuid::new_v4(),
+            email,
+            status
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+    let erased = EmailHash::of(&"erased@gmail.com".parse().unwrap());
+    sqlx::query!(
+        "INSERT INTO suppressed_emails (email_hash, suppressed_at) VALUES ($1, now())",
+        erased.as_ref()
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+
+    let import = import(
+        &test_stack,
+        &credentials,
+        &[("list", "newsletter"), ("confirmed", "true")],
+        "name,email\nNew Name,pending@gmail.com\nNew Name,left@gmail.com\nNew Name,erased@gmail.com\n",
+    )
+    .await;
+
+    assert_eq!(import["imported"], 2);
+    assert_eq!(import["failed"], 1);
+    assert_eq!(
+        subscriptions(pool).await,
+        [
+            (
+                "left@gmail.com".into(),
+                "New Name".into(),
+                "unsubscribed".into()
+            ),
+            (
+                "pending@gmail.com".into(),
+                "New Name".into(),
+                "confirmed".into()
+            ),
+        ]
+    );
+    // Existing subscribers keep their consent, pending ones confirm
+    let consents = sqlx::query!("SELECT action, source FROM consent_events")
+        .fetch_all(pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|row| (row.action, row.source))
+        .collect::<Vec<_>>();
+    assert_eq!(consents, [("confirm".into(), "import".into())]);
+}
+
+#[integration_test]
+fn imports_can_be_looked_up(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    let created = import(
+        &test_stack,
+        &credentials,
+        &[("list", "newsletter")],
+        "name,email\nJohn Doe,john.doe@gmail.com\n",
+    )
+    .await;
+    let import_id = created["id"].as_str().unwrap().parse().unwrap();
+
+    let found = test_stack
+        .client
+        .get_import(&credentials, import_id)
+        .await
+        .unwrap();
+    let unknown = test_stack
+        .client
+        .get_import(&credentials, Uuid::new_v4())
+        .await
+        .unwrap();
+
+    assert_eq!(found.status(), StatusCode::OK);
+    assert_eq!(found.json::<Value>().await.unwrap(), created);
+    assert_eq!(unknown.status(), StatusCode::NOT_FOUND);
+}
+
+#[integration_test]
+fn importing_requires_authentication(test_stack: TestStack) {
+    let response = test_stack
+        .client
+        .import_subscribers(
+            &Credentials {
+                username: "editor".into(),
+                password: "wrong".into(),
+            },
+            &[("list", "newsletter")],
+            CSV,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(subscriptions(&test_stack.app.pool).await.is_empty());
+}
+
+#[integration_test]
+fn erasure_removes_the_import_errors(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    mount_email_provider(&test_stack.email_server).await;
+    let created = import(
+        &test_stack,
+        &credentials,
+        &[("list", "newsletter"), ("confirmed", "true")],
+        "name,email\nJohn Doe,john.doe@gmail.com\n<John>,John.Doe@gmail.com\n",
+    )
+    .await;
+    assert_eq!(created["failed"], 1);
+    let subscriber_id = sqlx::query!("SELECT id FROM subscriptions")
+        .fetch_one(&test_stack.app.pool)
+        .await
+        .unwrap()
+        .id;
+    let token = test_stack
+        .app
+        .sign_token(TokenPurpose::Access, subscriber_id);
+
+    test_stack.client.erase_data(&token).await.unwrap();
+
+    let import_id = created["id"].as_str().unwrap().parse().unwrap();
+    let report = test_stack
+        .client
+        .import_errors(&credentials, import_id)
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert_eq!(report, "line,email,error\n");
+}
+
+#[integration_test]
+fn error_report_cells_are_not_read_as_formulas(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    let created = import(
+        &test_stack,
+        &credentials,
+        &[("list", "newsletter")],
+        "name,email\nJohn Doe,=1+2\n",
+    )
+    .await;
+
+    let import_id = created["id"].as_str().unwrap().parse().unwrap();
+    let report = test_stack
+        .client
+        .import_errors(&credentials, import_id)
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(report.lines().nth(1).unwrap().starts_with("2,'=1+2,"));
+}
@@ -0,0 +1,387 @@
+use chrono::{Duration, Utc};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+use zero2prod_macros::integration_test;
+use zero2prod_web::{
+    client::{Credentials, SubscriberQuery},
+    testing::{TestStack, TestSubscriber},
+};
+
+async fn list(
+    test_stack: &TestStack,
+    credentials: &Credentials,
+    query: &SubscriberQuery<'_>,
+) -> Value {
+    let response = test_stack
+        .client
+        .list_subscribers(credentials, query)
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    response.json().await.unwrap()
+}
+
+fn emails(page: &Value) -> Vec<&str> {
+    page["subscribers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|subscriber| subscriber["email"].as_str().unwrap())
+        .collect()
+}
+
+#[integration_test]
+fn subscribers_are_listed_newest_first_in_pages(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    for (age, email) in ["a@gmail.com", "b@gmail.com", "c@gmail.com"]
+        .into_iter()
+        .enumerate()
+    {
+        test_stack
+            .app
+            .insert_subscriber(
+                TestSubscriber::new(email).subscribed_at(Utc::now() - Duration::days(age as i64)),
+            )
+            .await;
+    }
+
+    let first = list(
+        &test_stack,
+        &credentials,
+        &SubscriberQuery {
+            limit: Some(2),
+            ..Default::default()
+        },
+    )
+    .await;
+    let second = list(
+        &test_stack,
+        &credentials,
+        &SubscriberQuery {
+            limit: Some(2),
+            after: first["next"].as_str(),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert_eq!(emails(&first), ["a@gmail.com", "b@gmail.com"]);
+    assert_eq!(emails(&second), ["c@gmail.com"]);
+    assert_eq!(second["next"], Value::Null);
+}
+
+#[integration_test]
+fn subscribers_can_be_filtered(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    test_stack
+        .app
+        .insert_subscriber(
+            TestSubscriber::new("john.doe@gmail.com").subscribed_at(Utc::now() - Duration::days(1)),
+        )
+        .await;
+    test_stack
+        .app
+        .insert_subscriber(
+            TestSubscriber::new("jane.doe@gmail.com")
+                .name("Jane Doe")
+                .status("pending")
+                .subscribed_at(Utc::now() - Duration::days(2)),
+        )
+        .await;
+    test_stack
+        .app
+        .insert_subscriber(
+            TestSubscriber::new("richard@gmail.com")
+                .name("Richard Roe")
+                .subscribed_at(Utc::now() - Duration::days(10)),
+        )
+        .await;
+    test_stack
+        .app
+        .insert_subscriber(
+            TestSubscriber::new("j_doe@gmail.com")
+                .name("Someone")
+                .subscribed_at(Utc::now() - Duration::days(1)),
+        )
+        .await;
+
+    let confirmed = list(
+        &test_stack,
+        &credentials,
+        &SubscriberQuery {
+            status: Some("confirmed"),
+            ..Default::default()
+        },
+    )
+    .await;
+    let by_email = list(
+        &test_stack,
+        &credentials,
+        &SubscriberQuery {
+            search: Some("JANE"),
+            ..Default::default()
+        },
+    )
+    .await;
+    let by_name = list(
+        &test_stack,
+        &credentials,
+        &SubscriberQuery {
+            search: Some("richard r"),
+            ..Default::default()
+        },
+    )
+    .await;
+    let wildcard = list(
+        &test_stack,
+        &credentials,
+        &SubscriberQuery {
+            search: Some("j_"),
+            ..Default::default()
+        },
+    )
+    .await;
+    let week_ago = (chrono::Utc::now() - chrono::Duration::days(7)).to_rfc3339();
+    let recent = list(
+        &test_stack,
+        &credentials,
+        &SubscriberQuery {
+            subscribed_after: Some(&week_ago),
+            status: Some("confirmed"),
+            ..Default::default()
+        },
+    )
+    .await;
+
+    assert_eq!(emails(&confirmed).len(), 3);
+    assert_eq!(emails(&by_email), ["jane.doe@gmail.com"]);
+    assert_eq!(emails(&by_name), ["richard@gmail.com"]);
+    assert_eq!(emails(&wildcard), ["j_doe@gmail.com"]);
+    let mut recent = emails(&recent);
+    recent.sort();
+    assert_eq!(recent, ["j_doe@gmail.com", "john.doe@gmail.com"]);
+}
+
+#[integration_test]
+fn invalid_listing_parameters_are_rejected(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+
+    for query in [
+        SubscriberQuery {
+            status: Some("bouncing"),
+            ..Default::default()
+        },
+        SubscriberQuery {
+            after: Some("yesterday"),
+            ..Default::default()
+        },
+    ] {
+        let response = test_stack
+            .client
+            .list_subscribers(&credentials, &query)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+    let unknown_list = test_stack
+        .client
+        .list_subscribers(
+            &credentials,
+            &SubscriberQuery {
+                list: Some("podcasts"),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(unknown_list.status(), StatusCode::NOT_FOUND);
+}
+
+#[integration_test]
+fn subscribers_can_be_looked_up(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    let id = test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("john.doe@gmail.com"))
+        .await;
+
+    let found = test_stack
+        .client
+        .get_subscriber(&credentials, id)
+        .await
+        .unwrap();
+    let unknown = test_stack
+        .client
+        .get_subscriber(&credentials, Uuid::new_v4())
+        .await
+        .unwrap();
+
+    assert_eq!(found.status(), StatusCode::OK);
+    let subscriber: Value = found.json().await.unwrap();
+    assert_eq!(subscriber["id"], id.to_string());
+    assert_eq!(subscriber["email"], "john.doe@gmail.com");
+    assert_eq!(subscriber["status"], "confirmed");
+    assert_eq!(subscriber["topics"], json!([]));
+    assert_eq!(unknown.status(), StatusCode::NOT_FOUND);
+}
+
+#[integration_test]
+fn subscribers_can_be_updated(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    for (slug, name) in [("releases", "Releases"), ("blog", "Blog posts")] {
+        test_stack
+            .client
+            .create_topic(
+                &credentials,
+                "newsletter",
+                &json!({"slug": slug, "name": name}),
+            )
+            .await
+            .unwrap();
+    }
+    let id = test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("john.doe@gmail.com").status("pending"))
+        .await;
+
+    let response = test_stack
+        .client
+        .update_subscriber(
+            &credentials,
+            id,
+            &json!({"name": "Johnny Doe", "status": "confirmed", "topics": ["blog"]}),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let subscriber: Value = response.json().await.unwrap();
+    assert_eq!(subscriber["name"], "Johnny Doe");
+    assert_eq!(subscriber["status"], "confirmed");
+    assert_eq!(
+        subscriber["topics"],
+        json!([
+            {"topic": "blog", "subscribed": true},
+            {"topic": "releases", "subscribed": false},
+        ])
+    );
+    let consents = sqlx::query!("SELECT action, source FROM consent_events ORDER BY action")
+        .fetch_all(&test_stack.app.pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|row| (row.action, row.source))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        consents,
+        [
+            ("confirm".into(), "api".into()),
+            ("preferences".into(), "api".into()),
+        ]
+    );
+}
+
+#[integration_test]
+fn invalid_updates_are_rejected(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    let id = test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("john.doe@gmail.com").status("pending"))
+        .await;
+
+    for update in [
+        json!({"name": "<script>"}),
+        json!({"status": "bouncing"}),
+        json!({"topics": ["podcasts"]}),
+    ] {
+        let response = test_stack
+            .client
+            .update_subscriber(&credentials, id, &update)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+    let unknown = test_stack
+        .client
+        .update_subscriber(&credentials, Uuid::new_v4(), &json!({"name": "Jane"}))
+        .await
+        .unwrap();
+    assert_eq!(unknown.status(), StatusCode::NOT_FOUND);
+}
+
+#[integration_test]
+fn subscribers_can_be_deleted(test_stack: TestStack) {
+    let credentials = test_stack.app.create_editor("editor").await;
+    let pool = &test_stack.app.pool;
+    let id = test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("john.doe@gmail.com").status("pending"))
+        .await;
+    sqlx::query!(
+        "INSERT INTO subscription_tokens (subscription_token, subscriber_id) VALUES ($1, $2)",
+        id.simple().to_string(),
+        id
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+
+    let deleted = test_stack
+        .client
+        .delete_subscriber(&credentials, id)
+        .await
+        .unwrap();
+    let again = test_stack
+        .client
+        .delete_subscriber(&credentials, id)
+        .await
+        .unwrap();
+
+    assert_eq!(deleted.status(), StatusCode::NO_CONTENT);
+    assert_eq!(again.status(), StatusCode::NOT_FOUND);
+    let remaining = sqlx::query!("SELECT count(*) AS \"count!\" FROM subscriptions")
+        .fetch_one(pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining.count, 0);
+}
+
+#[integration_test]
+fn managing_subscribers_requires_authentication(test_stack: TestStack) {
+    let credentials = Credentials {
+        username: "editor".into(),
+        password: "wrong".into(),
+    };
+    let id = test_stack
+        .app
+        .insert_subscriber(TestSubscriber::new("john.doe@gmail.com").status("pending"))
+        .await;
+
+    let responses = [
+        test_stack
+            .client
+            .list_subscribers(&credentials, &SubscriberQuery::default())
+            .await
+            .unwrap(),
+        test_stack
+            .client
+            .get_subscriber(&credentials, id)
+            .await
+            .unwrap(),
+        test_stack
+            .client
+            .update_subscriber(&credentials, id, &json!({"name": "Jane"}))
+            .await
+            .unwrap(),
+        test_stack
+            .client
+            .delete_subscriber(&credentials, id)
+            .await
+            .unwrap(),
+    ];
+
+    for response in responses {
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
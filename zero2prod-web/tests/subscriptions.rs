@@ -17,7 +17,7 @@ fn subscribe_returns_a_200_for_valid_form_data(test_stack: TestStack) {
 
     let response = test_stack
         .client
-        .subscribe(body)
+        .subscribe("newsletter", body)
         .await
         .expect("failed to execute request");
 
@@ -45,7 +45,7 @@ fn subscribe_sends_a_confirmation_email_for_valid_data(test_stack: TestStack) {
 
     let response = test_stack
         .client
-        .subscribe(body)
+        .subscribe("newsletter", body)
         .await
         .expect("Failed to execute request");
 
@@ -80,7 +80,7 @@ fn subscribe_returns_a_400_for_invalid_form_data(test_stack: TestStack) {
     for (body, error) in test_cases {
         let response = test_stack
             .client
-            .subscribe(body)
+            .subscribe("newsletter", body)
             .await
             .expect("Failed to execute request");
 
@@ -0,0 +1,81 @@
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use prost::Message;
+use reqwest::StatusCode;
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+use zero2prod_macros::integration_test;
+use zero2prod_web::{
+    configuration::{Configuration, OtlpConfig, OtlpProtocol},
+    testing::mount_email_provider,
+};
+
+const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+
+/// Exports the spans of the app to the mock of the email provider, which
+/// doubles as the collector.
+fn export_to_the_email_server(config: &mut Configuration) {
+    config.telemetry.otlp = Some(OtlpConfig {
+        endpoint: config.email_client.base_url.clone(),
+        protocol: OtlpProtocol::Http,
+        sampling_ratio: 1.0,
+    });
+}
+
+/// The names and trace ids of the spans the collector received.
+async fn exported_spans(collector: &MockServer) -> Vec<(String, String)> {
+    collector
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|request| request.url.path() == "/v1/traces")
+        .flat_map(|request| {
+            ExportTraceServiceRequest::decode(request.body.as_slice())
+                .expect("Invalid OTLP payload")
+                .resource_spans
+        })
+        .flat_map(|resource| resource.scope_spans)
+        .flat_map(|scope| scope.spans)
+        .map(|span| (span.name, hex::encode(span.trace_id)))
+        .collect()
+}
+
+#[integration_test(configure = export_to_the_email_server)]
+fn request_spans_are_exported_to_the_otlp_collector(test_stack: TestStack) {
+    mount_email_provider(&test_stack.email_server).await;
+    Mock::given(path("/v1/traces"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&test_stack.email_server)
+        .await;
+
+    let response = reqwest::Client::new()
+        .post(format!(
+            "{}/lists/newsletter/subscriptions",
+            test_stack.app.address
+        ))
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .header("traceparent", TRACEPARENT)
+        .body("name=John%20Doe&email=john.doe@gmail.com")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-request-id"], TRACE_ID);
+
+    tokio::task::spawn_blocking(zero2prod_web::telemetry::shutdown)
+        .await
+        .unwrap();
+
+    let spans = exported_spans(&test_stack.email_server).await;
+    for name in ["request", "Subscription"] {
+        let (_, trace_id) = spans
+            .iter()
+            .find(|(span, _)| span == name)
+            .unwrap_or_else(|| panic!("{} span not exported in {:?}", name, spans));
+        assert_eq!(trace_id, TRACE_ID);
+    }
+}
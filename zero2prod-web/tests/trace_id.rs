@@ -0,0 +1,82 @@
+use reqwest::StatusCode;
+use wiremock::{
+    matchers::{header, method, path},
+    Mock, ResponseTemplate,
+};
+use zero2prod_macros::integration_test;
+
+const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+#[integration_test]
+fn responses_carry_a_generated_request_id(test_stack: TestStack) {
+    let response = test_stack.client.health_live().await.unwrap();
+
+    let request_id = response.headers()["x-request-id"].to_str().unwrap();
+    assert!(uuid::Uuid::parse_str(request_id).is_ok());
+}
+
+#[integration_test]
+fn inbound_request_id_is_echoed(test_stack: TestStack) {
+    let response = reqwest::Client::new()
+        .get(format!("{}/health/live", test_stack.app.address))
+        .header("X-Request-Id", "customer-request-42")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.headers()["x-request-id"], "customer-request-42");
+}
+
+#[integration_test]
+fn invalid_inbound_request_id_is_replaced(test_stack: TestStack) {
+    let response = reqwest::Client::new()
+        .get(format!("{}/health/live", test_stack.app.address))
+        .header("X-Request-Id", "not valid; rm -rf /")
+        .send()
+        .await
+        .unwrap();
+
+    let request_id = response.headers()["x-request-id"].to_str().unwrap();
+    assert!(uuid::Uuid::parse_str(request_id).is_ok());
+}
+
+#[integration_test]
+fn traceparent_trace_id_is_echoed(test_stack: TestStack) {
+    let response = reqwest::Client::new()
+        .get(format!("{}/health/live", test_stack.app.address))
+        .header("traceparent", TRACEPARENT)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        response.headers()["x-request-id"],
+        "4bf92f3577b34da6a3ce929d0e0e4736"
+    );
+}
+
+#[integration_test]
+fn request_id_is_forwarded_to_the_email_provider(test_stack: TestStack) {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .and(header("X-Request-Id", "customer-request-42"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&test_stack.email_server)
+        .await;
+
+    let response = reqwest::Client::new()
+        .post(format!(
+            "{}/lists/newsletter/subscriptions",
+            test_stack.app.address
+        ))
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .header("X-Request-Id", "customer-request-42")
+        .body("name=John%20Doe&email=john.doe@gmail.com")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-request-id"], "customer-request-42");
+}